@@ -0,0 +1,165 @@
+use anyhow::{bail, Context};
+
+use crate::atlas::Atlas;
+use crate::geometry_cache::GeometryCache;
+use crate::ui_scene::UIScene;
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum Playback {
+    // Back to the first frame after the last.
+    #[default]
+    Loop,
+    // Forwards then backwards, without showing the end frames twice.
+    PingPong,
+    // Stops on the last frame.
+    Once,
+}
+
+// A sprite cycling through frames of an atlas, each shown for its own number
+// of seconds:
+//
+//     let frames = [("run0", 0.08), ("run1", 0.08), ("run2", 0.12)];
+//     let mut run = AnimatedSprite::new(&mut scene, &mut cache, device, &atlas, &frames, position)?;
+//     run.update(&mut scene, dt); // every frame
+//
+// Frames only change the sampled sub-rect, so they should all be the size of
+// the first one.
+pub struct AnimatedSprite {
+    pub playback: Playback,
+    // Multiplies the time passed to `update`.
+    pub speed: f32,
+    element: usize,
+    // Sub-rect of each frame and how long it's shown.
+    frames: Vec<([f32; 4], f32)>,
+    // Seconds into the cycle.
+    time: f32,
+    playing: bool,
+}
+
+impl AnimatedSprite {
+    // Starts playing from the first frame. Fails on names the atlas doesn't
+    // have and on frames without a positive duration.
+    pub fn new(
+        scene: &mut UIScene,
+        cache: &mut GeometryCache,
+        device: &wgpu::Device,
+        atlas: &Atlas,
+        frames: &[(&str, f32)],
+        position: cgmath::Vector2<f32>,
+    ) -> anyhow::Result<Self> {
+        let Some(&(first, _)) = frames.first() else {
+            bail!("an animated sprite needs at least one frame");
+        };
+        let frames = frames
+            .iter()
+            .map(|&(name, duration)| {
+                if duration <= 0.0 {
+                    bail!("frame {} lasts {} seconds", name, duration);
+                }
+                let uv = atlas
+                    .uv_rect(name)
+                    .with_context(|| format!("the atlas has no frame {}", name))?;
+                Ok((uv, duration))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let mut element = atlas
+            .sprite(cache, device, first, position)
+            .context("the atlas has no first frame")?;
+        element.draggable = false;
+        scene.elements.push(element);
+        Ok(Self {
+            playback: Playback::Loop,
+            speed: 1.0,
+            element: scene.elements.len() - 1,
+            frames,
+            time: 0.0,
+            playing: true,
+        })
+    }
+
+    pub fn element(&self) -> usize {
+        self.element
+    }
+
+    pub fn play(&mut self) {
+        if self.is_finished() {
+            self.time = 0.0;
+        }
+        self.playing = true;
+    }
+
+    pub fn pause(&mut self) {
+        self.playing = false;
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+
+    // Only `Playback::Once` finishes.
+    pub fn is_finished(&self) -> bool {
+        self.playback == Playback::Once && self.time >= self.duration()
+    }
+
+    // Seconds into the cycle.
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+
+    // Length of one cycle, which for `Playback::PingPong` is there and back.
+    pub fn duration(&self) -> f32 {
+        self.order().iter().map(|&i| self.frames[i].1).sum()
+    }
+
+    // Shown on the next `update`, playing or not.
+    pub fn seek(&mut self, time: f32) {
+        self.time = self.wrap(time);
+    }
+
+    // Jumps to where `frame` is first shown.
+    pub fn seek_frame(&mut self, frame: usize) {
+        let before = self.order().into_iter().take_while(|&i| i != frame);
+        self.time = before.map(|i| self.frames[i].1).sum();
+    }
+
+    // Index of the frame being shown.
+    pub fn frame(&self) -> usize {
+        let order = self.order();
+        let mut time = self.time;
+        for &i in &order {
+            if time < self.frames[i].1 {
+                return i;
+            }
+            time -= self.frames[i].1;
+        }
+        order.last().copied().unwrap_or(0)
+    }
+
+    pub fn update(&mut self, scene: &mut UIScene, dt: f32) {
+        if self.playing {
+            self.time = self.wrap(self.time + dt * self.speed);
+            if self.is_finished() {
+                self.playing = false;
+            }
+        }
+        let (uv, _) = self.frames[self.frame()];
+        scene.elements[self.element].instance.params = uv;
+    }
+
+    fn wrap(&self, time: f32) -> f32 {
+        let duration = self.duration();
+        match self.playback {
+            Playback::Once => time.clamp(0.0, duration),
+            Playback::Loop | Playback::PingPong => time.rem_euclid(duration),
+        }
+    }
+
+    // Frames in the order one cycle shows them.
+    fn order(&self) -> Vec<usize> {
+        let n = self.frames.len();
+        match self.playback {
+            Playback::Loop | Playback::Once => (0..n).collect(),
+            Playback::PingPong => (0..n).chain((1..n.saturating_sub(1)).rev()).collect(),
+        }
+    }
+}
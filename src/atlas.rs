@@ -0,0 +1,299 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::rc::Rc;
+
+use anyhow::{bail, Context};
+use image::{DynamicImage, GenericImageView, RgbaImage};
+use serde::Deserialize;
+
+use crate::geometry_cache::GeometryCache;
+use crate::texture::Texture;
+use crate::ui_scene::{Player, Shape, UIMaterial};
+
+// Border around each packed image, filled with its edge pixels so linear
+// filtering doesn't pull in the neighbours.
+pub(crate) const PADDING: u32 = 1;
+
+// Where an image sits in the atlas, in pixels from the top left.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct AtlasRegion {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+// Many small images in one texture, looked up by name. Sprites made from the
+// same atlas draw with one bind group, and same-sized ones share geometry
+// through a `GeometryCache`, so hundreds of icons are a handful of
+// instanced draws:
+//
+//     let atlas = Atlas::pack(device, queue, icons, sampler)?;
+//     scene.set_atlas(device, &atlas);
+//     let save = atlas.sprite(&mut cache, device, "save", position).unwrap();
+//     scene.elements.push(save);
+//
+// A scene samples one atlas at a time.
+pub struct Atlas {
+    pub texture: Texture,
+    pub width: u32,
+    pub height: u32,
+    regions: HashMap<String, AtlasRegion>,
+}
+
+impl Atlas {
+    // Packs the images into rows, tallest first.
+    pub fn pack(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        mut images: Vec<(String, DynamicImage)>,
+        sampler: Rc<wgpu::Sampler>,
+    ) -> anyhow::Result<Self> {
+        images.sort_by_key(|(_, image)| std::cmp::Reverse(image.height()));
+        let padded =
+            |image: &DynamicImage| (image.width() + PADDING * 2, image.height() + PADDING * 2);
+        let area: u32 = images
+            .iter()
+            .map(|(_, image)| {
+                let (w, h) = padded(image);
+                w * h
+            })
+            .sum();
+        let widest = images.iter().map(|(_, image)| padded(image).0).max();
+        let width = ((area as f32).sqrt().ceil() as u32)
+            .max(widest.unwrap_or(1))
+            .next_power_of_two();
+
+        let mut regions = HashMap::new();
+        let (mut x, mut y, mut row) = (0, 0, 0);
+        for (name, image) in &images {
+            if image.width() == 0 || image.height() == 0 {
+                bail!("atlas image {} is empty", name);
+            }
+            let (w, h) = padded(image);
+            if x + w > width {
+                (x, y, row) = (0, y + row, 0);
+            }
+            let region = AtlasRegion {
+                x: x + PADDING,
+                y: y + PADDING,
+                width: image.width(),
+                height: image.height(),
+            };
+            if regions.insert(name.clone(), region).is_some() {
+                bail!("two atlas images are named {}", name);
+            }
+            x += w;
+            row = row.max(h);
+        }
+        let height = (y + row).max(1);
+        let limit = device.limits().max_texture_dimension_2d;
+        if width > limit || height > limit {
+            bail!(
+                "atlas would be {}x{}, the device allows up to {}",
+                width,
+                height,
+                limit
+            );
+        }
+
+        let mut pixels = RgbaImage::new(width, height);
+        for (name, image) in &images {
+            let region = regions[name];
+            let x = (region.x - PADDING) as i64;
+            let y = (region.y - PADDING) as i64;
+            image::imageops::replace(&mut pixels, &extrude(&image.to_rgba8()), x, y);
+        }
+        let image = DynamicImage::ImageRgba8(pixels);
+        let texture = Texture::from_image(device, queue, &image, Some("Atlas"), sampler)?;
+        Ok(Self {
+            texture,
+            width,
+            height,
+            regions,
+        })
+    }
+
+    // Reads a sprite sheet in the JSON hash or array format most packers
+    // write, e.g. TexturePacker's, with the image next to it. Trimmed
+    // sprites come out at their trimmed size.
+    pub fn load(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        path: &str,
+        sampler: Rc<wgpu::Sampler>,
+    ) -> anyhow::Result<Self> {
+        let json = std::fs::read_to_string(path)?;
+        let sheet: Sheet = serde_json::from_str(&json)?;
+        let image_path = Path::new(path)
+            .parent()
+            .unwrap_or(Path::new(""))
+            .join(&sheet.meta.image);
+        let image = image::open(&image_path)
+            .with_context(|| format!("reading {}", image_path.display()))?;
+        Self::from_sheet(device, queue, sheet, &image, sampler)
+    }
+
+    // Same as `load`, for sheets that don't come from files.
+    pub fn from_json(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        json: &str,
+        image: &DynamicImage,
+        sampler: Rc<wgpu::Sampler>,
+    ) -> anyhow::Result<Self> {
+        Self::from_sheet(device, queue, serde_json::from_str(json)?, image, sampler)
+    }
+
+    fn from_sheet(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        sheet: Sheet,
+        image: &DynamicImage,
+        sampler: Rc<wgpu::Sampler>,
+    ) -> anyhow::Result<Self> {
+        let frames = match sheet.frames {
+            SheetFrames::Hash(frames) => frames.into_iter().collect(),
+            SheetFrames::Array(frames) => frames
+                .into_iter()
+                .map(|frame| (frame.filename, frame.frame))
+                .collect::<Vec<_>>(),
+        };
+        let (width, height) = image.dimensions();
+        let mut regions = HashMap::new();
+        for (name, frame) in frames {
+            if frame.rotated {
+                bail!("sprite {} is rotated, which isn't supported", name);
+            }
+            let SheetRect { x, y, w, h } = frame.frame;
+            if x + w > width || y + h > height {
+                bail!(
+                    "sprite {} lies outside the {}x{} image",
+                    name,
+                    width,
+                    height
+                );
+            }
+            regions.insert(
+                name,
+                AtlasRegion {
+                    x,
+                    y,
+                    width: w,
+                    height: h,
+                },
+            );
+        }
+        let texture = Texture::from_image(device, queue, image, Some("Atlas"), sampler)?;
+        Ok(Self {
+            texture,
+            width,
+            height,
+            regions,
+        })
+    }
+
+    pub fn region(&self, name: &str) -> Option<AtlasRegion> {
+        self.regions.get(name).copied()
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.regions.keys().map(String::as_str)
+    }
+
+    // The region as u0, v0, u1, v1, what `UIMaterial::Sprite` reads from
+    // `params`.
+    pub fn uv_rect(&self, name: &str) -> Option<[f32; 4]> {
+        let region = self.region(name)?;
+        let (w, h) = (self.width as f32, self.height as f32);
+        Some([
+            region.x as f32 / w,
+            region.y as f32 / h,
+            (region.x + region.width) as f32 / w,
+            (region.y + region.height) as f32 / h,
+        ])
+    }
+
+    // An element showing the named image at its pixel size. The vertex
+    // color is white, use the tint to color it.
+    pub fn sprite(
+        &self,
+        cache: &mut GeometryCache,
+        device: &wgpu::Device,
+        name: &str,
+        position: cgmath::Vector2<f32>,
+    ) -> Option<Player> {
+        let region = self.region(name)?;
+        let shape = Shape::Rect {
+            width: region.width as f32,
+            height: region.height as f32,
+        };
+        let white = [1.0, 1.0, 1.0];
+        let mut element = cache.element(device, position, shape, white, white);
+        element.material = UIMaterial::Sprite;
+        element.instance.params = self.uv_rect(name)?;
+        Some(element)
+    }
+
+    // Shows another image of the atlas in a sprite, e.g. the next frame of
+    // an animation. The size stays the same. Returns false for unknown names.
+    pub fn set_sprite(&self, element: &mut Player, name: &str) -> bool {
+        match self.uv_rect(name) {
+            Some(uv) => {
+                element.instance.params = uv;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+// `image` with `PADDING` around it, edge pixels repeating into it.
+pub(crate) fn extrude(image: &RgbaImage) -> RgbaImage {
+    let (width, height) = image.dimensions();
+    RgbaImage::from_fn(width + PADDING * 2, height + PADDING * 2, |x, y| {
+        let sx = x.saturating_sub(PADDING).min(width - 1);
+        let sy = y.saturating_sub(PADDING).min(height - 1);
+        *image.get_pixel(sx, sy)
+    })
+}
+
+#[derive(Deserialize)]
+struct Sheet {
+    frames: SheetFrames,
+    meta: SheetMeta,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SheetFrames {
+    Hash(HashMap<String, SheetFrame>),
+    Array(Vec<NamedFrame>),
+}
+
+#[derive(Deserialize)]
+struct SheetFrame {
+    frame: SheetRect,
+    #[serde(default)]
+    rotated: bool,
+}
+
+#[derive(Deserialize)]
+struct NamedFrame {
+    filename: String,
+    #[serde(flatten)]
+    frame: SheetFrame,
+}
+
+#[derive(Deserialize)]
+struct SheetRect {
+    x: u32,
+    y: u32,
+    w: u32,
+    h: u32,
+}
+
+#[derive(Deserialize)]
+struct SheetMeta {
+    image: String,
+}
@@ -0,0 +1,135 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::ui_scene::{UIScene, PARAM_PROGRESS};
+
+struct Slot<T> {
+    value: T,
+    // Bumped on every change so bindings can tell whether they're stale
+    // without comparing values.
+    version: u64,
+}
+
+// A shared value owned by the app. Clones refer to the same value, so the
+// app keeps one handle and bindings keep others.
+pub struct Observable<T> {
+    slot: Rc<RefCell<Slot<T>>>,
+}
+
+impl<T> Clone for Observable<T> {
+    fn clone(&self) -> Self {
+        Self {
+            slot: self.slot.clone(),
+        }
+    }
+}
+
+impl<T: Clone + PartialEq> Observable<T> {
+    pub fn new(value: T) -> Self {
+        Self {
+            slot: Rc::new(RefCell::new(Slot { value, version: 0 })),
+        }
+    }
+
+    pub fn get(&self) -> T {
+        self.slot.borrow().value.clone()
+    }
+
+    // Setting the current value again doesn't count as a change.
+    pub fn set(&self, value: T) {
+        let mut slot = self.slot.borrow_mut();
+        if slot.value != value {
+            slot.value = value;
+            slot.version += 1;
+        }
+    }
+
+    pub fn update(&self, f: impl FnOnce(&T) -> T) {
+        let value = f(&self.slot.borrow().value);
+        self.set(value);
+    }
+
+    fn version(&self) -> u64 {
+        self.slot.borrow().version
+    }
+}
+
+trait Binding {
+    // Applies the value if it changed since the last sync.
+    fn sync(&mut self, scene: &mut UIScene) -> bool;
+}
+
+struct PropertyBinding<T, F> {
+    source: Observable<T>,
+    // None until the first sync, so the initial value gets applied too.
+    seen: Option<u64>,
+    apply: F,
+}
+
+impl<T: Clone + PartialEq, F: FnMut(&T, &mut UIScene)> Binding for PropertyBinding<T, F> {
+    fn sync(&mut self, scene: &mut UIScene) -> bool {
+        let version = self.source.version();
+        if self.seen == Some(version) {
+            return false;
+        }
+        self.seen = Some(version);
+        // Cloned so `apply` is free to touch the observable itself.
+        let value = self.source.get();
+        (self.apply)(&value, scene);
+        true
+    }
+}
+
+// Connects observables to scene properties. Call `sync` once per frame
+// before `UIScene::prepare`.
+#[derive(Default)]
+pub struct Bindings {
+    bindings: Vec<Box<dyn Binding>>,
+}
+
+impl Bindings {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn bind<T: Clone + PartialEq + 'static>(
+        &mut self,
+        source: &Observable<T>,
+        apply: impl FnMut(&T, &mut UIScene) + 'static,
+    ) {
+        self.bindings.push(Box::new(PropertyBinding {
+            source: source.clone(),
+            seen: None,
+            apply,
+        }));
+    }
+
+    pub fn bind_visible(&mut self, source: &Observable<bool>, element: usize) {
+        self.bind(source, move |visible, scene| {
+            scene.elements[element].visible = *visible;
+        });
+    }
+
+    pub fn bind_tint(&mut self, source: &Observable<[f32; 4]>, element: usize) {
+        self.bind(source, move |tint, scene| {
+            scene.elements[element].instance.tint = *tint;
+        });
+    }
+
+    // For elements using a fill material.
+    pub fn bind_progress(&mut self, source: &Observable<f32>, element: usize) {
+        self.bind(source, move |progress, scene| {
+            scene.elements[element].instance.params[PARAM_PROGRESS] = *progress;
+        });
+    }
+
+    // Returns whether anything changed, so hosts that only render on demand
+    // know a new frame is needed.
+    pub fn sync(&mut self, scene: &mut UIScene) -> bool {
+        let mut dirty = false;
+        for binding in &mut self.bindings {
+            dirty |= binding.sync(scene);
+        }
+        dirty
+    }
+}
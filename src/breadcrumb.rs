@@ -0,0 +1,310 @@
+use std::rc::Rc;
+
+use winit::event::{ElementState, KeyboardInput, MouseButton, VirtualKeyCode, WindowEvent};
+
+use cgmath::Rotation3;
+
+use crate::element_pool::ElementPool;
+use crate::layout_direction::LayoutDirection;
+use crate::menu_bar::chevron;
+use crate::text::{GlyphAtlas, Text, TextFont};
+use crate::text_layout::{TextLayout, VerticalAlign};
+use crate::ui_scene::{Player, Shape, UIScene};
+
+#[derive(Debug, Clone)]
+pub struct BreadcrumbStyle {
+    pub height: f32,
+    pub padding: f32,
+    pub font_size: f32,
+    // Room for the separator between two crumbs.
+    pub spacing: f32,
+    pub crumb: [f32; 3],
+    // The last crumb, where the path currently is.
+    pub current: [f32; 3],
+    pub separator: [f32; 3],
+    pub text: [f32; 4],
+}
+
+impl Default for BreadcrumbStyle {
+    fn default() -> Self {
+        Self {
+            height: 22.0,
+            padding: 8.0,
+            font_size: 13.0,
+            spacing: 16.0,
+            crumb: [0.2, 0.2, 0.23],
+            current: [0.25, 0.35, 0.55],
+            separator: [0.6, 0.6, 0.65],
+            text: [0.85, 0.85, 0.88, 1.0],
+        }
+    }
+}
+
+// The path to the current location, e.g. a folder or a nested scene, where
+// clicking a crumb goes back to it. Kept at a fixed screen position whatever
+// the camera does.
+//
+// Crumbs are ordinary focusable elements, so Tab reaches them. Left and
+// Right then move between crumbs and Enter or Space navigates.
+//
+// Right to left, the path runs leftwards from the top right corner and the
+// arrow keys swap:
+//
+//     let mut path = Breadcrumb::new(font, position, BreadcrumbStyle::default());
+//     path.set_path(&mut scene, device, queue, &mut glyphs, &["Project", "Levels"]);
+//     if let Some(crumb) = path.input(&mut scene, queue, &event) {
+//         open(&path.path()[..=crumb]);
+//     }
+//     path.update(&mut scene, device, &mut glyphs); // every frame
+pub struct Breadcrumb {
+    pub style: BreadcrumbStyle,
+    // None follows `UIScene::direction`.
+    pub direction: Option<LayoutDirection>,
+    // Top leading corner in screen pixels, relative to the viewport.
+    position: cgmath::Vector2<f32>,
+    labels: Vec<String>,
+    crumbs: Vec<usize>,
+    separators: Vec<usize>,
+    font: Rc<TextFont>,
+    // One per crumb there has been, the ones past `crumbs` hidden.
+    texts: Vec<Text>,
+    crumb_pool: ElementPool,
+    separator_pool: ElementPool,
+    // Leading edge and width of each crumb, in screen pixels.
+    spans: Vec<(f32, f32)>,
+}
+
+impl Breadcrumb {
+    pub fn new(font: Rc<TextFont>, position: cgmath::Vector2<f32>, style: BreadcrumbStyle) -> Self {
+        let (height, color) = (style.height, style.crumb);
+        let separator = style.separator;
+        Self {
+            style,
+            direction: None,
+            position,
+            labels: Vec::new(),
+            crumbs: Vec::new(),
+            separators: Vec::new(),
+            font,
+            texts: Vec::new(),
+            crumb_pool: ElementPool::new(move |device| {
+                let mut element = Player::with_gradient(
+                    device,
+                    cgmath::Vector2::new(0.0, 0.0),
+                    cgmath::Vector2::new(height, height),
+                    color,
+                    color,
+                );
+                element.draggable = false;
+                element
+            }),
+            separator_pool: ElementPool::new(move |device| chevron(device, separator)),
+            spans: Vec::new(),
+        }
+    }
+
+    pub fn path(&self) -> &[String] {
+        &self.labels
+    }
+
+    pub fn set_path(
+        &mut self,
+        scene: &mut UIScene,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        atlas: &mut GlyphAtlas,
+        labels: &[&str],
+    ) {
+        let labels = labels.iter().map(|label| label.to_string()).collect();
+        self.set_labels(scene, device, queue, atlas, labels);
+    }
+
+    pub fn push(
+        &mut self,
+        scene: &mut UIScene,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        atlas: &mut GlyphAtlas,
+        label: &str,
+    ) {
+        let mut labels = self.labels.clone();
+        labels.push(label.to_string());
+        self.set_labels(scene, device, queue, atlas, labels);
+    }
+
+    fn set_labels(
+        &mut self,
+        scene: &mut UIScene,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        atlas: &mut GlyphAtlas,
+        labels: Vec<String>,
+    ) {
+        self.truncate(scene, labels.len());
+        let origin = cgmath::Vector2::new(0.0, 0.0);
+        while self.crumbs.len() < labels.len() {
+            if !self.crumbs.is_empty() {
+                let separator = self.separator_pool.acquire(scene, device, origin);
+                self.separators.push(separator);
+            }
+            let crumb = self.crumb_pool.acquire(scene, device, origin);
+            self.crumbs.push(crumb);
+        }
+        while self.texts.len() < labels.len() {
+            let mut text = Text::new(self.font.clone(), self.style.font_size);
+            let layout = TextLayout {
+                height: Some(self.style.height),
+                wrap: false,
+                vertical_align: VerticalAlign::Middle,
+                ..TextLayout::default()
+            };
+            text.set_layout(scene, device, queue, atlas, layout);
+            text.set_color(scene, self.style.text);
+            self.texts.push(text);
+        }
+        for (text, label) in self.texts.iter_mut().zip(&labels) {
+            if text.text() != label {
+                text.set_text(scene, device, queue, atlas, label);
+            }
+        }
+        self.labels = labels;
+        self.layout(scene, queue);
+    }
+
+    // Returns the crumb navigated to, after the path was cut back to it.
+    // Call before `UIScene::input`, which still needs the event.
+    pub fn input(
+        &mut self,
+        scene: &mut UIScene,
+        queue: &wgpu::Queue,
+        event: &WindowEvent,
+    ) -> Option<usize> {
+        match event {
+            WindowEvent::MouseInput {
+                state: ElementState::Pressed,
+                button: MouseButton::Left,
+                ..
+            } => {
+                let crumb = scene.hovered().and_then(|hovered| self.index_of(hovered))?;
+                self.navigate(scene, queue, crumb)
+            }
+            WindowEvent::KeyboardInput {
+                input:
+                    KeyboardInput {
+                        state: ElementState::Pressed,
+                        virtual_keycode: Some(key),
+                        ..
+                    },
+                ..
+            } => {
+                let crumb = scene.focused.and_then(|focused| self.index_of(focused))?;
+                let direction = self.direction.unwrap_or(scene.direction);
+                match *key {
+                    key if key == direction.backward_key() && crumb > 0 => {
+                        scene.set_focus(Some(self.crumbs[crumb - 1]));
+                        None
+                    }
+                    key if key == direction.forward_key() && crumb + 1 < self.crumbs.len() => {
+                        scene.set_focus(Some(self.crumbs[crumb + 1]));
+                        None
+                    }
+                    VirtualKeyCode::Return | VirtualKeyCode::Space => {
+                        self.navigate(scene, queue, crumb)
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    // Call before `UIScene::prepare` so the crumbs follow the camera.
+    pub fn update(&mut self, scene: &mut UIScene, device: &wgpu::Device, atlas: &mut GlyphAtlas) {
+        let scale = 1.0 / scene.camera.scale;
+        let direction = self.direction.unwrap_or(scene.direction);
+        let y = self.position.y + self.style.height / 2.0;
+        let mut place = |index: usize, x: f32| {
+            let x = direction.place(self.position.x, 0.0, x);
+            let world = scene.camera.screen_to_world(x, y);
+            let instance = &mut scene.elements[index].instance;
+            instance.position.x = world.x;
+            instance.position.y = world.y;
+            instance.scale = scale;
+        };
+
+        for (&crumb, &(left, width)) in self.crumbs.iter().zip(&self.spans) {
+            place(crumb, left + width / 2.0);
+        }
+        for (&separator, &(left, _)) in self.separators.iter().zip(self.spans.iter().skip(1)) {
+            place(separator, left - self.style.spacing / 2.0);
+        }
+        for &separator in &self.separators {
+            scene.elements[separator].instance.rotation =
+                cgmath::Quaternion::from_angle_z(direction.flow_angle());
+        }
+
+        for (i, text) in self.texts.iter_mut().enumerate() {
+            let shown = i < self.crumbs.len();
+            if let Some(&(left, width)) = self.spans.get(i).filter(|_| shown) {
+                // Labels start at the left of their crumb either way the path runs.
+                let x = direction.place(self.position.x, 0.0, left + width / 2.0)
+                    - (width / 2.0 - self.style.padding);
+                let top_left = scene.camera.screen_to_world(x, self.position.y);
+                text.set_scale(scene, scale);
+                text.set_position(scene, top_left);
+            }
+            text.set_visible(scene, shown);
+            text.update(scene, device, atlas);
+        }
+    }
+
+    fn navigate(
+        &mut self,
+        scene: &mut UIScene,
+        queue: &wgpu::Queue,
+        crumb: usize,
+    ) -> Option<usize> {
+        self.truncate(scene, crumb + 1);
+        self.labels.truncate(crumb + 1);
+        self.layout(scene, queue);
+        Some(crumb)
+    }
+
+    // The crumb `element` is, or a glyph of its label.
+    fn index_of(&self, element: usize) -> Option<usize> {
+        (0..self.crumbs.len())
+            .position(|i| self.crumbs[i] == element || self.texts[i].elements().contains(&element))
+    }
+
+    fn truncate(&mut self, scene: &mut UIScene, len: usize) {
+        while self.crumbs.len() > len {
+            if let Some(crumb) = self.crumbs.pop() {
+                self.crumb_pool.release(scene, crumb);
+            }
+            if let Some(separator) = self.separators.pop() {
+                self.separator_pool.release(scene, separator);
+            }
+        }
+    }
+
+    // Resizes the crumbs to their labels, the last one in the current color.
+    fn layout(&mut self, scene: &mut UIScene, queue: &wgpu::Queue) {
+        self.spans.clear();
+        let mut x = 0.0;
+        for (i, (&crumb, label)) in self.crumbs.iter().zip(&self.labels).enumerate() {
+            let width = self.font.advance(self.style.font_size, label) + self.style.padding * 2.0;
+            let shape = Shape::Rect {
+                width,
+                height: self.style.height,
+            };
+            let color = if i + 1 == self.crumbs.len() {
+                self.style.current
+            } else {
+                self.style.crumb
+            };
+            scene.elements[crumb].reshape(queue, shape, color, color);
+            self.spans.push((x, width));
+            x += width + self.style.spacing;
+        }
+    }
+}
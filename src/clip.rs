@@ -0,0 +1,79 @@
+use crate::tween::Easing;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Keyframe {
+    pub time: f32,
+    pub value: f32,
+    // Easing of the segment from this key to the next one.
+    pub easing: Easing,
+}
+
+// Keys stay in insertion order rather than time order, so their indices
+// remain valid while an editor drags them past each other.
+#[derive(Debug, Clone, Default)]
+pub struct Track {
+    pub name: String,
+    pub keys: Vec<Keyframe>,
+}
+
+impl Track {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            keys: Vec::new(),
+        }
+    }
+
+    pub fn insert(&mut self, key: Keyframe) -> usize {
+        self.keys.push(key);
+        self.keys.len() - 1
+    }
+
+    // Holds the first and last values outside the keyed range, None for a
+    // track without keys.
+    pub fn sample(&self, time: f32) -> Option<f32> {
+        let previous = self
+            .keys
+            .iter()
+            .filter(|key| key.time <= time)
+            .max_by(|a, b| a.time.total_cmp(&b.time));
+        let next = self
+            .keys
+            .iter()
+            .filter(|key| key.time > time)
+            .min_by(|a, b| a.time.total_cmp(&b.time));
+        match (previous, next) {
+            (Some(from), Some(to)) => {
+                let t = (time - from.time) / (to.time - from.time);
+                Some(from.value + (to.value - from.value) * from.easing.apply(t))
+            }
+            (Some(key), None) | (None, Some(key)) => Some(key.value),
+            (None, None) => None,
+        }
+    }
+}
+
+// A set of keyframed tracks, e.g. one per animated property.
+#[derive(Debug, Clone, Default)]
+pub struct Clip {
+    pub duration: f32,
+    pub tracks: Vec<Track>,
+}
+
+impl Clip {
+    pub fn new(duration: f32) -> Self {
+        Self {
+            duration,
+            tracks: Vec::new(),
+        }
+    }
+
+    pub fn add_track(&mut self, name: impl Into<String>) -> usize {
+        self.tracks.push(Track::new(name));
+        self.tracks.len() - 1
+    }
+
+    pub fn sample(&self, track: usize, time: f32) -> Option<f32> {
+        self.tracks.get(track)?.sample(time)
+    }
+}
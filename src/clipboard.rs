@@ -0,0 +1,36 @@
+// Where text fields cut and copy to and paste from, see
+// `UIScene::clipboard`. Apps wanting the system's clipboard implement it
+// over their platform's, e.g. with the arboard crate:
+//
+//     struct SystemClipboard(arboard::Clipboard);
+//
+//     impl Clipboard for SystemClipboard {
+//         fn get(&mut self) -> Option<String> {
+//             self.0.get_text().ok()
+//         }
+//         fn set(&mut self, text: &str) {
+//             let _ = self.0.set_text(text);
+//         }
+//     }
+//
+//     scene.clipboard = Box::new(SystemClipboard(arboard::Clipboard::new()?));
+pub trait Clipboard {
+    fn get(&mut self) -> Option<String>;
+    fn set(&mut self, text: &str);
+}
+
+// Only shared by the app's own fields, and gone when it quits.
+#[derive(Debug, Default)]
+pub struct LocalClipboard {
+    text: Option<String>,
+}
+
+impl Clipboard for LocalClipboard {
+    fn get(&mut self) -> Option<String> {
+        self.text.clone()
+    }
+
+    fn set(&mut self, text: &str) {
+        self.text = Some(text.to_string());
+    }
+}
@@ -0,0 +1,85 @@
+// Vertex colors and tints are linear, which is what blending and the sRGB
+// encoding on write expect. Colors picked in an editor or written as hex are
+// sRGB encoded, so go through `Color` to get the same color on screen:
+//
+//     let accent = Color::hex("#3d7be0").unwrap();
+//     Player::with_shape_rgba(device, position, shape, accent.into(), accent.into());
+//
+// Using sRGB values directly makes them come out lighter than authored.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Color {
+    // Linear, straight alpha.
+    rgba: [f32; 4],
+}
+
+impl Color {
+    pub const BLACK: Color = Color::linear(0.0, 0.0, 0.0);
+    pub const WHITE: Color = Color::linear(1.0, 1.0, 1.0);
+    pub const TRANSPARENT: Color = Color::linear(0.0, 0.0, 0.0).with_alpha(0.0);
+
+    pub const fn linear(r: f32, g: f32, b: f32) -> Self {
+        Self {
+            rgba: [r, g, b, 1.0],
+        }
+    }
+
+    pub fn srgb(r: f32, g: f32, b: f32) -> Self {
+        Self::linear(srgb_to_linear(r), srgb_to_linear(g), srgb_to_linear(b))
+    }
+
+    pub fn srgb8(r: u8, g: u8, b: u8) -> Self {
+        Self::srgb(r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0)
+    }
+
+    // "#rrggbb" or "#rrggbbaa", the '#' being optional. Alpha isn't encoded.
+    pub fn hex(hex: &str) -> Option<Self> {
+        let hex = hex.strip_prefix('#').unwrap_or(hex);
+        if !matches!(hex.len(), 6 | 8) || !hex.is_ascii() {
+            return None;
+        }
+        let byte = |i: usize| u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).ok();
+        let color = Self::srgb8(byte(0)?, byte(1)?, byte(2)?);
+        Some(match hex.len() {
+            8 => color.with_alpha(byte(3)? as f32 / 255.0),
+            _ => color,
+        })
+    }
+
+    pub const fn with_alpha(self, alpha: f32) -> Self {
+        let [r, g, b, _] = self.rgba;
+        Self {
+            rgba: [r, g, b, alpha],
+        }
+    }
+
+    pub fn to_linear(self) -> [f32; 4] {
+        self.rgba
+    }
+
+    pub fn to_srgb(self) -> [f32; 4] {
+        let [r, g, b, a] = self.rgba;
+        [linear_to_srgb(r), linear_to_srgb(g), linear_to_srgb(b), a]
+    }
+}
+
+impl From<Color> for [f32; 4] {
+    fn from(color: Color) -> Self {
+        color.to_linear()
+    }
+}
+
+pub fn srgb_to_linear(c: f32) -> f32 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+pub fn linear_to_srgb(c: f32) -> f32 {
+    if c <= 0.0031308 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
@@ -0,0 +1,177 @@
+use std::collections::HashSet;
+use std::fmt::Write as _;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use crate::frame_context::FrameContext;
+use crate::gpu_errors;
+use crate::renderer::Renderer;
+
+// State of the last frame, kept up to date by `Renderer::update` so the panic
+// hook has something to report without touching the renderer mid-panic.
+static LAST_FRAME: Mutex<Option<FrameReport>> = Mutex::new(None);
+
+#[derive(Debug, Clone, Default)]
+pub struct FrameReport {
+    pub frame: u64,
+    pub dt: f32,
+    pub surface: String,
+    pub elements: usize,
+    pub visible: usize,
+    pub pending_deletions: usize,
+    pub pipelines: Vec<String>,
+    // What holds GPU memory and roughly how many bytes, targets estimated
+    // from their size and format.
+    pub allocations: Vec<(String, u64)>,
+}
+
+impl FrameReport {
+    pub fn new(renderer: &Renderer, frame: &FrameContext) -> Self {
+        let scene = &renderer.ui_scene;
+        let config = &renderer.scene_config;
+
+        let mut pipelines = vec!["model".to_string()];
+        if let Some(tonemap) = &renderer.tonemap {
+            pipelines.push(format!("tonemap ({:?})", tonemap.tonemap));
+        }
+        for resources in renderer.gpu.cached_ui_resources() {
+            pipelines.push(format!(
+                "UI {:?}, {} samples{}: {} pipelines",
+                resources.format,
+                resources.samples,
+                if resources.depth { ", depth" } else { "" },
+                resources.pipeline_count(),
+            ));
+        }
+
+        // Elements made through a `GeometryCache` share their geometry,
+        // which is only counted once.
+        let mut seen = HashSet::new();
+        let mut geometry = 0;
+        let mut instances = 0;
+        for element in &scene.elements {
+            if seen.insert(Rc::as_ptr(&element.vertex_buffer)) {
+                geometry += element.vertex_buffer.size() + element.index_buffer.size();
+            }
+            instances += element
+                .instance_buffers
+                .iter()
+                .map(|buffer| buffer.size())
+                .sum::<u64>();
+        }
+        let texture = |texture: &wgpu::Texture| {
+            let size = texture.size();
+            let block = texture.format().block_size(None).unwrap_or(4) as u64;
+            size.width as u64 * size.height as u64 * block * texture.sample_count() as u64
+        };
+        let target = |format: wgpu::TextureFormat, samples: u32| {
+            let block = format.block_size(None).unwrap_or(4) as u64;
+            config.width as u64 * config.height as u64 * block * samples as u64
+        };
+        let mut allocations = vec![
+            (format!("UI geometry ({} distinct)", seen.len()), geometry),
+            ("UI instances".to_string(), instances),
+            (
+                "model depth".to_string(),
+                texture(&renderer.model_scene.depth_texture.texture),
+            ),
+        ];
+        if let Some(tonemap) = &renderer.tonemap {
+            allocations.push(("HDR target".to_string(), texture(&tonemap.target.texture)));
+        }
+        let samples = scene.resources.samples;
+        if samples > 1 {
+            allocations.push((
+                "UI MSAA targets".to_string(),
+                target(config.format, samples) + target(config.format, 1),
+            ));
+        }
+        if scene.resources.depth {
+            allocations.push((
+                "UI depth".to_string(),
+                target(crate::texture::Texture::DEPTH_FORMAT, samples),
+            ));
+        }
+
+        Self {
+            frame: frame.number,
+            dt: frame.dt,
+            surface: format!(
+                "{}x{} {:?}, scene {:?}",
+                renderer.config.width,
+                renderer.config.height,
+                renderer.config.format,
+                config.format
+            ),
+            elements: scene.elements.len(),
+            visible: scene.elements.iter().filter(|e| e.visible).count(),
+            pending_deletions: renderer.gpu.deletion_queue.len(),
+            pipelines,
+            allocations,
+        }
+    }
+
+    fn write(&self, out: &mut String) {
+        let _ = writeln!(out, "Frame {} ({:.2} ms)", self.frame, self.dt * 1000.0);
+        let _ = writeln!(out, "Surface {}", self.surface);
+        let _ = writeln!(
+            out,
+            "{} UI elements, {} visible, {} resources awaiting deletion",
+            self.elements, self.visible, self.pending_deletions
+        );
+        let _ = writeln!(out, "\nPipelines:");
+        for pipeline in &self.pipelines {
+            let _ = writeln!(out, "  {}", pipeline);
+        }
+        let _ = writeln!(out, "\nAllocations:");
+        for (name, bytes) in &self.allocations {
+            let _ = writeln!(out, "  {:<32} {:>10.1} KiB", name, *bytes as f64 / 1024.0);
+        }
+        let total = self.allocations.iter().map(|(_, bytes)| bytes).sum::<u64>();
+        let _ = writeln!(out, "  {:<32} {:>10.1} KiB", "total", total as f64 / 1024.0);
+    }
+}
+
+pub fn record(report: FrameReport) {
+    if let Ok(mut last) = LAST_FRAME.lock() {
+        *last = Some(report);
+    }
+}
+
+// Writes what the GPU side looked like on the last frame to `path` when the
+// app panics, then lets the previous hook print the panic as usual. Meant to
+// be attached to bug reports about device loss and running out of memory:
+//
+//     crash_report::install("wgpie-crash.txt");
+pub fn install(path: impl Into<PathBuf>) {
+    let path = path.into();
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let mut out = String::new();
+        let _ = writeln!(out, "{}", info);
+        if let Ok(time) = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH) {
+            let _ = writeln!(out, "At {} s since the epoch", time.as_secs());
+        }
+        let _ = writeln!(out, "While {}\n", gpu_errors::current_operations());
+        // A panic while recording leaves the lock poisoned, the report
+        // inside is still the last complete one.
+        let last = LAST_FRAME
+            .try_lock()
+            .map(|last| last.clone())
+            .unwrap_or_else(|e| match e {
+                std::sync::TryLockError::Poisoned(e) => e.into_inner().clone(),
+                std::sync::TryLockError::WouldBlock => None,
+            });
+        match last {
+            Some(report) => report.write(&mut out),
+            None => out.push_str("No frame recorded yet\n"),
+        }
+        match std::fs::write(&path, out) {
+            Ok(()) => eprintln!("Crash report written to {}", path.display()),
+            Err(e) => eprintln!("Writing crash report to {} failed: {}", path.display(), e),
+        }
+        previous(info);
+    }));
+}
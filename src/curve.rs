@@ -0,0 +1,184 @@
+use crate::tween::{Easing, EASING_SAMPLES};
+
+// Bisection steps when solving a segment for x, plenty for f32.
+const SOLVE_ITERATIONS: u32 = 24;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Handle {
+    In,
+    Out,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct CurvePoint {
+    pub position: cgmath::Vector2<f32>,
+    // Bezier control points, relative to `position`.
+    pub in_handle: cgmath::Vector2<f32>,
+    pub out_handle: cgmath::Vector2<f32>,
+}
+
+impl CurvePoint {
+    // A point with flat handles reaching `reach` along x on both sides.
+    pub fn new(x: f32, y: f32, reach: f32) -> Self {
+        Self {
+            position: cgmath::Vector2::new(x, y),
+            in_handle: cgmath::Vector2::new(-reach, 0.0),
+            out_handle: cgmath::Vector2::new(reach, 0.0),
+        }
+    }
+}
+
+// A function of x made of cubic bezier segments between points sorted by x.
+// Handles are kept from reaching past the neighboring points, so a segment
+// never leaves its own x range.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Curve {
+    points: Vec<CurvePoint>,
+}
+
+impl Curve {
+    pub fn new(mut points: Vec<CurvePoint>) -> Self {
+        assert!(!points.is_empty(), "a curve needs at least one point");
+        points.sort_by(|a, b| a.position.x.total_cmp(&b.position.x));
+        let mut curve = Self { points };
+        for i in 0..curve.points.len() {
+            curve.constrain(i);
+        }
+        curve
+    }
+
+    pub fn linear() -> Self {
+        Self::new(vec![
+            CurvePoint {
+                position: cgmath::Vector2::new(0.0, 0.0),
+                in_handle: cgmath::Vector2::new(0.0, 0.0),
+                out_handle: cgmath::Vector2::new(1.0 / 3.0, 1.0 / 3.0),
+            },
+            CurvePoint {
+                position: cgmath::Vector2::new(1.0, 1.0),
+                in_handle: cgmath::Vector2::new(-1.0 / 3.0, -1.0 / 3.0),
+                out_handle: cgmath::Vector2::new(0.0, 0.0),
+            },
+        ])
+    }
+
+    pub fn ease_in_out() -> Self {
+        Self::new(vec![
+            CurvePoint::new(0.0, 0.0, 0.42),
+            CurvePoint::new(1.0, 1.0, 0.42),
+        ])
+    }
+
+    pub fn points(&self) -> &[CurvePoint] {
+        &self.points
+    }
+
+    pub fn insert(&mut self, point: CurvePoint) -> usize {
+        let index = self
+            .points
+            .partition_point(|p| p.position.x <= point.position.x);
+        self.points.insert(index, point);
+        for i in index.saturating_sub(1)..(index + 2).min(self.points.len()) {
+            self.constrain(i);
+        }
+        index
+    }
+
+    // The last point can't be removed.
+    pub fn remove(&mut self, index: usize) -> Option<CurvePoint> {
+        if self.points.len() <= 1 {
+            return None;
+        }
+        let point = self.points.remove(index);
+        for i in index.saturating_sub(1)..(index + 1).min(self.points.len()) {
+            self.constrain(i);
+        }
+        Some(point)
+    }
+
+    // x is clamped between the neighboring points so the order never changes.
+    pub fn set_position(&mut self, index: usize, position: cgmath::Vector2<f32>) {
+        let min = index
+            .checked_sub(1)
+            .map_or(f32::NEG_INFINITY, |i| self.points[i].position.x);
+        let max = self
+            .points
+            .get(index + 1)
+            .map_or(f32::INFINITY, |p| p.position.x);
+        self.points[index].position = cgmath::Vector2::new(position.x.clamp(min, max), position.y);
+        for i in index.saturating_sub(1)..(index + 2).min(self.points.len()) {
+            self.constrain(i);
+        }
+    }
+
+    pub fn set_handle(&mut self, index: usize, handle: Handle, offset: cgmath::Vector2<f32>) {
+        let point = &mut self.points[index];
+        match handle {
+            Handle::In => point.in_handle = offset,
+            Handle::Out => point.out_handle = offset,
+        }
+        self.constrain(index);
+    }
+
+    pub fn evaluate(&self, x: f32) -> f32 {
+        let first = &self.points[0];
+        let last = &self.points[self.points.len() - 1];
+        if x <= first.position.x {
+            return first.position.y;
+        }
+        if x >= last.position.x {
+            return last.position.y;
+        }
+
+        let i = self.points.partition_point(|p| p.position.x <= x) - 1;
+        let [p0, p1, p2, p3] = self.segment(i);
+        let bezier = |s: f32, a: f32, b: f32, c: f32, d: f32| {
+            let r = 1.0 - s;
+            r * r * r * a + 3.0 * r * r * s * b + 3.0 * r * s * s * c + s * s * s * d
+        };
+        // The control points stay within the segment's x range, so x(s)
+        // does too and bisection converges on a solution.
+        let (mut low, mut high) = (0.0, 1.0);
+        for _ in 0..SOLVE_ITERATIONS {
+            let mid = (low + high) / 2.0;
+            if bezier(mid, p0.x, p1.x, p2.x, p3.x) < x {
+                low = mid;
+            } else {
+                high = mid;
+            }
+        }
+        bezier((low + high) / 2.0, p0.y, p1.y, p2.y, p3.y)
+    }
+
+    // The curve over 0..1 as an easing, e.g. for a `Tween`.
+    pub fn to_easing(&self) -> Easing {
+        let mut samples = [0.0; EASING_SAMPLES];
+        for (i, sample) in samples.iter_mut().enumerate() {
+            *sample = self.evaluate(i as f32 / (EASING_SAMPLES - 1) as f32);
+        }
+        Easing::Sampled(samples)
+    }
+
+    // Control points of the segment from point `i` to point `i + 1`.
+    pub fn segment(&self, i: usize) -> [cgmath::Vector2<f32>; 4] {
+        let (from, to) = (&self.points[i], &self.points[i + 1]);
+        [
+            from.position,
+            from.position + from.out_handle,
+            to.position + to.in_handle,
+            to.position,
+        ]
+    }
+
+    fn constrain(&mut self, index: usize) {
+        let x = self.points[index].position.x;
+        let previous = index.checked_sub(1).map(|i| self.points[i].position.x);
+        let next = self.points.get(index + 1).map(|p| p.position.x);
+        let point = &mut self.points[index];
+        point.in_handle.x = point
+            .in_handle
+            .x
+            .clamp(previous.map_or(0.0, |p| p - x), 0.0);
+        point.out_handle.x = point.out_handle.x.clamp(0.0, next.map_or(0.0, |n| n - x));
+    }
+}
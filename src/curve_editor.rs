@@ -0,0 +1,431 @@
+use std::time::{Duration, Instant};
+
+use cgmath::InnerSpace;
+use lyon::math::point;
+use lyon::path::Path;
+use winit::event::{ElementState, MouseButton, WindowEvent};
+
+use crate::curve::{Curve, CurvePoint, Handle};
+use crate::deletion_queue::DeletionQueue;
+use crate::element_pool::ElementPool;
+use crate::gauge::{stroke, stroke_geometry};
+use crate::ui_scene::{Player, Shape, UIScene};
+
+const DOUBLE_CLICK: Duration = Duration::from_millis(400);
+const POINT_RADIUS: f32 = 5.0;
+const HANDLE_RADIUS: f32 = 3.5;
+const HANDLE_LINE_WIDTH: f32 = 1.0;
+const GRID_LINE_WIDTH: f32 = 1.0;
+// Handle reach of points added by double clicking, in curve units.
+const NEW_POINT_REACH: f32 = 0.1;
+
+#[derive(Debug, Clone)]
+pub struct CurveEditorStyle {
+    pub size: cgmath::Vector2<f32>,
+    // Curve x always spans 0..1, this is the y range shown.
+    pub range: (f32, f32),
+    // Grid divisions per axis, 0 for no grid.
+    pub grid: u32,
+    // Snap dragged and added points to the grid.
+    pub snap: bool,
+    pub background: [f32; 3],
+    pub grid_color: [f32; 3],
+    pub curve: [f32; 3],
+    pub curve_width: f32,
+    pub point: [f32; 3],
+    pub selected: [f32; 3],
+    pub handle: [f32; 3],
+}
+
+impl Default for CurveEditorStyle {
+    fn default() -> Self {
+        Self {
+            size: cgmath::Vector2::new(240.0, 240.0),
+            range: (0.0, 1.0),
+            grid: 8,
+            snap: false,
+            background: [0.1, 0.1, 0.11],
+            grid_color: [0.18, 0.18, 0.2],
+            curve: [0.4, 0.75, 1.0],
+            curve_width: 2.0,
+            point: [0.9, 0.9, 0.9],
+            selected: [1.0, 0.7, 0.2],
+            handle: [0.6, 0.6, 0.65],
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Grip {
+    Point(usize),
+    Handle(usize, Handle),
+}
+
+// Edits a `Curve` with bezier handles. Dragging moves points and handles,
+// double clicking empty space adds a point and double clicking a point
+// removes it. The handles of the selected point move together so the curve
+// stays smooth through it. The end points only move vertically, so the curve
+// keeps spanning 0..1 and works as an easing.
+//
+// Feed window events to `input` before `UIScene::input` and skip the scene
+// for events it handled, then call `update` before `UIScene::prepare`.
+pub struct CurveEditor {
+    pub style: CurveEditorStyle,
+    curve: Curve,
+    position: cgmath::Vector2<f32>,
+    cursor: cgmath::Vector2<f32>,
+    selected: Option<usize>,
+    drag: Option<Grip>,
+    last_press: Option<(Instant, Option<Grip>)>,
+    // The drawing is out of date.
+    dirty: bool,
+    // The curve was edited since the last `update`.
+    changed: bool,
+    curve_element: usize,
+    handles_element: usize,
+    point_markers: ElementPool,
+    handle_markers: ElementPool,
+    live: Vec<(Grip, usize)>,
+}
+
+impl CurveEditor {
+    pub fn new(
+        scene: &mut UIScene,
+        device: &wgpu::Device,
+        position: cgmath::Vector2<f32>,
+        style: CurveEditorStyle,
+        curve: Curve,
+    ) -> Self {
+        assert!(curve.points().len() >= 2, "edited curves need two points");
+        let size = style.size;
+        let mut push = |mut element: Player| {
+            element.draggable = false;
+            scene.elements.push(element);
+            scene.elements.len() - 1
+        };
+
+        let background = style.background;
+        push(Player::with_gradient(
+            device, position, size, background, background,
+        ));
+        for i in 1..style.grid {
+            let t = i as f32 / style.grid as f32 - 0.5;
+            let color = style.grid_color;
+            push(Player::with_gradient(
+                device,
+                position + cgmath::Vector2::new(t * size.x, 0.0),
+                cgmath::Vector2::new(GRID_LINE_WIDTH, size.y),
+                color,
+                color,
+            ));
+            push(Player::with_gradient(
+                device,
+                position + cgmath::Vector2::new(0.0, t * size.y),
+                cgmath::Vector2::new(size.x, GRID_LINE_WIDTH),
+                color,
+                color,
+            ));
+        }
+
+        // Placeholder geometry, replaced on the first `update`.
+        let mut builder = Path::builder();
+        builder.begin(point(0.0, 0.0));
+        builder.line_to(point(1.0, 0.0));
+        builder.end(false);
+        let placeholder = builder.build();
+        let extent = size.x.max(size.y);
+        let curve_element = push(stroke(
+            device,
+            position,
+            &placeholder,
+            style.curve_width,
+            extent,
+            style.curve,
+        ));
+        let handles_element = push(stroke(
+            device,
+            position,
+            &placeholder,
+            HANDLE_LINE_WIDTH,
+            extent,
+            style.handle,
+        ));
+
+        let marker = |radius: f32| {
+            move |device: &wgpu::Device| {
+                let white = [1.0, 1.0, 1.0];
+                let shape = Shape::Circle { radius };
+                let origin = cgmath::Vector2::new(0.0, 0.0);
+                let mut element = Player::with_shape(device, origin, shape, white, white);
+                element.draggable = false;
+                element
+            }
+        };
+
+        Self {
+            style,
+            curve,
+            position,
+            cursor: position,
+            selected: None,
+            drag: None,
+            last_press: None,
+            dirty: true,
+            changed: false,
+            curve_element,
+            handles_element,
+            point_markers: ElementPool::new(marker(POINT_RADIUS)),
+            handle_markers: ElementPool::new(marker(HANDLE_RADIUS)),
+            live: Vec::new(),
+        }
+    }
+
+    pub fn curve(&self) -> &Curve {
+        &self.curve
+    }
+
+    pub fn set_curve(&mut self, curve: Curve) {
+        assert!(curve.points().len() >= 2, "edited curves need two points");
+        self.curve = curve;
+        self.selected = None;
+        self.drag = None;
+        self.dirty = true;
+    }
+
+    // Returns whether the editor used the event.
+    pub fn input(&mut self, scene: &UIScene, event: &WindowEvent) -> bool {
+        match event {
+            WindowEvent::CursorMoved { position, .. } => {
+                self.cursor = scene.camera.screen_to_world(
+                    position.x as f32 - scene.viewport.x,
+                    position.y as f32 - scene.viewport.y,
+                );
+                let Some(grip) = self.drag else {
+                    return false;
+                };
+                let target = self.snap(self.to_curve(self.cursor));
+                match grip {
+                    Grip::Point(i) => {
+                        let last = self.curve.points().len() - 1;
+                        let x = if i == 0 || i == last {
+                            self.curve.points()[i].position.x
+                        } else {
+                            target.x
+                        };
+                        self.curve
+                            .set_position(i, cgmath::Vector2::new(x, target.y));
+                    }
+                    Grip::Handle(i, handle) => self.move_handle(i, handle, target),
+                }
+                self.dirty = true;
+                self.changed = true;
+                true
+            }
+            WindowEvent::MouseInput {
+                state: ElementState::Pressed,
+                button: MouseButton::Left,
+                ..
+            } => {
+                if !self.contains(self.cursor) {
+                    return false;
+                }
+                let grip = self.grip_at(scene, self.cursor);
+                let now = Instant::now();
+                let double = self.last_press.is_some_and(|(last, last_grip)| {
+                    now - last < DOUBLE_CLICK && last_grip == grip
+                });
+                self.last_press = if double { None } else { Some((now, grip)) };
+
+                match (grip, double) {
+                    (Some(Grip::Point(i)), true) => {
+                        let last = self.curve.points().len() - 1;
+                        if i != 0 && i != last {
+                            self.curve.remove(i);
+                            self.selected = None;
+                            self.changed = true;
+                        }
+                    }
+                    (None, true) => {
+                        let p = self.snap(self.to_curve(self.cursor));
+                        let point = CurvePoint::new(p.x, p.y, NEW_POINT_REACH);
+                        self.selected = Some(self.curve.insert(point));
+                        self.changed = true;
+                    }
+                    (Some(grip), _) => {
+                        if let Grip::Point(i) = grip {
+                            self.selected = Some(i);
+                        }
+                        self.drag = Some(grip);
+                    }
+                    (None, false) => self.selected = None,
+                }
+                self.dirty = true;
+                true
+            }
+            WindowEvent::MouseInput {
+                state: ElementState::Released,
+                button: MouseButton::Left,
+                ..
+            } => self.drag.take().is_some(),
+            _ => false,
+        }
+    }
+
+    // Returns whether the curve changed since the last call, e.g. to refresh
+    // an easing made with `Curve::to_easing`.
+    pub fn update(
+        &mut self,
+        scene: &mut UIScene,
+        device: &wgpu::Device,
+        deletion_queue: &mut DeletionQueue,
+    ) -> bool {
+        if self.dirty {
+            self.dirty = false;
+            self.redraw(scene, device, deletion_queue);
+        }
+        std::mem::take(&mut self.changed)
+    }
+
+    fn redraw(
+        &mut self,
+        scene: &mut UIScene,
+        device: &wgpu::Device,
+        deletion_queue: &mut DeletionQueue,
+    ) {
+        let extent = self.style.size.x.max(self.style.size.y);
+        let to_point = |p: cgmath::Vector2<f32>| point(p.x, p.y);
+
+        let points = self.curve.points();
+        let mut builder = Path::builder();
+        builder.begin(to_point(self.to_local(points[0].position)));
+        for i in 0..points.len() - 1 {
+            let [_, from, to, end] = self.curve.segment(i).map(|p| to_point(self.to_local(p)));
+            builder.cubic_bezier_to(from, to, end);
+        }
+        builder.end(false);
+        let geometry = stroke_geometry(
+            &builder.build(),
+            self.style.curve_width,
+            extent,
+            self.style.curve,
+        );
+        scene.elements[self.curve_element].set_geometry(device, deletion_queue, &geometry);
+
+        for (grip, element) in self.live.drain(..) {
+            match grip {
+                Grip::Point(_) => self.point_markers.release(scene, element),
+                Grip::Handle(..) => self.handle_markers.release(scene, element),
+            }
+        }
+
+        let handles = self.selected.map_or(Vec::new(), |i| {
+            let point = &points[i];
+            [
+                (Handle::In, point.in_handle),
+                (Handle::Out, point.out_handle),
+            ]
+            .into_iter()
+            .filter(|(_, offset)| offset.magnitude2() > 0.0)
+            .map(|(handle, offset)| (i, handle, point.position + offset))
+            .collect()
+        });
+        let handles_element = &mut scene.elements[self.handles_element];
+        handles_element.visible = !handles.is_empty();
+        if !handles.is_empty() {
+            let mut builder = Path::builder();
+            for &(i, _, end) in &handles {
+                builder.begin(to_point(self.to_local(points[i].position)));
+                builder.line_to(to_point(self.to_local(end)));
+                builder.end(false);
+            }
+            let geometry = stroke_geometry(
+                &builder.build(),
+                HANDLE_LINE_WIDTH,
+                extent,
+                self.style.handle,
+            );
+            handles_element.set_geometry(device, deletion_queue, &geometry);
+        }
+
+        for (i, point) in points.iter().enumerate() {
+            let position = self.position + self.to_local(point.position);
+            let element = self.point_markers.acquire(scene, device, position);
+            let color = if self.selected == Some(i) {
+                self.style.selected
+            } else {
+                self.style.point
+            };
+            scene.elements[element].instance.tint = [color[0], color[1], color[2], 1.0];
+            self.live.push((Grip::Point(i), element));
+        }
+        for (i, handle, end) in handles {
+            let position = self.position + self.to_local(end);
+            let element = self.handle_markers.acquire(scene, device, position);
+            let color = self.style.handle;
+            scene.elements[element].instance.tint = [color[0], color[1], color[2], 1.0];
+            self.live.push((Grip::Handle(i, handle), element));
+        }
+    }
+
+    // Moves one handle and mirrors the other one's direction, keeping its
+    // length.
+    fn move_handle(&mut self, index: usize, handle: Handle, target: cgmath::Vector2<f32>) {
+        let point = self.curve.points()[index];
+        let offset = target - point.position;
+        self.curve.set_handle(index, handle, offset);
+
+        let (opposite, other) = match handle {
+            Handle::In => (Handle::Out, point.out_handle),
+            Handle::Out => (Handle::In, point.in_handle),
+        };
+        let length = other.magnitude();
+        if length > 0.0 && offset.magnitude2() > 0.0 {
+            self.curve
+                .set_handle(index, opposite, -offset.normalize() * length);
+        }
+    }
+
+    fn grip_at(&self, scene: &UIScene, point: cgmath::Vector2<f32>) -> Option<Grip> {
+        self.live
+            .iter()
+            .rev()
+            .find(|(_, element)| scene.elements[*element].contains(point))
+            .map(|&(grip, _)| grip)
+    }
+
+    fn contains(&self, point: cgmath::Vector2<f32>) -> bool {
+        let half = self.style.size / 2.0;
+        (point.x - self.position.x).abs() <= half.x && (point.y - self.position.y).abs() <= half.y
+    }
+
+    fn to_curve(&self, world: cgmath::Vector2<f32>) -> cgmath::Vector2<f32> {
+        let (low, high) = self.style.range;
+        let local = world - self.position + self.style.size / 2.0;
+        cgmath::Vector2::new(
+            local.x / self.style.size.x,
+            low + local.y / self.style.size.y * (high - low),
+        )
+    }
+
+    // Relative to the editor's center.
+    fn to_local(&self, curve: cgmath::Vector2<f32>) -> cgmath::Vector2<f32> {
+        let (low, high) = self.style.range;
+        cgmath::Vector2::new(
+            (curve.x - 0.5) * self.style.size.x,
+            ((curve.y - low) / (high - low) - 0.5) * self.style.size.y,
+        )
+    }
+
+    fn snap(&self, curve: cgmath::Vector2<f32>) -> cgmath::Vector2<f32> {
+        if !self.style.snap || self.style.grid == 0 {
+            return curve;
+        }
+        let grid = self.style.grid as f32;
+        let (low, high) = self.style.range;
+        let step = (high - low) / grid;
+        cgmath::Vector2::new(
+            (curve.x * grid).round() / grid,
+            low + ((curve.y - low) / step).round() * step,
+        )
+    }
+}
@@ -0,0 +1,77 @@
+pub const DEBUG_ENV: &str = "WGPIE_DEBUG";
+
+// Debug switches read from `WGPIE_DEBUG` at startup, e.g.
+// `WGPIE_DEBUG=overdraw,bounds,fps`, so user reports can be triaged without
+// rebuilding.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct DebugFlags {
+    // Draw UI elements additively so overlapping layers show up brighter.
+    pub overdraw: bool,
+    // Outline every UI element.
+    pub bounds: bool,
+    // Draw the triangle edges of UI elements over them, to check how paths
+    // were tessellated. Needs `Features::POLYGON_MODE_LINE`, toggled
+    // with `UIScene::WIREFRAME_ACTION` (F9) at runtime.
+    pub wireframe: bool,
+    // Log frames per second and frame times once a second.
+    pub fps: bool,
+    // Log adapter and surface details at startup.
+    pub adapter: bool,
+}
+
+impl DebugFlags {
+    pub fn from_env() -> Self {
+        match std::env::var(DEBUG_ENV) {
+            Ok(value) => Self::parse(&value),
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn parse(value: &str) -> Self {
+        let mut flags = Self::default();
+        for flag in value.split(',').map(str::trim).filter(|f| !f.is_empty()) {
+            match flag.to_ascii_lowercase().as_str() {
+                "overdraw" => flags.overdraw = true,
+                "bounds" => flags.bounds = true,
+                "wireframe" => flags.wireframe = true,
+                "fps" => flags.fps = true,
+                "adapter" => flags.adapter = true,
+                "all" => {
+                    flags = Self {
+                        overdraw: true,
+                        bounds: true,
+                        wireframe: true,
+                        fps: true,
+                        adapter: true,
+                    }
+                }
+                _ => log::warn!("Unknown {} flag `{}`", DEBUG_ENV, flag),
+            }
+        }
+        flags
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct FpsCounter {
+    frames: u32,
+    elapsed: f32,
+    worst: f32,
+}
+
+impl FpsCounter {
+    pub fn update(&mut self, dt: f32) {
+        self.frames += 1;
+        self.elapsed += dt;
+        self.worst = self.worst.max(dt);
+        if self.elapsed >= 1.0 {
+            log::info!(
+                "{:.1} fps, {:.2} ms average, {:.2} ms worst",
+                self.frames as f32 / self.elapsed,
+                self.elapsed * 1000.0 / self.frames as f32,
+                self.worst * 1000.0
+            );
+            *self = Self::default();
+        }
+    }
+}
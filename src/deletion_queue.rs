@@ -0,0 +1,100 @@
+use std::collections::VecDeque;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+pub enum GpuResource {
+    Buffer(wgpu::Buffer),
+    // Only destroyed if nothing else holds on to it by then.
+    SharedBuffer(Rc<wgpu::Buffer>),
+    Texture(wgpu::Texture),
+}
+
+impl From<wgpu::Buffer> for GpuResource {
+    fn from(buffer: wgpu::Buffer) -> Self {
+        GpuResource::Buffer(buffer)
+    }
+}
+
+impl From<Rc<wgpu::Buffer>> for GpuResource {
+    fn from(buffer: Rc<wgpu::Buffer>) -> Self {
+        GpuResource::SharedBuffer(buffer)
+    }
+}
+
+impl From<wgpu::Texture> for GpuResource {
+    fn from(texture: wgpu::Texture) -> Self {
+        GpuResource::Texture(texture)
+    }
+}
+
+impl GpuResource {
+    fn destroy(self) {
+        match self {
+            GpuResource::Buffer(buffer) => buffer.destroy(),
+            GpuResource::SharedBuffer(buffer) => {
+                if let Ok(buffer) = Rc::try_unwrap(buffer) {
+                    buffer.destroy();
+                }
+            }
+            GpuResource::Texture(texture) => texture.destroy(),
+        }
+    }
+}
+
+// Resources released while a frame may still be using them. Each one is
+// tagged with the submission that could reference it, and only destroyed
+// once the GPU reports that submission as done.
+#[derive(Default)]
+pub struct DeletionQueue {
+    // Number of submissions made so far.
+    submitted: u64,
+    // Highest submission known to have finished executing, written from
+    // wgpu's completion callback.
+    completed: Arc<AtomicU64>,
+    pending: VecDeque<(u64, GpuResource)>,
+}
+
+impl DeletionQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // The resource may be used by the frame being recorded right now, which
+    // will be the next submission.
+    pub fn defer(&mut self, resource: impl Into<GpuResource>) {
+        self.pending
+            .push_back((self.submitted + 1, resource.into()));
+    }
+
+    // Call right after `Queue::submit`.
+    pub fn on_submit(&mut self, queue: &wgpu::Queue) {
+        self.submitted += 1;
+        let submission = self.submitted;
+        let completed = self.completed.clone();
+        queue.on_submitted_work_done(move || {
+            completed.fetch_max(submission, Ordering::AcqRel);
+        });
+    }
+
+    // Destroys everything whose submission has completed. Submissions
+    // finish in order, so the queue is sorted by submission as well.
+    pub fn collect(&mut self) {
+        let completed = self.completed.load(Ordering::Acquire);
+        while let Some((submission, _)) = self.pending.front() {
+            if *submission > completed {
+                break;
+            }
+            let (_, resource) = self.pending.pop_front().unwrap();
+            resource.destroy();
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+}
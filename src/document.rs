@@ -0,0 +1,178 @@
+use cgmath::{Rotation, Vector2};
+
+use crate::ui_scene::UIScene;
+
+// Smallest zoom `zoom_to` goes down to, same as `OrthographicCamera::add_scale`.
+const MIN_ZOOM: f32 = 0.1;
+
+// What document coordinates are measured in. World units are CSS pixels,
+// 96 to the inch.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum Units {
+    #[default]
+    Pixels,
+    Points,
+    Millimeters,
+    Inches,
+}
+
+impl Units {
+    pub fn world_per_unit(&self) -> f32 {
+        match self {
+            Units::Pixels => 1.0,
+            Units::Points => 96.0 / 72.0,
+            Units::Millimeters => 96.0 / 25.4,
+            Units::Inches => 96.0,
+        }
+    }
+
+    // As written after lengths in SVG.
+    pub fn suffix(&self) -> &'static str {
+        match self {
+            Units::Pixels => "px",
+            Units::Points => "pt",
+            Units::Millimeters => "mm",
+            Units::Inches => "in",
+        }
+    }
+}
+
+// The content of a canvas, apart from where the camera happens to look: the
+// area that counts as the document, where its coordinates start and what
+// they're measured in. Fitting, zooming to a selection and exporting all go
+// by it, so tools agree on what "the document" is:
+//
+//     scene.document = Some(Document::page(210.0, 297.0, Units::Millimeters));
+//     scene.fit_document(24.0);
+//     scene.export_document_png(device, queue, 2.0, "page.png")?;
+//
+// The camera still pans freely past it.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Document {
+    // Content area, in world units.
+    pub min: Vector2<f32>,
+    pub max: Vector2<f32>,
+    // World point document coordinates count from, going right and down.
+    pub origin: Vector2<f32>,
+    pub units: Units,
+}
+
+impl Document {
+    // Content between two world corners, counted in pixels from its top
+    // left.
+    pub fn new(min: Vector2<f32>, max: Vector2<f32>) -> Self {
+        Self {
+            min,
+            max,
+            origin: Vector2::new(min.x, max.y),
+            units: Units::Pixels,
+        }
+    }
+
+    // A page of `width` by `height` units with its top left at the world
+    // origin.
+    pub fn page(width: f32, height: f32, units: Units) -> Self {
+        let k = units.world_per_unit();
+        let mut document = Self::new(Vector2::new(0.0, -height * k), Vector2::new(width * k, 0.0));
+        document.units = units;
+        document
+    }
+
+    // Width and height in document units.
+    pub fn size(&self) -> Vector2<f32> {
+        (self.max - self.min) / self.units.world_per_unit()
+    }
+
+    pub fn to_world(&self, point: Vector2<f32>) -> Vector2<f32> {
+        let k = self.units.world_per_unit();
+        self.origin + Vector2::new(point.x * k, -point.y * k)
+    }
+
+    pub fn to_document(&self, world: Vector2<f32>) -> Vector2<f32> {
+        let d = (world - self.origin) / self.units.world_per_unit();
+        Vector2::new(d.x, -d.y)
+    }
+
+    pub fn contains(&self, world: Vector2<f32>) -> bool {
+        (self.min.x..=self.max.x).contains(&world.x) && (self.min.y..=self.max.y).contains(&world.y)
+    }
+}
+
+impl UIScene {
+    // The document's area, or everything visible when there's no document.
+    pub fn content_bounds(&self) -> Option<(Vector2<f32>, Vector2<f32>)> {
+        if let Some(document) = &self.document {
+            return Some((document.min, document.max));
+        }
+        let visible = (0..self.elements.len())
+            .filter(|&i| self.elements[i].visible)
+            .collect::<Vec<_>>();
+        self.world_bounds(&visible)
+    }
+
+    // Smallest world rect holding the elements, rotation included.
+    pub fn world_bounds(&self, elements: &[usize]) -> Option<(Vector2<f32>, Vector2<f32>)> {
+        let corners = elements.iter().flat_map(|&i| {
+            let element = &self.elements[i];
+            let half = element.size * element.instance.scale / 2.0;
+            [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)].map(|(x, y)| {
+                let corner = cgmath::Vector3::new(half.x * x, half.y * y, 0.0);
+                let p = element.instance.rotation.rotate_vector(corner) + element.instance.position;
+                Vector2::new(p.x, p.y)
+            })
+        });
+        corners.fold(None, |bounds, p| {
+            let (min, max) = bounds.unwrap_or((p, p));
+            Some((
+                Vector2::new(min.x.min(p.x), min.y.min(p.y)),
+                Vector2::new(max.x.max(p.x), max.y.max(p.y)),
+            ))
+        })
+    }
+
+    // Centers the world rect in the viewport and zooms until it fills it,
+    // leaving `margin` screen pixels around. Stops any fling. Pan bounds
+    // still apply, so rects outside them spring back.
+    pub fn zoom_to(&mut self, min: Vector2<f32>, max: Vector2<f32>, margin: f32) {
+        let room = Vector2::new(
+            (self.viewport.width - margin * 2.0).max(1.0),
+            (self.viewport.height - margin * 2.0).max(1.0),
+        );
+        let size = max - min;
+        let scale = match (size.x > 0.0, size.y > 0.0) {
+            (true, true) => (room.x / size.x).min(room.y / size.y),
+            (true, false) => room.x / size.x,
+            (false, true) => room.y / size.y,
+            // A point, keep the zoom.
+            (false, false) => self.camera.scale,
+        };
+        let center = (min + max) / 2.0;
+        self.camera.scale = scale.max(MIN_ZOOM);
+        self.camera.position = center;
+        self.pan.offset = center;
+        self.pan.velocity = Vector2::new(0.0, 0.0);
+    }
+
+    // Returns false if none of the elements has a size.
+    pub fn zoom_to_selection(&mut self, elements: &[usize], margin: f32) -> bool {
+        match self.world_bounds(elements) {
+            Some((min, max)) => {
+                self.zoom_to(min, max, margin);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Shows the whole document, see `content_bounds`. Returns false if
+    // there's nothing to show.
+    pub fn fit_document(&mut self, margin: f32) -> bool {
+        match self.content_bounds() {
+            Some((min, max)) => {
+                self.zoom_to(min, max, margin);
+                true
+            }
+            None => false,
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use winit::event::WindowEvent;
+
+use crate::inspector::place;
+use crate::tween::{Easing, Tween};
+use crate::ui_scene::{Player, UIScene, Viewport};
+
+#[derive(Debug, Clone)]
+pub struct DropOverlayStyle {
+    pub fill: [f32; 3],
+    // Of the fill, the border is opaque.
+    pub opacity: f32,
+    pub border: [f32; 3],
+    pub border_width: f32,
+    // Gap between the border and the window edges.
+    pub inset: f32,
+    // Seconds to fade in and out.
+    pub fade: f32,
+}
+
+impl Default for DropOverlayStyle {
+    fn default() -> Self {
+        Self {
+            fill: [0.25, 0.45, 0.8],
+            opacity: 0.2,
+            border: [0.35, 0.6, 1.0],
+            border_width: 3.0,
+            inset: 8.0,
+            fade: 0.12,
+        }
+    }
+}
+
+// Highlights the whole window while files are dragged over it, so it's
+// clear dropping them does something. The dropped paths themselves come out
+// of `UIScene::drain_events` as `UIEvent::FileDropped`.
+//
+// Elements are drawn in the order they were added, so create the overlay
+// after the rest of the scene.
+pub struct DropOverlay {
+    pub style: DropOverlayStyle,
+    fill: usize,
+    // Top, bottom, left and right edges.
+    border: [usize; 4],
+    // Files currently over the window.
+    hovering: usize,
+    opacity: Tween,
+}
+
+impl DropOverlay {
+    pub fn new(scene: &mut UIScene, device: &wgpu::Device, style: DropOverlayStyle) -> Self {
+        let mut push = || {
+            let white = [1.0, 1.0, 1.0];
+            let origin = cgmath::Vector2::new(0.0, 0.0);
+            let size = cgmath::Vector2::new(1.0, 1.0);
+            let mut element = Player::with_gradient(device, origin, size, white, white);
+            element.draggable = false;
+            element.visible = false;
+            scene.elements.push(element);
+            scene.elements.len() - 1
+        };
+        let fill = push();
+        let border = [push(), push(), push(), push()];
+        let fade = style.fade;
+        Self {
+            style,
+            fill,
+            border,
+            hovering: 0,
+            opacity: Tween::new(0.0, fade, Easing::EaseOut),
+        }
+    }
+
+    pub fn is_hovering(&self) -> bool {
+        self.hovering > 0
+    }
+
+    // Call before `UIScene::input`, which still needs the event.
+    pub fn input(&mut self, event: &WindowEvent) {
+        match event {
+            WindowEvent::HoveredFile(_) => self.hovering += 1,
+            WindowEvent::HoveredFileCancelled => self.hovering = 0,
+            // Every hovered file is dropped.
+            WindowEvent::DroppedFile(_) => self.hovering = self.hovering.saturating_sub(1),
+            _ => return,
+        }
+        let target = if self.is_hovering() { 1.0 } else { 0.0 };
+        self.opacity.retarget(target);
+    }
+
+    // Call before `UIScene::prepare`, with the frame's time step.
+    pub fn update(&mut self, scene: &mut UIScene, queue: &wgpu::Queue, dt: f32) {
+        self.opacity.update(dt);
+        let opacity = self.opacity.value();
+        let style = &self.style;
+        let (w, inset) = (style.border_width, style.inset);
+        let (width, height) = (
+            scene.viewport.width - inset * 2.0,
+            scene.viewport.height - inset * 2.0,
+        );
+        let rect = |x: f32, y: f32, width: f32, height: f32| Viewport {
+            x,
+            y,
+            width,
+            height,
+        };
+        let edges = [
+            rect(inset, inset, width, w),
+            rect(inset, inset + height - w, width, w),
+            rect(inset, inset, w, height),
+            rect(inset + width - w, inset, w, height),
+        ];
+        let fill = (self.fill, rect(inset, inset, width, height), style.fill);
+        let border = self
+            .border
+            .into_iter()
+            .zip(edges)
+            .map(|(e, r)| (e, r, style.border));
+        for (element, rect, color) in std::iter::once(fill).chain(border) {
+            place(scene, queue, element, rect, color);
+            let alpha = if element == self.fill {
+                style.opacity
+            } else {
+                1.0
+            };
+            let element = &mut scene.elements[element];
+            element.instance.tint[3] = alpha * opacity;
+            element.visible &= opacity > 0.0;
+        }
+    }
+}
@@ -0,0 +1,351 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use anyhow::bail;
+use image::RgbaImage;
+
+use crate::atlas::{extrude, AtlasRegion, PADDING};
+use crate::texture::Texture;
+use crate::ui_scene::{Player, UIMaterial};
+
+// Repack once evicted and removed images leave this much of the texture
+// unusable.
+const REPACK_WASTE: f32 = 0.25;
+
+// Names an image added to a `DynamicAtlas`. Stays valid through evictions
+// and repacks, until the image is removed.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct AtlasHandle(u64);
+
+// An atlas for images that keep coming, like glyphs and emoji, in a texture
+// of fixed size. Images only take space while they're used: when one doesn't
+// fit, the least recently used ones are evicted and the rest packed tightly
+// again. Evicted images come back the next time they're asked for.
+//
+//     let glyph = atlas.insert(pixels)?;
+//     scene.set_atlas_texture(device, &atlas.texture);
+//     atlas.set_sprite(queue, &mut scene.elements[i], glyph); // every frame it's shown
+//     atlas.end_frame(queue);
+//
+// Repacking moves images, so sub-rects taken from `uv_rect` are only good
+// until `generation` changes.
+pub struct DynamicAtlas {
+    pub texture: Texture,
+    pub width: u32,
+    pub height: u32,
+    entries: HashMap<AtlasHandle, Entry>,
+    next_handle: u64,
+    shelves: Vec<Shelf>,
+    frame: u64,
+    generation: u64,
+    // Texels left behind by evicted and removed images.
+    wasted: u32,
+}
+
+struct Entry {
+    // Kept so the image can move or come back after eviction.
+    image: RgbaImage,
+    region: Option<AtlasRegion>,
+    last_used: u64,
+}
+
+// A row of images as tall as its tallest, filled from the left.
+pub(crate) struct Shelf {
+    y: u32,
+    height: u32,
+    filled: u32,
+}
+
+impl DynamicAtlas {
+    pub fn new(device: &wgpu::Device, width: u32, height: u32, sampler: Rc<wgpu::Sampler>) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Dynamic atlas"),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        Self {
+            texture: Texture {
+                texture,
+                view,
+                sampler,
+            },
+            width,
+            height,
+            entries: HashMap::new(),
+            next_handle: 0,
+            shelves: Vec::new(),
+            frame: 0,
+            generation: 0,
+            wasted: 0,
+        }
+    }
+
+    // Adds an image, which is uploaded the first time it's asked for. Fails
+    // on empty images and ones bigger than the whole atlas.
+    pub fn insert(&mut self, image: RgbaImage) -> anyhow::Result<AtlasHandle> {
+        let (width, height) = image.dimensions();
+        if width == 0 || height == 0 {
+            bail!("atlas image is empty");
+        }
+        if width + PADDING * 2 > self.width || height + PADDING * 2 > self.height {
+            bail!(
+                "a {}x{} image doesn't fit a {}x{} atlas",
+                width,
+                height,
+                self.width,
+                self.height
+            );
+        }
+        let handle = AtlasHandle(self.next_handle);
+        self.next_handle += 1;
+        self.entries.insert(
+            handle,
+            Entry {
+                image,
+                region: None,
+                last_used: self.frame,
+            },
+        );
+        Ok(handle)
+    }
+
+    pub fn remove(&mut self, handle: AtlasHandle) {
+        if let Some(Entry {
+            region: Some(region),
+            ..
+        }) = self.entries.remove(&handle)
+        {
+            self.wasted += padded_area(&region);
+        }
+    }
+
+    pub fn contains(&self, handle: AtlasHandle) -> bool {
+        self.entries.contains_key(&handle)
+    }
+
+    // Images added and not removed, evicted ones included.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    // Images in the texture right now.
+    pub fn resident(&self) -> usize {
+        self.entries.values().filter(|e| e.region.is_some()).count()
+    }
+
+    // Changes whenever images move.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    // Where the image is, as u0, v0, u1, v1 like `Atlas::uv_rect`, uploading
+    // it if it isn't there. Counts as a use. None for removed handles, and
+    // when the images used this frame leave no room.
+    pub fn uv_rect(&mut self, queue: &wgpu::Queue, handle: AtlasHandle) -> Option<[f32; 4]> {
+        let frame = self.frame;
+        self.entries.get_mut(&handle)?.last_used = frame;
+        let region = self.make_resident(queue, handle)?;
+        let (w, h) = (self.width as f32, self.height as f32);
+        Some([
+            region.x as f32 / w,
+            region.y as f32 / h,
+            (region.x + region.width) as f32 / w,
+            (region.y + region.height) as f32 / h,
+        ])
+    }
+
+    // Shows the image in a `UIMaterial::Sprite` element. Returns false if
+    // it couldn't be placed.
+    pub fn set_sprite(
+        &mut self,
+        queue: &wgpu::Queue,
+        element: &mut Player,
+        handle: AtlasHandle,
+    ) -> bool {
+        match self.uv_rect(queue, handle) {
+            Some(uv) => {
+                element.material = UIMaterial::Sprite;
+                element.instance.params = uv;
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Starts a new frame for eviction, repacking first if enough space has
+    // gone to waste.
+    pub fn end_frame(&mut self, queue: &wgpu::Queue) {
+        self.frame += 1;
+        if self.wasted as f32 > (self.width * self.height) as f32 * REPACK_WASTE {
+            self.repack(queue);
+        }
+    }
+
+    // Packs the resident images tightly again, tallest first.
+    pub fn repack(&mut self, queue: &wgpu::Queue) {
+        let mut resident = self
+            .entries
+            .iter()
+            .filter(|(_, e)| e.region.is_some())
+            .map(|(&handle, e)| (handle, e.image.height()))
+            .collect::<Vec<_>>();
+        resident.sort_by_key(|&(handle, height)| (std::cmp::Reverse(height), handle.0));
+        self.shelves.clear();
+        self.wasted = 0;
+        self.generation += 1;
+        for (handle, _) in resident {
+            let entry = self.entries.get_mut(&handle).unwrap();
+            let (width, height) = entry.image.dimensions();
+            entry.region = allocate(&mut self.shelves, self.width, self.height, width, height);
+            if let Some(region) = entry.region {
+                upload(queue, &self.texture, &entry.image, region);
+            }
+        }
+    }
+
+    fn make_resident(&mut self, queue: &wgpu::Queue, handle: AtlasHandle) -> Option<AtlasRegion> {
+        if let Some(region) = self.entries[&handle].region {
+            return Some(region);
+        }
+        let (width, height) = self.entries[&handle].image.dimensions();
+        let needed = (width + PADDING * 2) * (height + PADDING * 2);
+        let mut repacked = false;
+        loop {
+            if let Some(region) =
+                allocate(&mut self.shelves, self.width, self.height, width, height)
+            {
+                let entry = self.entries.get_mut(&handle).unwrap();
+                entry.region = Some(region);
+                upload(queue, &self.texture, &entry.image, region);
+                return Some(region);
+            }
+            // Evict until there's room on paper, then pack to make it real.
+            let mut free = self.width * self.height - self.used();
+            if repacked || free < needed {
+                loop {
+                    let Some(victim) = self.least_recently_used() else {
+                        if repacked {
+                            log::warn!("no room in the atlas for a {}x{} image", width, height);
+                            return None;
+                        }
+                        break;
+                    };
+                    let region = self.entries.get_mut(&victim).unwrap().region.take();
+                    free += region.map_or(0, |r| padded_area(&r));
+                    if free >= needed {
+                        break;
+                    }
+                }
+            }
+            self.repack(queue);
+            repacked = true;
+        }
+    }
+
+    // Texels taken by resident images, padding included.
+    fn used(&self) -> u32 {
+        self.entries
+            .values()
+            .filter_map(|e| e.region.as_ref())
+            .map(padded_area)
+            .sum()
+    }
+
+    // Resident and not used this frame.
+    fn least_recently_used(&self) -> Option<AtlasHandle> {
+        self.entries
+            .iter()
+            .filter(|(_, e)| e.region.is_some() && e.last_used < self.frame)
+            .min_by_key(|(handle, e)| (e.last_used, handle.0))
+            .map(|(&handle, _)| handle)
+    }
+}
+
+fn padded_area(region: &AtlasRegion) -> u32 {
+    (region.width + PADDING * 2) * (region.height + PADDING * 2)
+}
+
+// Puts the image on the lowest shelf it fits, or a new one below the rest.
+pub(crate) fn allocate(
+    shelves: &mut Vec<Shelf>,
+    atlas_width: u32,
+    atlas_height: u32,
+    width: u32,
+    height: u32,
+) -> Option<AtlasRegion> {
+    let (w, h) = (width + PADDING * 2, height + PADDING * 2);
+    let shelf = match shelves
+        .iter_mut()
+        .filter(|s| s.height >= h && s.filled + w <= atlas_width)
+        .min_by_key(|s| s.height)
+    {
+        Some(shelf) => shelf,
+        None => {
+            let y = shelves.last().map_or(0, |s| s.y + s.height);
+            if y + h > atlas_height || w > atlas_width {
+                return None;
+            }
+            shelves.push(Shelf {
+                y,
+                height: h,
+                filled: 0,
+            });
+            shelves.last_mut().unwrap()
+        }
+    };
+    let region = AtlasRegion {
+        x: shelf.filled + PADDING,
+        y: shelf.y + PADDING,
+        width,
+        height,
+    };
+    shelf.filled += w;
+    Some(region)
+}
+
+pub(crate) fn upload(
+    queue: &wgpu::Queue,
+    texture: &Texture,
+    image: &RgbaImage,
+    region: AtlasRegion,
+) {
+    let padded = extrude(image);
+    let (width, height) = padded.dimensions();
+    queue.write_texture(
+        wgpu::ImageCopyTexture {
+            aspect: wgpu::TextureAspect::All,
+            texture: &texture.texture,
+            mip_level: 0,
+            origin: wgpu::Origin3d {
+                x: region.x - PADDING,
+                y: region.y - PADDING,
+                z: 0,
+            },
+        },
+        &padded,
+        wgpu::ImageDataLayout {
+            offset: 0,
+            bytes_per_row: Some(4 * width),
+            rows_per_image: Some(height),
+        },
+        wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+    );
+}
@@ -0,0 +1,837 @@
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+use anyhow::Context;
+use winit::event::{ElementState, MouseButton, VirtualKeyCode, WindowEvent};
+
+use crate::guides::Guides;
+use crate::inspector::{place, Inspectable, Inspector, InspectorStyle, Property, PropertyValue};
+use crate::shortcuts::{Chord, ShortcutScope};
+use crate::system_fonts::SystemFonts;
+use crate::text::{GlyphAtlas, TextFont};
+use crate::tree_view::{TreeEvent, TreeStyle, TreeView};
+use crate::ui_scene::{Player, UIEvent, UIScene, Viewport};
+
+const TOGGLE: &str = "editor.toggle";
+const UNDO: &str = "editor.undo";
+const REDO: &str = "editor.redo";
+// Older edits are dropped.
+const MAX_UNDO: usize = 100;
+
+#[derive(Debug, Clone)]
+pub struct EditorStyle {
+    pub toggle: Chord,
+    // Of the panels' labels, looked up among the system's fonts. Where it
+    // isn't installed, the first family there is stands in.
+    pub font_family: String,
+    pub tree_width: f32,
+    pub tree: TreeStyle,
+    pub inspector: InspectorStyle,
+    pub gizmo: [f32; 3],
+    // Thickness of the frame around the selected element.
+    pub frame_width: f32,
+    // Side of the square in the middle of the selection that moves it.
+    pub handle_size: f32,
+    // Thickness of the rulers along the top and left of the scene, which
+    // guides are dragged out of.
+    pub ruler_size: f32,
+    pub ruler: [f32; 3],
+    pub ruler_ticks: [f32; 3],
+    // Smallest distance between ruler ticks, in screen pixels.
+    pub tick_spacing: f32,
+    pub guide: [f32; 3],
+    // How close a moved element's edge or middle has to come to a guide to
+    // snap to it, in screen pixels. 0 turns snapping off.
+    pub snap_distance: f32,
+}
+
+impl Default for EditorStyle {
+    fn default() -> Self {
+        Self {
+            toggle: Chord::new(VirtualKeyCode::F12),
+            font_family: "sans-serif".to_string(),
+            tree_width: 220.0,
+            tree: TreeStyle::default(),
+            inspector: InspectorStyle::default(),
+            gizmo: [1.0, 0.6, 0.1],
+            frame_width: 2.0,
+            handle_size: 12.0,
+            ruler_size: 16.0,
+            ruler: [0.12, 0.12, 0.14],
+            ruler_ticks: [0.5, 0.5, 0.55],
+            tick_spacing: 50.0,
+            guide: [0.1, 0.8, 0.9],
+            snap_distance: 6.0,
+        }
+    }
+}
+
+enum Edit {
+    Properties {
+        element: usize,
+        before: Vec<Property>,
+        after: Vec<Property>,
+    },
+    Reparent {
+        element: usize,
+        before: Option<usize>,
+        after: Option<usize>,
+    },
+    Guides {
+        before: Guides,
+        after: Guides,
+    },
+}
+
+// Elements making up the editor, created the first time it's shown so they
+// are drawn over the scene built until then.
+struct Panels {
+    tree: TreeView,
+    inspector: Inspector,
+    glyphs: GlyphAtlas,
+    // Top, bottom, left and right edges around the selection.
+    frame: [usize; 4],
+    handle: usize,
+    // Top and left.
+    rulers: [usize; 2],
+    // Grown as needed, the ones past what's shown are hidden.
+    ticks: Vec<usize>,
+    lines: Vec<usize>,
+}
+
+struct GizmoDrag {
+    element: usize,
+    press: cgmath::Vector2<f32>,
+    start: cgmath::Vector2<f32>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Axis {
+    Horizontal,
+    Vertical,
+}
+
+struct GuideDrag {
+    axis: Axis,
+    index: usize,
+    before: Guides,
+}
+
+// Stands in for an element while the inspector edits it, since the
+// inspector needs the scene the element lives in at the same time.
+struct Proxy {
+    properties: Vec<Property>,
+    changed: Vec<Property>,
+}
+
+impl Inspectable for Proxy {
+    fn properties(&self) -> Vec<Property> {
+        self.properties.clone()
+    }
+
+    fn set_property(&mut self, name: &str, value: &PropertyValue) {
+        if let Some(property) = self.properties.iter_mut().find(|p| p.name == name) {
+            property.value = value.clone();
+            self.changed.push(property.clone());
+        }
+    }
+}
+
+// A live editor over any scene: the element hierarchy in a tree on the
+// left, the properties of the selected element in an inspector on the right
+// and a frame around the selection, whose middle handle moves it. Clicking
+// an element in the scene selects it as well.
+//
+// Guides are dragged out of the rulers along the top and left of the scene,
+// moved by dragging them and removed by dropping them back on their ruler.
+// Moved elements snap to them. They're kept in `UIScene::guides`, so scene
+// snapshots save them.
+//
+// The toggle chord (F12 by default) shows and hides it. While shown,
+// Ctrl+Z and Ctrl+Shift+Z undo and redo property edits, moves and
+// reparenting. Elements are tracked by index, so removed ones stay in the
+// tree as the placeholders `UIScene::remove_element` leaves.
+pub struct Editor {
+    pub style: EditorStyle,
+    active: bool,
+    panels: Option<Panels>,
+    // Scene elements belonging to the editor itself.
+    owned: HashSet<usize>,
+    // Scene elements already added to the tree.
+    synced: usize,
+    items: HashMap<usize, usize>,
+    // Scene element of each tree item.
+    elements: Vec<usize>,
+    cursor: cgmath::Vector2<f32>,
+    mouse_down: bool,
+    // Properties of the selection when the button went down, to record
+    // what the press changed once it's released.
+    pending: Option<(usize, Vec<Property>)>,
+    drag: Option<GizmoDrag>,
+    guide_drag: Option<GuideDrag>,
+    done: Vec<Edit>,
+    undone: Vec<Edit>,
+}
+
+impl Editor {
+    pub fn new(scene: &mut UIScene, style: EditorStyle) -> Self {
+        if let Err(e) = scene.shortcuts.register(
+            style.toggle,
+            ShortcutScope::Global,
+            TOGGLE,
+            "Toggle the editor",
+        ) {
+            log::warn!("Couldn't bind the editor: {}", e);
+        }
+        Self {
+            style,
+            active: false,
+            panels: None,
+            owned: HashSet::new(),
+            synced: 0,
+            items: HashMap::new(),
+            elements: Vec::new(),
+            cursor: cgmath::Vector2::new(0.0, 0.0),
+            mouse_down: false,
+            pending: None,
+            drag: None,
+            guide_drag: None,
+            done: Vec::new(),
+            undone: Vec::new(),
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    // The scene element being inspected.
+    pub fn selected(&self) -> Option<usize> {
+        let panels = self.panels.as_ref()?;
+        let &item = panels.tree.selection().first()?;
+        Some(self.elements[item])
+    }
+
+    // Call before `UIScene::input`, and skip it if this returns true.
+    pub fn input(&mut self, scene: &mut UIScene, event: &WindowEvent) -> bool {
+        if !self.active {
+            return false;
+        }
+        let selected = self.selected();
+        let Some(panels) = &mut self.panels else {
+            return false;
+        };
+        match event {
+            WindowEvent::CursorMoved { position, .. } => {
+                self.cursor = cgmath::Vector2::new(
+                    position.x as f32 - scene.viewport.x,
+                    position.y as f32 - scene.viewport.y,
+                );
+                let consumed =
+                    panels.tree.input(scene, event) | panels.inspector.input(scene, event);
+                if let Some(drag) = &self.guide_drag {
+                    let world = scene.camera.screen_to_world(self.cursor.x, self.cursor.y);
+                    match drag.axis {
+                        Axis::Horizontal => scene.guides.horizontal[drag.index] = world.y,
+                        Axis::Vertical => scene.guides.vertical[drag.index] = world.x,
+                    }
+                    return true;
+                }
+                let Some(drag) = &self.drag else {
+                    return consumed;
+                };
+                let delta = (self.cursor - drag.press) / scene.camera.scale;
+                let moved = cgmath::Vector2::new(drag.start.x + delta.x, drag.start.y - delta.y);
+                let player = &scene.elements[drag.element];
+                let half = player.size * player.instance.scale / 2.0;
+                let reach = self.style.snap_distance / scene.camera.scale;
+                let snapped = scene.guides.snap(moved, half, reach);
+                let position = &mut scene.elements[drag.element].instance.position;
+                position.x = snapped.x;
+                position.y = snapped.y;
+                true
+            }
+            WindowEvent::MouseInput {
+                state: ElementState::Pressed,
+                button: MouseButton::Left,
+                ..
+            } => {
+                self.mouse_down = true;
+                self.pending =
+                    selected.map(|element| (element, scene.elements[element].properties()));
+                let on_handle = selected.filter(|&element| {
+                    handle_rect(scene, &scene.elements[element], self.style.handle_size)
+                        .contains(self.cursor)
+                });
+                if let Some(element) = on_handle {
+                    let position = scene.elements[element].instance.position;
+                    self.drag = Some(GizmoDrag {
+                        element,
+                        press: self.cursor,
+                        start: cgmath::Vector2::new(position.x, position.y),
+                    });
+                    return true;
+                }
+                if let Some(drag) = press_guide(&self.style, self.cursor, scene) {
+                    self.guide_drag = Some(drag);
+                    return true;
+                }
+                panels.inspector.input(scene, event) | panels.tree.input(scene, event)
+            }
+            WindowEvent::MouseInput {
+                state: ElementState::Released,
+                button: MouseButton::Left,
+                ..
+            } => {
+                self.mouse_down = false;
+                let consumed =
+                    panels.inspector.input(scene, event) | panels.tree.input(scene, event);
+                if let Some(drag) = self.guide_drag.take() {
+                    self.drop_guide(scene, drag);
+                    return true;
+                }
+                self.drag.take().is_some() || consumed
+            }
+            _ => panels.tree.input(scene, event) | panels.inspector.input(scene, event),
+        }
+    }
+
+    // Handles the editor's shortcuts and scene clicks, syncs the tree with
+    // the scene and places the panels. Call before `UIScene::prepare`.
+    pub fn update(&mut self, scene: &mut UIScene, device: &wgpu::Device, queue: &wgpu::Queue) {
+        let mut unhandled = Vec::new();
+        for event in scene.drain_events() {
+            match event {
+                UIEvent::Shortcut(action) if action == TOGGLE => self.toggle(scene, device),
+                UIEvent::Shortcut(action) if action == UNDO => self.undo(scene),
+                UIEvent::Shortcut(action) if action == REDO => self.redo(scene),
+                UIEvent::Clicked(element) if self.active && !self.owned.contains(&element) => {
+                    if let (Some(panels), Some(&item)) =
+                        (&mut self.panels, self.items.get(&element))
+                    {
+                        panels.tree.set_selection(&[item]);
+                    }
+                }
+                event => unhandled.push(event),
+            }
+        }
+        scene.requeue_events(unhandled);
+        if !self.active {
+            return;
+        }
+
+        self.sync(scene);
+        let tree_bounds = Viewport {
+            x: 0.0,
+            y: 0.0,
+            width: self.style.tree_width,
+            height: scene.viewport.height,
+        };
+        let inspector_position =
+            cgmath::Vector2::new(scene.viewport.width - self.style.inspector.width, 0.0);
+        let selected = self.selected();
+        let Some(mut panels) = self.panels.take() else {
+            return;
+        };
+
+        let first = scene.elements.len();
+        panels.tree.set_bounds(scene, device, tree_bounds);
+        for event in panels.tree.drain_events() {
+            if let TreeEvent::Reparented { item, parent } = event {
+                let element = self.elements[item];
+                let after = parent.map(|parent| self.elements[parent]);
+                let before = scene.elements[element].parent;
+                scene.set_parent(element, after);
+                self.push(Edit::Reparent {
+                    element,
+                    before,
+                    after,
+                });
+            }
+        }
+        panels.tree.update(scene, device, queue, &mut panels.glyphs);
+
+        panels.inspector.set_position(inspector_position);
+        match selected {
+            Some(element) => {
+                let mut proxy = Proxy {
+                    properties: scene.elements[element].properties(),
+                    changed: Vec::new(),
+                };
+                panels
+                    .inspector
+                    .update(scene, device, queue, &mut panels.glyphs, &mut proxy);
+                for property in proxy.changed {
+                    scene.elements[element].set_property(&property.name, &property.value);
+                }
+            }
+            None => panels.inspector.clear(scene),
+        }
+        self.owned.extend(first..scene.elements.len());
+
+        if !self.mouse_down {
+            if let Some((element, before)) = self.pending.take() {
+                let after = scene.elements[element].properties();
+                if after != before {
+                    self.push(Edit::Properties {
+                        element,
+                        before,
+                        after,
+                    });
+                }
+            }
+        }
+
+        self.place_gizmo(scene, queue, &panels, selected);
+        self.place_guides(scene, device, queue, &mut panels);
+        self.panels = Some(panels);
+    }
+
+    fn toggle(&mut self, scene: &mut UIScene, device: &wgpu::Device) {
+        self.active = !self.active;
+        if self.active && self.panels.is_none() {
+            if let Err(e) = self.create_panels(scene, device) {
+                log::error!("Couldn't show the editor: {:#}", e);
+                self.active = false;
+                return;
+            }
+        }
+        if self.active {
+            for (chord, action, description) in [
+                (
+                    Chord::new(VirtualKeyCode::Z).ctrl(),
+                    UNDO,
+                    "Undo the last edit",
+                ),
+                (
+                    Chord::new(VirtualKeyCode::Z).ctrl().shift(),
+                    REDO,
+                    "Redo the last undone edit",
+                ),
+            ] {
+                if let Err(e) =
+                    scene
+                        .shortcuts
+                        .register(chord, ShortcutScope::Global, action, description)
+                {
+                    log::warn!("Couldn't bind {}: {}", action, e);
+                }
+            }
+        } else {
+            scene.shortcuts.unregister(UNDO);
+            scene.shortcuts.unregister(REDO);
+            if let Some(panels) = &mut self.panels {
+                panels.inspector.clear(scene);
+            }
+            for &element in &self.owned {
+                scene.elements[element].visible = false;
+            }
+            self.drag = None;
+            self.guide_drag = None;
+            self.pending = None;
+        }
+    }
+
+    fn create_panels(&mut self, scene: &mut UIScene, device: &wgpu::Device) -> anyhow::Result<()> {
+        let font = Rc::new(load_font(&self.style.font_family)?);
+        let sampler = Rc::new(device.create_sampler(&wgpu::SamplerDescriptor {
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        }));
+        let glyphs = GlyphAtlas::new(device, sampler);
+        let first = scene.elements.len();
+        let bounds = Viewport {
+            x: 0.0,
+            y: 0.0,
+            width: self.style.tree_width,
+            height: scene.viewport.height,
+        };
+        let tree = TreeView::new(scene, device, font.clone(), bounds, self.style.tree.clone());
+        let inspector = Inspector::new(
+            font,
+            cgmath::Vector2::new(0.0, 0.0),
+            self.style.inspector.clone(),
+        );
+        let mut push = || push_rect(scene, device);
+        let frame = [push(), push(), push(), push()];
+        let handle = push();
+        let rulers = [push(), push()];
+        self.owned.extend(first..scene.elements.len());
+        self.panels = Some(Panels {
+            tree,
+            inspector,
+            glyphs,
+            frame,
+            handle,
+            rulers,
+            ticks: Vec::new(),
+            lines: Vec::new(),
+        });
+        Ok(())
+    }
+
+    // Adds elements created since the last update to the tree.
+    fn sync(&mut self, scene: &UIScene) {
+        let Some(panels) = &mut self.panels else {
+            return;
+        };
+        for element in self.synced..scene.elements.len() {
+            if self.owned.contains(&element) {
+                continue;
+            }
+            let parent = scene.elements[element]
+                .parent
+                .and_then(|parent| self.items.get(&parent).copied());
+            let item = panels.tree.add(parent, &format!("Element {}", element));
+            self.items.insert(element, item);
+            self.elements.push(element);
+        }
+        self.synced = scene.elements.len();
+    }
+
+    fn place_gizmo(
+        &self,
+        scene: &mut UIScene,
+        queue: &wgpu::Queue,
+        panels: &Panels,
+        selected: Option<usize>,
+    ) {
+        let Some(element) = selected else {
+            for element in panels.frame.into_iter().chain([panels.handle]) {
+                scene.elements[element].visible = false;
+            }
+            return;
+        };
+        let player = &scene.elements[element];
+        let center = scene.camera.world_to_screen(cgmath::Vector2::new(
+            player.instance.position.x,
+            player.instance.position.y,
+        ));
+        let half = player.size * player.instance.scale * scene.camera.scale / 2.0;
+        let handle = handle_rect(scene, player, self.style.handle_size);
+        let w = self.style.frame_width;
+        let (left, top) = (center.x - half.x - w, center.y - half.y - w);
+        let (width, height) = (half.x * 2.0 + w * 2.0, half.y * 2.0 + w * 2.0);
+        let edges = [
+            Viewport {
+                x: left,
+                y: top,
+                width,
+                height: w,
+            },
+            Viewport {
+                x: left,
+                y: top + height - w,
+                width,
+                height: w,
+            },
+            Viewport {
+                x: left,
+                y: top,
+                width: w,
+                height,
+            },
+            Viewport {
+                x: left + width - w,
+                y: top,
+                width: w,
+                height,
+            },
+        ];
+        for (&part, rect) in panels.frame.iter().zip(edges) {
+            place(scene, queue, part, rect, self.style.gizmo);
+        }
+        place(scene, queue, panels.handle, handle, self.style.gizmo);
+    }
+
+    // Guides dropped back on a ruler or off the canvas go away.
+    fn drop_guide(&mut self, scene: &mut UIScene, drag: GuideDrag) {
+        let [top, left] = rulers(&self.style, scene);
+        let canvas = canvas(&self.style, scene);
+        let removed = !canvas.contains(self.cursor)
+            || match drag.axis {
+                Axis::Horizontal => top.contains(self.cursor),
+                Axis::Vertical => left.contains(self.cursor),
+            };
+        if removed {
+            match drag.axis {
+                Axis::Horizontal => scene.guides.horizontal.remove(drag.index),
+                Axis::Vertical => scene.guides.vertical.remove(drag.index),
+            };
+        }
+        if scene.guides != drag.before {
+            self.push(Edit::Guides {
+                before: drag.before,
+                after: scene.guides.clone(),
+            });
+        }
+    }
+
+    fn place_guides(
+        &mut self,
+        scene: &mut UIScene,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        panels: &mut Panels,
+    ) {
+        let canvas = canvas(&self.style, scene);
+        let rulers = rulers(&self.style, scene);
+        let [top, left] = rulers;
+        let camera = &scene.camera;
+
+        // Ticks at 1, 2 or 5 times a power of ten world units, whichever is
+        // the first at least `tick_spacing` pixels apart.
+        let min_step = self.style.tick_spacing.max(1.0) / camera.scale;
+        let magnitude = 10f32.powf(min_step.log10().floor());
+        let step = [1.0, 2.0, 5.0, 10.0]
+            .into_iter()
+            .map(|m| m * magnitude)
+            .find(|&step| step >= min_step)
+            .unwrap_or(10.0 * magnitude);
+        let (w, size) = (1.0, self.style.ruler_size / 2.0);
+        let mut ticks = Vec::new();
+        let first = camera.screen_to_world(top.x + left.width, 0.0).x;
+        let last = camera.screen_to_world(top.x + top.width, 0.0).x;
+        let mut x = (first / step).ceil() * step;
+        while x <= last {
+            let screen = camera.world_to_screen(cgmath::Vector2::new(x, 0.0)).x;
+            ticks.push(Viewport {
+                x: screen,
+                y: top.height - size,
+                width: w,
+                height: size,
+            });
+            x += step;
+        }
+        let first = camera.screen_to_world(0.0, left.y + left.height).y;
+        let last = camera.screen_to_world(0.0, left.y + top.height).y;
+        let mut y = (first / step).ceil() * step;
+        while y <= last {
+            let screen = camera.world_to_screen(cgmath::Vector2::new(0.0, y)).y;
+            ticks.push(Viewport {
+                x: left.width - size + left.x,
+                y: screen,
+                width: size,
+                height: w,
+            });
+            y += step;
+        }
+
+        // Lines stop at the rulers, and aren't drawn at all off the canvas.
+        let mut lines = Vec::new();
+        for &y in &scene.guides.horizontal {
+            let screen = camera.world_to_screen(cgmath::Vector2::new(0.0, y)).y;
+            if screen >= top.height && screen < canvas.height {
+                lines.push(Viewport {
+                    x: canvas.x + left.width,
+                    y: screen,
+                    width: (canvas.width - left.width).max(0.0),
+                    height: w,
+                });
+            }
+        }
+        for &x in &scene.guides.vertical {
+            let screen = camera.world_to_screen(cgmath::Vector2::new(x, 0.0)).x;
+            if screen >= canvas.x + left.width && screen < canvas.x + canvas.width {
+                lines.push(Viewport {
+                    x: screen,
+                    y: top.height,
+                    width: w,
+                    height: (canvas.height - top.height).max(0.0),
+                });
+            }
+        }
+
+        for (&ruler, rect) in panels.rulers.iter().zip(rulers) {
+            place(scene, queue, ruler, rect, self.style.ruler);
+        }
+        for (pool, rects, color) in [
+            (&mut panels.ticks, ticks, self.style.ruler_ticks),
+            (&mut panels.lines, lines, self.style.guide),
+        ] {
+            let first = scene.elements.len();
+            while pool.len() < rects.len() {
+                pool.push(push_rect(scene, device));
+            }
+            self.owned.extend(first..scene.elements.len());
+            for (i, &element) in pool.iter().enumerate() {
+                match rects.get(i) {
+                    Some(&rect) => place(scene, queue, element, rect, color),
+                    None => scene.elements[element].visible = false,
+                }
+            }
+        }
+    }
+
+    fn push(&mut self, edit: Edit) {
+        self.done.push(edit);
+        if self.done.len() > MAX_UNDO {
+            self.done.remove(0);
+        }
+        self.undone.clear();
+    }
+
+    fn undo(&mut self, scene: &mut UIScene) {
+        if let Some(edit) = self.done.pop() {
+            self.apply(scene, &edit, true);
+            self.undone.push(edit);
+        }
+    }
+
+    fn redo(&mut self, scene: &mut UIScene) {
+        if let Some(edit) = self.undone.pop() {
+            self.apply(scene, &edit, false);
+            self.done.push(edit);
+        }
+    }
+
+    fn apply(&mut self, scene: &mut UIScene, edit: &Edit, undo: bool) {
+        match edit {
+            Edit::Properties {
+                element,
+                before,
+                after,
+            } => {
+                let properties = if undo { before } else { after };
+                for property in properties {
+                    scene.elements[*element].set_property(&property.name, &property.value);
+                }
+            }
+            Edit::Reparent {
+                element,
+                before,
+                after,
+            } => {
+                let parent = if undo { *before } else { *after };
+                scene.set_parent(*element, parent);
+                if let (Some(panels), Some(&item)) = (&mut self.panels, self.items.get(element)) {
+                    let parent = parent.and_then(|parent| self.items.get(&parent).copied());
+                    panels.tree.reparent(item, parent);
+                    // Not a new edit.
+                    panels.tree.drain_events();
+                }
+            }
+            Edit::Guides { before, after } => {
+                scene.guides = if undo { before } else { after }.clone();
+                self.guide_drag = None;
+            }
+        }
+    }
+}
+
+// The part of the screen between the tree and the inspector.
+fn canvas(style: &EditorStyle, scene: &UIScene) -> Viewport {
+    let x = style.tree_width;
+    Viewport {
+        x,
+        y: 0.0,
+        width: (scene.viewport.width - style.inspector.width - x).max(0.0),
+        height: scene.viewport.height,
+    }
+}
+
+// Top and left.
+fn rulers(style: &EditorStyle, scene: &UIScene) -> [Viewport; 2] {
+    let canvas = canvas(style, scene);
+    let size = style.ruler_size;
+    [
+        Viewport {
+            height: size,
+            ..canvas
+        },
+        Viewport {
+            width: size,
+            ..canvas
+        },
+    ]
+}
+
+// Starts dragging a new guide out of a ruler, or the guide under the
+// cursor.
+fn press_guide(
+    style: &EditorStyle,
+    cursor: cgmath::Vector2<f32>,
+    scene: &mut UIScene,
+) -> Option<GuideDrag> {
+    let [top, left] = rulers(style, scene);
+    let before = scene.guides.clone();
+    let world = scene.camera.screen_to_world(cursor.x, cursor.y);
+    let (axis, index) = if top.contains(cursor) {
+        scene.guides.horizontal.push(world.y);
+        (Axis::Horizontal, scene.guides.horizontal.len() - 1)
+    } else if left.contains(cursor) {
+        scene.guides.vertical.push(world.x);
+        (Axis::Vertical, scene.guides.vertical.len() - 1)
+    } else if canvas(style, scene).contains(cursor) {
+        let reach = style.snap_distance.max(3.0) / scene.camera.scale;
+        let near = |lines: &[f32], at: f32| {
+            lines
+                .iter()
+                .map(|line| (line - at).abs())
+                .enumerate()
+                .filter(|&(_, distance)| distance <= reach)
+                .min_by(|a, b| a.1.total_cmp(&b.1))
+        };
+        match (
+            near(&scene.guides.horizontal, world.y),
+            near(&scene.guides.vertical, world.x),
+        ) {
+            (Some(h), Some(v)) if v.1 < h.1 => (Axis::Vertical, v.0),
+            (Some(h), _) => (Axis::Horizontal, h.0),
+            (None, Some(v)) => (Axis::Vertical, v.0),
+            (None, None) => return None,
+        }
+    } else {
+        return None;
+    };
+    Some(GuideDrag {
+        axis,
+        index,
+        before,
+    })
+}
+
+// A white rect for `place` to size and color.
+fn push_rect(scene: &mut UIScene, device: &wgpu::Device) -> usize {
+    let white = [1.0, 1.0, 1.0];
+    let origin = cgmath::Vector2::new(0.0, 0.0);
+    let mut element =
+        Player::with_gradient(device, origin, cgmath::Vector2::new(1.0, 1.0), white, white);
+    element.draggable = false;
+    scene.elements.push(element);
+    scene.elements.len() - 1
+}
+
+// The square in the middle of `element` that drags it, in screen pixels.
+fn handle_rect(scene: &UIScene, element: &Player, size: f32) -> Viewport {
+    let center = scene.camera.world_to_screen(cgmath::Vector2::new(
+        element.instance.position.x,
+        element.instance.position.y,
+    ));
+    Viewport {
+        x: center.x - size / 2.0,
+        y: center.y - size / 2.0,
+        width: size,
+        height: size,
+    }
+}
+
+// Scanning the system's fonts takes a while, but only happens the first time
+// the editor is shown.
+fn load_font(family: &str) -> anyhow::Result<TextFont> {
+    let system = SystemFonts::load();
+    let font = match system.find(family, 400, false) {
+        Ok(font) => font,
+        Err(e) => {
+            let first = system
+                .families()
+                .into_iter()
+                .next()
+                .context("no fonts are installed")?;
+            log::warn!("{:#}, using {} instead", e, first);
+            system.find(&first, 400, false)?
+        }
+    };
+    font.text_font()
+}
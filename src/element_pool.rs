@@ -0,0 +1,87 @@
+use cgmath::Rotation3;
+
+use crate::tween::Tween;
+use crate::ui_scene::{Player, UIScene};
+
+// Recycles scene elements for things that spawn and despawn all the time
+// (bullets, toasts, particles drawn as elements). Released elements stay in
+// the scene hidden, and keep their vertex/index/instance buffers so the next
+// `acquire` doesn't allocate anything on the GPU.
+pub struct ElementPool {
+    factory: Box<dyn Fn(&wgpu::Device) -> Player>,
+    free: Vec<usize>,
+    live: usize,
+}
+
+impl ElementPool {
+    pub fn new(factory: impl Fn(&wgpu::Device) -> Player + 'static) -> Self {
+        Self {
+            factory: Box::new(factory),
+            free: Vec::new(),
+            live: 0,
+        }
+    }
+
+    // Creates elements ahead of time, e.g. during loading, so the first
+    // spawns don't stall on buffer creation.
+    pub fn reserve(&mut self, scene: &mut UIScene, device: &wgpu::Device, count: usize) {
+        for _ in 0..count {
+            let index = self.create(scene, device);
+            self.free.push(index);
+        }
+    }
+
+    pub fn acquire(
+        &mut self,
+        scene: &mut UIScene,
+        device: &wgpu::Device,
+        position: cgmath::Vector2<f32>,
+    ) -> usize {
+        let index = match self.free.pop() {
+            Some(index) => index,
+            None => self.create(scene, device),
+        };
+        self.live += 1;
+
+        let element = &mut scene.elements[index];
+        element.visible = true;
+        element.instance.position = cgmath::Vector3::new(position.x, position.y, 0.0);
+        element.instance.rotation =
+            cgmath::Quaternion::from_axis_angle(cgmath::Vector3::unit_z(), cgmath::Deg(0.0));
+        element.instance.scale = 1.0;
+        element.instance.tint = [1.0, 1.0, 1.0, 1.0];
+        element.instance.params = [0.0; 4];
+        let elevation = &mut element.elevation;
+        *elevation = Tween::new(0.0, elevation.duration, elevation.easing);
+        index
+    }
+
+    pub fn release(&mut self, scene: &mut UIScene, index: usize) {
+        debug_assert!(
+            !self.free.contains(&index),
+            "element {} released twice",
+            index
+        );
+        scene.elements[index].visible = false;
+        if scene.focused == Some(index) {
+            scene.set_focus(None);
+        }
+        self.free.push(index);
+        self.live -= 1;
+    }
+
+    pub fn live(&self) -> usize {
+        self.live
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.live + self.free.len()
+    }
+
+    fn create(&self, scene: &mut UIScene, device: &wgpu::Device) -> usize {
+        let mut element = (self.factory)(device);
+        element.visible = false;
+        scene.elements.push(element);
+        scene.elements.len() - 1
+    }
+}
@@ -0,0 +1,492 @@
+use std::path::Path;
+
+use anyhow::{bail, Context};
+use wgpu::util::DeviceExt;
+
+use lyon::path::{Event, Path as LyonPath};
+use lyon::tessellation::{LineCap, LineJoin};
+
+use crate::color::{linear_to_srgb, srgb_to_linear};
+use crate::frame_context::FrameContext;
+use crate::texture::Texture;
+use crate::ui_scene::{opaque, BlendMode, OrthographicCamera, UIScene, SHADOW_LAYERS};
+
+impl UIScene {
+    // Renders `element` and everything parented under it into a PNG with a
+    // transparent background, e.g. to save a diagram or a node graph:
+    //
+    //     scene.export_png(&device, &queue, graph, 2.0, "graph.png")?;
+    //
+    // `scale` is in pixels per world unit, so the image doesn't depend on the
+    // current zoom. Elements are drawn as of the last `prepare`, in draw
+    // order, ignoring clip rects. Blocks until the image is read back.
+    pub fn export_png(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        element: usize,
+        scale: f32,
+        path: impl AsRef<Path>,
+    ) -> anyhow::Result<()> {
+        let elements = self.subtree(element);
+        if elements.is_empty() {
+            bail!("element {element} has nothing visible to export");
+        }
+        let Some(bounds) = self.world_bounds(&elements) else {
+            bail!("nothing to export");
+        };
+        self.export_elements(device, queue, &elements, bounds, scale, path.as_ref())
+    }
+
+    // Like `export_png` for every visible element, e.g. for a poster of a
+    // large canvas. Images larger than the adapter's textures are rendered in
+    // tiles and stitched together, so the size is only bound by memory.
+    pub fn export_scene_png(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        scale: f32,
+        path: impl AsRef<Path>,
+    ) -> anyhow::Result<()> {
+        let elements = self
+            .draw_order()
+            .into_iter()
+            .filter(|&i| self.elements[i].visible)
+            .collect::<Vec<_>>();
+        if elements.is_empty() {
+            bail!("the scene has nothing visible to export");
+        }
+        let Some(bounds) = self.world_bounds(&elements) else {
+            bail!("nothing to export");
+        };
+        self.export_elements(device, queue, &elements, bounds, scale, path.as_ref())
+    }
+
+    // Like `export_scene_png` cropped to the document, see
+    // `UIScene::content_bounds`. Without a document it's the same.
+    pub fn export_document_png(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        scale: f32,
+        path: impl AsRef<Path>,
+    ) -> anyhow::Result<()> {
+        let Some(bounds) = self.content_bounds() else {
+            bail!("the scene has nothing visible to export");
+        };
+        let elements = self
+            .draw_order()
+            .into_iter()
+            .filter(|&i| self.elements[i].visible)
+            .collect::<Vec<_>>();
+        self.export_elements(device, queue, &elements, bounds, scale, path.as_ref())
+    }
+
+    fn export_elements(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        elements: &[usize],
+        (min, max): (cgmath::Vector2<f32>, cgmath::Vector2<f32>),
+        scale: f32,
+        path: &Path,
+    ) -> anyhow::Result<()> {
+        let Some(frame) = self.prepared else {
+            bail!("call `prepare` before exporting");
+        };
+        let width = ((max.x - min.x) * scale).ceil().max(1.0) as u32;
+        let height = ((max.y - min.y) * scale).ceil().max(1.0) as u32;
+        let format = self.resources.format;
+        let swizzle = match format {
+            wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb => false,
+            wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb => true,
+            _ => bail!("can't export from a {format:?} scene"),
+        };
+
+        let tile = device.limits().max_texture_dimension_2d.min(MAX_TILE);
+        let mut image = image::RgbaImage::new(width, height);
+        for y in (0..height).step_by(tile as usize) {
+            for x in (0..width).step_by(tile as usize) {
+                let (w, h) = (tile.min(width - x), tile.min(height - y));
+                // Tiles start on whole pixels, so their edges line up
+                // without seams.
+                let camera = OrthographicCamera {
+                    position: cgmath::Vector2::new(
+                        min.x + (x as f32 + w as f32 / 2.0) / scale,
+                        max.y - (y as f32 + h as f32 / 2.0) / scale,
+                    ),
+                    scale,
+                    width: w as f32,
+                    height: h as f32,
+                };
+                let pixels = self.render_tile(device, queue, &frame, elements, &camera, swizzle)?;
+                let pixels = image::RgbaImage::from_raw(w, h, pixels)
+                    .context("export pixels don't match the tile size")?;
+                image::imageops::replace(&mut image, &pixels, x as i64, y as i64);
+            }
+        }
+        image
+            .save_with_format(path, image::ImageFormat::Png)
+            .with_context(|| format!("writing {}", path.display()))
+    }
+
+    // `elements` as seen by `camera`, as straight alpha RGBA rows.
+    fn render_tile(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        frame: &FrameContext,
+        elements: &[usize],
+        camera: &OrthographicCamera,
+        swizzle: bool,
+    ) -> anyhow::Result<Vec<u8>> {
+        let srgb = self.resources.format.is_srgb();
+        // Keeps the scene's time, so animated materials match the screen.
+        let mut uniform = self.camera_uniform;
+        uniform.update_view_proj(camera);
+        let camera_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("UI Export camera buffer"),
+            contents: bytemuck::cast_slice(&[uniform]),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        let camera_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("ui_export_camera_bind_group"),
+            layout: &self.resources.camera_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: camera_buffer.as_entire_binding(),
+            }],
+        });
+
+        let (width, height) = (camera.width as u32, camera.height as u32);
+        let size = wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        };
+        // The pipelines were built for the scene's sample count.
+        let target = |samples: u32, usage: wgpu::TextureUsages| {
+            device.create_texture(&wgpu::TextureDescriptor {
+                label: Some("UI Export target"),
+                size,
+                mip_level_count: 1,
+                sample_count: samples,
+                dimension: wgpu::TextureDimension::D2,
+                format: self.resources.format,
+                usage: wgpu::TextureUsages::RENDER_ATTACHMENT | usage,
+                view_formats: &[],
+            })
+        };
+        let output = target(1, wgpu::TextureUsages::COPY_SRC);
+        let output_view = output.create_view(&wgpu::TextureViewDescriptor::default());
+        let samples = self.resources.samples;
+        let multisampled = (samples > 1)
+            .then(|| target(samples, wgpu::TextureUsages::empty()))
+            .map(|texture| texture.create_view(&wgpu::TextureViewDescriptor::default()));
+        // As are their depth tests.
+        let depth = self.resources.depth.then(|| {
+            device
+                .create_texture(&wgpu::TextureDescriptor {
+                    label: Some("UI Export depth"),
+                    size,
+                    mip_level_count: 1,
+                    sample_count: samples,
+                    dimension: wgpu::TextureDimension::D2,
+                    format: Texture::DEPTH_FORMAT,
+                    usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+                    view_formats: &[],
+                })
+                .create_view(&wgpu::TextureViewDescriptor::default())
+        });
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("UI Export encoder"),
+        });
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("UI Export pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: multisampled.as_ref().unwrap_or(&output_view),
+                resolve_target: multisampled.as_ref().map(|_| &output_view),
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                    store: multisampled.is_none(),
+                },
+            })],
+            depth_stencil_attachment: depth.as_ref().map(|view| {
+                wgpu::RenderPassDepthStencilAttachment {
+                    view,
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(1.0),
+                        store: false,
+                    }),
+                    stencil_ops: None,
+                }
+            }),
+        });
+        render_pass.set_bind_group(0, &camera_bind_group, &[]);
+        let batch = self.batch_buffers.get(frame);
+        for &i in elements {
+            // Added since, so there's no instance of it yet.
+            let Some(&slot) = self.batch_slots.get(i) else {
+                continue;
+            };
+            let element = &self.elements[i];
+            let material = element
+                .texture_fill
+                .as_deref()
+                .unwrap_or(&self.material_bind_group);
+            render_pass.set_bind_group(1, material, &[]);
+            render_pass.set_pipeline(self.resources.pipeline(element.material, element.blend));
+            render_pass.set_vertex_buffer(0, element.vertex_buffer.slice(..));
+            render_pass.set_index_buffer(element.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+            // Lifted elements have their own instances, their shadow's
+            // before theirs, and the others are in the batch.
+            if element.is_elevated() {
+                render_pass.set_vertex_buffer(1, element.instance_buffers.get(frame).slice(..));
+                render_pass.draw_indexed(0..element.num_indices, 0, 0..(SHADOW_LAYERS as u32 + 1));
+            } else {
+                render_pass.set_vertex_buffer(1, batch.slice(..));
+                render_pass.draw_indexed(0..element.num_indices, 0, slot..slot + 1);
+            }
+        }
+        drop(render_pass);
+
+        // Rows of a buffer copy have to be aligned.
+        let row = width * 4;
+        let padded_row = row.next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+        let readback = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("UI Export readback"),
+            size: (padded_row * height) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        encoder.copy_texture_to_buffer(
+            output.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &readback,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_row),
+                    rows_per_image: Some(height),
+                },
+            },
+            size,
+        );
+        queue.submit(Some(encoder.finish()));
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        readback
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                let _ = sender.send(result);
+            });
+        device.poll(wgpu::Maintain::Wait);
+        receiver
+            .recv()
+            .context("the export readback was dropped")?
+            .context("mapping the export readback")?;
+
+        let mut pixels = Vec::with_capacity((row * height) as usize);
+        {
+            let mapped = readback.slice(..).get_mapped_range();
+            for line in mapped.chunks(padded_row as usize) {
+                for pixel in line[..row as usize].chunks(4) {
+                    let [r, g, b, a] = [pixel[0], pixel[1], pixel[2], pixel[3]];
+                    let (r, b) = if swizzle { (b, r) } else { (r, b) };
+                    pixels.extend(unpremultiply([r, g, b, a], srgb));
+                }
+            }
+        }
+        readback.unmap();
+        Ok(pixels)
+    }
+
+    // The whole scene as an SVG document, with shapes and paths kept as
+    // curves so it stays crisp at any size, e.g. for print. Drawn in draw
+    // order at the elements' current transforms and tints, with fill
+    // materials drawn full and clip rects ignored. Elements made from raw
+    // geometry, like plots, have no vector form and are left out. The view
+    // box is the scene's document if it has one, sized in its units.
+    pub fn to_svg(&self) -> String {
+        let elements = self
+            .draw_order()
+            .into_iter()
+            .filter(|&i| self.elements[i].visible)
+            .collect::<Vec<_>>();
+        let (min, max) = self.content_bounds().unwrap_or((
+            cgmath::Vector2::new(0.0, 0.0),
+            cgmath::Vector2::new(0.0, 0.0),
+        ));
+        // World +y is up, SVG +y down, so the whole document is flipped.
+        let (width, height) = (max.x - min.x, max.y - min.y);
+        let (page_width, page_height) = match &self.document {
+            Some(document) => {
+                let (size, suffix) = (document.size(), document.units.suffix());
+                (format!("{}{suffix}", size.x), format!("{}{suffix}", size.y))
+            }
+            None => (width.to_string(), height.to_string()),
+        };
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{} {} {width} {height}\" \
+             width=\"{page_width}\" height=\"{page_height}\">\n",
+            min.x, -max.y,
+        );
+        svg += "<g transform=\"scale(1 -1)\">\n";
+        let mut skipped = 0;
+        for i in elements {
+            let element = &self.elements[i];
+            let Some(drawing) = element.drawing(SVG_TOLERANCE) else {
+                skipped += 1;
+                continue;
+            };
+            let instance = &element.instance;
+            let tint = instance.tint;
+            let color = |[r, g, b, a]: [f32; 4]| {
+                let rgb = [r * tint[0], g * tint[1], b * tint[2]]
+                    .map(linear_to_srgb)
+                    .map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8);
+                (
+                    format!("#{:02x}{:02x}{:02x}", rgb[0], rgb[1], rgb[2]),
+                    a * tint[3],
+                )
+            };
+            let angle = (2.0 * instance.rotation.v.z.atan2(instance.rotation.s)).to_degrees();
+            svg += &format!(
+                "<g transform=\"translate({} {}) rotate({}) scale({})\"{}>\n",
+                instance.position.x,
+                instance.position.y,
+                angle,
+                instance.scale,
+                match element.blend {
+                    BlendMode::Normal => "",
+                    BlendMode::Additive => " style=\"mix-blend-mode:plus-lighter\"",
+                    BlendMode::Multiply => " style=\"mix-blend-mode:multiply\"",
+                    BlendMode::Screen => " style=\"mix-blend-mode:screen\"",
+                },
+            );
+            let data = path_data(&drawing.path);
+            if let Some((left, right)) = drawing.fill {
+                let ((left, left_alpha), (right, right_alpha)) = (color(left), color(right));
+                let fill = if (&left, left_alpha) == (&right, right_alpha) {
+                    format!("fill=\"{left}\" fill-opacity=\"{left_alpha}\"")
+                } else {
+                    let half = element.size.x / 2.0;
+                    svg += &format!(
+                        "<linearGradient id=\"fill{i}\" gradientUnits=\"userSpaceOnUse\" x1=\"{}\" y1=\"0\" x2=\"{half}\" y2=\"0\">\
+                         <stop offset=\"0\" stop-color=\"{left}\" stop-opacity=\"{left_alpha}\"/>\
+                         <stop offset=\"1\" stop-color=\"{right}\" stop-opacity=\"{right_alpha}\"/>\
+                         </linearGradient>\n",
+                        -half,
+                    );
+                    format!("fill=\"url(#fill{i})\"")
+                };
+                // Paths are filled even-odd, like the tessellator does.
+                svg += &format!("<path d=\"{data}\" {fill} fill-rule=\"evenodd\"/>\n");
+            }
+            if let Some(outline) = drawing.outline {
+                let (stroke, alpha) = color(opaque(outline.color));
+                let cap = match outline.stroke.cap {
+                    LineCap::Butt => "butt",
+                    LineCap::Round => "round",
+                    LineCap::Square => "square",
+                };
+                let join = match outline.stroke.join {
+                    LineJoin::Round => "round",
+                    LineJoin::Bevel => "bevel",
+                    LineJoin::Miter | LineJoin::MiterClip => "miter",
+                };
+                svg += &format!(
+                    "<path d=\"{data}\" fill=\"none\" stroke=\"{stroke}\" stroke-opacity=\"{alpha}\" \
+                     stroke-width=\"{}\" stroke-linecap=\"{cap}\" stroke-linejoin=\"{join}\"/>\n",
+                    outline.stroke.width,
+                );
+            }
+            svg += "</g>\n";
+        }
+        svg += "</g>\n</svg>\n";
+        if skipped > 0 {
+            log::warn!(
+                "Left {} elements without a vector form out of the SVG",
+                skipped
+            );
+        }
+        svg
+    }
+
+    pub fn export_svg(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        std::fs::write(path.as_ref(), self.to_svg())
+            .with_context(|| format!("writing {}", path.as_ref().display()))
+    }
+
+    // `root` and its descendants that are drawn, in draw order.
+    fn subtree(&self, root: usize) -> Vec<usize> {
+        let in_subtree = |element: usize| {
+            let mut current = Some(element);
+            // Bounded in case of a parent cycle.
+            for _ in 0..=self.elements.len() {
+                match current {
+                    Some(i) if i == root => return true,
+                    Some(i) => current = self.elements[i].parent,
+                    None => return false,
+                }
+            }
+            false
+        };
+        self.draw_order()
+            .into_iter()
+            .filter(|&i| self.elements[i].visible && in_subtree(i))
+            .collect()
+    }
+}
+
+// Largest tile rendered at once, which keeps each readback at 64MB.
+const MAX_TILE: u32 = 4096;
+
+// How far flattened arcs may stray from the circle, in element units. Fine
+// enough to stay smooth when the document is scaled up.
+const SVG_TOLERANCE: f32 = 0.01;
+
+fn path_data(path: &LyonPath) -> String {
+    let mut data = String::new();
+    for event in path.iter() {
+        let segment = match event {
+            Event::Begin { at } => format!("M{} {}", at.x, at.y),
+            Event::Line { to, .. } => format!("L{} {}", to.x, to.y),
+            Event::Quadratic { ctrl, to, .. } => {
+                format!("Q{} {} {} {}", ctrl.x, ctrl.y, to.x, to.y)
+            }
+            Event::Cubic {
+                ctrl1, ctrl2, to, ..
+            } => format!(
+                "C{} {} {} {} {} {}",
+                ctrl1.x, ctrl1.y, ctrl2.x, ctrl2.y, to.x, to.y
+            ),
+            Event::End { close: true, .. } => "Z".to_string(),
+            Event::End { close: false, .. } => continue,
+        };
+        if !data.is_empty() {
+            data.push(' ');
+        }
+        data += &segment;
+    }
+    data
+}
+
+// The target holds premultiplied colors, PNGs straight ones. Blending
+// happened in linear space, so sRGB values are divided after decoding.
+fn unpremultiply([r, g, b, a]: [u8; 4], srgb: bool) -> [u8; 4] {
+    if a == 0 {
+        return [0; 4];
+    }
+    let alpha = a as f32 / 255.0;
+    let channel = |c: u8| {
+        let c = c as f32 / 255.0;
+        let c = if srgb {
+            linear_to_srgb(srgb_to_linear(c) / alpha)
+        } else {
+            c / alpha
+        };
+        (c.min(1.0) * 255.0).round() as u8
+    };
+    [channel(r), channel(g), channel(b), a]
+}
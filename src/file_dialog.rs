@@ -0,0 +1,154 @@
+use std::future::Future;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::task::{Context, Poll, Waker};
+
+use raw_window_handle::{HasRawWindowHandle, RawWindowHandle};
+use rfd::{AsyncFileDialog, FileHandle};
+
+use crate::ui_scene::{UIEvent, UIScene};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DialogKind {
+    Open,
+    OpenMany,
+    Save,
+    Folder,
+}
+
+// The window a dialog belongs to, so it stays on top of it.
+#[derive(Copy, Clone)]
+struct Parent(RawWindowHandle);
+
+unsafe impl HasRawWindowHandle for Parent {
+    fn raw_window_handle(&self) -> RawWindowHandle {
+        self.0
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct DialogOptions {
+    title: Option<String>,
+    directory: Option<PathBuf>,
+    file_name: Option<String>,
+    filters: Vec<(String, Vec<String>)>,
+    parent: Option<Parent>,
+}
+
+impl DialogOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn title(mut self, title: &str) -> Self {
+        self.title = Some(title.to_string());
+        self
+    }
+
+    pub fn directory(mut self, directory: impl Into<PathBuf>) -> Self {
+        self.directory = Some(directory.into());
+        self
+    }
+
+    // Suggested name for save dialogs.
+    pub fn file_name(mut self, file_name: &str) -> Self {
+        self.file_name = Some(file_name.to_string());
+        self
+    }
+
+    // Extensions without the dot, e.g. `filter("Images", &["png", "jpg"])`.
+    pub fn filter(mut self, name: &str, extensions: &[&str]) -> Self {
+        let extensions = extensions.iter().map(|e| e.to_string()).collect();
+        self.filters.push((name.to_string(), extensions));
+        self
+    }
+
+    // The window has to outlive the dialog.
+    pub fn parent<W: HasRawWindowHandle>(mut self, window: &W) -> Self {
+        self.parent = Some(Parent(window.raw_window_handle()));
+        self
+    }
+
+    fn dialog(&self) -> AsyncFileDialog {
+        let mut dialog = AsyncFileDialog::new();
+        if let Some(title) = &self.title {
+            dialog = dialog.set_title(title);
+        }
+        if let Some(directory) = &self.directory {
+            dialog = dialog.set_directory(directory);
+        }
+        if let Some(file_name) = &self.file_name {
+            dialog = dialog.set_file_name(file_name);
+        }
+        for (name, extensions) in &self.filters {
+            dialog = dialog.add_filter(name, extensions);
+        }
+        if let Some(parent) = &self.parent {
+            dialog = dialog.set_parent(parent);
+        }
+        dialog
+    }
+}
+
+type Pending = Pin<Box<dyn Future<Output = Option<Vec<FileHandle>>>>>;
+
+// Native open and save dialogs that don't block the render loop. Showing a
+// dialog returns an id right away, and once the user is done a
+// `UIEvent::FilesChosen` with that id comes out of `UIScene::drain_events`:
+//
+//     let id = renderer.dialogs.show(
+//         DialogKind::Open,
+//         DialogOptions::new().filter("Scenes", &["json"]).parent(&window),
+//     );
+//
+// The dialogs run on the platform's own thread or event loop and are only
+// polled here, once per `update`.
+#[derive(Default)]
+pub struct FileDialogs {
+    next_id: u64,
+    pending: Vec<(u64, Pending)>,
+}
+
+impl FileDialogs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn show(&mut self, kind: DialogKind, options: DialogOptions) -> u64 {
+        let dialog = options.dialog();
+        let future: Pending = match kind {
+            DialogKind::Open => Box::pin(async move { dialog.pick_file().await.map(|f| vec![f]) }),
+            DialogKind::OpenMany => Box::pin(dialog.pick_files()),
+            DialogKind::Save => Box::pin(async move { dialog.save_file().await.map(|f| vec![f]) }),
+            DialogKind::Folder => {
+                Box::pin(async move { dialog.pick_folder().await.map(|f| vec![f]) })
+            }
+        };
+        let id = self.next_id;
+        self.next_id += 1;
+        self.pending.push((id, future));
+        id
+    }
+
+    pub fn is_open(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    // Queues an event on `scene` for every dialog that closed.
+    pub fn update(&mut self, scene: &mut UIScene) {
+        // Nothing to wake, every pending dialog is polled again next frame.
+        let mut context = Context::from_waker(Waker::noop());
+        self.pending.retain_mut(|(id, future)| {
+            let Poll::Ready(files) = future.as_mut().poll(&mut context) else {
+                return true;
+            };
+            let paths = files
+                .unwrap_or_default()
+                .iter()
+                .map(|file| file.path().to_path_buf())
+                .collect();
+            scene.push_event(UIEvent::FilesChosen { dialog: *id, paths });
+            false
+        });
+    }
+}
@@ -0,0 +1,124 @@
+use std::rc::Rc;
+
+use crate::element_pool::ElementPool;
+use crate::text::{GlyphAtlas, Halo, Text, TextFont};
+use crate::tween::Easing;
+use crate::ui_scene::UIScene;
+
+const LIFETIME: f32 = 0.9;
+const RISE_DISTANCE: f32 = 40.0;
+
+struct Floating {
+    text: Text,
+    // Where the text is centered when spawned.
+    origin: cgmath::Vector2<f32>,
+    color: [f32; 4],
+    age: f32,
+}
+
+// Spawns short-lived text in world space, like damage numbers, that rises
+// and fades out. Finished texts give their glyph elements back to a pool the
+// next spawn takes them from, so a steady stream of hits doesn't keep
+// growing the scene:
+//
+//     let mut hits = FloatingText::new(font, 18.0, GlyphAtlas::sdf(device, sampler))
+//         .with_halo(Halo::outline(1.5, [0.0, 0.0, 0.0]));
+//     hits.spawn(&mut scene, device, queue, position, "-120", [1.0, 0.3, 0.2, 1.0]);
+//     hits.update(&mut scene, device, dt); // every frame
+pub struct FloatingText {
+    pub lifetime: f32,
+    pub rise_distance: f32,
+    halo: Option<Halo>,
+    font: Rc<TextFont>,
+    size: f32,
+    // Of the spawned texts only, which keeps the ones it rasterizes small.
+    atlas: GlyphAtlas,
+    floating: Vec<Floating>,
+    pool: ElementPool,
+}
+
+impl FloatingText {
+    pub fn new(font: Rc<TextFont>, size: f32, atlas: GlyphAtlas) -> Self {
+        Self {
+            lifetime: LIFETIME,
+            rise_distance: RISE_DISTANCE,
+            halo: None,
+            font,
+            size,
+            atlas,
+            floating: Vec::new(),
+            pool: Text::glyph_pool(),
+        }
+    }
+
+    // For text over busy backgrounds. Only drawn from an SDF atlas. Pooled
+    // glyphs keep the halo's color, so it can't change once set.
+    pub fn with_halo(mut self, halo: Halo) -> Self {
+        self.halo = Some(halo);
+        self
+    }
+
+    pub fn active(&self) -> usize {
+        self.floating.len()
+    }
+
+    // Creates glyph elements ahead of time, e.g. during loading, so the
+    // first spawns don't stall on buffer creation.
+    pub fn reserve(&mut self, scene: &mut UIScene, device: &wgpu::Device, glyphs: usize) {
+        self.pool.reserve(scene, device, glyphs);
+    }
+
+    // `position` is where the text is centered.
+    pub fn spawn(
+        &mut self,
+        scene: &mut UIScene,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        position: cgmath::Vector2<f32>,
+        text: &str,
+        color: [f32; 4],
+    ) {
+        let mut floating = Text::new(self.font.clone(), self.size);
+        floating.reserve(scene, device, &mut self.pool, text.chars().count());
+        floating.set_halo(scene, queue, self.halo);
+        floating.set_text(scene, device, queue, &mut self.atlas, text);
+        self.floating.push(Floating {
+            text: floating,
+            origin: position,
+            color,
+            age: 0.0,
+        });
+        let last = self.floating.len() - 1;
+        self.place(scene, last, 0.0);
+    }
+
+    pub fn update(&mut self, scene: &mut UIScene, device: &wgpu::Device, dt: f32) {
+        let mut i = 0;
+        while i < self.floating.len() {
+            let floating = &mut self.floating[i];
+            floating.age += dt;
+            if floating.age >= self.lifetime {
+                floating.text.release(scene, &mut self.pool);
+                self.floating.swap_remove(i);
+                continue;
+            }
+            floating.text.update(scene, device, &mut self.atlas);
+            let t = floating.age / self.lifetime;
+            self.place(scene, i, t);
+            i += 1;
+        }
+    }
+
+    // Rises and, after the first half, fades the text `t` through its life.
+    fn place(&mut self, scene: &mut UIScene, index: usize, t: f32) {
+        let floating = &mut self.floating[index];
+        let rise = Easing::EaseOut.apply(t) * self.rise_distance;
+        let text = &mut floating.text;
+        let top_left =
+            floating.origin + cgmath::Vector2::new(-text.width() / 2.0, text.height() / 2.0 + rise);
+        text.set_position(scene, top_left);
+        let fade = 1.0 - Easing::EaseIn.apply((t - 0.5) * 2.0);
+        let [r, g, b, a] = floating.color;
+        text.set_color(scene, [r, g, b, a * fade]);
+    }
+}
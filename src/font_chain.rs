@@ -0,0 +1,174 @@
+use std::ops::{Range, RangeInclusive};
+use std::rc::Rc;
+
+// What a font can draw, as far as picking one for a piece of text goes.
+// Rasterizers implement it for the fonts they load.
+pub trait Font {
+    fn name(&self) -> &str;
+    fn has_glyph(&self, c: char) -> bool;
+}
+
+// A font known by the characters it covers, for apps whose rasterizer lives
+// elsewhere or to stand in for a font that isn't loaded yet.
+#[derive(Debug, Clone)]
+pub struct Coverage {
+    pub name: String,
+    pub ranges: Vec<RangeInclusive<char>>,
+}
+
+impl Coverage {
+    pub fn new(name: &str, ranges: Vec<RangeInclusive<char>>) -> Self {
+        Self {
+            name: name.to_string(),
+            ranges,
+        }
+    }
+}
+
+impl Font for Coverage {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn has_glyph(&self, c: char) -> bool {
+        self.ranges.iter().any(|range| range.contains(&c))
+    }
+}
+
+// A part of the text drawn with one font of the chain, by byte range.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FontRun {
+    pub font: usize,
+    pub range: Range<usize>,
+}
+
+// Fonts tried in order for each cluster of a string, so mixed-language text
+// draws without tofu:
+//
+//     let fonts = FontChain::new(latin).fallback(cjk).fallback(emoji);
+//     for run in fonts.resolve("Hi 世界 👋🏽") {
+//         shape(fonts.font(run.font), &text[run.range]);
+//     }
+//
+// A cluster is a character with the marks, joiners and modifiers that attach
+// to it, which all have to come from the same font to combine.
+#[derive(Clone)]
+pub struct FontChain {
+    fonts: Vec<Rc<dyn Font>>,
+}
+
+impl FontChain {
+    pub fn new(primary: Rc<dyn Font>) -> Self {
+        Self {
+            fonts: vec![primary],
+        }
+    }
+
+    // Tried after the fonts already in the chain.
+    pub fn fallback(mut self, font: Rc<dyn Font>) -> Self {
+        self.fonts.push(font);
+        self
+    }
+
+    pub fn font(&self, index: usize) -> &dyn Font {
+        self.fonts[index].as_ref()
+    }
+
+    pub fn len(&self) -> usize {
+        self.fonts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fonts.is_empty()
+    }
+
+    // Splits `text` into runs of the first font covering each whole
+    // cluster. Clusters no font covers fully go to the first one with
+    // their base character, or the primary font, which draws tofu.
+    pub fn resolve(&self, text: &str) -> Vec<FontRun> {
+        let mut runs: Vec<FontRun> = Vec::new();
+        for range in clusters(text) {
+            let cluster = &text[range.clone()];
+            let font = self.pick(cluster);
+            match runs.last_mut() {
+                Some(run) if run.font == font => run.range.end = range.end,
+                _ => runs.push(FontRun { font, range }),
+            }
+        }
+        runs
+    }
+
+    fn pick(&self, cluster: &str) -> usize {
+        let covers = |font: &Rc<dyn Font>| {
+            cluster
+                .chars()
+                .filter(|&c| !is_invisible(c))
+                .all(|c| font.has_glyph(c))
+        };
+        let base = cluster.chars().next();
+        self.fonts
+            .iter()
+            .position(covers)
+            .or_else(|| {
+                let base = base?;
+                self.fonts.iter().position(|font| font.has_glyph(base))
+            })
+            .unwrap_or(0)
+    }
+}
+
+// Joiners and selectors that fonts needn't have glyphs for.
+fn is_invisible(c: char) -> bool {
+    matches!(c, '\u{200C}' | '\u{200D}' | '\u{FE00}'..='\u{FE0F}' | '\u{E0100}'..='\u{E01EF}')
+}
+
+// Characters that belong to the cluster before them.
+fn extends(c: char) -> bool {
+    matches!(c,
+        // Combining marks.
+        '\u{0300}'..='\u{036F}'
+        | '\u{1AB0}'..='\u{1AFF}'
+        | '\u{1DC0}'..='\u{1DFF}'
+        | '\u{20D0}'..='\u{20FF}'
+        | '\u{FE20}'..='\u{FE2F}'
+        // Japanese voicing marks.
+        | '\u{3099}'..='\u{309A}'
+        // Skin tones and emoji tag sequences.
+        | '\u{1F3FB}'..='\u{1F3FF}'
+        | '\u{E0020}'..='\u{E007F}')
+        || is_invisible(c)
+}
+
+fn is_regional_indicator(c: char) -> bool {
+    ('\u{1F1E6}'..='\u{1F1FF}').contains(&c)
+}
+
+// Byte ranges of the clusters of `text`. An approximation of Unicode's
+// extended grapheme clusters, covering combining marks, emoji ZWJ
+// sequences, skin tones and flags.
+pub(crate) fn clusters(text: &str) -> Vec<Range<usize>> {
+    let mut clusters: Vec<Range<usize>> = Vec::new();
+    let mut previous: Option<char> = None;
+    // Regional indicators pair up into flags.
+    let mut unpaired_flag = false;
+    for (i, c) in text.char_indices() {
+        let end = i + c.len_utf8();
+        let joins = match previous {
+            None => false,
+            Some('\u{200D}') => true,
+            Some(_) if extends(c) => true,
+            Some(_) => is_regional_indicator(c) && unpaired_flag,
+        };
+        if is_regional_indicator(c) {
+            unpaired_flag = !(joins && unpaired_flag);
+        } else if !extends(c) {
+            unpaired_flag = false;
+        }
+        match clusters.last_mut() {
+            Some(cluster) if joins => cluster.end = end,
+            _ => clusters.push(i..end),
+        }
+        previous = Some(c);
+    }
+    clusters
+}
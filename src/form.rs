@@ -0,0 +1,374 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::text::{GlyphAtlas, Text, TextFont};
+use crate::text_layout::TextLayout;
+use crate::ui_scene::UIScene;
+
+// What a widget of the form holds.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldValue {
+    Number(f32),
+    Text(String),
+    Bool(bool),
+}
+
+impl FieldValue {
+    pub fn as_number(&self) -> Option<f32> {
+        match self {
+            FieldValue::Number(number) => Some(*number),
+            _ => None,
+        }
+    }
+
+    pub fn as_text(&self) -> Option<&str> {
+        match self {
+            FieldValue::Text(text) => Some(text),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            FieldValue::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+}
+
+pub type FormValues = HashMap<String, FieldValue>;
+
+// Returns the message to show under the field when the value won't do.
+pub type Validator = Box<dyn Fn(&FieldValue) -> Result<(), String>>;
+
+type Rule = Box<dyn Fn(&FormValues) -> Result<(), String>>;
+
+// Text that isn't blank, or a box that's checked.
+pub fn required() -> Validator {
+    Box::new(|value| match value {
+        FieldValue::Text(text) if text.trim().is_empty() => Err("required".to_string()),
+        FieldValue::Bool(false) => Err("required".to_string()),
+        _ => Ok(()),
+    })
+}
+
+pub fn range(min: f32, max: f32) -> Validator {
+    Box::new(move |value| match value.as_number() {
+        Some(number) if number < min || number > max => {
+            Err(format!("must be between {} and {}", min, max))
+        }
+        _ => Ok(()),
+    })
+}
+
+// In characters.
+pub fn min_length(length: usize) -> Validator {
+    Box::new(move |value| match value.as_text() {
+        Some(text) if text.chars().count() < length => {
+            Err(format!("at least {} characters", length))
+        }
+        _ => Ok(()),
+    })
+}
+
+pub fn max_length(length: usize) -> Validator {
+    Box::new(move |value| match value.as_text() {
+        Some(text) if text.chars().count() > length => {
+            Err(format!("at most {} characters", length))
+        }
+        _ => Ok(()),
+    })
+}
+
+// Fails with `message` where `valid` returns false.
+pub fn check(valid: impl Fn(&FieldValue) -> bool + 'static, message: &str) -> Validator {
+    let message = message.to_string();
+    Box::new(move |value| {
+        if valid(value) {
+            Ok(())
+        } else {
+            Err(message.clone())
+        }
+    })
+}
+
+pub struct FormField {
+    name: String,
+    value: FieldValue,
+    validators: Vec<Validator>,
+    rules: Vec<Rule>,
+    // From the widget, when it can't make a value of what was entered.
+    input_error: Option<String>,
+    error: Option<String>,
+    // Changed since the form was made, or submitted. Errors only show on
+    // fields that were, so a fresh form isn't all red.
+    touched: bool,
+    // Top left and width of the message, in world units.
+    message_at: Option<(cgmath::Vector2<f32>, f32)>,
+    message: Option<Text>,
+}
+
+impl FormField {
+    // Checked in order, the first failing one gives the message.
+    pub fn validate(&mut self, validator: Validator) -> &mut Self {
+        self.validators.push(validator);
+        self
+    }
+
+    // Checks the field against others, e.g. a password against its
+    // confirmation. Runs after the field's own validators passed.
+    pub fn rule(
+        &mut self,
+        rule: impl Fn(&FormValues) -> Result<(), String> + 'static,
+    ) -> &mut Self {
+        self.rules.push(Box::new(rule));
+        self
+    }
+
+    // Where the field's error shows, usually right under its widget.
+    pub fn message_at(&mut self, position: cgmath::Vector2<f32>, width: f32) -> &mut Self {
+        self.message_at = Some((position, width));
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn value(&self) -> &FieldValue {
+        &self.value
+    }
+
+    // Also for fields that weren't touched yet, which don't show it.
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    fn check(&self, values: &FormValues) -> Option<String> {
+        if let Some(error) = &self.input_error {
+            return Some(error.clone());
+        }
+        self.validators
+            .iter()
+            .find_map(|validator| validator(&self.value).err())
+            .or_else(|| self.rules.iter().find_map(|rule| rule(values).err()))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FormStyle {
+    pub font_size: f32,
+    pub error: [f32; 4],
+}
+
+impl Default for FormStyle {
+    fn default() -> Self {
+        Self {
+            font_size: 11.0,
+            error: [1.0, 0.45, 0.4, 1.0],
+        }
+    }
+}
+
+// The values of a settings screen's widgets, checked together before they
+// are applied. Widgets report their values, fields show why theirs won't
+// do under them, and the submit element stays disabled while a field the
+// user changed is invalid:
+//
+//     let mut form = Form::new(font, FormStyle::default());
+//     form.field("name", FieldValue::Text(String::new()))
+//         .validate(required())
+//         .validate(max_length(32))
+//         .message_at(name_message_position, 200.0);
+//     form.field("port", FieldValue::Number(8080.0))
+//         .validate(range(1.0, 65535.0));
+//     form.set_submit(save_button);
+//
+//     form.set_value("port", FieldValue::Number(port.value())); // when it changes
+//     form.set_input_error("port", port.error());
+//     form.update(&mut scene, device, queue, &mut glyphs); // every frame
+//
+//     UIEvent::Clicked(element) if element == save_button => {
+//         if let Some(values) = form.submit() {
+//             save(values);
+//         }
+//     }
+pub struct Form {
+    pub style: FormStyle,
+    font: Rc<TextFont>,
+    fields: Vec<FormField>,
+    submit: Option<usize>,
+    dirty: bool,
+}
+
+impl Form {
+    pub fn new(font: Rc<TextFont>, style: FormStyle) -> Self {
+        Self {
+            style,
+            font,
+            fields: Vec::new(),
+            submit: None,
+            dirty: true,
+        }
+    }
+
+    // Adds a field with its initial value, or returns the one of that name.
+    pub fn field(&mut self, name: &str, value: FieldValue) -> &mut FormField {
+        self.dirty = true;
+        let index = match self.fields.iter().position(|field| field.name == name) {
+            Some(index) => index,
+            None => {
+                self.fields.push(FormField {
+                    name: name.to_string(),
+                    value,
+                    validators: Vec::new(),
+                    rules: Vec::new(),
+                    input_error: None,
+                    error: None,
+                    touched: false,
+                    message_at: None,
+                    message: None,
+                });
+                self.fields.len() - 1
+            }
+        };
+        &mut self.fields[index]
+    }
+
+    pub fn get(&self, name: &str) -> Option<&FormField> {
+        self.fields.iter().find(|field| field.name == name)
+    }
+
+    // The element that submits the form, disabled while it can't.
+    pub fn set_submit(&mut self, element: usize) {
+        self.submit = Some(element);
+        self.dirty = true;
+    }
+
+    pub fn set_value(&mut self, name: &str, value: FieldValue) {
+        let Some(field) = self.get_mut(name) else {
+            return;
+        };
+        if field.value != value {
+            field.value = value;
+            field.touched = true;
+            self.dirty = true;
+        }
+    }
+
+    // For widgets with errors of their own, like a `SpinBox` whose
+    // expression doesn't evaluate. The field counts as invalid until it's
+    // cleared.
+    pub fn set_input_error(&mut self, name: &str, error: Option<&str>) {
+        let Some(field) = self.get_mut(name) else {
+            return;
+        };
+        if field.input_error.as_deref() != error {
+            field.input_error = error.map(str::to_string);
+            field.touched |= error.is_some();
+            self.dirty = true;
+        }
+    }
+
+    fn get_mut(&mut self, name: &str) -> Option<&mut FormField> {
+        let field = self.fields.iter_mut().find(|field| field.name == name);
+        if field.is_none() {
+            log::warn!("the form has no field {}", name);
+        }
+        field
+    }
+
+    pub fn values(&self) -> FormValues {
+        self.fields
+            .iter()
+            .map(|field| (field.name.clone(), field.value.clone()))
+            .collect()
+    }
+
+    pub fn is_valid(&self) -> bool {
+        let values = self.values();
+        self.fields
+            .iter()
+            .all(|field| field.check(&values).is_none())
+    }
+
+    // The values if every field is valid. Otherwise shows the errors of all
+    // fields, changed or not.
+    pub fn submit(&mut self) -> Option<FormValues> {
+        self.validate();
+        for field in &mut self.fields {
+            field.touched = true;
+        }
+        self.dirty = true;
+        let valid = self.fields.iter().all(|field| field.error.is_none());
+        valid.then(|| self.values())
+    }
+
+    // Marks every field untouched, hiding their errors, e.g. after the
+    // values were applied.
+    pub fn reset_touched(&mut self) {
+        for field in &mut self.fields {
+            field.touched = false;
+        }
+        self.dirty = true;
+    }
+
+    fn validate(&mut self) {
+        let values = self.values();
+        for field in &mut self.fields {
+            field.error = field.check(&values);
+        }
+    }
+
+    // Validates what changed and shows the errors.
+    pub fn update(
+        &mut self,
+        scene: &mut UIScene,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        atlas: &mut GlyphAtlas,
+    ) {
+        if self.dirty {
+            self.dirty = false;
+            self.validate();
+            for field in &mut self.fields {
+                let Some((position, width)) = field.message_at else {
+                    continue;
+                };
+                let message = field.message.get_or_insert_with(|| {
+                    let mut text = Text::new(self.font.clone(), self.style.font_size);
+                    text.set_position(scene, position);
+                    text.set_color(scene, self.style.error);
+                    text.set_layout(
+                        scene,
+                        device,
+                        queue,
+                        atlas,
+                        TextLayout {
+                            max_width: Some(width),
+                            ..TextLayout::default()
+                        },
+                    );
+                    text
+                });
+                let error = field.error.as_deref().filter(|_| field.touched);
+                message.set_text(scene, device, queue, atlas, error.unwrap_or(""));
+                message.set_visible(scene, error.is_some());
+            }
+            if let Some(submit) = self.submit {
+                let blocked = self
+                    .fields
+                    .iter()
+                    .any(|field| field.touched && field.error.is_some());
+                scene.elements[submit].enabled = !blocked;
+            }
+        }
+        for message in self
+            .fields
+            .iter_mut()
+            .filter_map(|field| field.message.as_mut())
+        {
+            message.update(scene, device, atlas);
+        }
+    }
+}
@@ -0,0 +1,49 @@
+// How many frames the CPU may record ahead of the GPU. Per-frame resources
+// get one copy each, so writing frame N+2 never touches what frame N reads.
+pub const FRAMES_IN_FLIGHT: usize = 2;
+
+// Handed to `update`/`render` for one frame. `slot` picks which copy of the
+// per-frame resources is safe to write, `begin_frame` having waited for the
+// frame that last used it.
+#[derive(Debug, Copy, Clone)]
+pub struct FrameContext {
+    pub number: u64,
+    pub slot: usize,
+    // Seconds since the previous frame.
+    pub dt: f32,
+}
+
+// One `T` per frame in flight.
+pub struct PerFrame<T>([T; FRAMES_IN_FLIGHT]);
+
+impl<T> PerFrame<T> {
+    pub fn new(f: impl FnMut(usize) -> T) -> Self {
+        Self(std::array::from_fn(f))
+    }
+
+    pub fn get(&self, frame: &FrameContext) -> &T {
+        &self.0[frame.slot]
+    }
+
+    pub fn get_mut(&mut self, frame: &FrameContext) -> &mut T {
+        &mut self.0[frame.slot]
+    }
+
+    pub fn map<U>(&self, f: impl FnMut(&T) -> U) -> PerFrame<U> {
+        PerFrame(self.0.each_ref().map(f))
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
+        self.0.iter()
+    }
+}
+
+// Every copy, e.g. to hand them all to a `DeletionQueue`.
+impl<T> IntoIterator for PerFrame<T> {
+    type Item = T;
+    type IntoIter = std::array::IntoIter<T, FRAMES_IN_FLIGHT>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
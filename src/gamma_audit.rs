@@ -0,0 +1,77 @@
+use crate::color::srgb_to_linear;
+use crate::ui_scene::{Player, UIScene};
+
+// Blending is only correct when the GPU blends linear values: sRGB formats
+// decode before blending and encode after, float formats store linear values.
+// Plain unorm formats blend the encoded values, which darkens overlaps.
+pub fn blends_in_linear_space(format: wgpu::TextureFormat) -> bool {
+    format.is_srgb()
+        || matches!(
+            format,
+            wgpu::TextureFormat::Rgba16Float
+                | wgpu::TextureFormat::Rgba32Float
+                | wgpu::TextureFormat::Rg11b10Float
+        )
+}
+
+pub fn report(surface_format: wgpu::TextureFormat, scene_format: wgpu::TextureFormat) -> String {
+    let mut report = format!(
+        "Surface format: {:?} ({}), scene target: {:?}. ",
+        surface_format,
+        if surface_format.is_srgb() {
+            "sRGB encoded on write"
+        } else {
+            "no sRGB encoding"
+        },
+        scene_format
+    );
+    if blends_in_linear_space(scene_format) {
+        report.push_str(
+            "Blending happens in linear space: the half-transparent white square should match \
+             the left reference square.",
+        );
+    } else {
+        report.push_str(
+            "Blending happens in gamma space, overlaps will look too dark: the half-transparent \
+             white square will match the right reference square. Set \
+             `RendererOptions::force_linear_blending` to blend in a linear intermediate target.",
+        );
+    }
+    report
+}
+
+// Adds reference patterns above the scene content:
+// - a ramp of linear intensities from 0 to 1,
+// - 50% white blended over black, flanked by two opaque references: linear
+//   0.5 (what correct blending produces) and 0.5 in sRGB (what blending in
+//   gamma space produces).
+pub fn add_test_pattern(scene: &mut UIScene, device: &wgpu::Device) {
+    const STEPS: usize = 8;
+    let origin = cgmath::Vector2::new(-(STEPS as f32) * 25.0, 200.0);
+
+    for i in 0..STEPS {
+        let value = i as f32 / (STEPS - 1) as f32;
+        let mut step = Player::new(
+            device,
+            origin + cgmath::Vector2::new(i as f32 * 50.0 + 25.0, 0.0),
+            [value, value, value],
+        );
+        step.draggable = false;
+        scene.elements.push(step);
+    }
+
+    let blend_origin = cgmath::Vector2::new(0.0, 270.0);
+    let srgb_half = srgb_to_linear(0.5);
+    let patches: [(f32, [f32; 3], f32); 4] = [
+        (-75.0, [0.5, 0.5, 0.5], 1.0),
+        (0.0, [0.0, 0.0, 0.0], 1.0),
+        (0.0, [1.0, 1.0, 1.0], 0.5),
+        (75.0, [srgb_half, srgb_half, srgb_half], 1.0),
+    ];
+    for (x, color, alpha) in patches {
+        let mut patch = Player::new(device, blend_origin + cgmath::Vector2::new(x, 0.0), color);
+        patch.instance.tint[3] = alpha;
+        patch.draggable = false;
+        scene.elements.push(patch);
+    }
+}
@@ -0,0 +1,281 @@
+use cgmath::Rotation3;
+use lyon::math::point;
+use lyon::path::Path;
+use lyon::tessellation::{StrokeOptions, VertexBuffers};
+
+use crate::segment_label::SegmentLabel;
+use crate::tween::{Easing, Tween};
+use crate::ui_scene::{Outline, Player, Stroke, UIScene, Vertex};
+
+const NEEDLE_DURATION: f32 = 0.4;
+const NEEDLE_WIDTH: f32 = 4.0;
+const HUB_SIZE: f32 = 12.0;
+const TICK_LENGTH: f32 = 8.0;
+const TICK_WIDTH: f32 = 2.0;
+const TICK_GAP: f32 = 4.0;
+const LABEL_GLYPHS: usize = 5;
+// Arcs are drawn as polylines, one segment per this many degrees.
+const ARC_STEP: f32 = 3.0;
+
+#[derive(Debug, Clone)]
+pub struct GaugeZone {
+    pub from: f32,
+    pub to: f32,
+    pub color: [f32; 3],
+}
+
+#[derive(Debug, Clone)]
+pub struct GaugeStyle {
+    pub radius: f32,
+    pub thickness: f32,
+    // Angles in degrees, counterclockwise from +x. The dial runs clockwise
+    // from `start_angle` over `sweep`.
+    pub start_angle: f32,
+    pub sweep: f32,
+    pub min: f32,
+    pub max: f32,
+    // Number of intervals between tick marks, 0 for none.
+    pub ticks: u32,
+    pub track: [f32; 3],
+    // Drawn over the track, e.g. a red band near `max`.
+    pub zones: Vec<GaugeZone>,
+    pub needle: [f32; 3],
+    pub label: [f32; 3],
+}
+
+impl Default for GaugeStyle {
+    fn default() -> Self {
+        Self {
+            radius: 80.0,
+            thickness: 10.0,
+            start_angle: 225.0,
+            sweep: 270.0,
+            min: 0.0,
+            max: 100.0,
+            ticks: 10,
+            track: [0.2, 0.2, 0.2],
+            zones: Vec::new(),
+            needle: [0.95, 0.35, 0.2],
+            label: [1.0, 1.0, 1.0],
+        }
+    }
+}
+
+impl GaugeStyle {
+    fn angle(&self, value: f32) -> f32 {
+        let range = self.max - self.min;
+        let t = if range > 0.0 {
+            ((value - self.min) / range).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        self.start_angle - t * self.sweep
+    }
+}
+
+// An analog dial: track arc, warning zones, tick marks, a needle that eases
+// towards the value and the value as a number below the hub. Everything is
+// built once, updates only rotate the needle and change the label.
+pub struct Gauge {
+    pub style: GaugeStyle,
+    value: f32,
+    needle: Tween,
+    needle_element: usize,
+    label: SegmentLabel,
+    shown: Option<i32>,
+}
+
+impl Gauge {
+    pub fn new(
+        scene: &mut UIScene,
+        device: &wgpu::Device,
+        position: cgmath::Vector2<f32>,
+        style: GaugeStyle,
+    ) -> Self {
+        let mut push = |mut element: Player| {
+            element.draggable = false;
+            scene.elements.push(element);
+            scene.elements.len() - 1
+        };
+
+        let start = style.start_angle;
+        let end = style.start_angle - style.sweep;
+        push(arc(device, position, &style, start, end, style.track));
+        for zone in &style.zones {
+            let (from, to) = (style.angle(zone.from), style.angle(zone.to));
+            push(arc(device, position, &style, from, to, zone.color));
+        }
+        if style.ticks > 0 {
+            push(ticks(device, position, &style));
+        }
+
+        let white = [1.0, 1.0, 1.0];
+        let length = style.radius - style.thickness / 2.0 - TICK_GAP;
+        let needle_element = push(needle(device, position, length, style.needle));
+        push(Player::with_gradient(
+            device,
+            position,
+            cgmath::Vector2::new(HUB_SIZE, HUB_SIZE),
+            white,
+            white,
+        ));
+
+        let mut label = SegmentLabel::new(scene, device, LABEL_GLYPHS);
+        label.set_position(
+            scene,
+            position + cgmath::Vector2::new(0.0, -style.radius * 0.5),
+        );
+
+        let mut gauge = Self {
+            value: style.min,
+            needle: Tween::new(style.min, NEEDLE_DURATION, Easing::EaseOut),
+            needle_element,
+            label,
+            shown: None,
+            style,
+        };
+        gauge.update(scene, 0.0);
+        gauge
+    }
+
+    pub fn value(&self) -> f32 {
+        self.value
+    }
+
+    // Values outside the range pin the needle to the nearest end, the label
+    // still shows the real value.
+    pub fn set_value(&mut self, value: f32) {
+        self.value = value;
+        self.needle
+            .retarget(value.clamp(self.style.min, self.style.max));
+    }
+
+    pub fn update(&mut self, scene: &mut UIScene, dt: f32) {
+        self.needle.update(dt);
+        let angle = self.style.angle(self.needle.value());
+        // The needle geometry points up, at 90 degrees.
+        scene.elements[self.needle_element].instance.rotation =
+            cgmath::Quaternion::from_angle_z(cgmath::Deg(angle - 90.0));
+
+        let shown = self.value.round() as i32;
+        if self.shown != Some(shown) {
+            self.shown = Some(shown);
+            self.label
+                .set_text(scene, &shown.to_string(), self.style.label);
+        }
+    }
+}
+
+pub(crate) fn polar(radius: f32, degrees: f32) -> lyon::math::Point {
+    let (sin, cos) = degrees.to_radians().sin_cos();
+    point(radius * cos, radius * sin)
+}
+
+// Strokes `path` into an element whose uv spans a square of `extent` around
+// `position`.
+pub(crate) fn stroke(
+    device: &wgpu::Device,
+    position: cgmath::Vector2<f32>,
+    path: &Path,
+    width: f32,
+    extent: f32,
+    color: [f32; 3],
+) -> Player {
+    let geometry = stroke_geometry(path, width, extent, color);
+    let size = cgmath::Vector2::new(extent, extent);
+    Player::from_geometry(device, position, size, &geometry)
+}
+
+pub(crate) fn stroke_geometry(
+    path: &Path,
+    width: f32,
+    extent: f32,
+    color: [f32; 3],
+) -> VertexBuffers<Vertex, u16> {
+    let mut geometry: VertexBuffers<Vertex, u16> = VertexBuffers::new();
+    let outline = Outline {
+        stroke: Stroke::new(width),
+        color,
+    };
+    let size = cgmath::Vector2::new(extent, extent);
+    Player::stroke_into(
+        &mut geometry,
+        path,
+        &outline,
+        size,
+        StrokeOptions::DEFAULT_TOLERANCE,
+    );
+    geometry
+}
+
+fn arc(
+    device: &wgpu::Device,
+    position: cgmath::Vector2<f32>,
+    style: &GaugeStyle,
+    from: f32,
+    to: f32,
+    color: [f32; 3],
+) -> Player {
+    let steps = ((from - to).abs() / ARC_STEP).ceil().max(1.0) as u32;
+    let mut builder = Path::builder();
+    builder.begin(polar(style.radius, from));
+    for i in 1..=steps {
+        let angle = from + (to - from) * i as f32 / steps as f32;
+        builder.line_to(polar(style.radius, angle));
+    }
+    builder.end(false);
+
+    let extent = 2.0 * style.radius + style.thickness;
+    stroke(
+        device,
+        position,
+        &builder.build(),
+        style.thickness,
+        extent,
+        color,
+    )
+}
+
+fn ticks(device: &wgpu::Device, position: cgmath::Vector2<f32>, style: &GaugeStyle) -> Player {
+    let outer = style.radius - style.thickness / 2.0 - TICK_GAP;
+    let inner = outer - TICK_LENGTH;
+    let mut builder = Path::builder();
+    for i in 0..=style.ticks {
+        let angle = style.start_angle - style.sweep * i as f32 / style.ticks as f32;
+        builder.begin(polar(inner, angle));
+        builder.line_to(polar(outer, angle));
+        builder.end(false);
+    }
+
+    let extent = 2.0 * style.radius + style.thickness;
+    stroke(
+        device,
+        position,
+        &builder.build(),
+        TICK_WIDTH,
+        extent,
+        style.track,
+    )
+}
+
+fn needle(
+    device: &wgpu::Device,
+    position: cgmath::Vector2<f32>,
+    length: f32,
+    color: [f32; 3],
+) -> Player {
+    let mut builder = Path::builder();
+    builder.begin(point(0.0, 0.0));
+    builder.line_to(point(0.0, length));
+    builder.end(false);
+
+    let extent = 2.0 * length;
+    stroke(
+        device,
+        position,
+        &builder.build(),
+        NEEDLE_WIDTH,
+        extent,
+        color,
+    )
+}
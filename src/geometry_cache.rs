@@ -0,0 +1,93 @@
+use std::collections::HashMap;
+use std::rc::{Rc, Weak};
+
+use crate::ui_scene::{Player, Shape};
+
+struct Entry {
+    vertex_buffer: Weak<wgpu::Buffer>,
+    index_buffer: Weak<wgpu::Buffer>,
+    num_indices: u32,
+}
+
+// Tessellates each distinct shape and gradient once and lets every element
+// of that shape draw the same buffers, with its own instance for position,
+// scale and tint. Five hundred identical boxes then upload one box, and
+// draw in one instanced call where nothing else comes between them in the
+// draw order:
+//
+//     let mut cache = GeometryCache::new();
+//     let shape = Shape::Rect { width: 50.0, height: 50.0 };
+//     for i in 0..500 {
+//         let position = cgmath::Vector2::new(i as f32 * 60.0, 0.0);
+//         scene.elements.push(cache.element(device, position, shape, blue, blue));
+//     }
+//
+// The cache doesn't keep geometry alive: it's freed with the last element
+// using it. Changing the shape of one element with `set_shape` gives it
+// buffers of its own.
+#[derive(Default)]
+pub struct GeometryCache {
+    // Keyed by the debug output of shape and colors, which spells out every
+    // float exactly.
+    entries: HashMap<String, Entry>,
+}
+
+impl GeometryCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn element(
+        &mut self,
+        device: &wgpu::Device,
+        position: cgmath::Vector2<f32>,
+        shape: Shape,
+        left: [f32; 3],
+        right: [f32; 3],
+    ) -> Player {
+        let key = format!("{:?}", (shape, left, right));
+        if let Some(entry) = self.entries.get(&key) {
+            if let (Some(vertex_buffer), Some(index_buffer)) =
+                (entry.vertex_buffer.upgrade(), entry.index_buffer.upgrade())
+            {
+                return Player::with_shared_geometry(
+                    device,
+                    position,
+                    shape,
+                    (left, right),
+                    (vertex_buffer, index_buffer),
+                    entry.num_indices,
+                );
+            }
+        }
+
+        let element = Player::with_shape(device, position, shape, left, right);
+        self.entries.insert(
+            key,
+            Entry {
+                vertex_buffer: Rc::downgrade(&element.vertex_buffer),
+                index_buffer: Rc::downgrade(&element.index_buffer),
+                num_indices: element.num_indices,
+            },
+        );
+        element
+    }
+
+    // Shapes currently shared by at least one element.
+    pub fn len(&self) -> usize {
+        self.entries
+            .values()
+            .filter(|entry| entry.vertex_buffer.strong_count() > 0)
+            .count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // Forgets shapes no element uses anymore.
+    pub fn prune(&mut self) {
+        self.entries
+            .retain(|_, entry| entry.vertex_buffer.strong_count() > 0);
+    }
+}
@@ -0,0 +1,100 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::time::Instant;
+
+use crate::deletion_queue::DeletionQueue;
+use crate::frame_context::{FrameContext, FRAMES_IN_FLIGHT};
+use crate::gpu_errors;
+use crate::texture::{SamplerCache, SamplerConfig};
+use crate::ui_scene::UIResources;
+
+// Device, queue and the caches built on top of them. Scenes borrow this when
+// they're created so that two scenes targeting the same format share one
+// pipeline instead of compiling their own.
+pub struct GpuContext {
+    pub device: wgpu::Device,
+    pub queue: wgpu::Queue,
+    pub samplers: SamplerCache,
+    // What textures are sampled with unless asked otherwise.
+    pub default_sampler: SamplerConfig,
+    pub deletion_queue: DeletionQueue,
+    frame_number: u64,
+    last_frame: Instant,
+    in_flight: [Option<wgpu::SubmissionIndex>; FRAMES_IN_FLIGHT],
+    ui_resources: HashMap<(wgpu::TextureFormat, u32, bool), Rc<UIResources>>,
+}
+
+impl GpuContext {
+    pub fn new(device: wgpu::Device, queue: wgpu::Queue) -> Self {
+        Self {
+            device,
+            queue,
+            samplers: SamplerCache::default(),
+            default_sampler: SamplerConfig::default(),
+            deletion_queue: DeletionQueue::new(),
+            frame_number: 0,
+            last_frame: Instant::now(),
+            in_flight: Default::default(),
+            ui_resources: HashMap::new(),
+        }
+    }
+
+    // Blocks until the GPU is done with the frame that last used this
+    // frame's slot, so its per-frame resources can be written again.
+    pub fn begin_frame(&mut self) -> FrameContext {
+        let slot = (self.frame_number % FRAMES_IN_FLIGHT as u64) as usize;
+        if let Some(submission) = self.in_flight[slot].take() {
+            self.device
+                .poll(wgpu::Maintain::WaitForSubmissionIndex(submission));
+        }
+
+        let now = Instant::now();
+        let dt = (now - self.last_frame).as_secs_f32();
+        self.last_frame = now;
+
+        let frame = FrameContext {
+            number: self.frame_number,
+            slot,
+            dt,
+        };
+        self.frame_number += 1;
+        frame
+    }
+
+    // Submits the frame's work and lets the deletion queue free whatever
+    // earlier frames were the last to use.
+    pub fn submit<I: IntoIterator<Item = wgpu::CommandBuffer>>(
+        &mut self,
+        frame: &FrameContext,
+        command_buffers: I,
+    ) {
+        let submission = gpu_errors::scoped(
+            &self.device,
+            format!("submitting frame {}", frame.number),
+            || self.queue.submit(command_buffers),
+        );
+        self.in_flight[frame.slot] = Some(submission);
+        self.deletion_queue.on_submit(&self.queue);
+        self.deletion_queue.collect();
+    }
+
+    pub fn ui_resources(
+        &mut self,
+        format: wgpu::TextureFormat,
+        samples: u32,
+        depth: bool,
+    ) -> Rc<UIResources> {
+        let device = &self.device;
+        self.ui_resources
+            .entry((format, samples, depth))
+            .or_insert_with(|| Rc::new(UIResources::new(device, format, samples, depth)))
+            .clone()
+    }
+
+    // Every pipeline set built so far, for diagnostics.
+    pub fn cached_ui_resources(&self) -> impl Iterator<Item = &UIResources> {
+        self.ui_resources
+            .values()
+            .map(|resources| resources.as_ref())
+    }
+}
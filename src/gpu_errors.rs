@@ -0,0 +1,75 @@
+use std::backtrace::Backtrace;
+use std::cell::RefCell;
+
+// wgpu reports validation errors with its own labels only, and panics on
+// errors nobody captured. This keeps a stack of what the crate was doing
+// ("creating UI pipeline for RadialFill material", "submitting frame 12")
+// so errors can be reported with that context instead.
+thread_local! {
+    static OPERATIONS: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+// Pops its operation off the stack when dropped.
+pub struct Operation(());
+
+impl Drop for Operation {
+    fn drop(&mut self) {
+        OPERATIONS.with(|operations| operations.borrow_mut().pop());
+    }
+}
+
+pub fn operation(label: impl Into<String>) -> Operation {
+    OPERATIONS.with(|operations| operations.borrow_mut().push(label.into()));
+    Operation(())
+}
+
+pub(crate) fn current_operations() -> String {
+    OPERATIONS.with(|operations| {
+        let operations = operations.borrow();
+        if operations.is_empty() {
+            "<no operation>".to_string()
+        } else {
+            operations.join(" > ")
+        }
+    })
+}
+
+fn report(operations: &str, error: &wgpu::Error) {
+    // Only captured when RUST_BACKTRACE is set, like panics.
+    let backtrace = Backtrace::capture();
+    log::error!("wgpu error while {}:\n{}\n{}", operations, error, backtrace);
+}
+
+// Replaces wgpu's default panic on uncaptured errors with a report. Native
+// backends raise errors synchronously, so the operation stack still
+// describes the call that failed.
+pub fn install_handler(device: &wgpu::Device) {
+    device.on_uncaptured_error(Box::new(|error| {
+        report(&current_operations(), &error);
+    }));
+}
+
+// Runs `f` inside a validation error scope labelled `label`.
+pub fn scoped<T>(device: &wgpu::Device, label: impl Into<String>, f: impl FnOnce() -> T) -> T {
+    let _operation = operation(label);
+    device.push_error_scope(wgpu::ErrorFilter::Validation);
+    let result = f();
+    let operations = current_operations();
+    let error = device.pop_error_scope();
+
+    cfg_if::cfg_if! {
+        if #[cfg(target_arch = "wasm32")] {
+            // The browser resolves error scopes asynchronously.
+            wasm_bindgen_futures::spawn_local(async move {
+                if let Some(error) = error.await {
+                    report(&operations, &error);
+                }
+            });
+        } else {
+            if let Some(error) = pollster::block_on(error) {
+                report(&operations, &error);
+            }
+        }
+    }
+    result
+}
@@ -0,0 +1,46 @@
+use serde::{Deserialize, Serialize};
+
+// Lines laid out over a scene to align elements against, in world units.
+// The editor creates and moves them; they live on the scene so they're saved
+// and restored with its snapshots.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Guides {
+    // World y of each horizontal line.
+    pub horizontal: Vec<f32>,
+    // World x of each vertical line.
+    pub vertical: Vec<f32>,
+}
+
+impl Guides {
+    pub fn is_empty(&self) -> bool {
+        self.horizontal.is_empty() && self.vertical.is_empty()
+    }
+
+    // Moves a box centered on `center`, `half` across each way, so its
+    // nearest edge or middle lies on a guide at most `reach` away. Each axis
+    // snaps on its own.
+    pub fn snap(
+        &self,
+        center: cgmath::Vector2<f32>,
+        half: cgmath::Vector2<f32>,
+        reach: f32,
+    ) -> cgmath::Vector2<f32> {
+        cgmath::Vector2::new(
+            snap_axis(&self.vertical, center.x, half.x, reach),
+            snap_axis(&self.horizontal, center.y, half.y, reach),
+        )
+    }
+}
+
+fn snap_axis(lines: &[f32], center: f32, half: f32, reach: f32) -> f32 {
+    let mut best: Option<f32> = None;
+    for &line in lines {
+        for offset in [-half, 0.0, half] {
+            let shift = line - (center + offset);
+            if shift.abs() <= reach && !best.is_some_and(|best| best.abs() <= shift.abs()) {
+                best = Some(shift);
+            }
+        }
+    }
+    center + best.unwrap_or(0.0)
+}
@@ -0,0 +1,153 @@
+use crate::tween::{Easing, Tween};
+use crate::ui_scene::{Player, UIMaterial, UIScene, PARAM_PROGRESS};
+
+// How long the lag bar waits before catching up after damage, so the lost
+// amount stays readable for a moment.
+const LAG_DELAY: f32 = 0.5;
+const LAG_DURATION: f32 = 0.4;
+const DEPLETE_DURATION: f32 = 0.1;
+const REFILL_DURATION: f32 = 0.6;
+const DIVIDER_WIDTH: f32 = 2.0;
+
+#[derive(Debug, Clone)]
+pub struct BarStyle {
+    pub size: cgmath::Vector2<f32>,
+    pub background: [f32; 3],
+    // Left and right ends of the fill gradient.
+    pub fill: ([f32; 3], [f32; 3]),
+    // Trailing bar shown over the amount just lost.
+    pub lag: [f32; 3],
+    // Leading bar shown over the amount being refilled.
+    pub refill: [f32; 3],
+    // Number of equal segments, 1 draws a plain bar.
+    pub segments: u32,
+}
+
+impl Default for BarStyle {
+    fn default() -> Self {
+        Self {
+            size: cgmath::Vector2::new(200.0, 16.0),
+            background: [0.1, 0.1, 0.1],
+            fill: ([0.8, 0.15, 0.1], [0.95, 0.45, 0.2]),
+            lag: [1.0, 1.0, 1.0],
+            refill: [0.4, 0.9, 0.4],
+            segments: 1,
+        }
+    }
+}
+
+// A bar made of stacked scene elements: background, lag bar, fill and
+// segment dividers. The fill and lag bars use the linear fill material, so
+// changing the value only touches their progress parameter.
+pub struct HudBar {
+    pub style: BarStyle,
+    value: f32,
+    fill: Tween,
+    lag: Tween,
+    lag_hold: f32,
+    fill_element: usize,
+    lag_element: usize,
+}
+
+impl HudBar {
+    pub fn new(
+        scene: &mut UIScene,
+        device: &wgpu::Device,
+        position: cgmath::Vector2<f32>,
+        style: BarStyle,
+    ) -> Self {
+        let size = style.size;
+        let mut push = |mut element: Player, material: UIMaterial| {
+            element.draggable = false;
+            element.material = material;
+            element.instance.params[PARAM_PROGRESS] = 1.0;
+            scene.elements.push(element);
+            scene.elements.len() - 1
+        };
+
+        push(
+            Player::with_gradient(device, position, size, style.background, style.background),
+            UIMaterial::Solid,
+        );
+        // The lag bar is white and tinted, so it can switch between the lag
+        // and refill colors without new geometry.
+        let white = [1.0, 1.0, 1.0];
+        let lag_element = push(
+            Player::with_gradient(device, position, size, white, white),
+            UIMaterial::LinearFill,
+        );
+        let fill_element = push(
+            Player::with_gradient(device, position, size, style.fill.0, style.fill.1),
+            UIMaterial::LinearFill,
+        );
+
+        let segment_width = size.x / style.segments.max(1) as f32;
+        for i in 1..style.segments {
+            let x = position.x - size.x / 2.0 + segment_width * i as f32;
+            push(
+                Player::with_gradient(
+                    device,
+                    cgmath::Vector2::new(x, position.y),
+                    cgmath::Vector2::new(DIVIDER_WIDTH, size.y),
+                    style.background,
+                    style.background,
+                ),
+                UIMaterial::Solid,
+            );
+        }
+
+        let bar = Self {
+            style,
+            value: 1.0,
+            fill: Tween::new(1.0, DEPLETE_DURATION, Easing::EaseOut),
+            lag: Tween::new(1.0, LAG_DURATION, Easing::EaseInOut),
+            lag_hold: 0.0,
+            fill_element,
+            lag_element,
+        };
+        bar.set_lag_color(scene, bar.style.lag);
+        bar
+    }
+
+    pub fn value(&self) -> f32 {
+        self.value
+    }
+
+    pub fn set_value(&mut self, scene: &mut UIScene, value: f32) {
+        let value = value.clamp(0.0, 1.0);
+        if value < self.value {
+            // Damage: the fill drops right away, the lag bar trails behind.
+            self.fill.duration = DEPLETE_DURATION;
+            self.fill.retarget(value);
+            self.lag_hold = LAG_DELAY;
+            self.set_lag_color(scene, self.style.lag);
+        } else if value > self.value {
+            // Refill: the lag bar jumps ahead and the fill grows into it.
+            self.lag = Tween::new(value, LAG_DURATION, Easing::EaseInOut);
+            self.lag_hold = 0.0;
+            self.fill.duration = REFILL_DURATION;
+            self.fill.retarget(value);
+            self.set_lag_color(scene, self.style.refill);
+        }
+        self.value = value;
+    }
+
+    pub fn update(&mut self, scene: &mut UIScene, dt: f32) {
+        self.fill.update(dt);
+        if self.lag_hold > 0.0 {
+            self.lag_hold -= dt;
+            if self.lag_hold <= 0.0 {
+                self.lag.retarget(self.value);
+            }
+        }
+        self.lag.update(dt);
+
+        scene.elements[self.fill_element].instance.params[PARAM_PROGRESS] = self.fill.value();
+        scene.elements[self.lag_element].instance.params[PARAM_PROGRESS] = self.lag.value();
+    }
+
+    fn set_lag_color(&self, scene: &mut UIScene, color: [f32; 3]) {
+        let tint = &mut scene.elements[self.lag_element].instance.tint;
+        tint[..3].copy_from_slice(&color);
+    }
+}
@@ -0,0 +1,191 @@
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::JoinHandle;
+
+use anyhow::Context;
+use image::DynamicImage;
+
+// Which requests go first. Equal ones go in the order they were made.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Priority {
+    // On screen now.
+    Visible,
+    // About to scroll into view.
+    Nearby,
+    Background,
+}
+
+// Decodes images on worker threads, most urgent first, so scrolling a large
+// gallery shows what's on screen before what was asked for earlier:
+//
+//     let id = loader.request("photos/0042.jpg", Priority::Nearby);
+//     loader.set_priority(id, Priority::Visible); // scrolled into view
+//     loader.cancel(other); // scrolled far away
+//     for (id, image) in loader.poll() { /* upload with Texture::from_image */ }
+//
+// Only decoding happens off the render thread, uploading stays with the
+// caller. A decode that already started runs to the end, but a cancelled
+// one's image is dropped instead of coming out of `poll`.
+pub struct ImageLoader {
+    shared: Arc<Shared>,
+    workers: Vec<JoinHandle<()>>,
+    results: Receiver<(u64, anyhow::Result<DynamicImage>)>,
+    next_id: u64,
+}
+
+struct Shared {
+    queue: Mutex<Queue>,
+    // Signalled when jobs come in or the loader shuts down.
+    wake: Condvar,
+}
+
+#[derive(Default)]
+struct Queue {
+    // Waiting to be picked up, with their priority and request order.
+    // Galleries queue hundreds at most, so picking the next one is a scan,
+    // which keeps reprioritizing trivial.
+    waiting: HashMap<u64, (Priority, u64, PathBuf)>,
+    // Being decoded, and which of those were cancelled since.
+    decoding: HashSet<u64>,
+    cancelled: HashSet<u64>,
+    stopping: bool,
+}
+
+impl Queue {
+    fn next(&mut self) -> Option<(u64, PathBuf)> {
+        let (&id, _) = self
+            .waiting
+            .iter()
+            .min_by_key(|(_, (priority, order, _))| (*priority, *order))?;
+        let (_, _, path) = self.waiting.remove(&id)?;
+        self.decoding.insert(id);
+        Some((id, path))
+    }
+}
+
+impl ImageLoader {
+    // One worker per core, less one for the render thread.
+    pub fn new() -> Self {
+        let cores = std::thread::available_parallelism().map_or(2, |n| n.get());
+        Self::with_threads(cores.saturating_sub(1).max(1))
+    }
+
+    pub fn with_threads(threads: usize) -> Self {
+        let shared = Arc::new(Shared {
+            queue: Mutex::new(Queue::default()),
+            wake: Condvar::new(),
+        });
+        let (sender, results) = mpsc::channel();
+        let workers = (0..threads.max(1))
+            .map(|i| {
+                let shared = shared.clone();
+                let sender = sender.clone();
+                std::thread::Builder::new()
+                    .name(format!("image loader {}", i))
+                    .spawn(move || work(&shared, &sender))
+                    .expect("spawning an image loader thread")
+            })
+            .collect();
+        Self {
+            shared,
+            workers,
+            results,
+            next_id: 0,
+        }
+    }
+
+    // Queues `path` for decoding, returning the id its image comes out of
+    // `poll` with.
+    pub fn request(&mut self, path: impl Into<PathBuf>, priority: Priority) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        let mut queue = self.shared.queue.lock().unwrap();
+        queue.waiting.insert(id, (priority, id, path.into()));
+        drop(queue);
+        self.shared.wake.notify_one();
+        id
+    }
+
+    // Returns false if the request already started or finished.
+    pub fn set_priority(&mut self, id: u64, priority: Priority) -> bool {
+        let mut queue = self.shared.queue.lock().unwrap();
+        match queue.waiting.get_mut(&id) {
+            Some(job) => {
+                job.0 = priority;
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Its image won't come out of `poll`, even if it's being decoded.
+    pub fn cancel(&mut self, id: u64) {
+        let mut queue = self.shared.queue.lock().unwrap();
+        if queue.waiting.remove(&id).is_none() && queue.decoding.contains(&id) {
+            queue.cancelled.insert(id);
+        }
+    }
+
+    // Drops everything not decoded yet, e.g. when leaving a gallery.
+    pub fn cancel_all(&mut self) {
+        let mut queue = self.shared.queue.lock().unwrap();
+        queue.waiting.clear();
+        let decoding = queue.decoding.clone();
+        queue.cancelled.extend(decoding);
+    }
+
+    // Requests not finished or cancelled yet.
+    pub fn pending(&self) -> usize {
+        let queue = self.shared.queue.lock().unwrap();
+        queue.waiting.len() + queue.decoding.len() - queue.cancelled.len()
+    }
+
+    // Images decoded since the last call, or why they couldn't be. Call it
+    // once per frame.
+    pub fn poll(&mut self) -> Vec<(u64, anyhow::Result<DynamicImage>)> {
+        self.results.try_iter().collect()
+    }
+}
+
+impl Default for ImageLoader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for ImageLoader {
+    // Waits for decodes in progress, queued ones are dropped.
+    fn drop(&mut self) {
+        self.shared.queue.lock().unwrap().stopping = true;
+        self.shared.wake.notify_all();
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+fn work(shared: &Shared, results: &Sender<(u64, anyhow::Result<DynamicImage>)>) {
+    loop {
+        let (id, path) = {
+            let mut queue = shared.queue.lock().unwrap();
+            loop {
+                if queue.stopping {
+                    return;
+                }
+                if let Some(job) = queue.next() {
+                    break job;
+                }
+                queue = shared.wake.wait(queue).unwrap();
+            }
+        };
+        let image = image::open(&path).with_context(|| format!("decoding {}", path.display()));
+        let mut queue = shared.queue.lock().unwrap();
+        queue.decoding.remove(&id);
+        if !queue.cancelled.remove(&id) {
+            // The loader only goes away after joining its workers.
+            let _ = results.send((id, image));
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use cgmath::Vector2;
+use winit::event::MouseScrollDelta;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ScrollPriority {
+    // Scrolling over an element is handed to the element, the camera only
+    // scrolls over empty space. Past the camera's bounds scrolling is left
+    // to the scene behind the UI.
+    Widget,
+    // The camera always scrolls up to its bounds, elements never see scroll
+    // input.
+    Camera,
+}
+
+pub struct InputConfig {
+    // Pixels scrolled per `MouseScrollDelta::LineDelta` line (mouse wheels).
+    pub line_scroll_scale: f32,
+    // Multiplier for `MouseScrollDelta::PixelDelta` (touchpads).
+    pub pixel_scroll_scale: f32,
+    pub invert_scroll_x: bool,
+    pub invert_scroll_y: bool,
+    pub scroll_priority: ScrollPriority,
+    // Distance in pixels the cursor has to travel after a press before it
+    // counts as a drag rather than a click.
+    pub drag_deadzone: f32,
+    // Seconds a key is held before it repeats, and between repeats, see
+    // `TextEdit::update`.
+    pub key_repeat_delay: f32,
+    pub key_repeat_interval: f32,
+}
+
+impl Default for InputConfig {
+    fn default() -> Self {
+        Self {
+            line_scroll_scale: 40.0,
+            pixel_scroll_scale: 1.0,
+            invert_scroll_x: false,
+            invert_scroll_y: false,
+            scroll_priority: ScrollPriority::Widget,
+            drag_deadzone: 4.0,
+            key_repeat_delay: 0.5,
+            key_repeat_interval: 1.0 / 30.0,
+        }
+    }
+}
+
+impl InputConfig {
+    // Converts a winit scroll delta to pixels, with +y meaning "scroll up".
+    pub fn scroll_delta(&self, delta: &MouseScrollDelta) -> Vector2<f32> {
+        let mut delta = match delta {
+            MouseScrollDelta::LineDelta(x, y) => Vector2::new(*x, *y) * self.line_scroll_scale,
+            MouseScrollDelta::PixelDelta(position) => {
+                Vector2::new(position.x as f32, position.y as f32) * self.pixel_scroll_scale
+            }
+        };
+        if self.invert_scroll_x {
+            delta.x = -delta.x;
+        }
+        if self.invert_scroll_y {
+            delta.y = -delta.y;
+        }
+        delta
+    }
+
+    pub fn exceeds_deadzone(&self, from: Vector2<f32>, to: Vector2<f32>) -> bool {
+        let d = to - from;
+        d.x * d.x + d.y * d.y > self.drag_deadzone * self.drag_deadzone
+    }
+}
@@ -0,0 +1,634 @@
+use std::rc::Rc;
+
+use winit::event::{ElementState, MouseButton, WindowEvent};
+
+use crate::element_pool::ElementPool;
+use crate::text::{GlyphAtlas, Text, TextFont};
+use crate::text_layout::{TextAlign, TextLayout, VerticalAlign};
+use crate::ui_scene::{BlendMode, Player, Shape, UIMaterial, UIScene, Viewport};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PropertyValue {
+    F32 {
+        value: f32,
+        min: f32,
+        max: f32,
+    },
+    Bool(bool),
+    Color([f32; 3]),
+    // Both components share the range.
+    Vec2 {
+        value: cgmath::Vector2<f32>,
+        min: f32,
+        max: f32,
+    },
+    Enum {
+        selected: usize,
+        options: Vec<String>,
+    },
+}
+
+impl PropertyValue {
+    // Values that get the same editor, so it can be kept when only the
+    // value changed.
+    fn same_editor(&self, other: &PropertyValue) -> bool {
+        match (self, other) {
+            (PropertyValue::Enum { options: a, .. }, PropertyValue::Enum { options: b, .. }) => {
+                a.len() == b.len()
+            }
+            _ => std::mem::discriminant(self) == std::mem::discriminant(other),
+        }
+    }
+
+    // Slider position of a channel, 0..1.
+    fn channel(&self, channel: usize) -> f32 {
+        let t = match self {
+            PropertyValue::F32 { value, min, max } => (value - min) / (max - min),
+            PropertyValue::Color(color) => color[channel],
+            PropertyValue::Vec2 { value, min, max } => (value[channel] - min) / (max - min),
+            PropertyValue::Bool(_) | PropertyValue::Enum { .. } => 0.0,
+        };
+        if t.is_finite() {
+            t.clamp(0.0, 1.0)
+        } else {
+            0.0
+        }
+    }
+
+    fn set_channel(&mut self, channel: usize, t: f32) {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            PropertyValue::F32 { value, min, max } => *value = *min + (*max - *min) * t,
+            PropertyValue::Color(color) => color[channel] = t,
+            PropertyValue::Vec2 { value, min, max } => value[channel] = *min + (*max - *min) * t,
+            PropertyValue::Bool(_) | PropertyValue::Enum { .. } => {}
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Property {
+    pub name: String,
+    pub value: PropertyValue,
+}
+
+impl Property {
+    pub fn new(name: &str, value: PropertyValue) -> Self {
+        Self {
+            name: name.to_string(),
+            value,
+        }
+    }
+}
+
+// Something the inspector can edit. `properties` is read back every update,
+// so implementations can clamp or reject values in `set_property`.
+pub trait Inspectable {
+    fn properties(&self) -> Vec<Property>;
+    fn set_property(&mut self, name: &str, value: &PropertyValue);
+}
+
+const MATERIALS: [UIMaterial; 8] = [
+    UIMaterial::Solid,
+    UIMaterial::RadialFill,
+    UIMaterial::LinearFill,
+    UIMaterial::PaletteSwap,
+    UIMaterial::Sprite,
+    UIMaterial::Pattern,
+    UIMaterial::Gradient,
+    UIMaterial::Sdf,
+];
+
+const BLEND_MODES: [BlendMode; 4] = [
+    BlendMode::Normal,
+    BlendMode::Additive,
+    BlendMode::Multiply,
+    BlendMode::Screen,
+];
+
+impl Inspectable for Player {
+    fn properties(&self) -> Vec<Property> {
+        let position = self.instance.position;
+        let tint = self.instance.tint;
+        let material = MATERIALS
+            .iter()
+            .position(|&m| m == self.material)
+            .unwrap_or(0);
+        let blend = BLEND_MODES
+            .iter()
+            .position(|&b| b == self.blend)
+            .unwrap_or(0);
+        vec![
+            Property::new(
+                "position",
+                PropertyValue::Vec2 {
+                    value: cgmath::Vector2::new(position.x, position.y),
+                    min: -1000.0,
+                    max: 1000.0,
+                },
+            ),
+            Property::new(
+                "scale",
+                PropertyValue::F32 {
+                    value: self.instance.scale,
+                    min: 0.1,
+                    max: 4.0,
+                },
+            ),
+            Property::new("tint", PropertyValue::Color([tint[0], tint[1], tint[2]])),
+            Property::new(
+                "material",
+                PropertyValue::Enum {
+                    selected: material,
+                    options: MATERIALS.iter().map(|m| format!("{:?}", m)).collect(),
+                },
+            ),
+            Property::new(
+                "blend",
+                PropertyValue::Enum {
+                    selected: blend,
+                    options: BLEND_MODES.iter().map(|b| format!("{:?}", b)).collect(),
+                },
+            ),
+            Property::new("visible", PropertyValue::Bool(self.visible)),
+            Property::new("enabled", PropertyValue::Bool(self.enabled)),
+            Property::new("draggable", PropertyValue::Bool(self.draggable)),
+        ]
+    }
+
+    fn set_property(&mut self, name: &str, value: &PropertyValue) {
+        match (name, value) {
+            ("position", PropertyValue::Vec2 { value, .. }) => {
+                self.instance.position.x = value.x;
+                self.instance.position.y = value.y;
+            }
+            ("scale", PropertyValue::F32 { value, .. }) => self.instance.scale = *value,
+            ("tint", PropertyValue::Color(color)) => {
+                self.instance.tint[..3].copy_from_slice(color);
+            }
+            ("material", PropertyValue::Enum { selected, .. }) => {
+                self.material = MATERIALS[(*selected).min(MATERIALS.len() - 1)];
+            }
+            ("blend", PropertyValue::Enum { selected, .. }) => {
+                self.blend = BLEND_MODES[(*selected).min(BLEND_MODES.len() - 1)];
+            }
+            ("visible", PropertyValue::Bool(on)) => self.visible = *on,
+            ("enabled", PropertyValue::Bool(on)) => self.enabled = *on,
+            ("draggable", PropertyValue::Bool(on)) => self.draggable = *on,
+            _ => log::warn!("Element has no property {} of that type", name),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct InspectorStyle {
+    pub width: f32,
+    pub row_height: f32,
+    pub padding: f32,
+    pub font_size: f32,
+    // Room for the name column.
+    pub name_width: f32,
+    pub background: [f32; 3],
+    pub name: [f32; 4],
+    // Of the enum options.
+    pub text: [f32; 4],
+    pub track: [f32; 3],
+    pub fill: [f32; 3],
+    pub on: [f32; 3],
+    pub off: [f32; 3],
+}
+
+impl Default for InspectorStyle {
+    fn default() -> Self {
+        Self {
+            width: 280.0,
+            row_height: 22.0,
+            padding: 4.0,
+            font_size: 12.0,
+            name_width: 90.0,
+            background: [0.12, 0.12, 0.14],
+            name: [0.6, 0.6, 0.65, 1.0],
+            text: [0.85, 0.85, 0.88, 1.0],
+            track: [0.2, 0.2, 0.23],
+            fill: [0.35, 0.55, 0.85],
+            on: [0.35, 0.55, 0.85],
+            off: [0.25, 0.25, 0.28],
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Hit {
+    None,
+    // Slider for a channel of the value.
+    Slider(usize),
+    Toggle,
+    Option(usize),
+}
+
+// A name or option, in screen pixels relative to the viewport.
+struct Label {
+    text: String,
+    rect: Viewport,
+    align: TextAlign,
+    color: [f32; 4],
+}
+
+// One rect of an editor, in screen pixels relative to the viewport.
+struct Part {
+    rect: Viewport,
+    color: [f32; 3],
+    hit: Hit,
+}
+
+struct Field {
+    property: Property,
+    // Pool elements showing the parts, in the order `parts` lists them.
+    elements: Vec<usize>,
+    edited: bool,
+}
+
+struct Drag {
+    field: usize,
+    channel: usize,
+    track: Viewport,
+}
+
+// A panel of editors for the properties of one `Inspectable`, kept at a
+// fixed screen position whatever the camera does. Every property gets a row
+// with its name and an editor: sliders for numbers, vectors and colors, a
+// toggle for booleans and a row of buttons for enums.
+//
+// Editors are rects from a pool and labels are kept, so inspecting
+// something else reuses them:
+//
+//     let mut inspector = Inspector::new(font, position, InspectorStyle::default());
+//     if !inspector.input(&scene, &event) { // before the scene's
+//         scene.input(&event);
+//     }
+//     inspector.update(&mut scene, device, queue, &mut glyphs, &mut selected); // every frame
+pub struct Inspector {
+    pub style: InspectorStyle,
+    // Top left in screen pixels, relative to the viewport.
+    position: cgmath::Vector2<f32>,
+    fields: Vec<Field>,
+    background: Option<usize>,
+    pool: ElementPool,
+    font: Rc<TextFont>,
+    // The ones past what the fields show are hidden.
+    labels: Vec<Text>,
+    cursor: cgmath::Vector2<f32>,
+    drag: Option<Drag>,
+}
+
+impl Inspector {
+    pub fn new(font: Rc<TextFont>, position: cgmath::Vector2<f32>, style: InspectorStyle) -> Self {
+        Self {
+            style,
+            position,
+            fields: Vec::new(),
+            background: None,
+            font,
+            labels: Vec::new(),
+            pool: ElementPool::new(|device| {
+                let white = [1.0, 1.0, 1.0];
+                let mut element = Player::with_gradient(
+                    device,
+                    cgmath::Vector2::new(0.0, 0.0),
+                    cgmath::Vector2::new(1.0, 1.0),
+                    white,
+                    white,
+                );
+                element.draggable = false;
+                element
+            }),
+            cursor: cgmath::Vector2::new(0.0, 0.0),
+            drag: None,
+        }
+    }
+
+    pub fn set_position(&mut self, position: cgmath::Vector2<f32>) {
+        self.position = position;
+    }
+
+    // Drops the editors, e.g. when nothing is selected anymore.
+    pub fn clear(&mut self, scene: &mut UIScene) {
+        for field in self.fields.drain(..) {
+            for element in field.elements {
+                self.pool.release(scene, element);
+            }
+        }
+        if let Some(background) = self.background.take() {
+            self.pool.release(scene, background);
+        }
+        for label in &mut self.labels {
+            label.set_visible(scene, false);
+        }
+        self.drag = None;
+    }
+
+    // Call before `UIScene::input`, and skip it if this returns true.
+    pub fn input(&mut self, scene: &UIScene, event: &WindowEvent) -> bool {
+        match event {
+            WindowEvent::CursorMoved { position, .. } => {
+                self.cursor = cgmath::Vector2::new(
+                    position.x as f32 - scene.viewport.x,
+                    position.y as f32 - scene.viewport.y,
+                );
+                let Some(drag) = &self.drag else {
+                    return false;
+                };
+                let t = (self.cursor.x - drag.track.x) / drag.track.width;
+                let field = &mut self.fields[drag.field];
+                field.property.value.set_channel(drag.channel, t);
+                field.edited = true;
+                true
+            }
+            WindowEvent::MouseInput {
+                state: ElementState::Pressed,
+                button: MouseButton::Left,
+                ..
+            } => self.press(),
+            WindowEvent::MouseInput {
+                state: ElementState::Released,
+                button: MouseButton::Left,
+                ..
+            } => self.drag.take().is_some(),
+            _ => false,
+        }
+    }
+
+    fn press(&mut self) -> bool {
+        let cursor = self.cursor;
+        let Some(row) = self.row_at(cursor) else {
+            return false;
+        };
+        let parts = self.parts(row);
+        let hit = parts
+            .iter()
+            .find(|part| part.hit != Hit::None && part.rect.contains(cursor));
+        let field = &mut self.fields[row];
+        match hit.map(|part| (part.hit, part.rect)) {
+            Some((Hit::Slider(channel), track)) => {
+                field
+                    .property
+                    .value
+                    .set_channel(channel, (cursor.x - track.x) / track.width);
+                self.drag = Some(Drag {
+                    field: row,
+                    channel,
+                    track,
+                });
+            }
+            Some((Hit::Toggle, _)) => {
+                if let PropertyValue::Bool(on) = &mut field.property.value {
+                    *on = !*on;
+                }
+            }
+            Some((Hit::Option(option), _)) => {
+                if let PropertyValue::Enum { selected, .. } = &mut field.property.value {
+                    *selected = option;
+                }
+            }
+            Some((Hit::None, _)) | None => return true,
+        }
+        field.edited = true;
+        true
+    }
+
+    // Writes edited values to `target`, reads its properties back and places
+    // the editors. Call before `UIScene::prepare`.
+    pub fn update(
+        &mut self,
+        scene: &mut UIScene,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        atlas: &mut GlyphAtlas,
+        target: &mut dyn Inspectable,
+    ) {
+        for field in &mut self.fields {
+            if std::mem::take(&mut field.edited) {
+                target.set_property(&field.property.name, &field.property.value);
+            }
+        }
+
+        let properties = target.properties();
+        let same = properties.len() == self.fields.len()
+            && properties.iter().zip(&self.fields).all(|(p, field)| {
+                p.name == field.property.name && p.value.same_editor(&field.property.value)
+            });
+        if same {
+            for (property, field) in properties.into_iter().zip(&mut self.fields) {
+                field.property = property;
+            }
+        } else {
+            self.clear(scene);
+            let origin = cgmath::Vector2::new(0.0, 0.0);
+            self.background = Some(self.pool.acquire(scene, device, origin));
+            for (row, property) in properties.into_iter().enumerate() {
+                self.fields.push(Field {
+                    property,
+                    elements: Vec::new(),
+                    edited: false,
+                });
+                let count = self.parts(row).len();
+                self.fields[row].elements = (0..count)
+                    .map(|_| self.pool.acquire(scene, device, origin))
+                    .collect();
+            }
+        }
+
+        if let Some(background) = self.background {
+            let rect = Viewport {
+                x: self.position.x,
+                y: self.position.y,
+                width: self.style.width,
+                height: self.fields.len() as f32 * self.style.row_height,
+            };
+            place(scene, queue, background, rect, self.style.background);
+        }
+        for row in 0..self.fields.len() {
+            for (part, &element) in self.parts(row).iter().zip(&self.fields[row].elements) {
+                place(scene, queue, element, part.rect, part.color);
+            }
+        }
+        self.update_labels(scene, device, queue, atlas);
+    }
+
+    fn update_labels(
+        &mut self,
+        scene: &mut UIScene,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        atlas: &mut GlyphAtlas,
+    ) {
+        let labels = (0..self.fields.len())
+            .flat_map(|row| self.row_labels(row))
+            .collect::<Vec<_>>();
+        let scale = 1.0 / scene.camera.scale;
+        for (i, label) in labels.iter().enumerate() {
+            if i == self.labels.len() {
+                let mut text = Text::new(self.font.clone(), self.style.font_size);
+                text.set_color(scene, label.color);
+                self.labels.push(text);
+            }
+            let text = &mut self.labels[i];
+            let layout = TextLayout {
+                max_width: Some(label.rect.width.max(0.0)),
+                height: Some(label.rect.height),
+                wrap: false,
+                align: label.align,
+                vertical_align: VerticalAlign::Middle,
+                ..TextLayout::default()
+            };
+            if *text.layout() != layout {
+                text.set_layout(scene, device, queue, atlas, layout);
+            }
+            if text.text() != label.text {
+                text.set_text(scene, device, queue, atlas, &label.text);
+            }
+            // Over the editors, which the pool may add after the glyphs.
+            for &glyph in text.elements() {
+                scene.elements[glyph].z_index = 1;
+            }
+            text.set_color(scene, label.color);
+            let top_left = scene.camera.screen_to_world(label.rect.x, label.rect.y);
+            text.set_scale(scene, scale);
+            text.set_position(scene, top_left);
+            text.set_visible(scene, true);
+        }
+        for (i, text) in self.labels.iter_mut().enumerate() {
+            if i >= labels.len() {
+                text.set_visible(scene, false);
+            }
+            text.update(scene, device, atlas);
+        }
+    }
+
+    fn row_at(&self, point: cgmath::Vector2<f32>) -> Option<usize> {
+        let offset = point - self.position;
+        if !(0.0..self.style.width).contains(&offset.x) || offset.y < 0.0 {
+            return None;
+        }
+        let row = (offset.y / self.style.row_height) as usize;
+        (row < self.fields.len()).then_some(row)
+    }
+
+    // The name of a row, then the options of an enum.
+    fn row_labels(&self, row: usize) -> Vec<Label> {
+        let style = &self.style;
+        let property = &self.fields[row].property;
+        let rect = Viewport {
+            x: self.position.x + style.padding,
+            y: self.position.y + row as f32 * style.row_height,
+            width: style.name_width - style.padding * 2.0,
+            height: style.row_height,
+        };
+        let mut labels = vec![Label {
+            text: property.name.clone(),
+            rect,
+            align: TextAlign::Left,
+            color: style.name,
+        }];
+        if let PropertyValue::Enum { options, .. } = &property.value {
+            let buttons = self
+                .parts(row)
+                .into_iter()
+                .filter(|part| matches!(part.hit, Hit::Option(_)));
+            for (option, part) in options.iter().zip(buttons) {
+                labels.push(Label {
+                    text: option.clone(),
+                    rect: part.rect,
+                    align: TextAlign::Center,
+                    color: style.text,
+                });
+            }
+        }
+        labels
+    }
+
+    // The rects making up a row's editor.
+    fn parts(&self, row: usize) -> Vec<Part> {
+        let style = &self.style;
+        let property = &self.fields[row].property;
+        let top = self.position.y + row as f32 * style.row_height + style.padding;
+        let height = style.row_height - style.padding * 2.0;
+        let rect = |x: f32, width: f32| Viewport {
+            x,
+            y: top,
+            width: width.max(0.0),
+            height,
+        };
+        let part = |rect, color, hit| Part { rect, color, hit };
+        let mut parts = Vec::new();
+
+        let left = self.position.x + style.name_width;
+        let width = style.width - style.name_width - style.padding;
+        let slider = |parts: &mut Vec<Part>, x: f32, width: f32, channel: usize, fill| {
+            let t = property.value.channel(channel);
+            parts.push(part(rect(x, width), style.track, Hit::Slider(channel)));
+            parts.push(part(rect(x, width * t), fill, Hit::None));
+        };
+        match &property.value {
+            PropertyValue::F32 { .. } => slider(&mut parts, left, width, 0, style.fill),
+            PropertyValue::Vec2 { .. } => {
+                let half = (width - style.padding) / 2.0;
+                slider(&mut parts, left, half, 0, style.fill);
+                slider(&mut parts, left + half + style.padding, half, 1, style.fill);
+            }
+            PropertyValue::Color(color) => {
+                let channel_width = (width - height - style.padding * 3.0) / 3.0;
+                let channels = [[1.0, 0.2, 0.2], [0.2, 0.9, 0.2], [0.3, 0.4, 1.0]];
+                for (i, fill) in channels.into_iter().enumerate() {
+                    let x = left + i as f32 * (channel_width + style.padding);
+                    slider(&mut parts, x, channel_width, i, fill);
+                }
+                parts.push(part(rect(left + width - height, height), *color, Hit::None));
+            }
+            PropertyValue::Bool(on) => {
+                let color = if *on { style.on } else { style.off };
+                parts.push(part(rect(left, height), color, Hit::Toggle));
+            }
+            PropertyValue::Enum { selected, options } => {
+                let count = options.len().max(1) as f32;
+                let option_width = (width - style.padding * (count - 1.0)) / count;
+                for i in 0..options.len() {
+                    let x = left + i as f32 * (option_width + style.padding);
+                    let color = if i == *selected { style.on } else { style.off };
+                    parts.push(part(rect(x, option_width), color, Hit::Option(i)));
+                }
+            }
+        }
+        parts
+    }
+}
+
+// Fits a white rect element to `rect`, a screen rect relative to the
+// viewport, resizing it only when the size changed.
+pub(crate) fn place(
+    scene: &mut UIScene,
+    queue: &wgpu::Queue,
+    element: usize,
+    rect: Viewport,
+    color: [f32; 3],
+) {
+    let world = scene
+        .camera
+        .screen_to_world(rect.x + rect.width / 2.0, rect.y + rect.height / 2.0);
+    let scale = 1.0 / scene.camera.scale;
+    let element = &mut scene.elements[element];
+    let size = cgmath::Vector2::new(rect.width, rect.height);
+    if element.size != size {
+        let white = [1.0, 1.0, 1.0];
+        let shape = Shape::Rect {
+            width: size.x,
+            height: size.y,
+        };
+        element.reshape(queue, shape, white, white);
+    }
+    element.visible = rect.width > 0.0 && rect.height > 0.0;
+    element.instance.position.x = world.x;
+    element.instance.position.y = world.y;
+    element.instance.scale = scale;
+    element.instance.tint = [color[0], color[1], color[2], 1.0];
+}
@@ -0,0 +1,143 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use cgmath::Vector2;
+
+// Only the most recent part of a drag counts towards the fling velocity,
+// otherwise a slow drag followed by a quick flick would barely move.
+const VELOCITY_WINDOW: Duration = Duration::from_millis(100);
+// How much a drag past the bounds is damped.
+const RUBBER_BAND_RESISTANCE: f32 = 0.35;
+const MIN_VELOCITY: f32 = 1.0;
+
+#[derive(Debug, Copy, Clone)]
+pub struct Bounds {
+    pub min: Vector2<f32>,
+    pub max: Vector2<f32>,
+}
+
+impl Bounds {
+    pub fn clamp(&self, point: Vector2<f32>) -> Vector2<f32> {
+        Vector2::new(
+            point.x.clamp(self.min.x, self.max.x),
+            point.y.clamp(self.min.y, self.max.y),
+        )
+    }
+}
+
+// Tracks a scroll offset driven by drags and flings: velocity is sampled
+// while dragging, decays with friction after release and springs back
+// (rubber-bands) when the offset ends up outside `bounds`.
+pub struct Kinetic {
+    pub offset: Vector2<f32>,
+    pub velocity: Vector2<f32>,
+    // Fraction of velocity lost per second.
+    pub friction: f32,
+    // How quickly an overscrolled offset returns inside the bounds.
+    pub stiffness: f32,
+    pub bounds: Option<Bounds>,
+    samples: VecDeque<(Instant, Vector2<f32>)>,
+    dragging: bool,
+}
+
+impl Default for Kinetic {
+    fn default() -> Self {
+        Self {
+            offset: Vector2::new(0.0, 0.0),
+            velocity: Vector2::new(0.0, 0.0),
+            friction: 0.95,
+            stiffness: 12.0,
+            bounds: None,
+            samples: VecDeque::new(),
+            dragging: false,
+        }
+    }
+}
+
+impl Kinetic {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_dragging(&self) -> bool {
+        self.dragging
+    }
+
+    pub fn begin_drag(&mut self) {
+        self.dragging = true;
+        self.velocity = Vector2::new(0.0, 0.0);
+        self.samples.clear();
+        self.samples.push_back((Instant::now(), self.offset));
+    }
+
+    pub fn drag_by(&mut self, mut delta: Vector2<f32>) {
+        let now = Instant::now();
+        if let Some(bounds) = self.bounds {
+            let next = self.offset + delta;
+            let overscroll = next - bounds.clamp(next);
+            if overscroll.x != 0.0 {
+                delta.x *= RUBBER_BAND_RESISTANCE;
+            }
+            if overscroll.y != 0.0 {
+                delta.y *= RUBBER_BAND_RESISTANCE;
+            }
+        }
+        self.offset += delta;
+
+        self.samples.push_back((now, self.offset));
+        while let Some(&(time, _)) = self.samples.front() {
+            if now - time > VELOCITY_WINDOW {
+                self.samples.pop_front();
+            } else {
+                break;
+            }
+        }
+    }
+
+    pub fn end_drag(&mut self) {
+        self.dragging = false;
+        if let (Some(&(start, from)), Some(&(end, to))) =
+            (self.samples.front(), self.samples.back())
+        {
+            let elapsed = (end - start).as_secs_f32();
+            if elapsed > 0.0 {
+                self.velocity = (to - from) / elapsed;
+            }
+        }
+        self.samples.clear();
+    }
+
+    // Adds a velocity impulse, e.g. from a mouse wheel notch.
+    pub fn fling(&mut self, velocity: Vector2<f32>) {
+        self.velocity += velocity;
+    }
+
+    // Adds the velocity needed for the fling to coast roughly `distance`
+    // before friction stops it.
+    pub fn fling_distance(&mut self, distance: Vector2<f32>) {
+        self.velocity += distance * -(1.0 - self.friction).ln();
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        if self.dragging {
+            return;
+        }
+
+        self.offset += self.velocity * dt;
+        self.velocity *= (1.0 - self.friction).powf(dt);
+
+        if let Some(bounds) = self.bounds {
+            let clamped = bounds.clamp(self.offset);
+            if clamped != self.offset {
+                // Overscrolling eats velocity quickly, then the spring pulls
+                // the offset back inside.
+                self.velocity *= (-self.stiffness * dt).exp();
+                self.offset += (clamped - self.offset) * (1.0 - (-self.stiffness * dt).exp());
+            }
+        }
+
+        if self.velocity.x.abs() < MIN_VELOCITY && self.velocity.y.abs() < MIN_VELOCITY {
+            self.velocity = Vector2::new(0.0, 0.0);
+        }
+    }
+}
@@ -0,0 +1,240 @@
+use std::time::{Duration, Instant};
+
+use cgmath::Rotation3;
+use lyon::path::Path;
+
+use crate::gauge::{polar, stroke};
+use crate::ui_scene::{Player, Shape, UIScene};
+
+const DOUBLE_CLICK: Duration = Duration::from_millis(400);
+const INDICATOR_WIDTH: f32 = 3.0;
+const DETENT_LENGTH: f32 = 5.0;
+const DETENT_WIDTH: f32 = 2.0;
+const DETENT_GAP: f32 = 4.0;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum KnobDrag {
+    // Dragging up turns the knob up.
+    Vertical,
+    // The knob follows the cursor around its center.
+    Circular,
+}
+
+#[derive(Debug, Clone)]
+pub struct KnobStyle {
+    pub radius: f32,
+    // Angles in degrees, counterclockwise from +x. Turning up goes clockwise
+    // from `start_angle` over `sweep`.
+    pub start_angle: f32,
+    pub sweep: f32,
+    pub min: f32,
+    pub max: f32,
+    // Value restored by double clicking.
+    pub default: f32,
+    // Number of steps the value snaps to, 0 for a continuous knob.
+    pub detents: u32,
+    pub drag: KnobDrag,
+    // Vertical drag distance covering the whole range, in world units.
+    pub sensitivity: f32,
+    // Scales drag movement while shift is held.
+    pub fine: f32,
+    pub body: [f32; 3],
+    pub indicator: [f32; 3],
+}
+
+impl Default for KnobStyle {
+    fn default() -> Self {
+        Self {
+            radius: 24.0,
+            start_angle: 225.0,
+            sweep: 270.0,
+            min: 0.0,
+            max: 1.0,
+            default: 0.5,
+            detents: 0,
+            drag: KnobDrag::Vertical,
+            sensitivity: 200.0,
+            fine: 0.1,
+            body: [0.25, 0.25, 0.28],
+            indicator: [1.0, 1.0, 1.0],
+        }
+    }
+}
+
+impl KnobStyle {
+    fn angle(&self, value: f32) -> f32 {
+        let range = self.max - self.min;
+        let t = if range > 0.0 {
+            ((value - self.min) / range).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        self.start_angle - t * self.sweep
+    }
+
+    fn snap(&self, value: f32) -> f32 {
+        let value = value.clamp(self.min, self.max);
+        if self.detents == 0 {
+            return value;
+        }
+        let step = (self.max - self.min) / self.detents as f32;
+        self.min + ((value - self.min) / step).round() * step
+    }
+}
+
+// A rotary control driven by dragging on it. Shift slows the drag down for
+// fine adjustments and double clicking resets to the default value. The knob
+// reads the press state from the scene, so call `update` after the scene got
+// its input for the frame.
+pub struct Knob {
+    pub style: KnobStyle,
+    value: f32,
+    // Unsnapped value while dragging, so small movements add up to a detent.
+    raw: f32,
+    body: usize,
+    indicator: usize,
+    grab: Option<cgmath::Vector2<f32>>,
+    last_press: Option<Instant>,
+}
+
+impl Knob {
+    pub fn new(
+        scene: &mut UIScene,
+        device: &wgpu::Device,
+        position: cgmath::Vector2<f32>,
+        style: KnobStyle,
+    ) -> Self {
+        let mut push = |mut element: Player| {
+            element.draggable = false;
+            scene.elements.push(element);
+            scene.elements.len() - 1
+        };
+
+        if style.detents > 0 {
+            let inner = style.radius + DETENT_GAP;
+            let extent = 2.0 * (inner + DETENT_LENGTH);
+            let mut builder = Path::builder();
+            for i in 0..=style.detents {
+                let angle = style.start_angle - style.sweep * i as f32 / style.detents as f32;
+                builder.begin(polar(inner, angle));
+                builder.line_to(polar(inner + DETENT_LENGTH, angle));
+                builder.end(false);
+            }
+            let path = builder.build();
+            push(stroke(
+                device,
+                position,
+                &path,
+                DETENT_WIDTH,
+                extent,
+                style.body,
+            ));
+        }
+
+        let body = push(Player::with_shape(
+            device,
+            position,
+            Shape::Circle {
+                radius: style.radius,
+            },
+            style.body,
+            style.body,
+        ));
+
+        // Points up, rotated into place by `set_value`.
+        let mut builder = Path::builder();
+        builder.begin(polar(style.radius * 0.35, 90.0));
+        builder.line_to(polar(style.radius * 0.85, 90.0));
+        builder.end(false);
+        let path = builder.build();
+        let mut indicator = stroke(
+            device,
+            position,
+            &path,
+            INDICATOR_WIDTH,
+            2.0 * style.radius,
+            style.indicator,
+        );
+        indicator.draggable = false;
+        scene.elements.push(indicator);
+        let indicator = scene.elements.len() - 1;
+        scene.set_parent(indicator, Some(body));
+
+        let mut knob = Self {
+            value: style.default,
+            raw: style.default,
+            body,
+            indicator,
+            grab: None,
+            last_press: None,
+            style,
+        };
+        knob.set_value(scene, knob.style.default);
+        knob
+    }
+
+    pub fn value(&self) -> f32 {
+        self.value
+    }
+
+    // Returns whether the value changed.
+    pub fn set_value(&mut self, scene: &mut UIScene, value: f32) -> bool {
+        self.raw = value.clamp(self.style.min, self.style.max);
+        self.apply(scene, self.style.snap(value))
+    }
+
+    // Returns whether dragging or a reset changed the value this frame.
+    pub fn update(&mut self, scene: &mut UIScene) -> bool {
+        let pressed = scene
+            .pressed()
+            .is_some_and(|element| element == self.body || element == self.indicator);
+        if !pressed {
+            self.grab = None;
+            return false;
+        }
+
+        let cursor = scene.cursor();
+        let Some(last) = self.grab.replace(cursor) else {
+            // Pressed this frame.
+            let now = Instant::now();
+            let double = self
+                .last_press
+                .is_some_and(|last| now - last < DOUBLE_CLICK);
+            if double {
+                self.last_press = None;
+                return self.set_value(scene, self.style.default);
+            }
+            self.last_press = Some(now);
+            self.raw = self.value;
+            return false;
+        };
+
+        let range = self.style.max - self.style.min;
+        let mut delta = match self.style.drag {
+            KnobDrag::Vertical => (cursor.y - last.y) / self.style.sensitivity * range,
+            KnobDrag::Circular => {
+                let center = scene.elements[self.body].instance.position;
+                let angle =
+                    |p: cgmath::Vector2<f32>| (p.y - center.y).atan2(p.x - center.x).to_degrees();
+                // Wrapped so crossing the -180/180 seam doesn't jump.
+                let turned = (angle(cursor) - angle(last) + 540.0) % 360.0 - 180.0;
+                -turned / self.style.sweep * range
+            }
+        };
+        if scene.shortcuts.modifiers().shift() {
+            delta *= self.style.fine;
+        }
+
+        self.raw = (self.raw + delta).clamp(self.style.min, self.style.max);
+        self.apply(scene, self.style.snap(self.raw))
+    }
+
+    fn apply(&mut self, scene: &mut UIScene, value: f32) -> bool {
+        let changed = value != self.value;
+        self.value = value;
+        let angle = self.style.angle(value);
+        scene.elements[self.indicator].instance.rotation =
+            cgmath::Quaternion::from_angle_z(cgmath::Deg(angle - 90.0));
+        changed
+    }
+}
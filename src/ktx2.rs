@@ -0,0 +1,225 @@
+use std::rc::Rc;
+
+use anyhow::{bail, Context};
+
+use crate::texture::Texture;
+
+const IDENTIFIER: [u8; 12] = [
+    0xAB, b'K', b'T', b'X', b' ', b'2', b'0', 0xBB, b'\r', b'\n', 0x1A, b'\n',
+];
+// Identifier, nine header fields and the index up to the level index.
+const LEVELS_OFFSET: usize = 80;
+
+// A KTX2 texture holding BC1, BC3 or BC7 blocks, or plain RGBA8. Block
+// compressed backgrounds take a quarter to an eighth of the GPU memory
+// decoded ones do, but not every adapter samples them, so pair them with an
+// uncompressed copy:
+//
+//     let background = ktx2::load_texture(device, queue, "sky.ktx2", Some("sky.png"), sampler)?;
+//
+// Supercompressed files (Basis, zstd) and arrays, cubemaps and 3D textures
+// aren't supported.
+pub struct Ktx2 {
+    pub format: wgpu::TextureFormat,
+    pub width: u32,
+    pub height: u32,
+    // Largest first, as stored.
+    pub levels: Vec<Vec<u8>>,
+}
+
+impl Ktx2 {
+    pub fn parse(bytes: &[u8]) -> anyhow::Result<Self> {
+        if bytes.len() < LEVELS_OFFSET || bytes[..12] != IDENTIFIER {
+            bail!("not a KTX2 file");
+        }
+        let u32_at =
+            |offset: usize| u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap());
+        let vk_format = u32_at(12);
+        let (width, height, depth) = (u32_at(20), u32_at(24), u32_at(28));
+        let (layers, faces, level_count) = (u32_at(32), u32_at(36), u32_at(40));
+        let supercompression = u32_at(44);
+        let format = format_of(vk_format)
+            .with_context(|| format!("unsupported KTX2 format {}", vk_format))?;
+        if supercompression != 0 {
+            bail!("supercompressed KTX2 files aren't supported");
+        }
+        if depth > 0 || layers > 1 || faces != 1 {
+            bail!("only 2D KTX2 textures are supported");
+        }
+        if width == 0 || height == 0 {
+            bail!("KTX2 texture is {}x{}", width, height);
+        }
+        let (block, _) = format.block_dimensions();
+        if width % block != 0 || height % block != 0 {
+            bail!(
+                "{}x{} isn't a whole number of {}x{} blocks",
+                width,
+                height,
+                block,
+                block
+            );
+        }
+
+        // Halving down to 1x1 makes the most levels there can be.
+        let max_levels = 32 - width.max(height).leading_zeros();
+        if level_count > max_levels {
+            bail!(
+                "KTX2 texture of {}x{} can't have {} levels",
+                width,
+                height,
+                level_count
+            );
+        }
+        // Zero asks the loader to generate mips, which leaves just the one.
+        let level_count = level_count.max(1) as usize;
+        let mut levels = Vec::with_capacity(level_count);
+        for level in 0..level_count as u32 {
+            let entry = LEVELS_OFFSET + level as usize * 24;
+            let Some(index) = bytes.get(entry..entry + 16) else {
+                bail!("KTX2 level index is cut off");
+            };
+            let offset = u64::from_le_bytes(index[..8].try_into().unwrap()) as usize;
+            let length = u64::from_le_bytes(index[8..].try_into().unwrap()) as usize;
+            let expected = level_size(format, width, height, level);
+            if length != expected {
+                bail!(
+                    "KTX2 level {} holds {} bytes, expected {}",
+                    level,
+                    length,
+                    expected
+                );
+            }
+            let data = offset
+                .checked_add(length)
+                .and_then(|end| bytes.get(offset..end))
+                .with_context(|| format!("KTX2 level {} lies past the end of the file", level))?;
+            levels.push(data.to_vec());
+        }
+        Ok(Self {
+            format,
+            width,
+            height,
+            levels,
+        })
+    }
+
+    // Whether `device` can sample the texture as is.
+    pub fn is_supported(&self, device: &wgpu::Device) -> bool {
+        device.features().contains(self.format.required_features())
+    }
+
+    pub fn upload(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        label: Option<&str>,
+        sampler: Rc<wgpu::Sampler>,
+    ) -> anyhow::Result<Texture> {
+        if !self.is_supported(device) {
+            bail!("the device can't sample {:?} textures", self.format);
+        }
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label,
+            size: wgpu::Extent3d {
+                width: self.width,
+                height: self.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: self.levels.len() as u32,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: self.format,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        let (block, _) = self.format.block_dimensions();
+        let block_size = self.format.block_size(None).unwrap_or(4);
+        for (level, data) in self.levels.iter().enumerate() {
+            // Copies cover whole blocks, even where the level is smaller.
+            let blocks_x = blocks(self.width, level as u32, block);
+            let blocks_y = blocks(self.height, level as u32, block);
+            queue.write_texture(
+                wgpu::ImageCopyTexture {
+                    aspect: wgpu::TextureAspect::All,
+                    texture: &texture,
+                    mip_level: level as u32,
+                    origin: wgpu::Origin3d::ZERO,
+                },
+                data,
+                wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(blocks_x * block_size),
+                    rows_per_image: Some(blocks_y),
+                },
+                wgpu::Extent3d {
+                    width: blocks_x * block,
+                    height: blocks_y * block,
+                    depth_or_array_layers: 1,
+                },
+            );
+        }
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        Ok(Texture {
+            texture,
+            view,
+            sampler,
+        })
+    }
+}
+
+// Loads the KTX2 file at `path`, or the image at `fallback` where the
+// device can't sample its format. Without a fallback that's an error.
+pub fn load_texture(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    path: &str,
+    fallback: Option<&str>,
+    sampler: Rc<wgpu::Sampler>,
+) -> anyhow::Result<Texture> {
+    let bytes = std::fs::read(path).with_context(|| format!("reading {}", path))?;
+    let ktx2 = Ktx2::parse(&bytes).with_context(|| format!("parsing {}", path))?;
+    if ktx2.is_supported(device) {
+        return ktx2.upload(device, queue, Some(path), sampler);
+    }
+    let Some(fallback) = fallback else {
+        bail!(
+            "the device can't sample {:?} textures like {} and there's no fallback",
+            ktx2.format,
+            path
+        );
+    };
+    log::info!(
+        "{:?} isn't supported, loading {} instead",
+        ktx2.format,
+        fallback
+    );
+    let image = image::open(fallback).with_context(|| format!("reading {}", fallback))?;
+    Texture::from_image(device, queue, &image, Some(fallback), sampler)
+}
+
+// The wgpu format for a Vulkan one, RGB-only BC1 included since it decodes
+// the same apart from punch-through alpha.
+fn format_of(vk_format: u32) -> Option<wgpu::TextureFormat> {
+    use wgpu::TextureFormat::*;
+    Some(match vk_format {
+        37 => Rgba8Unorm,
+        43 => Rgba8UnormSrgb,
+        131 | 133 => Bc1RgbaUnorm,
+        132 | 134 => Bc1RgbaUnormSrgb,
+        137 => Bc3RgbaUnorm,
+        138 => Bc3RgbaUnormSrgb,
+        145 => Bc7RgbaUnorm,
+        146 => Bc7RgbaUnormSrgb,
+        _ => return None,
+    })
+}
+
+fn blocks(size: u32, level: u32, block: u32) -> u32 {
+    (size >> level).max(1).div_ceil(block)
+}
+
+fn level_size(format: wgpu::TextureFormat, width: u32, height: u32, level: u32) -> usize {
+    let (block, _) = format.block_dimensions();
+    let block_size = format.block_size(None).unwrap_or(4);
+    (blocks(width, level, block) * blocks(height, level, block) * block_size) as usize
+}
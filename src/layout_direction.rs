@@ -0,0 +1,63 @@
+use serde::{Deserialize, Serialize};
+use winit::event::VirtualKeyCode;
+
+// Which way horizontal layouts run. Right to left mirrors them for Arabic,
+// Hebrew and other RTL locales: rows fill from the right, indents and
+// paddings lead from the right, and arrows pointing along the flow turn
+// around. Set for the whole scene with `UIScene::direction`, or for a
+// subtree with `Player::direction`.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum LayoutDirection {
+    #[default]
+    LeftToRight,
+    RightToLeft,
+}
+
+impl LayoutDirection {
+    // For a BCP 47 tag like "ar-EG" or "he", by its language.
+    pub fn for_locale(locale: &str) -> Self {
+        let language = locale.split(['-', '_']).next().unwrap_or("");
+        match language.to_ascii_lowercase().as_str() {
+            "ar" | "arc" | "ckb" | "dv" | "fa" | "he" | "iw" | "ks" | "ps" | "sd" | "ug" | "ur"
+            | "yi" => LayoutDirection::RightToLeft,
+            _ => LayoutDirection::LeftToRight,
+        }
+    }
+
+    pub fn is_rtl(self) -> bool {
+        self == LayoutDirection::RightToLeft
+    }
+
+    // Where a point `offset` past the leading edge of a span ends up, with
+    // `start` the span's left edge and `width` its width.
+    pub fn place(self, start: f32, width: f32, offset: f32) -> f32 {
+        match self {
+            LayoutDirection::LeftToRight => start + offset,
+            LayoutDirection::RightToLeft => start + width - offset,
+        }
+    }
+
+    // Turn for icons drawn pointing right, like chevrons, to point along
+    // the flow instead.
+    pub fn flow_angle(self) -> cgmath::Deg<f32> {
+        match self {
+            LayoutDirection::LeftToRight => cgmath::Deg(0.0),
+            LayoutDirection::RightToLeft => cgmath::Deg(180.0),
+        }
+    }
+
+    // The arrow key moving along the flow, which is Left in RTL.
+    pub fn forward_key(self) -> VirtualKeyCode {
+        match self {
+            LayoutDirection::LeftToRight => VirtualKeyCode::Right,
+            LayoutDirection::RightToLeft => VirtualKeyCode::Left,
+        }
+    }
+
+    pub fn backward_key(self) -> VirtualKeyCode {
+        match self {
+            LayoutDirection::LeftToRight => VirtualKeyCode::Left,
+            LayoutDirection::RightToLeft => VirtualKeyCode::Right,
+        }
+    }
+}
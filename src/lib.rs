@@ -1,8 +1,86 @@
-mod model;
-mod model_renderer;
-mod resources;
-mod texture;
-mod ui_scene;
+pub mod animated_sprite;
+pub mod atlas;
+pub mod binding;
+pub mod breadcrumb;
+pub mod clip;
+pub mod clipboard;
+pub mod color;
+#[cfg(all(feature = "crash-report", not(target_arch = "wasm32")))]
+pub mod crash_report;
+pub mod curve;
+pub mod curve_editor;
+pub mod debug_flags;
+pub mod deletion_queue;
+pub mod document;
+pub mod drop_overlay;
+pub mod dynamic_atlas;
+#[cfg(feature = "editor")]
+pub mod editor;
+pub mod element_pool;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod export;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod file_dialog;
+pub mod floating_text;
+pub mod font_chain;
+pub mod form;
+pub mod frame_context;
+pub mod gamma_audit;
+pub mod gauge;
+pub mod geometry_cache;
+pub mod gpu_context;
+pub mod gpu_errors;
+pub mod guides;
+pub mod hud_bar;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod image_loader;
+pub mod input;
+pub mod inspector;
+pub mod kinetic;
+pub mod knob;
+pub mod ktx2;
+pub mod layout_direction;
+pub mod menu_bar;
+pub mod model;
+pub mod model_renderer;
+pub mod nine_slice;
+pub mod overlay;
+pub mod path_builder;
+pub mod plots;
+pub mod region;
+#[cfg(all(feature = "remote-debug", not(target_arch = "wasm32")))]
+pub mod remote_debug;
+pub mod render_target;
+pub mod renderer;
+pub mod resources;
+pub mod rich_text;
+pub mod ring_buffer;
+pub mod scene_builder;
+pub mod search_box;
+pub mod segment_label;
+pub mod selection_outline;
+pub mod shortcuts;
+pub mod snapshot;
+pub mod spin_box;
+pub mod splitter;
+pub mod streaming_texture;
+pub mod style;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod system_fonts;
+pub mod text;
+pub mod text_caret;
+pub mod text_edit;
+pub mod text_input;
+pub mod text_layout;
+pub mod texture;
+pub mod timeline;
+pub mod title_bar;
+pub mod tonemap;
+pub mod tree_view;
+pub mod tween;
+pub mod ui_scene;
+pub mod units;
+pub mod window_settings;
 
 use winit::{
     event::*,
@@ -10,83 +88,35 @@ use winit::{
     window::{Window, WindowBuilder},
 };
 
+use frame_context::FrameContext;
+pub use renderer::{Renderer, RendererOptions};
+use ui_scene::Viewport;
+use window_settings::{FullscreenMode, MonitorInfo, WindowSettings};
+
+// The winit-owned window and event loop side of things. Embedders that bring
+// their own window use `Renderer` directly instead.
 struct State {
-    surface: wgpu::Surface,
-    device: wgpu::Device,
-    queue: wgpu::Queue,
-    config: wgpu::SurfaceConfiguration,
-    size: winit::dpi::PhysicalSize<u32>,
     window: Window,
-    model_scene: model_renderer::ModelScene,
-    ui_scene: ui_scene::UIScene,
+    fullscreen: FullscreenMode,
+    renderer: Renderer,
+    // Caret the input method was last told about, see `update_ime`.
+    ime: Option<Viewport>,
 }
 
 impl State {
     async fn new(window: Window) -> Self {
         let size = window.inner_size();
-        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
-            backends: wgpu::Backends::all(),
-            dx12_shader_compiler: Default::default(),
-        });
-
-        let surface = unsafe { instance.create_surface(&window) }.unwrap();
-        let adapter = instance
-            .request_adapter(&wgpu::RequestAdapterOptions {
-                power_preference: wgpu::PowerPreference::default(),
-                compatible_surface: Some(&surface),
-                force_fallback_adapter: false,
-            })
-            .await
-            .unwrap();
-
-        let (device, queue) = adapter
-            .request_device(
-                &wgpu::DeviceDescriptor {
-                    features: wgpu::Features::empty(),
-                    limits: if cfg!(target_arch = "wasm32") {
-                        wgpu::Limits::downlevel_webgl2_defaults()
-                    } else {
-                        wgpu::Limits::default()
-                    },
-                    label: None,
-                },
-                None,
-            )
-            .await
-            .unwrap();
-
-        let surface_caps = surface.get_capabilities(&adapter);
-
-        let surface_format = surface_caps
-            .formats
-            .iter()
-            .copied()
-            .find(|f| f.is_srgb())
-            .unwrap_or(surface_caps.formats[0]);
-
-        let config = wgpu::SurfaceConfiguration {
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-            format: surface_format,
-            width: size.width,
-            height: size.height,
-            present_mode: surface_caps.present_modes[0],
-            alpha_mode: surface_caps.alpha_modes[0],
-            view_formats: vec![],
-        };
-
-        surface.configure(&device, &config);
-        let model_scene = model_renderer::ModelScene::new(&device, &config, &queue).await;
-        let ui_scene = ui_scene::UIScene::new(&device, &config).await;
+        // The window is owned by `State` alongside the renderer, so it
+        // outlives the surface.
+        let mut renderer =
+            unsafe { Renderer::new(&window, size, RendererOptions::from_env()) }.await;
+        renderer.set_scale_factor(window.scale_factor());
 
         Self {
             window,
-            surface,
-            device,
-            queue,
-            config,
-            size,
-            model_scene,
-            ui_scene,
+            fullscreen: FullscreenMode::Windowed,
+            renderer,
+            ime: None,
         }
     }
 
@@ -94,42 +124,73 @@ impl State {
         &self.window
     }
 
+    pub fn monitors(&self) -> Vec<MonitorInfo> {
+        window_settings::monitors(&self.window)
+    }
+
+    pub fn set_fullscreen(&mut self, mode: FullscreenMode, monitor: Option<usize>) {
+        window_settings::set_fullscreen(&self.window, mode, monitor);
+        self.fullscreen = mode;
+        // Some platforms apply the change without a Resized event.
+        self.resize(self.window.inner_size());
+    }
+
+    pub fn toggle_fullscreen(&mut self) {
+        match self.fullscreen {
+            FullscreenMode::Windowed => self.set_fullscreen(FullscreenMode::Borderless, None),
+            _ => self.set_fullscreen(FullscreenMode::Windowed, None),
+        }
+    }
+
+    pub fn save_window_settings(&self) {
+        let settings = WindowSettings::capture(&self.window, self.fullscreen);
+        if let Err(e) = settings.save(window_settings::SETTINGS_FILE) {
+            log::warn!("Couldn't save window settings: {}", e);
+        }
+    }
+
     pub fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
-        if new_size.width > 0 && new_size.height > 0 {
-            self.size = new_size;
-            self.config.width = new_size.width;
-            self.config.height = new_size.height;
-            self.surface.configure(&self.device, &self.config);
-            self.model_scene.resize(&self.device, &self.config);
+        // Minimizing reports a zero size on some platforms.
+        if new_size.width == 0 || new_size.height == 0 {
+            self.renderer.suspend();
+            return;
         }
+        self.renderer.resume();
+        self.renderer.resize(new_size);
     }
 
     pub fn input(&mut self, event: &WindowEvent) -> bool {
-        self.model_scene.input(event);
-        false
+        self.renderer.input(event)
+    }
+
+    pub fn update(&mut self, frame: &FrameContext) {
+        self.renderer.update(frame);
+        self.update_ime();
     }
 
-    pub fn update(&mut self) {
-        self.model_scene.update(&self.queue);
+    // Input methods are on while a text element has focus, with their
+    // candidate window just under its caret so it doesn't cover the text
+    // being composed. Only tells winit when the caret moved.
+    fn update_ime(&mut self) {
+        let caret = self.renderer.ui_scene.caret_rect();
+        if caret == self.ime {
+            return;
+        }
+        if caret.is_some() != self.ime.is_some() {
+            self.window.set_ime_allowed(caret.is_some());
+        }
+        if let Some(caret) = caret {
+            self.window
+                .set_ime_position(winit::dpi::PhysicalPosition::new(
+                    caret.x,
+                    caret.y + caret.height,
+                ));
+        }
+        self.ime = caret;
     }
 
-    pub fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
-        let output = self.surface.get_current_texture()?;
-        let view = output
-            .texture
-            .create_view(&wgpu::TextureViewDescriptor::default());
-
-        let mut encoder = self
-            .device
-            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
-                label: Some("Render Encoder"),
-            });
-
-        self.model_scene.render(&mut encoder, &view);
-        // self.ui_scene.render(&mut encoder, &view);
-        self.queue.submit(std::iter::once(encoder.finish()));
-        output.present();
-        Ok(())
+    pub fn render(&mut self, frame: &FrameContext) -> Result<(), wgpu::SurfaceError> {
+        self.renderer.render(frame)
     }
 }
 
@@ -141,12 +202,18 @@ pub async fn run() {
             console_log::init_with_level(log::Level::Warn).expect("Couldn't initialize logger");
         } else {
             env_logger::init();
+            #[cfg(feature = "crash-report")]
+            crash_report::install("wgpie-crash.txt");
         }
     }
 
     let event_loop = EventLoop::new();
 
-    let window = WindowBuilder::new().build(&event_loop).unwrap();
+    let settings = WindowSettings::load(window_settings::SETTINGS_FILE);
+    let window = settings
+        .apply_to_builder(WindowBuilder::new())
+        .build(&event_loop)
+        .unwrap();
     #[cfg(target_arch = "wasm32")]
     {
         // Winit prevents sizing with CSS, so we have to set
@@ -165,20 +232,34 @@ pub async fn run() {
     }
 
     let mut state = State::new(window).await;
+    log::info!("Monitors: {:?}", state.monitors());
+    if settings.fullscreen != FullscreenMode::Windowed {
+        settings.apply_to_window(state.window());
+        state.fullscreen = settings.fullscreen;
+    }
 
     event_loop.run(move |event, _, control_flow| match event {
         Event::RedrawRequested(window_id) if window_id == state.window().id() => {
-            state.update();
-            match state.render() {
+            let frame = state.renderer.begin_frame();
+            state.update(&frame);
+            match state.render(&frame) {
                 Ok(_) => {}
-                Err(wgpu::SurfaceError::Lost) => state.resize(state.size),
+                Err(wgpu::SurfaceError::Lost) => state.resize(state.renderer.size),
                 Err(wgpu::SurfaceError::OutOfMemory) => *control_flow = ControlFlow::Exit,
                 Err(e) => eprintln!("{:?}", e),
             }
         }
         Event::MainEventsCleared => {
-            state.window().request_redraw();
+            if state.renderer.is_suspended() {
+                // Nothing to draw, sleep until the next event.
+                *control_flow = ControlFlow::Wait;
+            } else {
+                *control_flow = ControlFlow::Poll;
+                state.window().request_redraw();
+            }
         }
+        Event::Suspended => state.renderer.suspend(),
+        Event::Resumed => state.renderer.resume(),
         Event::WindowEvent {
             ref event,
             window_id,
@@ -194,12 +275,28 @@ pub async fn run() {
                                 ..
                             },
                         ..
-                    } => *control_flow = ControlFlow::Exit,
+                    } => {
+                        state.save_window_settings();
+                        *control_flow = ControlFlow::Exit;
+                    }
+                    WindowEvent::KeyboardInput {
+                        input:
+                            KeyboardInput {
+                                state: ElementState::Pressed,
+                                virtual_keycode: Some(VirtualKeyCode::F11),
+                                ..
+                            },
+                        ..
+                    } => state.toggle_fullscreen(),
 
                     WindowEvent::Resized(physical_size) => {
                         state.resize(*physical_size);
                     }
-                    WindowEvent::ScaleFactorChanged { new_inner_size, .. } => {
+                    WindowEvent::ScaleFactorChanged {
+                        scale_factor,
+                        new_inner_size,
+                    } => {
+                        state.renderer.set_scale_factor(*scale_factor);
                         // new_inner_size is &&mut so we have to dereference it twice
                         state.resize(**new_inner_size);
                     }
@@ -0,0 +1,562 @@
+use std::rc::Rc;
+
+use winit::event::{ElementState, KeyboardInput, MouseButton, VirtualKeyCode, WindowEvent};
+
+use crate::path_builder::PathBuilder;
+use crate::text::{GlyphAtlas, Text, TextFont};
+use crate::text_layout::{TextLayout, VerticalAlign};
+use crate::ui_scene::{Player, Shape, UIScene};
+
+const CHEVRON_SIZE: f32 = 8.0;
+
+#[derive(Debug, Clone)]
+pub struct MenuItem {
+    pub label: String,
+    // Reported when the item is activated. Items with children open them
+    // instead.
+    pub action: String,
+    pub enabled: bool,
+    pub children: Vec<MenuItem>,
+}
+
+impl MenuItem {
+    pub fn new(label: &str, action: &str) -> Self {
+        Self {
+            label: label.to_string(),
+            action: action.to_string(),
+            enabled: true,
+            children: Vec::new(),
+        }
+    }
+
+    pub fn submenu(label: &str, children: Vec<MenuItem>) -> Self {
+        Self {
+            children,
+            ..Self::new(label, "")
+        }
+    }
+
+    pub fn disabled(mut self) -> Self {
+        self.enabled = false;
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MenuResponse {
+    Ignored,
+    Handled,
+    Activated(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct MenuBarStyle {
+    // Distance from the top of the viewport, e.g. to sit under a `TitleBar`.
+    pub top: f32,
+    // Height of the bar and of every dropdown row.
+    pub height: f32,
+    pub padding: f32,
+    pub font_size: f32,
+    pub min_dropdown_width: f32,
+    pub background: [f32; 3],
+    pub item: [f32; 3],
+    pub dropdown: [f32; 3],
+    pub chevron: [f32; 3],
+    pub text: [f32; 4],
+    // Multiplies the color of the highlighted item.
+    pub hover_tint: [f32; 4],
+    // Multiplies the color of items whose menu is open.
+    pub open_tint: [f32; 4],
+}
+
+impl Default for MenuBarStyle {
+    fn default() -> Self {
+        Self {
+            top: 0.0,
+            height: 24.0,
+            padding: 10.0,
+            font_size: 13.0,
+            min_dropdown_width: 160.0,
+            background: [0.15, 0.15, 0.17],
+            item: [0.19, 0.19, 0.22],
+            dropdown: [0.22, 0.22, 0.25],
+            chevron: [0.7, 0.7, 0.75],
+            text: [0.85, 0.85, 0.88, 1.0],
+            hover_tint: [1.5, 1.5, 1.6, 1.0],
+            open_tint: [1.3, 1.3, 1.4, 1.0],
+        }
+    }
+}
+
+// Right-pointing triangle marking submenus, also used as a separator.
+pub(crate) fn chevron(device: &wgpu::Device, color: [f32; 3]) -> Player {
+    let half = CHEVRON_SIZE / 2.0;
+    let mut path = PathBuilder::new();
+    path.move_to(-half * 0.6, half)
+        .line_to(half * 0.6, 0.0)
+        .line_to(-half * 0.6, -half)
+        .close();
+    let mut element = path.fill(device, cgmath::Vector2::new(0.0, 0.0), color);
+    element.draggable = false;
+    element
+}
+
+struct Node {
+    label: String,
+    action: String,
+    enabled: bool,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    // Top left and size in screen pixels, relative to the viewport.
+    origin: cgmath::Vector2<f32>,
+    size: cgmath::Vector2<f32>,
+    element: usize,
+    chevron: Option<usize>,
+    text: Text,
+}
+
+impl Node {
+    fn contains(&self, point: cgmath::Vector2<f32>) -> bool {
+        let offset = point - self.origin;
+        (0.0..self.size.x).contains(&offset.x) && (0.0..self.size.y).contains(&offset.y)
+    }
+}
+
+// A row of menus along the top of the viewport with nested dropdowns, kept
+// at fixed screen positions whatever the camera does. Dropdowns are drawn in
+// element order like everything else, so create the bar after the content
+// it should cover.
+//
+// F10 moves keyboard focus to the bar. The arrows then walk the menus, Enter
+// or Space activates and Escape backs out one level:
+//
+//     let file = MenuItem::submenu("File", vec![MenuItem::new("Open", "open")]);
+//     let mut menu = MenuBar::new(&mut scene, device, queue, &mut glyphs, font, vec![file],
+//         MenuBarStyle::default());
+//     menu.update(&mut scene, device, &mut glyphs); // every frame
+pub struct MenuBar {
+    pub style: MenuBarStyle,
+    font: Rc<TextFont>,
+    nodes: Vec<Node>,
+    roots: Vec<usize>,
+    // Nodes whose children are showing, outermost first.
+    open: Vec<usize>,
+    highlighted: Option<usize>,
+    // Whether the bar takes keyboard input.
+    active: bool,
+    cursor: cgmath::Vector2<f32>,
+    background: usize,
+}
+
+impl MenuBar {
+    pub fn new(
+        scene: &mut UIScene,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        atlas: &mut GlyphAtlas,
+        font: Rc<TextFont>,
+        items: Vec<MenuItem>,
+        style: MenuBarStyle,
+    ) -> Self {
+        let background_color = style.background;
+        let background = Self::push(
+            scene,
+            Player::with_gradient(
+                device,
+                cgmath::Vector2::new(0.0, 0.0),
+                cgmath::Vector2::new(scene.viewport.width, style.height),
+                background_color,
+                background_color,
+            ),
+        );
+
+        let mut bar = Self {
+            style,
+            font,
+            nodes: Vec::new(),
+            roots: Vec::new(),
+            open: Vec::new(),
+            highlighted: None,
+            active: false,
+            cursor: cgmath::Vector2::new(0.0, 0.0),
+            background,
+        };
+        bar.roots = bar.flatten(items, None);
+        bar.layout();
+
+        let layout = TextLayout {
+            height: Some(bar.style.height),
+            wrap: false,
+            vertical_align: VerticalAlign::Middle,
+            ..TextLayout::default()
+        };
+        // Nodes are in depth-first order, so submenus are drawn over the
+        // dropdowns they open from.
+        for i in 0..bar.nodes.len() {
+            let node = &bar.nodes[i];
+            let color = if node.parent.is_some() {
+                bar.style.dropdown
+            } else {
+                bar.style.item
+            };
+            let mut element = Player::with_gradient(
+                device,
+                cgmath::Vector2::new(0.0, 0.0),
+                node.size,
+                color,
+                color,
+            );
+            element.enabled = node.enabled;
+            let has_chevron = node.parent.is_some() && !node.children.is_empty();
+            bar.nodes[i].element = Self::push(scene, element);
+            if has_chevron {
+                bar.nodes[i].chevron = Some(Self::push(scene, chevron(device, bar.style.chevron)));
+            }
+            // Labels of disabled items gray out with them.
+            let node = &mut bar.nodes[i];
+            node.text
+                .set_layout(scene, device, queue, atlas, layout.clone());
+            node.text.set_text(scene, device, queue, atlas, &node.label);
+            node.text.set_color(scene, bar.style.text);
+            for &glyph in node.text.elements() {
+                scene.set_parent(glyph, Some(node.element));
+            }
+        }
+        bar
+    }
+
+    // Keeps the bar spanning the viewport.
+    pub fn resize(&self, scene: &mut UIScene, queue: &wgpu::Queue, width: f32) {
+        let shape = Shape::Rect {
+            width,
+            height: self.style.height,
+        };
+        let color = self.style.background;
+        scene.elements[self.background].reshape(queue, shape, color, color);
+    }
+
+    pub fn is_open(&self) -> bool {
+        !self.open.is_empty()
+    }
+
+    // Action of the highlighted item, e.g. for a status line describing it.
+    pub fn highlighted_action(&self) -> Option<&str> {
+        self.highlighted
+            .map(|node| self.nodes[node].action.as_str())
+            .filter(|action| !action.is_empty())
+    }
+
+    pub fn close(&mut self) {
+        self.open.clear();
+        self.highlighted = None;
+        self.active = false;
+    }
+
+    // Call before `UIScene::input`, and skip it unless this returns
+    // `Ignored`.
+    pub fn input(&mut self, scene: &UIScene, event: &WindowEvent) -> MenuResponse {
+        match event {
+            WindowEvent::CursorMoved { position, .. } => {
+                self.cursor = cgmath::Vector2::new(
+                    position.x as f32 - scene.viewport.x,
+                    position.y as f32 - scene.viewport.y,
+                );
+                match self.node_at(self.cursor) {
+                    Some(node) => self.hover(node),
+                    None if !self.is_open() && !self.active => self.highlighted = None,
+                    None => {}
+                }
+                MenuResponse::Ignored
+            }
+            WindowEvent::MouseInput {
+                state: ElementState::Pressed,
+                button: MouseButton::Left,
+                ..
+            } => self.press(),
+            WindowEvent::KeyboardInput {
+                input:
+                    KeyboardInput {
+                        state: ElementState::Pressed,
+                        virtual_keycode: Some(key),
+                        ..
+                    },
+                ..
+            } => self.key(*key),
+            _ => MenuResponse::Ignored,
+        }
+    }
+
+    fn hover(&mut self, node: usize) {
+        if !self.is_open() {
+            self.highlighted = Some(node);
+            return;
+        }
+        // Moving over another menu follows it, and submenus open on hover.
+        self.open = self.path_to(node);
+        self.open.pop();
+        self.highlighted = Some(node);
+        if self.nodes[node].enabled && !self.nodes[node].children.is_empty() {
+            self.open.push(node);
+        }
+    }
+
+    fn press(&mut self) -> MenuResponse {
+        let Some(node) = self.node_at(self.cursor) else {
+            let on_bar =
+                (self.style.top..self.style.top + self.style.height).contains(&self.cursor.y);
+            // Clicking elsewhere closes the menus and still reaches the scene.
+            self.close();
+            return if on_bar {
+                MenuResponse::Handled
+            } else {
+                MenuResponse::Ignored
+            };
+        };
+
+        let (enabled, leaf) = (
+            self.nodes[node].enabled,
+            self.nodes[node].children.is_empty(),
+        );
+        if self.nodes[node].parent.is_none() && self.open.first() == Some(&node) {
+            self.close();
+            self.highlighted = Some(node);
+        } else if enabled && leaf {
+            return self.activate(node);
+        } else if enabled {
+            self.open_menu(node);
+        }
+        MenuResponse::Handled
+    }
+
+    fn key(&mut self, key: VirtualKeyCode) -> MenuResponse {
+        if key == VirtualKeyCode::F10 {
+            if self.active {
+                self.close();
+            } else {
+                self.active = true;
+                self.highlighted = self.roots.first().copied();
+            }
+            return MenuResponse::Handled;
+        }
+        if !self.active {
+            return MenuResponse::Ignored;
+        }
+        let Some(current) = self.highlighted else {
+            return MenuResponse::Ignored;
+        };
+
+        if !self.is_open() {
+            match key {
+                VirtualKeyCode::Left => self.highlighted = Some(self.step(current, -1)),
+                VirtualKeyCode::Right => self.highlighted = Some(self.step(current, 1)),
+                VirtualKeyCode::Down | VirtualKeyCode::Return | VirtualKeyCode::Space => {
+                    self.open_menu(current)
+                }
+                VirtualKeyCode::Escape => self.close(),
+                _ => return MenuResponse::Ignored,
+            }
+            return MenuResponse::Handled;
+        }
+
+        let enabled = self.nodes[current].enabled;
+        let submenu = enabled && !self.nodes[current].children.is_empty();
+        match key {
+            VirtualKeyCode::Up => self.highlighted = Some(self.step(current, -1)),
+            VirtualKeyCode::Down => self.highlighted = Some(self.step(current, 1)),
+            VirtualKeyCode::Right if submenu => self.open_menu(current),
+            VirtualKeyCode::Right => self.open_menu(self.step(self.open[0], 1)),
+            VirtualKeyCode::Left if self.open.len() > 1 => self.highlighted = self.open.pop(),
+            VirtualKeyCode::Left => self.open_menu(self.step(self.open[0], -1)),
+            VirtualKeyCode::Return | VirtualKeyCode::Space if submenu => self.open_menu(current),
+            VirtualKeyCode::Return | VirtualKeyCode::Space if enabled => {
+                return self.activate(current)
+            }
+            VirtualKeyCode::Return | VirtualKeyCode::Space => {}
+            VirtualKeyCode::Escape => self.highlighted = self.open.pop(),
+            _ => return MenuResponse::Ignored,
+        }
+        MenuResponse::Handled
+    }
+
+    fn open_menu(&mut self, node: usize) {
+        self.open = self.path_to(node);
+        self.active = true;
+        let children = &self.nodes[node].children;
+        self.highlighted = children
+            .iter()
+            .copied()
+            .find(|&child| self.nodes[child].enabled)
+            .or(children.first().copied())
+            .or(Some(node));
+    }
+
+    fn activate(&mut self, node: usize) -> MenuResponse {
+        let action = self.nodes[node].action.clone();
+        self.close();
+        MenuResponse::Activated(action)
+    }
+
+    // Next enabled sibling in `direction`, wrapping around.
+    fn step(&self, node: usize, direction: isize) -> usize {
+        let siblings = match self.nodes[node].parent {
+            Some(parent) => &self.nodes[parent].children,
+            None => &self.roots,
+        };
+        let count = siblings.len() as isize;
+        let start = siblings.iter().position(|&n| n == node).unwrap_or(0) as isize;
+        (1..=count)
+            .map(|step| siblings[(start + direction * step).rem_euclid(count) as usize])
+            .find(|&n| self.nodes[n].enabled)
+            .unwrap_or(node)
+    }
+
+    // The node and its ancestors, outermost first.
+    fn path_to(&self, node: usize) -> Vec<usize> {
+        let mut path = vec![node];
+        while let Some(parent) = self.nodes[path[path.len() - 1]].parent {
+            path.push(parent);
+        }
+        path.reverse();
+        path
+    }
+
+    fn is_shown(&self, node: usize) -> bool {
+        self.nodes[node]
+            .parent
+            .is_none_or(|parent| self.open.contains(&parent))
+    }
+
+    // The innermost shown node under `point`.
+    fn node_at(&self, point: cgmath::Vector2<f32>) -> Option<usize> {
+        (0..self.nodes.len())
+            .rev()
+            .find(|&i| self.is_shown(i) && self.nodes[i].contains(point))
+    }
+
+    // Call before `UIScene::prepare` so the bar follows the camera.
+    pub fn update(&mut self, scene: &mut UIScene, device: &wgpu::Device, atlas: &mut GlyphAtlas) {
+        let scale = 1.0 / scene.camera.scale;
+        let width = scene.elements[self.background].size.x;
+        let mut place = |index: usize, x: f32, y: f32, visible: bool| {
+            let world = scene.camera.screen_to_world(x, y);
+            let element = &mut scene.elements[index];
+            element.instance.position.x = world.x;
+            element.instance.position.y = world.y;
+            element.instance.scale = scale;
+            element.visible = visible;
+        };
+
+        place(
+            self.background,
+            width / 2.0,
+            self.style.top + self.style.height / 2.0,
+            true,
+        );
+        for (i, node) in self.nodes.iter().enumerate() {
+            let center = node.origin + node.size / 2.0;
+            let shown = self.is_shown(i);
+            place(node.element, center.x, center.y, shown);
+            if let Some(chevron) = node.chevron {
+                let x = node.origin.x + node.size.x - self.style.padding - CHEVRON_SIZE / 2.0;
+                place(chevron, x, center.y, shown);
+            }
+        }
+
+        for (i, node) in self.nodes.iter().enumerate() {
+            scene.elements[node.element].instance.tint = if self.highlighted == Some(i) {
+                self.style.hover_tint
+            } else if self.open.contains(&i) {
+                self.style.open_tint
+            } else {
+                [1.0; 4]
+            };
+        }
+
+        for i in 0..self.nodes.len() {
+            let shown = self.is_shown(i);
+            let node = &mut self.nodes[i];
+            let top_left = scene
+                .camera
+                .screen_to_world(node.origin.x + self.style.padding, node.origin.y);
+            node.text.set_scale(scene, scale);
+            node.text.set_position(scene, top_left);
+            node.text.set_visible(scene, shown);
+            node.text.update(scene, device, atlas);
+        }
+    }
+
+    fn flatten(&mut self, items: Vec<MenuItem>, parent: Option<usize>) -> Vec<usize> {
+        let mut indices = Vec::with_capacity(items.len());
+        for item in items {
+            let index = self.nodes.len();
+            self.nodes.push(Node {
+                label: item.label,
+                action: item.action,
+                enabled: item.enabled,
+                parent,
+                children: Vec::new(),
+                origin: cgmath::Vector2::new(0.0, 0.0),
+                size: cgmath::Vector2::new(0.0, 0.0),
+                element: 0,
+                chevron: None,
+                text: Text::new(self.font.clone(), self.style.font_size),
+            });
+            self.nodes[index].children = self.flatten(item.children, Some(index));
+            indices.push(index);
+        }
+        indices
+    }
+
+    fn layout(&mut self) {
+        let height = self.style.height;
+        let mut x = 0.0;
+        for i in 0..self.roots.len() {
+            let root = self.roots[i];
+            let width = self.label_width(&self.nodes[root].label);
+            self.nodes[root].origin = cgmath::Vector2::new(x, self.style.top);
+            self.nodes[root].size = cgmath::Vector2::new(width, height);
+            x += width;
+            self.layout_children(root);
+        }
+    }
+
+    // Root menus drop down below their item, submenus open to the right.
+    fn layout_children(&mut self, node: usize) {
+        let (origin, size) = (self.nodes[node].origin, self.nodes[node].size);
+        let start = if self.nodes[node].parent.is_none() {
+            cgmath::Vector2::new(origin.x, origin.y + size.y)
+        } else {
+            cgmath::Vector2::new(origin.x + size.x, origin.y)
+        };
+        let children = self.nodes[node].children.clone();
+        let width = children
+            .iter()
+            .map(|&child| {
+                let node = &self.nodes[child];
+                let chevron = if node.children.is_empty() {
+                    0.0
+                } else {
+                    CHEVRON_SIZE + self.style.padding
+                };
+                self.label_width(&node.label) + chevron
+            })
+            .fold(self.style.min_dropdown_width, f32::max);
+
+        for (row, &child) in children.iter().enumerate() {
+            let height = self.style.height;
+            self.nodes[child].origin = start + cgmath::Vector2::new(0.0, row as f32 * height);
+            self.nodes[child].size = cgmath::Vector2::new(width, height);
+            self.layout_children(child);
+        }
+    }
+
+    fn label_width(&self, label: &str) -> f32 {
+        self.font.advance(self.style.font_size, label) + self.style.padding * 2.0
+    }
+
+    fn push(scene: &mut UIScene, mut element: Player) -> usize {
+        element.draggable = false;
+        scene.elements.push(element);
+        scene.elements.len() - 1
+    }
+}
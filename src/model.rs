@@ -1,13 +1,69 @@
 use std::ops::Range;
 
-use crate::texture;
+use crate::{gpu_errors, texture};
 
 pub struct Material {
     pub name: String,
     pub diffuse_texture: texture::Texture,
+    pub sampler: texture::SamplerConfig,
     pub bind_group: wgpu::BindGroup,
 }
 
+impl Material {
+    pub fn new(
+        device: &wgpu::Device,
+        name: String,
+        diffuse_texture: texture::Texture,
+        sampler: texture::SamplerConfig,
+        layout: &wgpu::BindGroupLayout,
+    ) -> Self {
+        let bind_group = gpu_errors::scoped(device, format!("creating material {}", name), || {
+            Self::create_bind_group(device, &diffuse_texture, layout)
+        });
+        Self {
+            name,
+            diffuse_texture,
+            sampler,
+            bind_group,
+        }
+    }
+
+    // Swaps the sampler used by this material, e.g. to switch a texture to
+    // repeat or linear filtering, without reloading it.
+    pub fn set_sampler(
+        &mut self,
+        device: &wgpu::Device,
+        samplers: &mut texture::SamplerCache,
+        sampler: texture::SamplerConfig,
+        layout: &wgpu::BindGroupLayout,
+    ) {
+        self.sampler = sampler;
+        self.diffuse_texture.sampler = samplers.get(device, sampler);
+        self.bind_group = Self::create_bind_group(device, &self.diffuse_texture, layout);
+    }
+
+    fn create_bind_group(
+        device: &wgpu::Device,
+        diffuse_texture: &texture::Texture,
+        layout: &wgpu::BindGroupLayout,
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&diffuse_texture.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&diffuse_texture.sampler),
+                },
+            ],
+            label: None,
+        })
+    }
+}
+
 pub struct Mesh {
     pub name: String,
     pub vertex_buffer: wgpu::Buffer,
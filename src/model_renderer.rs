@@ -4,6 +4,7 @@ use cgmath::{Rotation3, SquareMatrix};
 use wgpu::util::DeviceExt;
 use winit::event::{ElementState, KeyboardInput, VirtualKeyCode, WindowEvent};
 
+use crate::gpu_context::GpuContext;
 use crate::model;
 use crate::model::DrawModel;
 use crate::model::Vertex;
@@ -188,11 +189,8 @@ pub struct ModelScene {
 }
 
 impl ModelScene {
-    pub async fn new(
-        device: &wgpu::Device,
-        config: &wgpu::SurfaceConfiguration,
-        queue: &wgpu::Queue,
-    ) -> Self {
+    pub async fn new(gpu: &mut GpuContext, config: &wgpu::SurfaceConfiguration) -> Self {
+        let device = &gpu.device;
         let depth_texture =
             texture::Texture::create_depth_texture(&device, &config, "depth_texture");
 
@@ -326,10 +324,16 @@ impl ModelScene {
             multiview: None, // 5.
         });
 
-        let obj_model =
-            resources::load_model("prop_floor_barrel.obj", &device, &queue, &texture_bind_group_layout)
-                .await
-                .unwrap();
+        let obj_model = resources::load_model(
+            "prop_floor_barrel.obj",
+            device,
+            &gpu.queue,
+            &texture_bind_group_layout,
+            &mut gpu.samplers,
+            gpu.default_sampler,
+        )
+        .await
+        .unwrap();
 
         let instances: Vec<Instance> = vec![
             Instance {
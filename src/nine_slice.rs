@@ -0,0 +1,151 @@
+use anyhow::Context;
+use lyon::tessellation::VertexBuffers;
+
+use crate::atlas::Atlas;
+use crate::deletion_queue::DeletionQueue;
+use crate::ui_scene::{Player, Shape, UIMaterial, UIScene, Vertex};
+
+// Widths of the border strips, in pixels of the source image. Corners are
+// drawn at that size, edges stretch along the border and the middle both
+// ways.
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct Insets {
+    pub left: f32,
+    pub right: f32,
+    pub top: f32,
+    pub bottom: f32,
+}
+
+impl Insets {
+    pub fn uniform(width: f32) -> Self {
+        Self {
+            left: width,
+            right: width,
+            top: width,
+            bottom: width,
+        }
+    }
+}
+
+// A panel drawn from an atlas image cut into nine parts, so it stretches to
+// any size without distorting its borders:
+//
+//     let mut panel = NineSlice::new(&mut scene, device, &atlas, "panel", Insets::uniform(8.0), position, size)?;
+//     panel.set_size(&mut scene, device, queue, deletion_queue, bigger);
+//
+// Panels smaller than their borders shrink the borders to fit.
+pub struct NineSlice {
+    element: usize,
+    // Size of the source image, in pixels.
+    source: cgmath::Vector2<f32>,
+    insets: Insets,
+    size: cgmath::Vector2<f32>,
+}
+
+impl NineSlice {
+    pub fn new(
+        scene: &mut UIScene,
+        device: &wgpu::Device,
+        atlas: &Atlas,
+        name: &str,
+        insets: Insets,
+        position: cgmath::Vector2<f32>,
+        size: cgmath::Vector2<f32>,
+    ) -> anyhow::Result<Self> {
+        let region = atlas
+            .region(name)
+            .with_context(|| format!("the atlas has no image {}", name))?;
+        let uv = atlas
+            .uv_rect(name)
+            .with_context(|| format!("the atlas has no image {}", name))?;
+        let panel = Self {
+            element: scene.elements.len(),
+            source: cgmath::Vector2::new(region.width as f32, region.height as f32),
+            insets,
+            size,
+        };
+        let mut element = Player::from_geometry(device, position, size, &panel.geometry());
+        element.material = UIMaterial::Sprite;
+        element.instance.params = uv;
+        scene.elements.push(element);
+        Ok(panel)
+    }
+
+    pub fn element(&self) -> usize {
+        self.element
+    }
+
+    pub fn size(&self) -> cgmath::Vector2<f32> {
+        self.size
+    }
+
+    // Rewrites the geometry in place, so resizing every frame, e.g. while
+    // dragging a window edge, doesn't allocate.
+    pub fn set_size(
+        &mut self,
+        scene: &mut UIScene,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        deletion_queue: &mut DeletionQueue,
+        size: cgmath::Vector2<f32>,
+    ) {
+        if size == self.size {
+            return;
+        }
+        self.size = size;
+        let element = &mut scene.elements[self.element];
+        element.upload_geometry(device, queue, deletion_queue, &self.geometry());
+        element.size = size;
+        element.shape = Shape::Rect {
+            width: size.x,
+            height: size.y,
+        };
+    }
+
+    // Three by three quads, with uv across the source image going up like
+    // the element does.
+    fn geometry(&self) -> VertexBuffers<Vertex, u16> {
+        let Insets {
+            left,
+            right,
+            top,
+            bottom,
+        } = self.insets;
+        let fit = |a: f32, b: f32, room: f32| {
+            let k = if a + b > room && a + b > 0.0 {
+                room / (a + b)
+            } else {
+                1.0
+            };
+            (a * k, b * k)
+        };
+        let (left, right) = fit(left, right, self.size.x);
+        let (bottom, top) = fit(bottom, top, self.size.y);
+        let half = self.size / 2.0;
+        let (w, h) = (self.source.x.max(1.0), self.source.y.max(1.0));
+        let xs = [-half.x, -half.x + left, half.x - right, half.x];
+        let us = [0.0, self.insets.left / w, 1.0 - self.insets.right / w, 1.0];
+        let ys = [-half.y, -half.y + bottom, half.y - top, half.y];
+        let vs = [0.0, self.insets.bottom / h, 1.0 - self.insets.top / h, 1.0];
+
+        let mut geometry = VertexBuffers::new();
+        for (&y, &v) in ys.iter().zip(&vs) {
+            for (&x, &u) in xs.iter().zip(&us) {
+                geometry.vertices.push(Vertex {
+                    position: [x, y, 0.0],
+                    color: [1.0; 4],
+                    uv: [u, v],
+                });
+            }
+        }
+        for row in 0..3u16 {
+            for column in 0..3u16 {
+                let i = row * 4 + column;
+                geometry
+                    .indices
+                    .extend_from_slice(&[i, i + 1, i + 5, i, i + 5, i + 4]);
+            }
+        }
+        geometry
+    }
+}
@@ -0,0 +1,125 @@
+use std::time::{Duration, Instant};
+
+use winit::event::WindowEvent;
+use winit::window::{Window, WindowBuilder, WindowLevel};
+
+// How often hit testing is switched back on while clicks pass through, see
+// `Overlay::update`.
+const HITTEST_RECHECK: Duration = Duration::from_millis(100);
+
+// A rectangle in physical window pixels.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Region {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Region {
+    pub fn contains(&self, x: f32, y: f32) -> bool {
+        x >= self.x && x < self.x + self.width && y >= self.y && y < self.y + self.height
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ClickThrough {
+    Never,
+    Always,
+    // Only the listed regions take input, clicks anywhere else reach the
+    // application underneath.
+    OutsideRegions(Vec<Region>),
+}
+
+#[derive(Debug, Clone)]
+pub struct OverlayOptions {
+    pub always_on_top: bool,
+    pub click_through: ClickThrough,
+}
+
+impl Default for OverlayOptions {
+    fn default() -> Self {
+        Self {
+            always_on_top: true,
+            click_through: ClickThrough::Never,
+        }
+    }
+}
+
+impl OverlayOptions {
+    // Pair with `RendererOptions::transparent` so the surface is composited
+    // with per-pixel alpha.
+    pub fn apply_to_builder(&self, builder: WindowBuilder) -> WindowBuilder {
+        let level = if self.always_on_top {
+            WindowLevel::AlwaysOnTop
+        } else {
+            WindowLevel::Normal
+        };
+        builder
+            .with_transparent(true)
+            .with_decorations(false)
+            .with_window_level(level)
+    }
+}
+
+// Keeps the window's hit testing in sync with the click-through setting.
+pub struct Overlay {
+    pub options: OverlayOptions,
+    hittest: bool,
+    disabled_at: Instant,
+}
+
+impl Overlay {
+    pub fn new(window: &Window, options: OverlayOptions) -> Self {
+        let mut overlay = Self {
+            options,
+            hittest: true,
+            disabled_at: Instant::now(),
+        };
+        let hittest = overlay.options.click_through != ClickThrough::Always;
+        overlay.set_hittest(window, hittest);
+        overlay
+    }
+
+    pub fn set_click_through(&mut self, window: &Window, click_through: ClickThrough) {
+        let hittest = click_through != ClickThrough::Always;
+        self.options.click_through = click_through;
+        self.set_hittest(window, hittest);
+    }
+
+    pub fn input(&mut self, window: &Window, event: &WindowEvent) {
+        if let (WindowEvent::CursorMoved { position, .. }, ClickThrough::OutsideRegions(regions)) =
+            (event, &self.options.click_through)
+        {
+            let (x, y) = (position.x as f32, position.y as f32);
+            let inside = regions.iter().any(|region| region.contains(x, y));
+            self.set_hittest(window, inside);
+        }
+    }
+
+    // Once hit testing is off the window stops getting cursor events, so it
+    // can't notice the cursor entering a region. Turning it back on now and
+    // then lets the next `CursorMoved` decide again.
+    pub fn update(&mut self, window: &Window) {
+        if matches!(self.options.click_through, ClickThrough::OutsideRegions(_))
+            && !self.hittest
+            && self.disabled_at.elapsed() >= HITTEST_RECHECK
+        {
+            self.set_hittest(window, true);
+        }
+    }
+
+    fn set_hittest(&mut self, window: &Window, hittest: bool) {
+        if hittest == self.hittest {
+            return;
+        }
+        if let Err(e) = window.set_cursor_hittest(hittest) {
+            log::warn!("Click-through isn't supported on this platform: {}", e);
+            return;
+        }
+        self.hittest = hittest;
+        if !hittest {
+            self.disabled_at = Instant::now();
+        }
+    }
+}
@@ -0,0 +1,149 @@
+use lyon::math::{point, Point};
+use lyon::path::Path;
+
+use crate::ui_scene::{Outline, Player, Stroke};
+
+#[derive(Debug, Copy, Clone)]
+enum Command {
+    MoveTo(Point),
+    LineTo(Point),
+    QuadraticTo(Point, Point),
+    CubicTo(Point, Point, Point),
+    Close,
+}
+
+// Free-form outlines for scene elements, in coordinates relative to the
+// element's position:
+//
+//     let mut arrow = PathBuilder::new();
+//     arrow
+//         .move_to(-20.0, -10.0)
+//         .line_to(0.0, 20.0)
+//         .line_to(20.0, -10.0)
+//         .close();
+//     renderer.ui_scene.elements.push(arrow.fill(device, position, [1.0, 0.8, 0.2]));
+//
+// Drawing without a `move_to` first starts at the origin. The commands are
+// kept, so the same builder can make several elements.
+#[derive(Debug, Clone, Default)]
+pub struct PathBuilder {
+    commands: Vec<Command>,
+}
+
+impl PathBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn move_to(&mut self, x: f32, y: f32) -> &mut Self {
+        self.commands.push(Command::MoveTo(point(x, y)));
+        self
+    }
+
+    pub fn line_to(&mut self, x: f32, y: f32) -> &mut Self {
+        self.commands.push(Command::LineTo(point(x, y)));
+        self
+    }
+
+    pub fn quadratic_to(&mut self, cx: f32, cy: f32, x: f32, y: f32) -> &mut Self {
+        self.commands
+            .push(Command::QuadraticTo(point(cx, cy), point(x, y)));
+        self
+    }
+
+    pub fn cubic_to(&mut self, c1: (f32, f32), c2: (f32, f32), x: f32, y: f32) -> &mut Self {
+        self.commands.push(Command::CubicTo(
+            point(c1.0, c1.1),
+            point(c2.0, c2.1),
+            point(x, y),
+        ));
+        self
+    }
+
+    // Closes the current subpath back to its start.
+    pub fn close(&mut self) -> &mut Self {
+        self.commands.push(Command::Close);
+        self
+    }
+
+    pub fn fill(
+        &self,
+        device: &wgpu::Device,
+        position: cgmath::Vector2<f32>,
+        color: [f32; 3],
+    ) -> Player {
+        Player::from_path(device, position, &self.path(), Some(color), None)
+    }
+
+    pub fn stroke(
+        &self,
+        device: &wgpu::Device,
+        position: cgmath::Vector2<f32>,
+        stroke: Stroke,
+        color: [f32; 3],
+    ) -> Player {
+        let outline = Outline { stroke, color };
+        Player::from_path(device, position, &self.path(), None, Some(outline))
+    }
+
+    pub fn fill_and_stroke(
+        &self,
+        device: &wgpu::Device,
+        position: cgmath::Vector2<f32>,
+        fill: [f32; 3],
+        outline: Outline,
+    ) -> Player {
+        Player::from_path(device, position, &self.path(), Some(fill), Some(outline))
+    }
+
+    pub fn path(&self) -> Path {
+        let mut builder = Path::builder();
+        // lyon wants every subpath explicitly begun and ended.
+        let mut open = false;
+        let mut start = point(0.0, 0.0);
+        let mut last = start;
+        for &command in &self.commands {
+            if let Command::MoveTo(to) = command {
+                if open {
+                    builder.end(false);
+                }
+                builder.begin(to);
+                open = true;
+                (start, last) = (to, to);
+                continue;
+            }
+            if let Command::Close = command {
+                if open {
+                    builder.end(true);
+                    open = false;
+                    last = start;
+                }
+                continue;
+            }
+            if !open {
+                builder.begin(last);
+                open = true;
+                start = last;
+            }
+            last = match command {
+                Command::LineTo(to) => {
+                    builder.line_to(to);
+                    to
+                }
+                Command::QuadraticTo(control, to) => {
+                    builder.quadratic_bezier_to(control, to);
+                    to
+                }
+                Command::CubicTo(c1, c2, to) => {
+                    builder.cubic_bezier_to(c1, c2, to);
+                    to
+                }
+                Command::MoveTo(_) | Command::Close => unreachable!(),
+            };
+        }
+        if open {
+            builder.end(false);
+        }
+        builder.build()
+    }
+}
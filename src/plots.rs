@@ -0,0 +1,299 @@
+use lyon::tessellation::VertexBuffers;
+
+use crate::ring_buffer::RingBuffer;
+use crate::ui_scene::{opaque, Player, UIScene, Vertex};
+
+const VERTICES_PER_BAR: usize = 4;
+const CURSOR_WIDTH: f32 = 2.0;
+
+// A row of bars in a single element. Bars are written straight into the
+// vertex buffer, so changing a few of them uploads only those vertices.
+struct Bars {
+    element: usize,
+    count: usize,
+    size: cgmath::Vector2<f32>,
+    // Fraction of each slot left empty between bars.
+    gap: f32,
+    colors: ([f32; 3], [f32; 3]),
+}
+
+impl Bars {
+    fn new(
+        scene: &mut UIScene,
+        device: &wgpu::Device,
+        position: cgmath::Vector2<f32>,
+        size: cgmath::Vector2<f32>,
+        count: usize,
+        gap: f32,
+        colors: ([f32; 3], [f32; 3]),
+    ) -> Self {
+        let bars = Self {
+            element: scene.elements.len(),
+            count,
+            size,
+            gap,
+            colors,
+        };
+
+        let mut geometry: VertexBuffers<Vertex, u16> = VertexBuffers::new();
+        for i in 0..count {
+            let base = (i * VERTICES_PER_BAR) as u16;
+            geometry.vertices.extend(bars.vertices(i, 0.0, 0.0));
+            geometry
+                .indices
+                .extend([0, 1, 2, 0, 2, 3].map(|index| base + index));
+        }
+        let mut element = Player::from_geometry(device, position, size, &geometry);
+        element.draggable = false;
+        scene.elements.push(element);
+        bars
+    }
+
+    // The bar spans `low` to `high`, both 0..1 of the height.
+    fn vertices(&self, bar: usize, low: f32, high: f32) -> [Vertex; VERTICES_PER_BAR] {
+        let slot = self.size.x / self.count as f32;
+        let left = bar as f32 * slot + slot * self.gap / 2.0;
+        let right = (bar + 1) as f32 * slot - slot * self.gap / 2.0;
+        let corner = |x: f32, y: f32| {
+            let u = x / self.size.x;
+            Vertex {
+                position: [x - self.size.x / 2.0, (y - 0.5) * self.size.y, 0.0],
+                color: opaque(
+                    [0, 1, 2].map(|i| self.colors.0[i] + (self.colors.1[i] - self.colors.0[i]) * u),
+                ),
+                uv: [u, y],
+            }
+        };
+        [
+            corner(left, low),
+            corner(right, low),
+            corner(right, high),
+            corner(left, high),
+        ]
+    }
+
+    // Writes bars `first..` from `spans`, one upload for the whole run.
+    fn write(&self, scene: &UIScene, queue: &wgpu::Queue, first: usize, spans: &[(f32, f32)]) {
+        let vertices: Vec<Vertex> = spans
+            .iter()
+            .enumerate()
+            .flat_map(|(i, &(low, high))| self.vertices(first + i, low, high))
+            .collect();
+        let offset = first * VERTICES_PER_BAR * std::mem::size_of::<Vertex>();
+        queue.write_buffer(
+            &scene.elements[self.element].vertex_buffer,
+            offset as wgpu::BufferAddress,
+            bytemuck::cast_slice(&vertices),
+        );
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HistogramStyle {
+    pub size: cgmath::Vector2<f32>,
+    pub bins: usize,
+    // Samples outside the range count towards the first or last bin.
+    pub range: (f32, f32),
+    pub gap: f32,
+    // Left and right ends of the bar gradient.
+    pub colors: ([f32; 3], [f32; 3]),
+}
+
+impl Default for HistogramStyle {
+    fn default() -> Self {
+        Self {
+            size: cgmath::Vector2::new(240.0, 80.0),
+            bins: 32,
+            range: (0.0, 1.0),
+            gap: 0.2,
+            colors: ([0.3, 0.7, 0.4], [0.9, 0.4, 0.3]),
+        }
+    }
+}
+
+// Distribution of the samples in a ring buffer, e.g. frame times. Heights
+// are relative to the fullest bin.
+pub struct Histogram {
+    pub style: HistogramStyle,
+    bars: Bars,
+    heights: Vec<f32>,
+    seen: Option<u64>,
+}
+
+impl Histogram {
+    pub fn new(
+        scene: &mut UIScene,
+        device: &wgpu::Device,
+        position: cgmath::Vector2<f32>,
+        style: HistogramStyle,
+    ) -> Self {
+        let bins = style.bins.max(1);
+        let bars = Bars::new(
+            scene,
+            device,
+            position,
+            style.size,
+            bins,
+            style.gap,
+            style.colors,
+        );
+        Self {
+            style,
+            bars,
+            heights: vec![0.0; bins],
+            seen: None,
+        }
+    }
+
+    // Recounts when the buffer got new samples and uploads the bins whose
+    // height changed.
+    pub fn update(&mut self, scene: &UIScene, queue: &wgpu::Queue, samples: &RingBuffer<f32>) {
+        if self.seen == Some(samples.written()) {
+            return;
+        }
+        self.seen = Some(samples.written());
+
+        let (low, high) = self.style.range;
+        let bins = self.heights.len();
+        let mut counts = vec![0u32; bins];
+        for sample in samples.iter() {
+            let t = (sample - low) / (high - low);
+            let bin = ((t * bins as f32) as isize).clamp(0, bins as isize - 1);
+            counts[bin as usize] += 1;
+        }
+        let fullest = counts.iter().copied().max().unwrap_or(0).max(1) as f32;
+        let heights: Vec<f32> = counts.iter().map(|&c| c as f32 / fullest).collect();
+
+        // Upload each run of changed bins in one go.
+        let mut bin = 0;
+        while bin < bins {
+            if heights[bin] == self.heights[bin] {
+                bin += 1;
+                continue;
+            }
+            let start = bin;
+            while bin < bins && heights[bin] != self.heights[bin] {
+                bin += 1;
+            }
+            let spans: Vec<(f32, f32)> = heights[start..bin].iter().map(|&h| (0.0, h)).collect();
+            self.bars.write(scene, queue, start, &spans);
+        }
+        self.heights = heights;
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct WaveformStyle {
+    pub size: cgmath::Vector2<f32>,
+    pub columns: usize,
+    // Samples folded into each column, which shows their min to max.
+    pub samples_per_column: usize,
+    // Sample values mapped to the bottom and top edges.
+    pub range: (f32, f32),
+    pub colors: ([f32; 3], [f32; 3]),
+    pub cursor: [f32; 3],
+}
+
+impl Default for WaveformStyle {
+    fn default() -> Self {
+        Self {
+            size: cgmath::Vector2::new(320.0, 80.0),
+            columns: 160,
+            samples_per_column: 64,
+            range: (-1.0, 1.0),
+            colors: ([0.3, 0.8, 1.0], [0.3, 0.8, 1.0]),
+            cursor: [1.0, 1.0, 1.0],
+        }
+    }
+}
+
+// Envelope of a sample stream, e.g. audio. Drawn in sweep mode like a
+// patient monitor: new columns overwrite the oldest ones in place behind a
+// moving cursor, so a new column uploads only its own four vertices instead
+// of scrolling the whole plot.
+pub struct Waveform {
+    pub style: WaveformStyle,
+    bars: Bars,
+    cursor: usize,
+    column: usize,
+    // Min, max and count of the column being filled.
+    pending: (f32, f32, usize),
+    seen: u64,
+}
+
+impl Waveform {
+    pub fn new(
+        scene: &mut UIScene,
+        device: &wgpu::Device,
+        position: cgmath::Vector2<f32>,
+        style: WaveformStyle,
+    ) -> Self {
+        let columns = style.columns.max(1);
+        let bars = Bars::new(
+            scene,
+            device,
+            position,
+            style.size,
+            columns,
+            0.0,
+            style.colors,
+        );
+        let mut cursor = Player::with_gradient(
+            device,
+            position,
+            cgmath::Vector2::new(CURSOR_WIDTH, style.size.y),
+            style.cursor,
+            style.cursor,
+        );
+        cursor.draggable = false;
+        scene.elements.push(cursor);
+
+        Self {
+            style,
+            bars,
+            cursor: scene.elements.len() - 1,
+            column: 0,
+            pending: (f32::INFINITY, f32::NEG_INFINITY, 0),
+            seen: 0,
+        }
+    }
+
+    // Folds in the samples pushed since the last update. If the buffer
+    // wrapped in between, the overwritten samples are skipped.
+    pub fn update(&mut self, scene: &mut UIScene, queue: &wgpu::Queue, samples: &RingBuffer<f32>) {
+        let (low, high) = self.style.range;
+        let columns = self.bars.count;
+        let mut run_start = self.column;
+        let mut spans = Vec::new();
+        for sample in samples.since(self.seen) {
+            let (min, max, count) = &mut self.pending;
+            *min = min.min(sample);
+            *max = max.max(sample);
+            *count += 1;
+            if *count < self.style.samples_per_column.max(1) {
+                continue;
+            }
+
+            let normalize = |v: f32| ((v - low) / (high - low)).clamp(0.0, 1.0);
+            spans.push((normalize(*min), normalize(*max)));
+            self.pending = (f32::INFINITY, f32::NEG_INFINITY, 0);
+            self.column = (self.column + 1) % columns;
+            // Wrapping past the right edge starts a new upload run.
+            if self.column == 0 {
+                self.bars.write(scene, queue, run_start, &spans);
+                spans.clear();
+                run_start = 0;
+            }
+        }
+        self.seen = samples.written();
+
+        if !spans.is_empty() {
+            self.bars.write(scene, queue, run_start, &spans);
+        }
+
+        let slot = self.style.size.x / columns as f32;
+        let position = &scene.elements[self.bars.element].instance.position;
+        let x = position.x - self.style.size.x / 2.0 + self.column as f32 * slot;
+        scene.elements[self.cursor].instance.position.x = x;
+    }
+}
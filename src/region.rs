@@ -0,0 +1,231 @@
+use lyon::math::{point, Box2D, Point};
+use lyon::path::Path;
+use lyon::tessellation::{BuffersBuilder, FillOptions, FillTessellator, FillVertex, VertexBuffers};
+
+use crate::ui_scene::{opaque, Player, Shape, Vertex};
+
+// Pieces thinner than this are rounding noise from clipping.
+const MIN_AREA: f32 = 1e-4;
+
+// An area made of disjoint convex pieces, for combining shapes before they
+// become an element:
+//
+//     let frame = Region::from_shape(Shape::Rect { width: 200.0, height: 200.0 })
+//         .subtract(&Region::from_shape(Shape::Circle { radius: 80.0 }));
+//     renderer.ui_scene.elements.push(frame.element(device, position, dark, dark));
+//
+// Combining clips every piece of one region against every piece of the
+// other, which is plenty fast for UI shapes but not meant for per-frame use
+// on complex paths.
+#[derive(Debug, Clone, Default)]
+pub struct Region {
+    // Counterclockwise.
+    pieces: Vec<Vec<Point>>,
+}
+
+impl Region {
+    pub fn from_shape(shape: Shape) -> Self {
+        Self::from_path(&shape.path())
+    }
+
+    // Filled with the even-odd rule, like elements made from paths.
+    pub fn from_path(path: &Path) -> Self {
+        let mut geometry: VertexBuffers<Point, u16> = VertexBuffers::new();
+        FillTessellator::new()
+            .tessellate_path(
+                path,
+                &FillOptions::default(),
+                &mut BuffersBuilder::new(&mut geometry, |vertex: FillVertex| vertex.position()),
+            )
+            .unwrap();
+        let pieces = geometry
+            .indices
+            .chunks_exact(3)
+            .map(|triangle| {
+                triangle
+                    .iter()
+                    .map(|&i| geometry.vertices[i as usize])
+                    .collect()
+            })
+            .filter_map(counterclockwise)
+            .collect();
+        Self { pieces }
+    }
+
+    pub fn translated(mut self, x: f32, y: f32) -> Self {
+        for piece in &mut self.pieces {
+            for p in piece {
+                *p = point(p.x + x, p.y + y);
+            }
+        }
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pieces.is_empty()
+    }
+
+    pub fn union(&self, other: &Region) -> Region {
+        let mut pieces = self.pieces.clone();
+        pieces.extend(other.subtract(self).pieces);
+        Region { pieces }
+    }
+
+    pub fn subtract(&self, other: &Region) -> Region {
+        let mut pieces = self.pieces.clone();
+        for cutter in &other.pieces {
+            let cutter_bounds = bounds(cutter);
+            pieces = pieces
+                .into_iter()
+                .flat_map(|piece| {
+                    if bounds(&piece).intersects(&cutter_bounds) {
+                        difference(piece, cutter)
+                    } else {
+                        vec![piece]
+                    }
+                })
+                .collect();
+        }
+        Region { pieces }
+    }
+
+    pub fn intersect(&self, other: &Region) -> Region {
+        let mut pieces = Vec::new();
+        for a in &self.pieces {
+            let a_bounds = bounds(a);
+            for b in &other.pieces {
+                if !a_bounds.intersects(&bounds(b)) {
+                    continue;
+                }
+                let clipped = b.iter().zip(b.iter().cycle().skip(1)).try_fold(
+                    a.clone(),
+                    |piece, (&from, &to)| {
+                        let inside = clip(&piece, from, to, true);
+                        (inside.len() >= 3).then_some(inside)
+                    },
+                );
+                pieces.extend(clipped.and_then(counterclockwise));
+            }
+        }
+        Region { pieces }
+    }
+
+    pub fn contains(&self, p: Point) -> bool {
+        self.pieces.iter().any(|piece| {
+            piece
+                .iter()
+                .zip(piece.iter().cycle().skip(1))
+                .all(|(&from, &to)| side(from, to, p) >= 0.0)
+        })
+    }
+
+    // Empty at the origin for an empty region, so its element can't be hit.
+    pub fn bounds(&self) -> Box2D {
+        Box2D::from_points(self.pieces.iter().flatten().copied())
+    }
+
+    // Each piece fanned into triangles, with uv spanning the bounding box.
+    pub fn geometry(&self, left: [f32; 3], right: [f32; 3]) -> VertexBuffers<Vertex, u16> {
+        let bounds = self.bounds();
+        let extent = bounds.size();
+        let mut geometry: VertexBuffers<Vertex, u16> = VertexBuffers::new();
+        for piece in &self.pieces {
+            let base = geometry.vertices.len() as u16;
+            geometry.vertices.extend(piece.iter().map(|p| {
+                let uv = [
+                    (p.x - bounds.min.x) / extent.width.max(f32::EPSILON),
+                    (p.y - bounds.min.y) / extent.height.max(f32::EPSILON),
+                ];
+                Vertex {
+                    position: [p.x, p.y, 0.0],
+                    color: opaque([0, 1, 2].map(|i| left[i] + (right[i] - left[i]) * uv[0])),
+                    uv,
+                }
+            }));
+            for i in 1..piece.len() as u16 - 1 {
+                geometry.indices.extend([base, base + i, base + i + 1]);
+            }
+        }
+        geometry
+    }
+
+    // An element with the region drawn relative to `position`. Hit testing
+    // uses the bounding box, holes included.
+    pub fn element(
+        &self,
+        device: &wgpu::Device,
+        position: cgmath::Vector2<f32>,
+        left: [f32; 3],
+        right: [f32; 3],
+    ) -> Player {
+        let bounds = self.bounds();
+        let size = cgmath::Vector2::new(
+            2.0 * bounds.min.x.abs().max(bounds.max.x.abs()),
+            2.0 * bounds.min.y.abs().max(bounds.max.y.abs()),
+        );
+        Player::from_geometry(device, position, size, &self.geometry(left, right))
+    }
+}
+
+// Positive when `p` is left of the line from `from` to `to`.
+fn side(from: Point, to: Point, p: Point) -> f32 {
+    (to - from).cross(p - from)
+}
+
+fn area(polygon: &[Point]) -> f32 {
+    polygon
+        .iter()
+        .zip(polygon.iter().cycle().skip(1))
+        .map(|(a, b)| a.x * b.y - b.x * a.y)
+        .sum::<f32>()
+        / 2.0
+}
+
+fn bounds(polygon: &[Point]) -> Box2D {
+    Box2D::from_points(polygon.iter().copied())
+}
+
+// Drops slivers and makes the winding counterclockwise.
+fn counterclockwise(mut polygon: Vec<Point>) -> Option<Vec<Point>> {
+    let area = area(&polygon);
+    if area.abs() < MIN_AREA {
+        return None;
+    }
+    if area < 0.0 {
+        polygon.reverse();
+    }
+    Some(polygon)
+}
+
+// The part of a convex polygon left of (or, with `keep_left` false, right
+// of) the line through `from` and `to`.
+fn clip(polygon: &[Point], from: Point, to: Point, keep_left: bool) -> Vec<Point> {
+    let sign = if keep_left { 1.0 } else { -1.0 };
+    let mut result = Vec::with_capacity(polygon.len() + 1);
+    for (&a, &b) in polygon.iter().zip(polygon.iter().cycle().skip(1)) {
+        let (da, db) = (side(from, to, a) * sign, side(from, to, b) * sign);
+        if da >= 0.0 {
+            result.push(a);
+        }
+        if (da >= 0.0) != (db >= 0.0) {
+            result.push(a.lerp(b, da / (da - db)));
+        }
+    }
+    result
+}
+
+// A convex piece minus a convex cutter, as convex pieces: what lies outside
+// each cutter edge and inside all the previous ones.
+fn difference(piece: Vec<Point>, cutter: &[Point]) -> Vec<Vec<Point>> {
+    let mut pieces = Vec::new();
+    let mut remaining = piece;
+    for (&from, &to) in cutter.iter().zip(cutter.iter().cycle().skip(1)) {
+        pieces.extend(counterclockwise(clip(&remaining, from, to, false)));
+        remaining = clip(&remaining, from, to, true);
+        if remaining.len() < 3 {
+            // Nothing of the piece is inside the cutter.
+            return pieces;
+        }
+    }
+    pieces
+}
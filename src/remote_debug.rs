@@ -0,0 +1,306 @@
+use std::io::{self, Read, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::frame_context::FrameContext;
+use crate::inspector::{Inspectable, PropertyValue};
+use crate::ring_buffer::RingBuffer;
+use crate::ui_scene::UIScene;
+
+// Frames the stats average over.
+const STATS_FRAMES: usize = 120;
+// Requests longer than this are dropped along with their client.
+const MAX_REQUEST: usize = 64 * 1024;
+// Clients that leave more of their responses unread than this are dropped,
+// rather than buffering for them without end.
+const MAX_BACKLOG: usize = 4 * 1024 * 1024;
+
+#[derive(Deserialize)]
+#[serde(tag = "cmd", rename_all = "snake_case")]
+enum Request {
+    Tree,
+    Properties {
+        id: usize,
+    },
+    Set {
+        id: usize,
+        name: String,
+        value: Value,
+    },
+    Stats,
+}
+
+struct Client {
+    stream: TcpStream,
+    // Bytes of a request whose newline hasn't arrived yet.
+    pending: Vec<u8>,
+    // Responses the socket didn't take yet.
+    outgoing: Vec<u8>,
+}
+
+// Lets an external tool inspect and edit a running scene. Clients connect over
+// TCP and send one JSON request per line, each answered by one JSON line:
+//
+//     {"cmd": "tree"}                                 every element
+//     {"cmd": "properties", "id": 3}                  what the inspector shows
+//     {"cmd": "set", "id": 3, "name": "scale", "value": 2.0}
+//     {"cmd": "stats"}                                frame times and counts
+//
+// Failed requests get `{"error": "..."}`. Values of `set` have the shape
+// `properties` reports them in, and enums also take the option's name:
+//
+//     renderer.remote_debug = Some(RemoteDebug::bind("127.0.0.1:9229")?);
+//
+// There is no authentication, so only bind to localhost. Sockets are only
+// polled in `update`, once per frame, and never block it: responses a client
+// doesn't read right away wait for the next frames.
+//
+// This is plain TCP, e.g. for `nc` or a native inspector. Browser pages
+// can't open raw sockets, so they need a WebSocket to TCP bridge like
+// websockify in between.
+pub struct RemoteDebug {
+    listener: TcpListener,
+    clients: Vec<Client>,
+    frame_times: RingBuffer<f32>,
+    frame: u64,
+}
+
+impl RemoteDebug {
+    pub fn bind(address: impl ToSocketAddrs) -> io::Result<Self> {
+        let listener = TcpListener::bind(address)?;
+        listener.set_nonblocking(true)?;
+        log::info!("Remote debugging on {}", listener.local_addr()?);
+        Ok(Self {
+            listener,
+            clients: Vec::new(),
+            frame_times: RingBuffer::new(STATS_FRAMES),
+            frame: 0,
+        })
+    }
+
+    pub fn client_count(&self) -> usize {
+        self.clients.len()
+    }
+
+    // Accepts new clients and answers everything they sent since the last
+    // frame. Call before `UIScene::prepare` so edits show up this frame.
+    pub fn update(&mut self, scene: &mut UIScene, frame: &FrameContext) {
+        self.frame_times.push(frame.dt);
+        self.frame = frame.number;
+
+        loop {
+            match self.listener.accept() {
+                Ok((stream, address)) => {
+                    if let Err(e) = stream.set_nonblocking(true) {
+                        log::warn!("Dropping remote debug client {}: {}", address, e);
+                        continue;
+                    }
+                    log::info!("Remote debug client {} connected", address);
+                    self.clients.push(Client {
+                        stream,
+                        pending: Vec::new(),
+                        outgoing: Vec::new(),
+                    });
+                }
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                Err(e) => {
+                    log::warn!("Accepting remote debug client failed: {}", e);
+                    break;
+                }
+            }
+        }
+
+        let mut clients = std::mem::take(&mut self.clients);
+        clients.retain_mut(|client| self.serve(client, scene));
+        self.clients = clients;
+    }
+
+    // Returns whether the client is still connected.
+    fn serve(&self, client: &mut Client, scene: &mut UIScene) -> bool {
+        let mut buffer = [0; 4096];
+        loop {
+            match client.stream.read(&mut buffer) {
+                Ok(0) => return false,
+                Ok(read) => client.pending.extend_from_slice(&buffer[..read]),
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(_) => return false,
+            }
+            // Answered as lines come in, so only an unfinished request can
+            // grow past the limit.
+            self.answer(client, scene);
+            if client.pending.len() > MAX_REQUEST || client.outgoing.len() > MAX_BACKLOG {
+                return false;
+            }
+        }
+        flush(client)
+    }
+
+    fn answer(&self, client: &mut Client, scene: &mut UIScene) {
+        while let Some(end) = client.pending.iter().position(|&b| b == b'\n') {
+            let line = client.pending.drain(..=end).collect::<Vec<_>>();
+            let response = match serde_json::from_slice::<Request>(&line) {
+                Ok(request) => self
+                    .handle(request, scene)
+                    .unwrap_or_else(|error| json!({ "error": error })),
+                Err(e) => json!({ "error": format!("bad request: {}", e) }),
+            };
+            client
+                .outgoing
+                .extend_from_slice(response.to_string().as_bytes());
+            client.outgoing.push(b'\n');
+        }
+    }
+
+    fn handle(&self, request: Request, scene: &mut UIScene) -> Result<Value, String> {
+        let count = scene.elements.len();
+        let element = |id: usize| {
+            (id < count)
+                .then_some(id)
+                .ok_or_else(|| format!("no element {}", id))
+        };
+        match request {
+            Request::Tree => Ok(json!({ "elements": tree(scene) })),
+            Request::Properties { id } => {
+                let element = &scene.elements[element(id)?];
+                let properties = element
+                    .properties()
+                    .into_iter()
+                    .map(|property| (property.name, to_json(&property.value)))
+                    .collect::<serde_json::Map<_, _>>();
+                Ok(json!({ "id": id, "properties": properties }))
+            }
+            Request::Set { id, name, value } => {
+                let element = &mut scene.elements[element(id)?];
+                let current = element
+                    .properties()
+                    .into_iter()
+                    .find(|property| property.name == name)
+                    .ok_or_else(|| format!("element {} has no property {}", id, name))?;
+                let value = from_json(&current.value, &value)
+                    .ok_or_else(|| format!("{} doesn't fit property {}", value, name))?;
+                element.set_property(&name, &value);
+                // Read back, in case the element clamped or rejected it.
+                let value = element
+                    .properties()
+                    .into_iter()
+                    .find(|property| property.name == name)
+                    .map(|property| to_json(&property.value));
+                Ok(json!({ "id": id, "name": name, "value": value }))
+            }
+            Request::Stats => {
+                let frames = self.frame_times.len().max(1) as f32;
+                let average = self.frame_times.iter().sum::<f32>() / frames;
+                let worst = self.frame_times.iter().fold(0.0, f32::max);
+                Ok(json!({
+                    "frame": self.frame,
+                    "fps": if average > 0.0 { 1.0 / average } else { 0.0 },
+                    "average_ms": average * 1000.0,
+                    "worst_ms": worst * 1000.0,
+                    "elements": scene.elements.len(),
+                    "visible": scene.elements.iter().filter(|e| e.visible).count(),
+                }))
+            }
+        }
+    }
+}
+
+// Writes what the socket takes without waiting. Returns whether the client
+// is still connected.
+fn flush(client: &mut Client) -> bool {
+    let mut written = 0;
+    while written < client.outgoing.len() {
+        match client.stream.write(&client.outgoing[written..]) {
+            Ok(0) => return false,
+            Ok(n) => written += n,
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => break,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(_) => return false,
+        }
+    }
+    client.outgoing.drain(..written);
+    true
+}
+
+fn tree(scene: &UIScene) -> Vec<Value> {
+    scene
+        .elements
+        .iter()
+        .enumerate()
+        .map(|(id, element)| {
+            let position = element.instance.position;
+            json!({
+                "id": id,
+                "parent": element.parent,
+                "shape": format!("{:?}", element.shape),
+                "position": [position.x, position.y],
+                "size": [element.size.x, element.size.y],
+                "z_index": element.z_index,
+                "visible": element.visible,
+                "enabled": scene.is_enabled(id),
+                "focused": scene.focused == Some(id),
+                "hovered": scene.hovered() == Some(id),
+            })
+        })
+        .collect()
+}
+
+fn to_json(value: &PropertyValue) -> Value {
+    match value {
+        PropertyValue::F32 { value, min, max } => {
+            json!({ "type": "f32", "value": value, "min": min, "max": max })
+        }
+        PropertyValue::Bool(value) => json!({ "type": "bool", "value": value }),
+        PropertyValue::Color(value) => json!({ "type": "color", "value": value }),
+        PropertyValue::Vec2 { value, min, max } => {
+            json!({ "type": "vec2", "value": [value.x, value.y], "min": min, "max": max })
+        }
+        PropertyValue::Enum { selected, options } => {
+            json!({ "type": "enum", "value": selected, "options": options })
+        }
+    }
+}
+
+// `value` as a new value for a property currently set to `current`. Ranges
+// are the property's own, which clients can't change.
+fn from_json(current: &PropertyValue, value: &Value) -> Option<PropertyValue> {
+    let f32s = |value: &Value| -> Option<Vec<f32>> {
+        value
+            .as_array()?
+            .iter()
+            .map(|v| v.as_f64().map(|v| v as f32))
+            .collect()
+    };
+    Some(match current {
+        PropertyValue::F32 { min, max, .. } => PropertyValue::F32 {
+            value: value.as_f64()? as f32,
+            min: *min,
+            max: *max,
+        },
+        PropertyValue::Bool(_) => PropertyValue::Bool(value.as_bool()?),
+        PropertyValue::Color(_) => PropertyValue::Color(f32s(value)?.try_into().ok()?),
+        PropertyValue::Vec2 { min, max, .. } => {
+            let [x, y] = f32s(value)?.try_into().ok()?;
+            PropertyValue::Vec2 {
+                value: cgmath::Vector2::new(x, y),
+                min: *min,
+                max: *max,
+            }
+        }
+        PropertyValue::Enum { options, .. } => {
+            let selected = match value {
+                Value::String(name) => options.iter().position(|o| o == name)?,
+                _ => value.as_u64()? as usize,
+            };
+            if selected >= options.len() {
+                return None;
+            }
+            PropertyValue::Enum {
+                selected,
+                options: options.clone(),
+            }
+        }
+    })
+}
@@ -0,0 +1,218 @@
+use crate::deletion_queue::DeletionQueue;
+use crate::frame_context::FrameContext;
+use crate::gpu_context::GpuContext;
+use crate::texture::{SamplerConfig, Texture};
+use crate::ui_scene::{Background, UIScene};
+
+// A texture scenes render into and elements sample, see
+// `UIScene::set_texture_fill`.
+pub struct RenderTarget {
+    pub texture: Texture,
+    // For scenes rendering into it, which take their format and size from
+    // a surface configuration.
+    pub config: wgpu::SurfaceConfiguration,
+}
+
+impl RenderTarget {
+    pub fn new(gpu: &mut GpuContext, format: wgpu::TextureFormat, width: u32, height: u32) -> Self {
+        let config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            format,
+            width: width.max(1),
+            height: height.max(1),
+            present_mode: wgpu::PresentMode::Fifo,
+            alpha_mode: wgpu::CompositeAlphaMode::Opaque,
+            view_formats: vec![],
+        };
+        let sampler = gpu.samplers.get(&gpu.device, SamplerConfig::linear());
+        Self {
+            texture: Self::create_texture(&gpu.device, &config, sampler),
+            config,
+        }
+    }
+
+    // Replaces the texture, so fills sampling it have to be set again.
+    pub fn resize(&mut self, device: &wgpu::Device, width: u32, height: u32) {
+        self.config.width = width.max(1);
+        self.config.height = height.max(1);
+        let sampler = self.texture.sampler.clone();
+        self.texture = Self::create_texture(device, &self.config, sampler);
+    }
+
+    fn create_texture(
+        device: &wgpu::Device,
+        config: &wgpu::SurfaceConfiguration,
+        sampler: std::rc::Rc<wgpu::Sampler>,
+    ) -> Texture {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Render target"),
+            size: wgpu::Extent3d {
+                width: config.width,
+                height: config.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: config.format,
+            usage: config.usage,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        Texture {
+            texture,
+            view,
+            sampler,
+        }
+    }
+}
+
+// A scene of its own drawn into a texture, and the elements of the main
+// scene showing it.
+pub struct OffscreenScene {
+    pub scene: UIScene,
+    pub target: RenderTarget,
+    // Inactive scenes are neither updated nor rendered, their elements keep
+    // showing the last frame.
+    pub active: bool,
+    elements: Vec<usize>,
+}
+
+impl OffscreenScene {
+    // Elements of the main scene filled with this one.
+    pub fn elements(&self) -> &[usize] {
+        &self.elements
+    }
+}
+
+// Scenes rendered into textures before the main scene, for minimaps and
+// picture-in-picture views:
+//
+//     let minimap = renderer.offscreen.add(&mut renderer.gpu, format, 256, 256);
+//     renderer.offscreen.show_in(minimap, &mut renderer.ui_scene, &renderer.gpu.device, element);
+//     renderer.offscreen.get_mut(minimap).scene.camera.scale = 0.2;
+//
+// `Renderer` updates and renders them along with its own scenes. They clear
+// to transparent unless given another `Background`.
+#[derive(Default)]
+pub struct OffscreenScenes {
+    scenes: Vec<OffscreenScene>,
+}
+
+impl OffscreenScenes {
+    // Returns the new scene's id. `format` should be the one the main scene
+    // renders in, so both encode colors the same way.
+    pub fn add(
+        &mut self,
+        gpu: &mut GpuContext,
+        format: wgpu::TextureFormat,
+        width: u32,
+        height: u32,
+    ) -> usize {
+        let target = RenderTarget::new(gpu, format, width, height);
+        let mut scene = pollster::block_on(UIScene::new(gpu, &target.config));
+        scene.elements.clear();
+        scene.background = Background::Solid([0.0; 4]);
+        self.scenes.push(OffscreenScene {
+            scene,
+            target,
+            active: true,
+            elements: Vec::new(),
+        });
+        self.scenes.len() - 1
+    }
+
+    pub fn get(&self, id: usize) -> &OffscreenScene {
+        &self.scenes[id]
+    }
+
+    pub fn get_mut(&mut self, id: usize) -> &mut OffscreenScene {
+        &mut self.scenes[id]
+    }
+
+    pub fn len(&self) -> usize {
+        self.scenes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.scenes.is_empty()
+    }
+
+    // Fills `element` of `main` with the scene's target.
+    pub fn show_in(
+        &mut self,
+        id: usize,
+        main: &mut UIScene,
+        device: &wgpu::Device,
+        element: usize,
+    ) {
+        let offscreen = &mut self.scenes[id];
+        main.set_texture_fill(device, element, &offscreen.target.texture);
+        if !offscreen.elements.contains(&element) {
+            offscreen.elements.push(element);
+        }
+    }
+
+    // Stops filling `element` with the scene.
+    pub fn hide_in(&mut self, id: usize, main: &mut UIScene, element: usize) {
+        let offscreen = &mut self.scenes[id];
+        offscreen.elements.retain(|&e| e != element);
+        main.clear_texture_fill(element);
+    }
+
+    // Resizes the target and the scene's viewport with it, then fills the
+    // elements showing it again.
+    pub fn resize(
+        &mut self,
+        id: usize,
+        main: &mut UIScene,
+        device: &wgpu::Device,
+        width: u32,
+        height: u32,
+    ) {
+        let offscreen = &mut self.scenes[id];
+        offscreen.target.resize(device, width, height);
+        offscreen.scene.resize(device, &offscreen.target.config);
+        for &element in &offscreen.elements {
+            main.set_texture_fill(device, element, &offscreen.target.texture);
+        }
+    }
+
+    // Prepares the active scenes, and has `main` redraw the elements showing
+    // those that changed. Call before preparing `main`.
+    pub fn update(
+        &mut self,
+        frame: &FrameContext,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        deletion_queue: &mut DeletionQueue,
+        main: &mut UIScene,
+    ) {
+        for offscreen in self.scenes.iter_mut().filter(|o| o.active) {
+            offscreen.scene.retessellate(device, queue, deletion_queue);
+            offscreen
+                .scene
+                .prepare(frame, device, queue, deletion_queue);
+            if offscreen.scene.damage().is_some() {
+                for &element in &offscreen.elements {
+                    main.invalidate(element);
+                }
+            }
+        }
+    }
+
+    // Records the active scenes into their targets, before the main scene
+    // samples them.
+    pub fn render(
+        &mut self,
+        frame: &FrameContext,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+    ) {
+        for offscreen in self.scenes.iter_mut().filter(|o| o.active) {
+            offscreen
+                .scene
+                .render(frame, queue, encoder, &offscreen.target.texture.view);
+        }
+    }
+}
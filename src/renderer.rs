@@ -0,0 +1,494 @@
+use raw_window_handle::{HasRawDisplayHandle, HasRawWindowHandle};
+use winit::event::WindowEvent;
+
+use crate::debug_flags::{DebugFlags, FpsCounter};
+use crate::frame_context::FrameContext;
+use crate::gpu_context::GpuContext;
+use crate::render_target::OffscreenScenes;
+use crate::texture::SamplerConfig;
+use crate::tonemap::{Tonemap, TonemapPass, HDR_FORMAT};
+use crate::{gamma_audit, gpu_errors, model_renderer, ui_scene};
+
+#[derive(Debug, Clone, Default)]
+pub struct RendererOptions {
+    // Render scenes into a float target and tonemap it onto the surface,
+    // so bright content doesn't clip. Ignored where float targets aren't
+    // renderable.
+    pub hdr: Option<Tonemap>,
+    // Blend in a linear float target even without HDR, for surfaces that
+    // would otherwise blend gamma-encoded values.
+    pub force_linear_blending: bool,
+    // Log how blending behaves on this surface and draw reference patterns.
+    pub gamma_audit: bool,
+    // Clear to transparent and composite the surface with per-pixel alpha,
+    // for overlay windows (see `overlay::OverlayOptions`).
+    pub transparent: bool,
+    // Samples per pixel for the UI, see `UIScene::with_msaa`. None or 1
+    // turns multisampling off.
+    pub msaa: Option<u32>,
+    // Give the UI a depth buffer, see `UISceneOptions::depth`.
+    pub ui_depth: bool,
+    // Render at a fraction of the window size and blow the frame up by this
+    // whole factor, for pixel art. Needs float targets, see `TonemapPass`.
+    pub pixel_scale: Option<u32>,
+    // How model textures are filtered unless their material says otherwise.
+    pub texture_sampler: SamplerConfig,
+    pub debug: DebugFlags,
+    // Don't acquire or present frames in which nothing changed, see
+    // `UIScene::damage`. The window keeps showing the last one, which saves
+    // the GPU work of static editors and dashboards. Hosts drawing into the
+    // surface themselves should leave this off.
+    pub skip_idle_frames: bool,
+}
+
+impl RendererOptions {
+    // Defaults plus whatever `WGPIE_DEBUG` and `WGPIE_HDR` ask for.
+    pub fn from_env() -> Self {
+        Self {
+            hdr: Tonemap::from_env(),
+            debug: DebugFlags::from_env(),
+            ..Default::default()
+        }
+    }
+}
+
+// Lets code outside the renderer release its own GPU resources while the
+// window is hidden or minimized.
+pub trait LifecycleHook {
+    fn suspended(&mut self, _gpu: &mut GpuContext) {}
+    fn resumed(&mut self, _gpu: &mut GpuContext) {}
+}
+
+// Everything needed to draw into a window, without owning the window or the
+// event loop. Hosts that embed the crate (Qt, GTK, tauri...) create this from
+// their own native window handle and drive `resize`/`update`/`render`
+// themselves.
+pub struct Renderer {
+    pub surface: wgpu::Surface,
+    pub gpu: GpuContext,
+    pub config: wgpu::SurfaceConfiguration,
+    // Same as `config` except for the format, which is the HDR format when
+    // tonemapping is enabled.
+    pub scene_config: wgpu::SurfaceConfiguration,
+    pub tonemap: Option<TonemapPass>,
+    pub size: winit::dpi::PhysicalSize<u32>,
+    pub model_scene: model_renderer::ModelScene,
+    pub ui_scene: ui_scene::UIScene,
+    // Rendered before `ui_scene`, which can show them in its elements.
+    pub offscreen: OffscreenScenes,
+    pub hooks: Vec<Box<dyn LifecycleHook>>,
+    pub fps: Option<FpsCounter>,
+    #[cfg(feature = "editor")]
+    pub editor: crate::editor::Editor,
+    #[cfg(not(target_arch = "wasm32"))]
+    pub dialogs: crate::file_dialog::FileDialogs,
+    // Off until given a server, see `RemoteDebug::bind`.
+    #[cfg(all(feature = "remote-debug", not(target_arch = "wasm32")))]
+    pub remote_debug: Option<crate::remote_debug::RemoteDebug>,
+    suspended: bool,
+    skip_idle_frames: bool,
+    // The model camera as of the last frame, and whether neither scene has
+    // changed since.
+    model_camera: Vec<u8>,
+    idle: bool,
+}
+
+impl Renderer {
+    /// # Safety
+    ///
+    /// `window` must stay valid for as long as the returned `Renderer` lives.
+    pub async unsafe fn new<W>(
+        window: &W,
+        size: winit::dpi::PhysicalSize<u32>,
+        options: RendererOptions,
+    ) -> Self
+    where
+        W: HasRawWindowHandle + HasRawDisplayHandle,
+    {
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+            backends: wgpu::Backends::all(),
+            dx12_shader_compiler: Default::default(),
+        });
+
+        let surface = instance.create_surface(window).unwrap();
+        let adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: wgpu::PowerPreference::default(),
+                compatible_surface: Some(&surface),
+                force_fallback_adapter: false,
+            })
+            .await
+            .unwrap();
+
+        let (device, queue) = adapter
+            .request_device(
+                &wgpu::DeviceDescriptor {
+                    // The wireframe debug view and compressed textures, see
+                    // `ktx2::Ktx2`, where available.
+                    features: adapter.features()
+                        & (wgpu::Features::POLYGON_MODE_LINE
+                            | wgpu::Features::TEXTURE_COMPRESSION_BC),
+                    limits: if cfg!(target_arch = "wasm32") {
+                        wgpu::Limits::downlevel_webgl2_defaults()
+                    } else {
+                        wgpu::Limits::default()
+                    },
+                    label: None,
+                },
+                None,
+            )
+            .await
+            .unwrap();
+
+        gpu_errors::install_handler(&device);
+        if options.debug.adapter {
+            log::info!("Adapter: {:?}", adapter.get_info());
+            log::info!(
+                "Surface capabilities: {:?}",
+                surface.get_capabilities(&adapter)
+            );
+        }
+
+        let surface_caps = surface.get_capabilities(&adapter);
+
+        let surface_format = choose_surface_format(&surface_caps.formats);
+        let alpha_mode = choose_alpha_mode(&surface_caps.alpha_modes, options.transparent);
+
+        let config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            format: surface_format,
+            width: size.width,
+            height: size.height,
+            present_mode: surface_caps.present_modes[0],
+            alpha_mode,
+            view_formats: vec![],
+        };
+
+        surface.configure(&device, &config);
+
+        // Without an sRGB surface the scene is rendered into a linear target
+        // and the tonemap pass does the sRGB encoding, so colors match
+        // platforms that do offer sRGB surfaces.
+        let needs_encoding = !surface_format.is_srgb();
+        let pixel_scale = options.pixel_scale.unwrap_or(1).max(1);
+        let tonemap_mode = options.hdr.or((options.force_linear_blending
+            || needs_encoding
+            || pixel_scale > 1)
+            .then_some(Tonemap::Clamp));
+        let tonemap = match tonemap_mode {
+            Some(tonemap) if TonemapPass::is_supported(&adapter) => {
+                Some(TonemapPass::new(&device, &config, tonemap, pixel_scale))
+            }
+            Some(_) => {
+                log::warn!(
+                    "{:?} isn't renderable on this adapter, HDR disabled",
+                    HDR_FORMAT
+                );
+                if pixel_scale > 1 {
+                    log::warn!("Pixel scaling needs float targets, rendering at full size");
+                }
+                if needs_encoding {
+                    log::warn!(
+                        "Surface format {:?} isn't sRGB, overlaps will blend darker than intended",
+                        surface_format
+                    );
+                }
+                None
+            }
+            None => None,
+        };
+        let mut scene_config = config.clone();
+        if let Some(tonemap) = &tonemap {
+            scene_config.format = HDR_FORMAT;
+            (scene_config.width, scene_config.height) = tonemap.target_size();
+        }
+
+        let mut gpu = GpuContext::new(device, queue);
+        gpu.default_sampler = options.texture_sampler;
+        let mut model_scene = model_renderer::ModelScene::new(&mut gpu, &scene_config).await;
+        if options.transparent {
+            model_scene.clear_color = wgpu::Color::TRANSPARENT;
+        }
+        let ui_options = ui_scene::UISceneOptions {
+            samples: options.msaa.unwrap_or(1),
+            depth: options.ui_depth,
+        };
+        let mut ui_scene =
+            ui_scene::UIScene::with_options(&mut gpu, &scene_config, ui_options).await;
+
+        ui_scene.debug = options.debug;
+        if options.gamma_audit {
+            log::info!(
+                "{}",
+                gamma_audit::report(config.format, scene_config.format)
+            );
+            gamma_audit::add_test_pattern(&mut ui_scene, &gpu.device);
+        }
+
+        #[cfg(feature = "editor")]
+        let editor = crate::editor::Editor::new(&mut ui_scene, Default::default());
+
+        Self {
+            surface,
+            gpu,
+            config,
+            scene_config,
+            tonemap,
+            size,
+            model_scene,
+            ui_scene,
+            offscreen: OffscreenScenes::default(),
+            hooks: Vec::new(),
+            fps: options.debug.fps.then(FpsCounter::default),
+            #[cfg(feature = "editor")]
+            editor,
+            #[cfg(not(target_arch = "wasm32"))]
+            dialogs: crate::file_dialog::FileDialogs::new(),
+            #[cfg(all(feature = "remote-debug", not(target_arch = "wasm32")))]
+            remote_debug: None,
+            suspended: false,
+            skip_idle_frames: options.skip_idle_frames,
+            model_camera: Vec::new(),
+            idle: false,
+        }
+    }
+
+    pub fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
+        if new_size.width > 0 && new_size.height > 0 {
+            self.size = new_size;
+            self.config.width = new_size.width;
+            self.config.height = new_size.height;
+            self.scene_config.width = new_size.width;
+            self.scene_config.height = new_size.height;
+            self.surface.configure(&self.gpu.device, &self.config);
+            if let Some(tonemap) = &mut self.tonemap {
+                tonemap.resize(&self.gpu.device, &self.config);
+                (self.scene_config.width, self.scene_config.height) = tonemap.target_size();
+            }
+            self.model_scene
+                .resize(&self.gpu.device, &self.scene_config);
+            self.ui_scene.resize(&self.gpu.device, &self.scene_config);
+        }
+    }
+
+    // The window's, so `units::Length::Points` come out the same size on
+    // any display. Pixel scaling makes each scene pixel bigger already.
+    pub fn set_scale_factor(&mut self, scale_factor: f64) {
+        let pixel_scale = self.tonemap.as_ref().map_or(1, |t| t.pixel_scale());
+        self.ui_scene.scale_factor = scale_factor as f32 / pixel_scale as f32;
+    }
+
+    pub fn is_suspended(&self) -> bool {
+        self.suspended
+    }
+
+    // Keeps scenes and their state alive but shrinks the size dependent
+    // targets to 1x1 and stops rendering, for hidden or minimized windows.
+    // Tray-style hosts hide their window with `set_visible(false)` and call
+    // this, then `resume` when showing it again.
+    pub fn suspend(&mut self) {
+        if self.suspended {
+            return;
+        }
+        self.suspended = true;
+
+        let mut tiny = self.config.clone();
+        tiny.width = 1;
+        tiny.height = 1;
+        if let Some(tonemap) = &mut self.tonemap {
+            tonemap.resize(&self.gpu.device, &tiny);
+        }
+        tiny.format = self.scene_config.format;
+        self.model_scene.resize(&self.gpu.device, &tiny);
+        // Its viewport comes back with the size on `resume`.
+        self.ui_scene.resize(&self.gpu.device, &tiny);
+        for hook in &mut self.hooks {
+            hook.suspended(&mut self.gpu);
+        }
+
+        // Let in-flight frames finish so the deletion queue can free
+        // everything right away.
+        self.gpu.device.poll(wgpu::Maintain::Wait);
+        self.gpu.deletion_queue.collect();
+    }
+
+    pub fn resume(&mut self) {
+        if !self.suspended {
+            return;
+        }
+        self.suspended = false;
+        for hook in &mut self.hooks {
+            hook.resumed(&mut self.gpu);
+        }
+        self.resize(self.size);
+    }
+
+    pub fn input(&mut self, event: &WindowEvent) -> bool {
+        // Scenes only know their own pixels, which pixel scaling shrinks.
+        let scaled;
+        let event = match (event, &self.tonemap) {
+            (
+                WindowEvent::CursorMoved {
+                    device_id,
+                    position,
+                    ..
+                },
+                Some(tonemap),
+            ) if tonemap.pixel_scale() > 1 => {
+                let (x, y) = tonemap.to_target(position.x as f32, position.y as f32);
+                #[allow(deprecated)]
+                {
+                    scaled = WindowEvent::CursorMoved {
+                        device_id: *device_id,
+                        position: winit::dpi::PhysicalPosition::new(x as f64, y as f64),
+                        modifiers: Default::default(),
+                    };
+                }
+                &scaled
+            }
+            _ => event,
+        };
+        #[cfg(feature = "editor")]
+        if self.editor.input(&mut self.ui_scene, event) {
+            return true;
+        }
+        if self.ui_scene.input(event) {
+            return true;
+        }
+        self.model_scene.input(event);
+        false
+    }
+
+    // Starts a frame, waiting if the GPU is still too far behind. Pass the
+    // result to `update` and then `render`.
+    pub fn begin_frame(&mut self) -> FrameContext {
+        self.gpu.begin_frame()
+    }
+
+    pub fn update(&mut self, frame: &FrameContext) {
+        if let Some(fps) = &mut self.fps {
+            fps.update(frame.dt);
+        }
+        self.model_scene.update(&self.gpu.queue);
+        #[cfg(not(target_arch = "wasm32"))]
+        self.dialogs.update(&mut self.ui_scene);
+        #[cfg(feature = "editor")]
+        self.editor
+            .update(&mut self.ui_scene, &self.gpu.device, &self.gpu.queue);
+        #[cfg(all(feature = "remote-debug", not(target_arch = "wasm32")))]
+        if let Some(remote_debug) = &mut self.remote_debug {
+            remote_debug.update(&mut self.ui_scene, frame);
+        }
+        self.offscreen.update(
+            frame,
+            &self.gpu.device,
+            &self.gpu.queue,
+            &mut self.gpu.deletion_queue,
+            &mut self.ui_scene,
+        );
+        self.ui_scene.retessellate(
+            &self.gpu.device,
+            &self.gpu.queue,
+            &mut self.gpu.deletion_queue,
+        );
+        self.ui_scene.prepare(
+            frame,
+            &self.gpu.device,
+            &self.gpu.queue,
+            &mut self.gpu.deletion_queue,
+        );
+        let model_camera = bytemuck::bytes_of(&self.model_scene.camera_uniform);
+        self.idle = self.ui_scene.damage().is_none() && model_camera == self.model_camera;
+        if !self.idle {
+            self.model_camera = model_camera.to_vec();
+        }
+        if let Some(tonemap) = &self.tonemap {
+            tonemap.update(&self.gpu.queue);
+        }
+        #[cfg(all(feature = "crash-report", not(target_arch = "wasm32")))]
+        crate::crash_report::record(crate::crash_report::FrameReport::new(self, frame));
+    }
+
+    pub fn render(&mut self, frame: &FrameContext) -> Result<(), wgpu::SurfaceError> {
+        if self.suspended {
+            return Ok(());
+        }
+        if self.skip_idle_frames && self.idle {
+            // Still submits, so buffer writes go out and the deletion queue
+            // keeps freeing.
+            self.gpu.submit(frame, std::iter::empty());
+            return Ok(());
+        }
+        let output = match self.surface.get_current_texture() {
+            Ok(output) => output,
+            Err(error) => {
+                // Nothing was shown, so the next frame can't count as idle.
+                self.model_camera.clear();
+                return Err(error);
+            }
+        };
+        let view = output
+            .texture
+            .create_view(&wgpu::TextureViewDescriptor::default());
+
+        let mut encoder = self
+            .gpu
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Render Encoder"),
+            });
+
+        let scene_view = match &self.tonemap {
+            Some(tonemap) => &tonemap.target.view,
+            None => &view,
+        };
+        self.offscreen.render(frame, &self.gpu.queue, &mut encoder);
+        self.model_scene.render(&mut encoder, scene_view);
+        self.ui_scene
+            .render(frame, &self.gpu.queue, &mut encoder, scene_view);
+        if let Some(tonemap) = &self.tonemap {
+            tonemap.render(&mut encoder, &view);
+        }
+        self.gpu.submit(frame, std::iter::once(encoder.finish()));
+        output.present();
+        Ok(())
+    }
+}
+
+// Prefers 8 bit sRGB formats in whichever channel order the platform lists
+// first (Bgra on most desktop surfaces, Rgba on some Android/web ones), then
+// any sRGB format, then whatever comes first.
+fn choose_surface_format(formats: &[wgpu::TextureFormat]) -> wgpu::TextureFormat {
+    use wgpu::TextureFormat::*;
+    formats
+        .iter()
+        .copied()
+        .find(|f| matches!(f, Bgra8UnormSrgb | Rgba8UnormSrgb))
+        .or_else(|| formats.iter().copied().find(|f| f.is_srgb()))
+        .unwrap_or(formats[0])
+}
+
+// Opaque scenes ask for that explicitly rather than taking the first mode,
+// which some compositors list as premultiplied. Alpha blending over a
+// transparent clear color leaves premultiplied colors behind, so that's the
+// preferred mode for transparent windows.
+fn choose_alpha_mode(
+    modes: &[wgpu::CompositeAlphaMode],
+    transparent: bool,
+) -> wgpu::CompositeAlphaMode {
+    use wgpu::CompositeAlphaMode::*;
+    let preferred: &[_] = if transparent {
+        &[PreMultiplied, PostMultiplied, Inherit]
+    } else {
+        &[Opaque, Auto, Inherit]
+    };
+    let mode = preferred
+        .iter()
+        .copied()
+        .find(|mode| modes.contains(mode))
+        .unwrap_or(modes[0]);
+    if transparent && mode == Opaque {
+        log::warn!("Surface doesn't support transparency, the window will be opaque");
+    }
+    mode
+}
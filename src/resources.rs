@@ -1,5 +1,6 @@
 use cfg_if::cfg_if;
 use std::io::{BufReader, Cursor};
+use std::rc::Rc;
 use wgpu::util::DeviceExt;
 
 use crate::{model, texture};
@@ -61,9 +62,10 @@ pub async fn load_texture(
     file_name: &str,
     device: &wgpu::Device,
     queue: &wgpu::Queue,
+    sampler: Rc<wgpu::Sampler>,
 ) -> anyhow::Result<texture::Texture> {
     let data = load_binary(file_name).await?;
-    texture::Texture::from_bytes(device, queue, &data, file_name)
+    texture::Texture::from_bytes(device, queue, &data, file_name, sampler)
 }
 
 pub async fn load_model(
@@ -71,6 +73,8 @@ pub async fn load_model(
     device: &wgpu::Device,
     queue: &wgpu::Queue,
     layout: &wgpu::BindGroupLayout,
+    samplers: &mut texture::SamplerCache,
+    sampler: texture::SamplerConfig,
 ) -> anyhow::Result<model::Model> {
     let obj_text = load_string(file_name).await?;
     let obj_cursor = Cursor::new(obj_text);
@@ -92,31 +96,20 @@ pub async fn load_model(
 
     let mut materials = Vec::new();
     for m in obj_materials? {
+        let diffuse_sampler = samplers.get(device, sampler);
         let diffuse_texture = if String::is_empty(&m.diffuse_texture) {
-            load_texture("MissingTexture.png", device, queue).await?
+            load_texture("MissingTexture.png", device, queue, diffuse_sampler).await?
         } else {
-            load_texture(&m.diffuse_texture, device, queue).await?
+            load_texture(&m.diffuse_texture, device, queue, diffuse_sampler).await?
         };
-        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            layout,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: wgpu::BindingResource::TextureView(&diffuse_texture.view),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: wgpu::BindingResource::Sampler(&diffuse_texture.sampler),
-                },
-            ],
-            label: None,
-        });
-
-        materials.push(model::Material {
-            name: m.name,
+
+        materials.push(model::Material::new(
+            device,
+            m.name,
             diffuse_texture,
-            bind_group,
-        })
+            sampler,
+            layout,
+        ))
     }
 
     let meshes = models
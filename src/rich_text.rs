@@ -0,0 +1,176 @@
+use std::ops::Range;
+
+use crate::color::Color;
+
+// How a span differs from the rest of its `Text`. What's left unset comes
+// from the text.
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct SpanStyle {
+    // Linear, with straight alpha.
+    pub color: Option<[f32; 4]>,
+    // Font height in world units.
+    pub size: Option<f32>,
+    pub bold: bool,
+    pub italic: bool,
+}
+
+impl SpanStyle {
+    pub fn color(mut self, color: [f32; 4]) -> Self {
+        self.color = Some(color);
+        self
+    }
+
+    pub fn size(mut self, size: f32) -> Self {
+        self.size = Some(size);
+        self
+    }
+
+    pub fn bold(mut self) -> Self {
+        self.bold = true;
+        self
+    }
+
+    pub fn italic(mut self) -> Self {
+        self.italic = true;
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Span {
+    // Bytes of the text.
+    pub range: Range<usize>,
+    pub style: SpanStyle,
+}
+
+// Text made of styled spans, for one `Text` to draw damage numbers, chat
+// lines or highlighted keywords without an element per style:
+//
+//     let hit = RichText::new()
+//         .push("Critical ", SpanStyle::default())
+//         .push("1200", SpanStyle::default().color(red).size(32.0).bold());
+//     text.set_rich_text(&mut scene, device, queue, &mut glyphs, hit);
+//
+// or from markup, see `markup`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RichText {
+    text: String,
+    // In order, covering the text.
+    spans: Vec<Span>,
+}
+
+impl RichText {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Appends `text` in `style`. Runs in the same style merge into a span.
+    pub fn push(mut self, text: &str, style: SpanStyle) -> Self {
+        self.append(text, style);
+        self
+    }
+
+    fn append(&mut self, text: &str, style: SpanStyle) {
+        if text.is_empty() {
+            return;
+        }
+        let start = self.text.len();
+        self.text.push_str(text);
+        let end = self.text.len();
+        match self.spans.last_mut() {
+            Some(span) if span.style == style => span.range.end = end,
+            _ => self.spans.push(Span {
+                range: start..end,
+                style,
+            }),
+        }
+    }
+
+    // Reads inline tags, which nest:
+    //
+    //     "Deals <color=#ff4040><b>120</b></color> damage to <i>Goblin</i>"
+    //
+    // <b>, <i>, <color=#rrggbb> or #rrggbbaa in sRGB like `Color::hex`, and
+    // <size=24>. Anything else in angle brackets is shown as it is, and
+    // tags left open run to the end.
+    pub fn markup(markup: &str) -> Self {
+        let mut rich = Self::new();
+        let mut colors: Vec<[f32; 4]> = Vec::new();
+        let mut sizes: Vec<f32> = Vec::new();
+        let (mut bold, mut italic) = (0u32, 0u32);
+        let mut rest = markup;
+        while !rest.is_empty() {
+            let style = SpanStyle {
+                color: colors.last().copied(),
+                size: sizes.last().copied(),
+                bold: bold > 0,
+                italic: italic > 0,
+            };
+            let Some(open) = rest.find('<') else {
+                rich.append(rest, style);
+                break;
+            };
+            rich.append(&rest[..open], style);
+            rest = &rest[open..];
+            let tag = rest[1..].find('>').map(|end| &rest[1..end + 1]);
+            let known = match tag {
+                Some("b") => {
+                    bold += 1;
+                    true
+                }
+                Some("/b") if bold > 0 => {
+                    bold -= 1;
+                    true
+                }
+                Some("i") => {
+                    italic += 1;
+                    true
+                }
+                Some("/i") if italic > 0 => {
+                    italic -= 1;
+                    true
+                }
+                Some("/color") => colors.pop().is_some(),
+                Some("/size") => sizes.pop().is_some(),
+                Some(tag) => {
+                    if let Some(color) = tag.strip_prefix("color=").and_then(Color::hex) {
+                        colors.push(color.to_linear());
+                        true
+                    } else if let Some(size) = tag
+                        .strip_prefix("size=")
+                        .and_then(|size| size.parse::<f32>().ok())
+                        .filter(|size| *size > 0.0)
+                    {
+                        sizes.push(size);
+                        true
+                    } else {
+                        false
+                    }
+                }
+                None => false,
+            };
+            match (known, tag) {
+                (true, Some(tag)) => rest = &rest[tag.len() + 2..],
+                _ => {
+                    rich.append("<", style);
+                    rest = &rest[1..];
+                }
+            }
+        }
+        rich
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn spans(&self) -> &[Span] {
+        &self.spans
+    }
+}
+
+impl From<&str> for RichText {
+    fn from(text: &str) -> Self {
+        Self::new().push(text, SpanStyle::default())
+    }
+}
@@ -0,0 +1,66 @@
+// Keeps the last `capacity` values pushed. Readers remember `written()` and
+// later ask for what came `since`, so a producer can keep pushing without
+// knowing who reads.
+#[derive(Debug, Clone)]
+pub struct RingBuffer<T> {
+    items: Vec<T>,
+    capacity: usize,
+    written: u64,
+}
+
+impl<T: Copy> RingBuffer<T> {
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "a ring buffer needs room for one item");
+        Self {
+            items: Vec::with_capacity(capacity),
+            capacity,
+            written: 0,
+        }
+    }
+
+    pub fn push(&mut self, item: T) {
+        let slot = (self.written % self.capacity as u64) as usize;
+        if slot < self.items.len() {
+            self.items[slot] = item;
+        } else {
+            self.items.push(item);
+        }
+        self.written += 1;
+    }
+
+    pub fn extend(&mut self, items: impl IntoIterator<Item = T>) {
+        for item in items {
+            self.push(item);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    // Number of items pushed so far, including overwritten ones.
+    pub fn written(&self) -> u64 {
+        self.written
+    }
+
+    // Oldest to newest.
+    pub fn iter(&self) -> impl Iterator<Item = T> + '_ {
+        self.since(0)
+    }
+
+    // Items pushed after `written` items, oldest first. Items that were
+    // already overwritten are skipped.
+    pub fn since(&self, written: u64) -> impl Iterator<Item = T> + '_ {
+        let oldest = self.written - self.items.len() as u64;
+        let first = written.clamp(oldest, self.written);
+        (first..self.written).map(move |i| self.items[(i % self.capacity as u64) as usize])
+    }
+}
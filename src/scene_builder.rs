@@ -0,0 +1,388 @@
+use std::collections::HashMap;
+
+use lyon::path::builder::BorderRadii;
+
+use crate::binding::{Bindings, Observable};
+use crate::ui_scene::{
+    BlendMode, Outline, Player, Shape, Stroke, Tessellation, UIEvent, UIMaterial, UIScene,
+};
+
+type ClickHandler = Box<dyn FnMut(&mut UIScene, usize)>;
+// Connects an observable to the element once it exists.
+type BindingSpec = Box<dyn FnOnce(&mut Bindings, usize)>;
+
+struct ElementSpec {
+    shape: Shape,
+    colors: ([f32; 3], [f32; 3]),
+    filled: bool,
+    outline: Option<Outline>,
+    position: cgmath::Vector2<f32>,
+    material: UIMaterial,
+    blend: BlendMode,
+    z_index: i32,
+    draggable: bool,
+    tessellation: Tessellation,
+    on_click: Option<ClickHandler>,
+    bindings: Vec<BindingSpec>,
+}
+
+// Fluent setup for prototypes, each shape method (`rect`, `circle`, ...)
+// starts a new element and the calls after it configure that element:
+//
+//     let mut clicks = UIScene::builder()
+//         .rect(120.0, 40.0)
+//         .color(0.2, 0.6, 0.9)
+//         .at(0.0, 100.0)
+//         .on_click(|scene, element| scene.elements[element].instance.tint[3] = 0.5)
+//         .build(&mut renderer.ui_scene, &renderer.gpu.device);
+//
+// and then `clicks.handle(&mut renderer.ui_scene)` once per frame.
+#[derive(Default)]
+pub struct SceneBuilder {
+    elements: Vec<ElementSpec>,
+    // Open groups, each entry being the group's absolute offset.
+    groups: Vec<cgmath::Vector2<f32>>,
+}
+
+impl UIScene {
+    pub fn builder() -> SceneBuilder {
+        SceneBuilder::default()
+    }
+}
+
+impl SceneBuilder {
+    pub fn rect(self, width: f32, height: f32) -> Self {
+        self.shape(Shape::Rect { width, height })
+    }
+
+    // Same radius on every corner, use `shape` for per-corner radii.
+    pub fn rounded_rect(self, width: f32, height: f32, radius: f32) -> Self {
+        self.shape(Shape::RoundedRect {
+            width,
+            height,
+            radii: BorderRadii::new(radius),
+        })
+    }
+
+    pub fn circle(self, radius: f32) -> Self {
+        self.shape(Shape::Circle { radius })
+    }
+
+    pub fn ellipse(self, rx: f32, ry: f32) -> Self {
+        self.shape(Shape::Ellipse { rx, ry })
+    }
+
+    pub fn polygon(self, sides: u32, radius: f32) -> Self {
+        self.shape(Shape::Polygon { sides, radius })
+    }
+
+    pub fn star(self, points: u32, outer: f32, inner: f32) -> Self {
+        self.shape(Shape::Star {
+            points,
+            outer,
+            inner,
+        })
+    }
+
+    // Angles in degrees, counterclockwise from +x.
+    pub fn arc(self, radius: f32, inner: f32, start: f32, end: f32) -> Self {
+        self.shape(Shape::Arc {
+            radius,
+            inner,
+            start,
+            end,
+        })
+    }
+
+    pub fn shape(mut self, shape: Shape) -> Self {
+        self.elements.push(ElementSpec {
+            shape,
+            colors: ([1.0; 3], [1.0; 3]),
+            filled: true,
+            outline: None,
+            position: self.offset(),
+            material: UIMaterial::Solid,
+            blend: BlendMode::Normal,
+            z_index: 0,
+            draggable: false,
+            tessellation: Tessellation::default(),
+            on_click: None,
+            bindings: Vec::new(),
+        });
+        self
+    }
+
+    pub fn color(self, r: f32, g: f32, b: f32) -> Self {
+        self.gradient([r, g, b], [r, g, b])
+    }
+
+    pub fn gradient(mut self, left: [f32; 3], right: [f32; 3]) -> Self {
+        self.current().colors = (left, right);
+        self
+    }
+
+    pub fn outline(mut self, width: f32, color: [f32; 3]) -> Self {
+        self.current().outline = Some(Outline {
+            stroke: Stroke::new(width),
+            color,
+        });
+        self
+    }
+
+    // Leaves out the fill of an outlined element.
+    pub fn hollow(mut self) -> Self {
+        self.current().filled = false;
+        self
+    }
+
+    // Relative to the enclosing groups.
+    pub fn at(mut self, x: f32, y: f32) -> Self {
+        let position = self.offset() + cgmath::Vector2::new(x, y);
+        self.current().position = position;
+        self
+    }
+
+    // Elements added until the matching `end` are positioned relative to
+    // (x, y).
+    pub fn group(mut self, x: f32, y: f32) -> Self {
+        let offset = self.offset() + cgmath::Vector2::new(x, y);
+        self.groups.push(offset);
+        self
+    }
+
+    pub fn end(mut self) -> Self {
+        self.groups.pop().expect("`end` without a matching `group`");
+        self
+    }
+
+    pub fn material(mut self, material: UIMaterial) -> Self {
+        self.current().material = material;
+        self
+    }
+
+    pub fn blend(mut self, blend: BlendMode) -> Self {
+        self.current().blend = blend;
+        self
+    }
+
+    pub fn z_index(mut self, z_index: i32) -> Self {
+        self.current().z_index = z_index;
+        self
+    }
+
+    // Applied by `UIScene::retessellate` on the first frame.
+    pub fn tessellation(mut self, tessellation: Tessellation) -> Self {
+        self.current().tessellation = tessellation;
+        self
+    }
+
+    pub fn draggable(mut self) -> Self {
+        self.current().draggable = true;
+        self
+    }
+
+    pub fn on_click(mut self, handler: impl FnMut(&mut UIScene, usize) + 'static) -> Self {
+        self.current().on_click = Some(Box::new(handler));
+        self
+    }
+
+    // Calls `apply` with the value and the element whenever `source`
+    // changes, once `build_bound` created the element.
+    pub fn bind<T: Clone + PartialEq + 'static>(
+        mut self,
+        source: &Observable<T>,
+        mut apply: impl FnMut(&T, &mut UIScene, usize) + 'static,
+    ) -> Self {
+        let source = source.clone();
+        self.current()
+            .bindings
+            .push(Box::new(move |bindings, element| {
+                bindings.bind(&source, move |value, scene| apply(value, scene, element));
+            }));
+        self
+    }
+
+    // Binds one of the properties in `bound`, e.g.
+    // `.bound(&shown, bound::visible)`.
+    pub fn bound<T: Clone + PartialEq + 'static>(
+        mut self,
+        source: &Observable<T>,
+        bind: fn(&mut Bindings, &Observable<T>, usize),
+    ) -> Self {
+        let source = source.clone();
+        self.current()
+            .bindings
+            .push(Box::new(move |bindings, element| {
+                bind(bindings, &source, element)
+            }));
+        self
+    }
+
+    // Elements with bound properties need `build_bound`.
+    pub fn build(self, scene: &mut UIScene, device: &wgpu::Device) -> ClickHandlers {
+        assert!(
+            self.elements.iter().all(|spec| spec.bindings.is_empty()),
+            "bound properties need `build_bound`"
+        );
+        self.build_bound(scene, device, &mut Bindings::new())
+    }
+
+    // Like `build`, adding the bound properties to `bindings`, which apply
+    // them from their next `sync`.
+    pub fn build_bound(
+        self,
+        scene: &mut UIScene,
+        device: &wgpu::Device,
+        bindings: &mut Bindings,
+    ) -> ClickHandlers {
+        let mut handlers = HashMap::new();
+        for spec in self.elements {
+            let mut element = match spec.outline {
+                Some(outline) => Player::with_outline(
+                    device,
+                    spec.position,
+                    spec.shape,
+                    spec.filled.then_some(spec.colors),
+                    outline,
+                ),
+                None => Player::with_shape(
+                    device,
+                    spec.position,
+                    spec.shape,
+                    spec.colors.0,
+                    spec.colors.1,
+                ),
+            };
+            element.material = spec.material;
+            element.blend = spec.blend;
+            element.z_index = spec.z_index;
+            element.draggable = spec.draggable;
+            element.tessellation = spec.tessellation;
+            scene.elements.push(element);
+            let index = scene.elements.len() - 1;
+            if let Some(on_click) = spec.on_click {
+                handlers.insert(index, on_click);
+            }
+            for bind in spec.bindings {
+                bind(bindings, index);
+            }
+        }
+        ClickHandlers { handlers }
+    }
+
+    fn offset(&self) -> cgmath::Vector2<f32> {
+        self.groups
+            .last()
+            .copied()
+            .unwrap_or(cgmath::Vector2::new(0.0, 0.0))
+    }
+
+    fn current(&mut self) -> &mut ElementSpec {
+        self.elements
+            .last_mut()
+            .expect("add an element before configuring it")
+    }
+}
+
+// Properties `SceneBuilder::bound` and the `scene!` macro's `name <- source`
+// can bind, named after them.
+pub mod bound {
+    use crate::binding::{Bindings, Observable};
+
+    pub fn visible(bindings: &mut Bindings, source: &Observable<bool>, element: usize) {
+        bindings.bind_visible(source, element);
+    }
+
+    pub fn tint(bindings: &mut Bindings, source: &Observable<[f32; 4]>, element: usize) {
+        bindings.bind_tint(source, element);
+    }
+
+    pub fn progress(bindings: &mut Bindings, source: &Observable<f32>, element: usize) {
+        bindings.bind_progress(source, element);
+    }
+}
+
+// Click callbacks registered through the builder, keyed by element index.
+pub struct ClickHandlers {
+    handlers: HashMap<usize, ClickHandler>,
+}
+
+impl ClickHandlers {
+    // Runs the callbacks for this frame's clicks. Other events are left in
+    // the scene for `drain_events`.
+    pub fn handle(&mut self, scene: &mut UIScene) {
+        let mut unhandled = Vec::new();
+        for event in scene.drain_events() {
+            match event {
+                UIEvent::Clicked(element) if self.handlers.contains_key(&element) => {
+                    (self.handlers.get_mut(&element).unwrap())(scene, element);
+                }
+                event => unhandled.push(event),
+            }
+        }
+        scene.requeue_events(unhandled);
+    }
+}
+
+/// Declarative front end for `SceneBuilder`. Each element is a builder
+/// method with its arguments, followed by its properties: `name: value`
+/// calls `.name(value)`, `name: (a, b)` calls `.name(a, b)`, a bare `name`
+/// calls `.name()` and `name <- source` binds the property of that name in
+/// `scene_builder::bound` to an `Observable`. `group(x, y) { ... }` nests
+/// elements under an offset. Bound properties are built with `build_bound`:
+///
+/// ```no_run
+/// use window::binding::{Bindings, Observable};
+/// use window::scene;
+/// use window::ui_scene::UIScene;
+///
+/// fn panel(scene: &mut UIScene, device: &wgpu::Device) {
+///     let shown = Observable::new(true);
+///     let width = Observable::new(120.0);
+///     let mut bindings = Bindings::new();
+///     let mut clicks = scene! {
+///         group(0.0, 100.0) {
+///             rect(120.0, 40.0) { color: (0.2, 0.6, 0.9), draggable, visible <- shown }
+///             rect(120.0, 40.0) {
+///                 color: (0.9, 0.3, 0.2),
+///                 at: (0.0, -50.0),
+///                 bind: (&width, |width: &f32, scene: &mut UIScene, element| {
+///                     scene.elements[element].instance.scale = width / 120.0;
+///                 }),
+///                 on_click: |scene, element| scene.set_focus(Some(element)),
+///             }
+///         }
+///     }
+///     .build_bound(scene, device, &mut bindings);
+///
+///     shown.set(false);
+///     bindings.sync(scene); // once per frame, before `prepare`
+///     clicks.handle(scene);
+/// }
+/// ```
+#[macro_export]
+macro_rules! scene {
+    (@items $builder:expr;) => { $builder };
+    (@items $builder:expr; group($x:expr, $y:expr) { $($children:tt)* } $($rest:tt)*) => {
+        $crate::scene!(@items $crate::scene!(@items $builder.group($x, $y); $($children)*).end(); $($rest)*)
+    };
+    (@items $builder:expr; $shape:ident($($args:expr),* $(,)?) { $($props:tt)* } $($rest:tt)*) => {
+        $crate::scene!(@items $crate::scene!(@props $builder.$shape($($args),*); $($props)*); $($rest)*)
+    };
+    (@props $builder:expr;) => { $builder };
+    (@props $builder:expr; $prop:ident: ($($args:expr),* $(,)?) $(, $($rest:tt)*)?) => {
+        $crate::scene!(@props $builder.$prop($($args),*); $($($rest)*)?)
+    };
+    (@props $builder:expr; $prop:ident: $arg:expr $(, $($rest:tt)*)?) => {
+        $crate::scene!(@props $builder.$prop($arg); $($($rest)*)?)
+    };
+    (@props $builder:expr; $prop:ident <- $source:expr $(, $($rest:tt)*)?) => {
+        $crate::scene!(@props $builder.bound(&$source, $crate::scene_builder::bound::$prop); $($($rest)*)?)
+    };
+    (@props $builder:expr; $flag:ident $(, $($rest:tt)*)?) => {
+        $crate::scene!(@props $builder.$flag(); $($($rest)*)?)
+    };
+    ($($items:tt)*) => {
+        $crate::scene!(@items $crate::ui_scene::UIScene::builder(); $($items)*)
+    };
+}
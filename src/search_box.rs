@@ -0,0 +1,317 @@
+use std::ops::Range;
+use std::rc::Rc;
+
+use winit::event::{ElementState, KeyboardInput, VirtualKeyCode, WindowEvent};
+
+use crate::text::{GlyphAtlas, Text, TextFont};
+use crate::text_caret::TextCaret;
+use crate::text_edit::TextEdit;
+use crate::text_layout::{TextLayout, VerticalAlign};
+use crate::ui_scene::{Player, UIEvent, UIScene};
+
+#[derive(Debug, Clone)]
+pub struct SearchBoxStyle {
+    pub width: f32,
+    pub height: f32,
+    pub padding: f32,
+    pub font_size: f32,
+    // Seconds typing has to pause for before the query goes out, so lists
+    // aren't filtered again on every key.
+    pub debounce: f32,
+    pub field: [f32; 3],
+    pub text: [f32; 4],
+    pub placeholder: [f32; 4],
+    pub selection: [f32; 3],
+}
+
+impl Default for SearchBoxStyle {
+    fn default() -> Self {
+        Self {
+            width: 200.0,
+            height: 22.0,
+            padding: 6.0,
+            font_size: 13.0,
+            debounce: 0.25,
+            field: [0.1, 0.1, 0.12],
+            text: [0.85, 0.85, 0.88, 1.0],
+            placeholder: [0.45, 0.45, 0.5, 1.0],
+            selection: [0.22, 0.32, 0.5],
+        }
+    }
+}
+
+// A one line field for filtering lists and trees. The query goes out as
+// `UIEvent::SearchChanged` once typing pauses, or right away on Enter.
+// Escape clears the field, and leaves it when it's already empty:
+//
+//     let mut search = SearchBox::new(&mut scene, device, font, position, "Filter",
+//         SearchBoxStyle::default());
+//     search.input(&mut scene, &event); // for every window event
+//     search.update(&mut scene, device, queue, &mut glyphs, dt); // every frame
+//
+//     UIEvent::SearchChanged { element, query } if element == search.element() => {
+//         tree.set_filter(&query);
+//     }
+pub struct SearchBox {
+    pub style: SearchBoxStyle,
+    field: usize,
+    caret: TextCaret,
+    text: Text,
+    placeholder: Text,
+    placeholder_name: String,
+    // Whether the texts were given their boxes, which takes the atlas.
+    laid_out: bool,
+    edit: TextEdit,
+    editing: bool,
+    // The last query sent out.
+    query: String,
+    // Seconds since the text last changed, until it's sent out.
+    pending: Option<f32>,
+    dirty: bool,
+}
+
+impl SearchBox {
+    // `position` is the center of the field. The text shows from the first
+    // `update` on.
+    pub fn new(
+        scene: &mut UIScene,
+        device: &wgpu::Device,
+        font: Rc<TextFont>,
+        position: cgmath::Vector2<f32>,
+        placeholder: &str,
+        style: SearchBoxStyle,
+    ) -> Self {
+        let size = cgmath::Vector2::new(style.width, style.height);
+        let mut element = Player::with_gradient(device, position, size, style.field, style.field);
+        element.draggable = false;
+        scene.elements.push(element);
+        let field = scene.elements.len() - 1;
+        // Placed by `redraw`.
+        let caret = TextCaret::new(
+            scene,
+            device,
+            field,
+            style.font_size,
+            style.text,
+            style.selection,
+        );
+        let top_left = cgmath::Vector2::new(
+            position.x - style.width / 2.0 + style.padding,
+            position.y + style.height / 2.0,
+        );
+        let mut text = Text::new(font.clone(), style.font_size);
+        text.set_position(scene, top_left);
+        text.set_color(scene, style.text);
+        let mut placeholder_text = Text::new(font, style.font_size);
+        placeholder_text.set_position(scene, top_left);
+        placeholder_text.set_color(scene, style.placeholder);
+
+        Self {
+            field,
+            caret,
+            text,
+            placeholder: placeholder_text,
+            placeholder_name: placeholder.to_string(),
+            laid_out: false,
+            edit: TextEdit::new(""),
+            editing: false,
+            query: String::new(),
+            pending: None,
+            dirty: true,
+            style,
+        }
+    }
+
+    // The field, which `UIEvent::SearchChanged` names.
+    pub fn element(&self) -> usize {
+        self.field
+    }
+
+    // As last sent out, which may lag behind what's typed.
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    // Replaces what's typed and sends it out on the next `update`, e.g. to
+    // restore a saved filter.
+    pub fn set_query(&mut self, query: &str) {
+        self.edit.set_text(query);
+        self.pending = Some(self.style.debounce);
+        self.dirty = true;
+    }
+
+    // Takes keys while focused. Returns true if the event was used.
+    pub fn input(&mut self, scene: &mut UIScene, event: &WindowEvent) -> bool {
+        if !self.editing {
+            return false;
+        }
+        // Keys go to the input method while it's composing.
+        let composing = self.edit.is_composing();
+        if let (
+            false,
+            WindowEvent::KeyboardInput {
+                input:
+                    KeyboardInput {
+                        state: ElementState::Pressed,
+                        virtual_keycode: Some(key),
+                        ..
+                    },
+                ..
+            },
+        ) = (composing, event)
+        {
+            match key {
+                VirtualKeyCode::Return | VirtualKeyCode::NumpadEnter => {
+                    self.send(scene);
+                    return true;
+                }
+                VirtualKeyCode::Escape if self.edit.text().is_empty() => {
+                    scene.set_focus(None);
+                    return true;
+                }
+                VirtualKeyCode::Escape => {
+                    self.edit.set_text("");
+                    self.send(scene);
+                    self.dirty = true;
+                    return true;
+                }
+                _ => {}
+            }
+        }
+        let before = self.edit.text().to_string();
+        let used = self.edit.input(scene, event);
+        if used {
+            self.edited(&before);
+        }
+        used
+    }
+
+    // Follows focus, repeats held keys, sends the query once typing paused
+    // and redraws what changed.
+    pub fn update(
+        &mut self,
+        scene: &mut UIScene,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        atlas: &mut GlyphAtlas,
+        dt: f32,
+    ) {
+        // Clicks on the text and caret land on their own elements.
+        if scene.focused.is_some_and(|element| {
+            self.caret.contains(element)
+                || self.text.elements().contains(&element)
+                || self.placeholder.elements().contains(&element)
+        }) {
+            scene.set_focus(Some(self.field));
+        }
+        let focused = scene.focused == Some(self.field);
+        if focused != self.editing {
+            self.editing = focused;
+            self.dirty = true;
+        }
+        if self.editing {
+            let before = self.edit.text().to_string();
+            if self.edit.update(scene, dt) {
+                self.edited(&before);
+            }
+        }
+        if let Some(pending) = &mut self.pending {
+            *pending += dt;
+            if *pending >= self.style.debounce {
+                self.send(scene);
+            }
+        }
+
+        if self.dirty {
+            self.redraw(scene, device, queue, atlas);
+        }
+        self.text.update(scene, device, atlas);
+        self.placeholder.update(scene, device, atlas);
+    }
+
+    // Restarts the wait if the text changed, rather than only the caret.
+    fn edited(&mut self, before: &str) {
+        self.dirty = true;
+        if self.edit.text() != before {
+            self.pending = Some(0.0);
+        }
+    }
+
+    fn send(&mut self, scene: &mut UIScene) {
+        self.pending = None;
+        if self.edit.text() == self.query {
+            return;
+        }
+        self.query = self.edit.text().to_string();
+        scene.push_event(UIEvent::SearchChanged {
+            element: self.field,
+            query: self.query.clone(),
+        });
+    }
+
+    fn redraw(
+        &mut self,
+        scene: &mut UIScene,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        atlas: &mut GlyphAtlas,
+    ) {
+        self.dirty = false;
+        let style = &self.style;
+        if !self.laid_out {
+            let layout = TextLayout {
+                max_width: Some((style.width - 2.0 * style.padding).max(0.0)),
+                height: Some(style.height),
+                wrap: false,
+                vertical_align: VerticalAlign::Middle,
+                ..TextLayout::default()
+            };
+            self.text
+                .set_layout(scene, device, queue, atlas, layout.clone());
+            self.placeholder
+                .set_layout(scene, device, queue, atlas, layout);
+            self.placeholder
+                .set_text(scene, device, queue, atlas, &self.placeholder_name);
+            self.laid_out = true;
+        }
+        let shown = self.edit.display_text().into_owned();
+        self.text.set_text(scene, device, queue, atlas, &shown);
+        self.placeholder.set_visible(scene, shown.is_empty());
+
+        // The caret and selection, on the line as laid out.
+        let field = scene.elements[self.field].instance.position;
+        let text_left = field.x - style.width / 2.0 + style.padding;
+        let (range, left) = match self.text.metrics().lines.first() {
+            Some(line) => (line.range.clone(), text_left + line.x),
+            None => (0..0, text_left),
+        };
+        self.caret
+            .measure(&self.text.font, style.font_size, &shown, range);
+        self.caret
+            .place(scene, queue, &self.edit, left, self.editing);
+    }
+}
+
+// Where `query` first occurs in `text`, ignoring case, as bytes of `text`.
+// None for a blank query, which callers filtering by it take as no filter.
+pub fn find_match(text: &str, query: &str) -> Option<Range<usize>> {
+    let query = query.trim();
+    if query.is_empty() {
+        return None;
+    }
+    for (start, _) in text.char_indices() {
+        let mut rest = text[start..].char_indices();
+        let mut end = start;
+        let found = query.chars().all(|q| match rest.next() {
+            Some((i, c)) if c.to_lowercase().eq(q.to_lowercase()) => {
+                end = start + i + c.len_utf8();
+                true
+            }
+            _ => false,
+        });
+        if found {
+            return Some(start..end);
+        }
+    }
+    None
+}
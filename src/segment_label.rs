@@ -0,0 +1,185 @@
+use crate::style::{TextOutline, TextShadow};
+use crate::ui_scene::{Player, UIScene};
+
+// Numbers are drawn as seven-segment glyphs, one scene element per segment,
+// for readouts that don't need a font or a `GlyphAtlas`.
+const GLYPH_WIDTH: f32 = 12.0;
+const GLYPH_HEIGHT: f32 = 20.0;
+const GLYPH_SPACING: f32 = 4.0;
+const SEGMENT_THICKNESS: f32 = 3.0;
+
+// Segments a to g, as (center, horizontal).
+const SEGMENTS: [([f32; 2], bool); 7] = [
+    ([0.0, GLYPH_HEIGHT / 2.0], true),
+    ([GLYPH_WIDTH / 2.0, GLYPH_HEIGHT / 4.0], false),
+    ([GLYPH_WIDTH / 2.0, -GLYPH_HEIGHT / 4.0], false),
+    ([0.0, -GLYPH_HEIGHT / 2.0], true),
+    ([-GLYPH_WIDTH / 2.0, -GLYPH_HEIGHT / 4.0], false),
+    ([-GLYPH_WIDTH / 2.0, GLYPH_HEIGHT / 4.0], false),
+    ([0.0, 0.0], true),
+];
+
+// Lit segments per character, bit 0 is segment a.
+fn glyph_mask(c: char) -> Option<u8> {
+    const DIGITS: [u8; 10] = [0x3f, 0x06, 0x5b, 0x4f, 0x66, 0x6d, 0x7d, 0x07, 0x7f, 0x6f];
+    match c {
+        '0'..='9' => Some(DIGITS[c as usize - '0' as usize]),
+        '-' => Some(0x40),
+        _ => None,
+    }
+}
+
+struct Segment {
+    element: usize,
+    // Copies of the segment drawn under all segments of the label.
+    shadow: Option<usize>,
+    outline: Option<usize>,
+    offset: cgmath::Vector2<f32>,
+}
+
+// A short line of digits centered on `position`. All segment elements are
+// created up front, changing the text only toggles their visibility.
+pub struct SegmentLabel {
+    position: cgmath::Vector2<f32>,
+    max_glyphs: usize,
+    segments: Vec<Segment>,
+    shadow: Option<TextShadow>,
+    outline: Option<TextOutline>,
+    alpha: f32,
+}
+
+impl SegmentLabel {
+    pub fn new(scene: &mut UIScene, device: &wgpu::Device, max_glyphs: usize) -> Self {
+        Self::with_effects(scene, device, max_glyphs, None, None)
+    }
+
+    // Draws each segment again under the label: grown by the outline's width,
+    // and moved by the shadow's offset, outline included. Shadows and
+    // outlines go in before the segments so every glyph sits on top of them.
+    pub fn with_effects(
+        scene: &mut UIScene,
+        device: &wgpu::Device,
+        max_glyphs: usize,
+        shadow: Option<TextShadow>,
+        outline: Option<TextOutline>,
+    ) -> Self {
+        let count = max_glyphs * SEGMENTS.len();
+        let grow = outline.map_or(0.0, |outline| outline.width * 2.0);
+        let mut push = |i: usize, grow: f32| {
+            let horizontal = SEGMENTS[i % SEGMENTS.len()].1;
+            let size = if horizontal {
+                cgmath::Vector2::new(GLYPH_WIDTH, SEGMENT_THICKNESS)
+            } else {
+                cgmath::Vector2::new(SEGMENT_THICKNESS, GLYPH_HEIGHT / 2.0)
+            };
+            let white = [1.0, 1.0, 1.0];
+            let mut element = Player::with_gradient(
+                device,
+                cgmath::Vector2::new(0.0, 0.0),
+                size + cgmath::Vector2::new(grow, grow),
+                white,
+                white,
+            );
+            element.draggable = false;
+            element.visible = false;
+            scene.elements.push(element);
+            scene.elements.len() - 1
+        };
+        let shadows = shadow.map(|_| (0..count).map(|i| push(i, grow)).collect::<Vec<_>>());
+        let outlines = outline.map(|_| (0..count).map(|i| push(i, grow)).collect::<Vec<_>>());
+        let segments = (0..count)
+            .map(|i| Segment {
+                element: push(i, 0.0),
+                shadow: shadows.as_ref().map(|shadows| shadows[i]),
+                outline: outlines.as_ref().map(|outlines| outlines[i]),
+                offset: cgmath::Vector2::new(0.0, 0.0),
+            })
+            .collect();
+
+        Self {
+            position: cgmath::Vector2::new(0.0, 0.0),
+            max_glyphs,
+            segments,
+            shadow,
+            outline,
+            alpha: 1.0,
+        }
+    }
+
+    // Only digits and '-' are drawn, anything past the label's glyph count
+    // is cut off.
+    pub fn set_text(&mut self, scene: &mut UIScene, text: &str, color: [f32; 3]) {
+        let glyphs = text.chars().filter_map(glyph_mask).take(self.max_glyphs);
+        let count = glyphs.clone().count();
+        let start = -((count as f32 - 1.0) * (GLYPH_WIDTH + GLYPH_SPACING)) / 2.0;
+
+        self.hide(scene);
+        for (i, mask) in glyphs.enumerate() {
+            let x = start + i as f32 * (GLYPH_WIDTH + GLYPH_SPACING);
+            for (s, (center, _)) in SEGMENTS.iter().enumerate() {
+                let segment = &mut self.segments[i * SEGMENTS.len() + s];
+                segment.offset = cgmath::Vector2::new(x + center[0], center[1]);
+                for element in segment.elements() {
+                    scene.elements[element].visible = mask & (1 << s) != 0;
+                }
+                scene.elements[segment.element].instance.tint = [color[0], color[1], color[2], 1.0];
+            }
+        }
+        self.set_position(scene, self.position);
+        self.set_alpha(scene, 1.0);
+    }
+
+    pub fn set_position(&mut self, scene: &mut UIScene, position: cgmath::Vector2<f32>) {
+        self.position = position;
+        let shadow_offset = self
+            .shadow
+            .map_or(cgmath::Vector2::new(0.0, 0.0), |shadow| shadow.offset);
+        for segment in &self.segments {
+            let at = position + segment.offset;
+            let mut place = |element: usize, at: cgmath::Vector2<f32>| {
+                let position = &mut scene.elements[element].instance.position;
+                position.x = at.x;
+                position.y = at.y;
+            };
+            place(segment.element, at);
+            if let Some(outline) = segment.outline {
+                place(outline, at);
+            }
+            if let Some(shadow) = segment.shadow {
+                place(shadow, at + shadow_offset);
+            }
+        }
+    }
+
+    // Fades the shadow and outline along with the digits.
+    pub fn set_alpha(&mut self, scene: &mut UIScene, alpha: f32) {
+        self.alpha = alpha;
+        for segment in &self.segments {
+            scene.elements[segment.element].instance.tint[3] = alpha;
+            if let (Some(element), Some(shadow)) = (segment.shadow, self.shadow) {
+                let [r, g, b, a] = shadow.color;
+                scene.elements[element].instance.tint = [r, g, b, a * alpha];
+            }
+            if let (Some(element), Some(outline)) = (segment.outline, self.outline) {
+                let [r, g, b, a] = outline.color;
+                scene.elements[element].instance.tint = [r, g, b, a * alpha];
+            }
+        }
+    }
+
+    pub fn hide(&self, scene: &mut UIScene) {
+        for segment in &self.segments {
+            for element in segment.elements() {
+                scene.elements[element].visible = false;
+            }
+        }
+    }
+}
+
+impl Segment {
+    fn elements(&self) -> impl Iterator<Item = usize> {
+        [Some(self.element), self.shadow, self.outline]
+            .into_iter()
+            .flatten()
+    }
+}
@@ -0,0 +1,174 @@
+use std::collections::HashMap;
+
+use lyon::tessellation::{
+    BuffersBuilder, FillOptions, FillTessellator, FillVertex, LineJoin, StrokeOptions,
+    StrokeTessellator, StrokeVertex, VertexBuffers,
+};
+
+use crate::deletion_queue::DeletionQueue;
+use crate::ui_scene::{opaque, Player, UIScene, Vertex};
+
+// Zoom steps per doubling. Within a step the outline is at most ~9% off the
+// requested width.
+const TIERS_PER_OCTAVE: f32 = 4.0;
+// Largest distance from the true silhouette, in screen pixels.
+const TOLERANCE_PIXELS: f32 = 0.25;
+
+#[derive(Debug, Clone)]
+pub struct SelectionOutlineStyle {
+    pub color: [f32; 3],
+    // How far the outline reaches past the element, in screen pixels.
+    pub width: f32,
+}
+
+impl Default for SelectionOutlineStyle {
+    fn default() -> Self {
+        Self {
+            color: [1.0, 0.6, 0.1],
+            width: 3.0,
+        }
+    }
+}
+
+// Marks the selected element with its own silhouette, inflated by a few
+// screen pixels and drawn behind it, so selections follow any shape or path
+// rather than its bounding box:
+//
+//     let mut outline = SelectionOutline::new(&mut scene, device, Default::default());
+//     outline.select(Some(clicked));
+//     outline.update(&mut scene, device, queue, deletion_queue); // every frame
+//
+// Geometry is tessellated once per zoom tier and kept until the selection
+// changes, so zooming back and forth doesn't tessellate again. The outline
+// sits one z index below the selected element. Elements made from raw
+// geometry have no silhouette and aren't outlined.
+pub struct SelectionOutline {
+    pub style: SelectionOutlineStyle,
+    element: usize,
+    selected: Option<usize>,
+    // Tessellated outlines of the selected element by zoom tier.
+    tiers: HashMap<i32, VertexBuffers<Vertex, u16>>,
+    // Tier the outline element's buffers currently hold.
+    uploaded: Option<i32>,
+}
+
+impl SelectionOutline {
+    pub fn new(scene: &mut UIScene, device: &wgpu::Device, style: SelectionOutlineStyle) -> Self {
+        // Raw geometry, so retessellating the scene leaves it alone. Zero
+        // sized, so clicks go through to whatever is underneath.
+        let origin = cgmath::Vector2::new(0.0, 0.0);
+        let mut element = Player::from_geometry(device, origin, origin, &VertexBuffers::new());
+        element.draggable = false;
+        element.visible = false;
+        scene.elements.push(element);
+        Self {
+            style,
+            element: scene.elements.len() - 1,
+            selected: None,
+            tiers: HashMap::new(),
+            uploaded: None,
+        }
+    }
+
+    pub fn selected(&self) -> Option<usize> {
+        self.selected
+    }
+
+    pub fn select(&mut self, element: Option<usize>) {
+        if element != self.selected {
+            self.selected = element;
+            self.tiers.clear();
+            self.uploaded = None;
+        }
+    }
+
+    // Call before `UIScene::prepare`, after the selected element moved.
+    pub fn update(
+        &mut self,
+        scene: &mut UIScene,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        deletion_queue: &mut DeletionQueue,
+    ) {
+        let target = self
+            .selected
+            .filter(|&i| i < scene.elements.len() && scene.elements[i].visible);
+        let Some(target) = target else {
+            scene.elements[self.element].visible = false;
+            return;
+        };
+
+        let zoom = scene.camera.scale * scene.elements[target].instance.scale;
+        let tier = (zoom.max(f32::EPSILON).log2() * TIERS_PER_OCTAVE).round() as i32;
+        if !self.tiers.contains_key(&tier) {
+            let tier_zoom = 2f32.powf(tier as f32 / TIERS_PER_OCTAVE);
+            let Some(geometry) = self.inflate(&scene.elements[target], tier_zoom) else {
+                scene.elements[self.element].visible = false;
+                return;
+            };
+            self.tiers.insert(tier, geometry);
+        }
+        let outline = &mut scene.elements[self.element];
+        if self.uploaded != Some(tier) {
+            outline.upload_geometry(device, queue, deletion_queue, &self.tiers[&tier]);
+            self.uploaded = Some(tier);
+        }
+
+        let source = &scene.elements[target];
+        let (position, rotation, scale) = (
+            source.instance.position,
+            source.instance.rotation,
+            source.instance.scale,
+        );
+        let (z_index, clip, parent) = (source.z_index, source.clip, source.parent);
+        let outline = &mut scene.elements[self.element];
+        outline.instance.position = position;
+        outline.instance.rotation = rotation;
+        outline.instance.scale = scale;
+        outline.z_index = z_index.saturating_sub(1);
+        outline.clip = clip;
+        outline.parent = parent;
+        outline.visible = true;
+    }
+
+    // The element's silhouette grown by the style's width at `zoom` screen
+    // pixels per element unit, in element units.
+    fn inflate(&self, element: &Player, zoom: f32) -> Option<VertexBuffers<Vertex, u16>> {
+        let drawing = element.drawing(TOLERANCE_PIXELS / zoom)?;
+        // The element's own stroke reaches half its width past the path.
+        let reach = drawing
+            .outline
+            .map_or(0.0, |outline| outline.stroke.width / 2.0);
+        let width = 2.0 * (reach + self.style.width / zoom);
+        let color = opaque(self.style.color);
+        let vertex = |p: lyon::math::Point| Vertex {
+            position: [p.x, p.y, 0.0],
+            color,
+            uv: [0.0, 0.0],
+        };
+
+        let mut geometry = VertexBuffers::new();
+        let tolerance = TOLERANCE_PIXELS / zoom;
+        // Fill and a round-joined stroke together are the offset shape.
+        // Unfilled paths may be open, and only get the stroke.
+        if drawing.fill.is_some() {
+            FillTessellator::new()
+                .tessellate_path(
+                    &drawing.path,
+                    &FillOptions::tolerance(tolerance),
+                    &mut BuffersBuilder::new(&mut geometry, |v: FillVertex| vertex(v.position())),
+                )
+                .ok()?;
+        }
+        StrokeTessellator::new()
+            .tessellate_path(
+                &drawing.path,
+                &StrokeOptions::tolerance(tolerance)
+                    .with_line_width(width)
+                    .with_line_join(LineJoin::Round),
+                &mut BuffersBuilder::new(&mut geometry, |v: StrokeVertex| vertex(v.position())),
+            )
+            .ok()?;
+        (!geometry.indices.is_empty()).then_some(geometry)
+    }
+}
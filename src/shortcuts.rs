@@ -0,0 +1,169 @@
+use std::fmt;
+
+use winit::event::{ElementState, KeyboardInput, ModifiersState, VirtualKeyCode, WindowEvent};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Chord {
+    pub modifiers: ModifiersState,
+    pub key: VirtualKeyCode,
+}
+
+impl Chord {
+    pub fn new(key: VirtualKeyCode) -> Self {
+        Self {
+            modifiers: ModifiersState::empty(),
+            key,
+        }
+    }
+
+    pub fn ctrl(mut self) -> Self {
+        self.modifiers |= ModifiersState::CTRL;
+        self
+    }
+
+    pub fn shift(mut self) -> Self {
+        self.modifiers |= ModifiersState::SHIFT;
+        self
+    }
+
+    pub fn alt(mut self) -> Self {
+        self.modifiers |= ModifiersState::ALT;
+        self
+    }
+
+    pub fn logo(mut self) -> Self {
+        self.modifiers |= ModifiersState::LOGO;
+        self
+    }
+}
+
+impl fmt::Display for Chord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.ctrl() {
+            write!(f, "Ctrl+")?;
+        }
+        if self.modifiers.shift() {
+            write!(f, "Shift+")?;
+        }
+        if self.modifiers.alt() {
+            write!(f, "Alt+")?;
+        }
+        if self.modifiers.logo() {
+            write!(f, "Super+")?;
+        }
+        write!(f, "{:?}", self.key)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ShortcutScope {
+    Global,
+    // Only fires while the element with this index has focus.
+    Focused(usize),
+}
+
+pub struct Shortcut {
+    pub chord: Chord,
+    pub scope: ShortcutScope,
+    pub action: String,
+    pub description: String,
+}
+
+#[derive(Default)]
+pub struct ShortcutRegistry {
+    shortcuts: Vec<Shortcut>,
+    modifiers: ModifiersState,
+}
+
+impl ShortcutRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn modifiers(&self) -> ModifiersState {
+        self.modifiers
+    }
+
+    // Fails if the chord is already bound in the same scope. The same chord
+    // may be bound globally and per element; the focused binding wins.
+    pub fn register(
+        &mut self,
+        chord: Chord,
+        scope: ShortcutScope,
+        action: &str,
+        description: &str,
+    ) -> anyhow::Result<()> {
+        if let Some(existing) = self
+            .shortcuts
+            .iter()
+            .find(|s| s.chord == chord && s.scope == scope)
+        {
+            anyhow::bail!(
+                "{} is already bound to \"{}\" in {:?}",
+                chord,
+                existing.action,
+                scope
+            );
+        }
+        self.shortcuts.push(Shortcut {
+            chord,
+            scope,
+            action: action.to_string(),
+            description: description.to_string(),
+        });
+        Ok(())
+    }
+
+    pub fn unregister(&mut self, action: &str) {
+        self.shortcuts.retain(|s| s.action != action);
+    }
+
+    // Returns the action triggered by this event, if any.
+    pub fn input(&mut self, event: &WindowEvent, focused: Option<usize>) -> Option<&str> {
+        match event {
+            WindowEvent::ModifiersChanged(modifiers) => {
+                self.modifiers = *modifiers;
+                None
+            }
+            WindowEvent::KeyboardInput {
+                input:
+                    KeyboardInput {
+                        state: ElementState::Pressed,
+                        virtual_keycode: Some(key),
+                        ..
+                    },
+                ..
+            } => {
+                let chord = Chord {
+                    modifiers: self.modifiers,
+                    key: *key,
+                };
+                let focused = focused.and_then(|element| {
+                    self.shortcuts
+                        .iter()
+                        .find(|s| s.chord == chord && s.scope == ShortcutScope::Focused(element))
+                });
+                focused
+                    .or_else(|| {
+                        self.shortcuts
+                            .iter()
+                            .find(|s| s.chord == chord && s.scope == ShortcutScope::Global)
+                    })
+                    .map(|s| s.action.as_str())
+            }
+            _ => None,
+        }
+    }
+
+    // Chord/description pairs sorted by chord, for a help overlay.
+    pub fn help(&self) -> Vec<(String, String)> {
+        let mut lines = self
+            .shortcuts
+            .iter()
+            .map(|s| (s.chord.to_string(), s.description.clone()))
+            .collect::<Vec<_>>();
+        lines.sort();
+        lines.dedup();
+        lines
+    }
+}
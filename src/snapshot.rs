@@ -0,0 +1,165 @@
+use anyhow::bail;
+use serde::{Deserialize, Serialize};
+
+use crate::guides::Guides;
+use crate::tween::Tween;
+use crate::ui_scene::{BlendMode, UIMaterial, UIScene, Viewport};
+
+// Everything about an element that changes while the app runs, apart from
+// its geometry, see `SceneSnapshot`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ElementState {
+    pub position: [f32; 3],
+    // Quaternion, vector part first.
+    pub rotation: [f32; 4],
+    pub scale: f32,
+    pub tint: [f32; 4],
+    pub params: [f32; 4],
+    pub elevation: Tween,
+    pub material: UIMaterial,
+    pub blend: BlendMode,
+    pub z_index: i32,
+    pub visible: bool,
+    pub enabled: bool,
+    pub draggable: bool,
+    pub parent: Option<usize>,
+    pub clip: Option<Viewport>,
+    pub clips_children: bool,
+}
+
+// The CPU side of a scene at one point in time, for rewinding while
+// debugging or dumping the state a crash happened in:
+//
+//     let before = scene.snapshot();
+//     ...
+//     scene.restore(&before)?;
+//
+// Snapshots only apply to the scene they were taken of, or one built the same
+// way: elements are matched by index. The scene has no randomness of its own;
+// apps seeding an RNG should keep the seed next to the snapshot.
+//
+// Only state the `UIScene` holds is captured. Left out are:
+//
+// - geometry changed by `set_shape` or `reshape`, which lives in GPU buffers
+// - the model scene's camera, which the `Renderer` owns
+// - `StyleTransitions`, `Clip`s and their `Timeline` playheads and other
+//   animations the app owns, whose current values are captured but not
+//   where they're heading
+//
+// Apps needing those restored have to save them alongside.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SceneSnapshot {
+    // The camera follows the pan, see `UIScene::prepare`.
+    pub pan_offset: [f32; 2],
+    pub pan_velocity: [f32; 2],
+    pub zoom: f32,
+    pub focused: Option<usize>,
+    pub disabled_tint: [f32; 4],
+    pub elements: Vec<ElementState>,
+    // Missing from snapshots saved before guides existed.
+    #[serde(default)]
+    pub guides: Guides,
+}
+
+impl SceneSnapshot {
+    pub fn load(path: &str) -> anyhow::Result<Self> {
+        Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+    }
+
+    pub fn save(&self, path: &str) -> anyhow::Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+impl UIScene {
+    pub fn snapshot(&self) -> SceneSnapshot {
+        let elements = self
+            .elements
+            .iter()
+            .map(|element| {
+                let instance = &element.instance;
+                let rotation = instance.rotation;
+                ElementState {
+                    position: instance.position.into(),
+                    rotation: [rotation.v.x, rotation.v.y, rotation.v.z, rotation.s],
+                    scale: instance.scale,
+                    tint: instance.tint,
+                    params: instance.params,
+                    elevation: element.elevation,
+                    material: element.material,
+                    blend: element.blend,
+                    z_index: element.z_index,
+                    visible: element.visible,
+                    enabled: element.enabled,
+                    draggable: element.draggable,
+                    parent: element.parent,
+                    clip: element.clip,
+                    clips_children: element.clips_children,
+                }
+            })
+            .collect();
+        SceneSnapshot {
+            pan_offset: self.pan.offset.into(),
+            pan_velocity: self.pan.velocity.into(),
+            zoom: self.camera.scale,
+            focused: self.focused,
+            disabled_tint: self.disabled_tint,
+            elements,
+            guides: self.guides.clone(),
+        }
+    }
+
+    // Takes effect on the next `prepare`. Fails without changing anything if
+    // the scene has a different number of elements than the snapshot, or
+    // the snapshot doesn't hold up, e.g. a hand edited file.
+    pub fn restore(&mut self, snapshot: &SceneSnapshot) -> anyhow::Result<()> {
+        let count = self.elements.len();
+        if snapshot.elements.len() != count {
+            bail!(
+                "snapshot has {} elements, the scene {}",
+                snapshot.elements.len(),
+                count
+            );
+        }
+        if let Some((i, parent)) = snapshot
+            .elements
+            .iter()
+            .enumerate()
+            .find_map(|(i, state)| state.parent.filter(|&p| p >= count).map(|p| (i, p)))
+        {
+            bail!("element {} of the snapshot has no parent {}", i, parent);
+        }
+        if let Some(focused) = snapshot.focused.filter(|&f| f >= count) {
+            bail!("snapshot focuses element {}, which doesn't exist", focused);
+        }
+        if !snapshot.zoom.is_finite() || snapshot.zoom <= 0.0 {
+            bail!("snapshot has a zoom of {}", snapshot.zoom);
+        }
+        for (element, state) in self.elements.iter_mut().zip(&snapshot.elements) {
+            let [x, y, z, s] = state.rotation;
+            element.instance.position = state.position.into();
+            element.instance.rotation = cgmath::Quaternion::new(s, x, y, z);
+            element.instance.scale = state.scale;
+            element.instance.tint = state.tint;
+            element.instance.params = state.params;
+            element.elevation = state.elevation;
+            element.material = state.material;
+            element.blend = state.blend;
+            element.z_index = state.z_index;
+            element.visible = state.visible;
+            element.enabled = state.enabled;
+            element.draggable = state.draggable;
+            element.parent = state.parent;
+            element.clip = state.clip;
+            element.clips_children = state.clips_children;
+        }
+        self.pan.offset = snapshot.pan_offset.into();
+        self.pan.velocity = snapshot.pan_velocity.into();
+        self.camera.scale = snapshot.zoom;
+        self.focused = snapshot.focused;
+        self.disabled_tint = snapshot.disabled_tint;
+        self.guides = snapshot.guides.clone();
+        Ok(())
+    }
+}
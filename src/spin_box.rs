@@ -0,0 +1,611 @@
+use std::rc::Rc;
+
+use anyhow::{bail, Context};
+use winit::event::{ElementState, KeyboardInput, VirtualKeyCode, WindowEvent};
+
+use crate::text::{GlyphAtlas, Text, TextFont};
+use crate::text_caret::TextCaret;
+use crate::text_edit::TextEdit;
+use crate::text_layout::{TextAlign, TextLayout, VerticalAlign};
+use crate::ui_scene::{Player, UIScene};
+
+// Between the field and the error under it.
+const ERROR_GAP: f32 = 3.0;
+
+#[derive(Debug, Clone)]
+pub struct SpinBoxStyle {
+    pub width: f32,
+    pub height: f32,
+    // Of the label part, the field takes the rest of `width`.
+    pub label_width: f32,
+    pub padding: f32,
+    pub font_size: f32,
+    pub min: f32,
+    pub max: f32,
+    // Scrubbing and the arrow keys move by whole steps, 0 for a continuous
+    // value. Typed values aren't snapped.
+    pub step: f32,
+    // Shown after the decimal point.
+    pub decimals: usize,
+    // Horizontal drag across the label per step, in world units.
+    pub sensitivity: f32,
+    // Multiplies steps while shift is held.
+    pub coarse: f32,
+    pub align: TextAlign,
+    pub label: [f32; 3],
+    pub field: [f32; 3],
+    pub text: [f32; 4],
+    pub selection: [f32; 3],
+    // The field and the message under it while what's typed isn't a valid
+    // value.
+    pub error: [f32; 3],
+    pub error_text: [f32; 4],
+}
+
+impl Default for SpinBoxStyle {
+    fn default() -> Self {
+        Self {
+            width: 160.0,
+            height: 22.0,
+            label_width: 60.0,
+            padding: 6.0,
+            font_size: 13.0,
+            min: f32::NEG_INFINITY,
+            max: f32::INFINITY,
+            step: 0.1,
+            decimals: 2,
+            sensitivity: 4.0,
+            coarse: 10.0,
+            align: TextAlign::Right,
+            label: [0.18, 0.18, 0.2],
+            field: [0.1, 0.1, 0.12],
+            text: [0.85, 0.85, 0.88, 1.0],
+            selection: [0.22, 0.32, 0.5],
+            error: [0.35, 0.1, 0.1],
+            error_text: [1.0, 0.45, 0.4, 1.0],
+        }
+    }
+}
+
+impl SpinBoxStyle {
+    fn snap(&self, value: f32) -> f32 {
+        let value = value.clamp(self.min, self.max);
+        if self.step <= 0.0 {
+            return value;
+        }
+        let origin = if self.min.is_finite() { self.min } else { 0.0 };
+        let snapped = origin + ((value - origin) / self.step).round() * self.step;
+        snapped.clamp(self.min, self.max)
+    }
+
+    fn format(&self, value: f32) -> String {
+        format!("{:.*}", self.decimals, value)
+    }
+
+    fn field_width(&self) -> f32 {
+        self.width - self.label_width
+    }
+}
+
+// A number field with a label. Dragging the label sideways scrubs the value
+// in steps, and clicking the field edits it as text, which may be
+// arithmetic like "2*32" or "(1920 - 40) / 3". What doesn't evaluate to a
+// number within range turns the field red, with the reason under it, until
+// it's fixed or focus leaves and the value it had comes back:
+//
+//     let mut width = SpinBox::new(&mut scene, device, font, position, "Width",
+//         SpinBoxStyle { min: 1.0, max: 4096.0, step: 1.0, decimals: 0, ..SpinBoxStyle::default() });
+//     width.input(&mut scene, &event); // for every window event
+//     if width.update(&mut scene, device, queue, &mut glyphs, dt) { // every frame
+//         resize(width.value());
+//     }
+//
+// While editing, Enter applies what's typed, Escape goes back to the value
+// and the up and down arrows step it.
+pub struct SpinBox {
+    pub style: SpinBoxStyle,
+    value: f32,
+    label: usize,
+    label_name: String,
+    field: usize,
+    caret: TextCaret,
+    label_text: Text,
+    value_text: Text,
+    error_text: Text,
+    // Whether the texts were given their boxes, which takes the atlas.
+    laid_out: bool,
+    edit: TextEdit,
+    editing: bool,
+    error: Option<String>,
+    // The cursor and the unsnapped value while scrubbing, so small drags
+    // add up to a step.
+    scrub: Option<(cgmath::Vector2<f32>, f32)>,
+    changed: bool,
+    dirty: bool,
+}
+
+impl SpinBox {
+    // `position` is the center of the whole box. The text shows from the
+    // first `update` on.
+    pub fn new(
+        scene: &mut UIScene,
+        device: &wgpu::Device,
+        font: Rc<TextFont>,
+        position: cgmath::Vector2<f32>,
+        label: &str,
+        style: SpinBoxStyle,
+    ) -> Self {
+        let height = style.height;
+        let left = position.x - style.width / 2.0;
+        let top = position.y + height / 2.0;
+        let mut push = |x: f32, size: cgmath::Vector2<f32>, color: [f32; 3]| {
+            let center = cgmath::Vector2::new(x, position.y);
+            let mut element = Player::with_gradient(device, center, size, color, color);
+            element.draggable = false;
+            scene.elements.push(element);
+            scene.elements.len() - 1
+        };
+        let label_element = push(
+            left + style.label_width / 2.0,
+            cgmath::Vector2::new(style.label_width, height),
+            style.label,
+        );
+        let field = push(
+            left + style.label_width + style.field_width() / 2.0,
+            cgmath::Vector2::new(style.field_width(), height),
+            style.field,
+        );
+        // Placed by `redraw`.
+        let caret = TextCaret::new(
+            scene,
+            device,
+            field,
+            style.font_size,
+            style.text,
+            style.selection,
+        );
+        let mut label_text = Text::new(font.clone(), style.font_size);
+        label_text.set_position(scene, cgmath::Vector2::new(left + style.padding, top));
+        label_text.set_color(scene, style.text);
+        let mut value_text = Text::new(font.clone(), style.font_size);
+        value_text.set_position(
+            scene,
+            cgmath::Vector2::new(left + style.label_width + style.padding, top),
+        );
+        value_text.set_color(scene, style.text);
+        let mut error_text = Text::new(font, style.font_size * 0.85);
+        error_text.set_position(
+            scene,
+            cgmath::Vector2::new(
+                left + style.label_width,
+                position.y - height / 2.0 - ERROR_GAP,
+            ),
+        );
+        error_text.set_color(scene, style.error_text);
+
+        let value = style.snap(0.0);
+        Self {
+            value,
+            label: label_element,
+            label_name: label.to_string(),
+            field,
+            caret,
+            label_text,
+            value_text,
+            error_text,
+            laid_out: false,
+            edit: TextEdit::new(&style.format(value)),
+            editing: false,
+            error: None,
+            scrub: None,
+            changed: false,
+            dirty: true,
+            style,
+        }
+    }
+
+    pub fn value(&self) -> f32 {
+        self.value
+    }
+
+    // Clamped to the range. Shows on the next `update`, and doesn't count as
+    // a change there. Returns whether the value changed.
+    pub fn set_value(&mut self, value: f32) -> bool {
+        let value = value.clamp(self.style.min, self.style.max);
+        let changed = value != self.value;
+        self.value = value;
+        if self.editing {
+            self.show_value();
+        }
+        self.dirty = true;
+        changed
+    }
+
+    // Why what's typed isn't a valid value.
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    pub fn is_editing(&self) -> bool {
+        self.editing
+    }
+
+    // Takes keys while editing. Returns true if the event was used.
+    pub fn input(&mut self, scene: &mut UIScene, event: &WindowEvent) -> bool {
+        if !self.editing {
+            return false;
+        }
+        // Keys go to the input method while it's composing.
+        let composing = self.edit.is_composing();
+        if let (
+            false,
+            WindowEvent::KeyboardInput {
+                input:
+                    KeyboardInput {
+                        state: ElementState::Pressed,
+                        virtual_keycode: Some(key),
+                        ..
+                    },
+                ..
+            },
+        ) = (composing, event)
+        {
+            match key {
+                VirtualKeyCode::Return | VirtualKeyCode::NumpadEnter => {
+                    if self.apply() {
+                        self.show_value();
+                    }
+                    return true;
+                }
+                VirtualKeyCode::Escape => {
+                    self.show_value();
+                    return true;
+                }
+                VirtualKeyCode::Up | VirtualKeyCode::Down => {
+                    let up = *key == VirtualKeyCode::Up;
+                    self.step(scene, if up { 1.0 } else { -1.0 });
+                    return true;
+                }
+                _ => {}
+            }
+        }
+        let used = self.edit.input(scene, event);
+        if used {
+            self.validate();
+        }
+        used
+    }
+
+    // Starts and ends editing as the field gains and loses focus, scrubs
+    // while the label is dragged, and redraws what changed. Returns whether
+    // the value changed since the last update.
+    pub fn update(
+        &mut self,
+        scene: &mut UIScene,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        atlas: &mut GlyphAtlas,
+        dt: f32,
+    ) -> bool {
+        // Clicks on the text and caret land on their own elements.
+        if scene.focused.is_some_and(|element| self.is_field(element)) {
+            scene.set_focus(Some(self.field));
+        }
+        let focused = scene.focused == Some(self.field);
+        if focused && !self.editing {
+            self.editing = true;
+            self.show_value();
+        } else if !focused && self.editing {
+            if !self.apply() {
+                self.error = None;
+            }
+            self.editing = false;
+            self.dirty = true;
+        }
+        if self.editing && self.edit.update(scene, dt) {
+            self.validate();
+        }
+        self.update_scrub(scene);
+
+        if self.dirty {
+            self.redraw(scene, device, queue, atlas);
+        }
+        self.label_text.update(scene, device, atlas);
+        self.value_text.update(scene, device, atlas);
+        self.error_text.update(scene, device, atlas);
+        std::mem::take(&mut self.changed)
+    }
+
+    fn is_field(&self, element: usize) -> bool {
+        self.caret.contains(element) || self.value_text.elements().contains(&element)
+    }
+
+    fn update_scrub(&mut self, scene: &mut UIScene) {
+        let pressed = scene.pressed().is_some_and(|element| {
+            element == self.label || self.label_text.elements().contains(&element)
+        });
+        if !pressed {
+            self.scrub = None;
+            return;
+        }
+        let cursor = scene.cursor();
+        let Some((last, raw)) = self.scrub else {
+            self.scrub = Some((cursor, self.value));
+            return;
+        };
+        let mut delta = (cursor.x - last.x) / self.style.sensitivity * self.style.step.max(0.01);
+        if scene.shortcuts.modifiers().shift() {
+            delta *= self.style.coarse;
+        }
+        let raw = (raw + delta).clamp(self.style.min, self.style.max);
+        self.scrub = Some((cursor, raw));
+        self.set(self.style.snap(raw));
+    }
+
+    // From the typed value if it's valid, the current one otherwise.
+    fn step(&mut self, scene: &UIScene, direction: f32) {
+        let mut step = self.style.step.max(0.01);
+        if scene.shortcuts.modifiers().shift() {
+            step *= self.style.coarse;
+        }
+        let from = self.parse(self.edit.text()).unwrap_or(self.value);
+        self.set(self.style.snap(from + direction * step));
+        self.show_value();
+    }
+
+    fn set(&mut self, value: f32) {
+        if value != self.value {
+            self.value = value;
+            self.changed = true;
+            self.dirty = true;
+        }
+    }
+
+    // Takes the typed value, or shows why it can't. Returns whether it was
+    // valid.
+    fn apply(&mut self) -> bool {
+        match self.parse(self.edit.text()) {
+            Ok(value) => {
+                self.set(value);
+                self.error = None;
+                true
+            }
+            Err(error) => {
+                self.error = Some(error);
+                self.dirty = true;
+                false
+            }
+        }
+    }
+
+    fn validate(&mut self) {
+        self.error = self.parse(self.edit.text()).err();
+        self.dirty = true;
+    }
+
+    // Replaces what's typed with the value, all selected so typing
+    // replaces it.
+    fn show_value(&mut self) {
+        self.edit.set_text(&self.style.format(self.value));
+        self.edit.select_all();
+        self.error = None;
+        self.dirty = true;
+    }
+
+    fn parse(&self, text: &str) -> Result<f32, String> {
+        let value = evaluate(text).map_err(|error| error.to_string())? as f32;
+        let style = &self.style;
+        if !value.is_finite() {
+            Err("not a number".to_string())
+        } else if value < style.min {
+            Err(format!("must be at least {}", style.format(style.min)))
+        } else if value > style.max {
+            Err(format!("must be at most {}", style.format(style.max)))
+        } else {
+            Ok(value)
+        }
+    }
+
+    fn lay_out(
+        &mut self,
+        scene: &mut UIScene,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        atlas: &mut GlyphAtlas,
+    ) {
+        let style = &self.style;
+        let box_layout = |width: f32, align: TextAlign| TextLayout {
+            max_width: Some((width - 2.0 * style.padding).max(0.0)),
+            height: Some(style.height),
+            wrap: false,
+            align,
+            vertical_align: VerticalAlign::Middle,
+            ..TextLayout::default()
+        };
+        let label_layout = box_layout(style.label_width, TextAlign::Left);
+        let value_layout = box_layout(style.field_width(), style.align);
+        let error_layout = TextLayout {
+            max_width: Some(style.field_width()),
+            ..TextLayout::default()
+        };
+        self.label_text
+            .set_layout(scene, device, queue, atlas, label_layout);
+        self.label_text
+            .set_text(scene, device, queue, atlas, &self.label_name);
+        self.value_text
+            .set_layout(scene, device, queue, atlas, value_layout);
+        self.error_text
+            .set_layout(scene, device, queue, atlas, error_layout);
+        self.laid_out = true;
+    }
+
+    fn redraw(
+        &mut self,
+        scene: &mut UIScene,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        atlas: &mut GlyphAtlas,
+    ) {
+        self.dirty = false;
+        if !self.laid_out {
+            self.lay_out(scene, device, queue, atlas);
+        }
+        let style = &self.style;
+        let field = &mut scene.elements[self.field];
+        let color = if self.error.is_some() {
+            style.error
+        } else {
+            style.field
+        };
+        field.reshape(queue, field.shape, color, color);
+
+        let shown = if self.editing {
+            self.edit.display_text().into_owned()
+        } else {
+            style.format(self.value)
+        };
+        self.value_text
+            .set_text(scene, device, queue, atlas, &shown);
+        let error = self.error.as_deref().unwrap_or("");
+        self.error_text.set_text(scene, device, queue, atlas, error);
+        self.error_text.set_visible(scene, self.error.is_some());
+
+        // The caret and selection, on the first line as laid out.
+        let text_left = scene.elements[self.field].instance.position.x - style.field_width() / 2.0
+            + style.padding;
+        let (range, left) = match self.value_text.metrics().lines.first() {
+            Some(line) => (line.range.clone(), text_left + line.x),
+            None => (0..0, text_left),
+        };
+        self.caret
+            .measure(&self.value_text.font, style.font_size, &shown, range);
+        self.caret
+            .place(scene, queue, &self.edit, left, self.editing);
+    }
+}
+
+// Evaluates arithmetic typed into a field: numbers, + - * / % and ^ for
+// powers, parentheses, and the constants pi, tau and e.
+pub fn evaluate(expression: &str) -> anyhow::Result<f64> {
+    let mut parser = Parser {
+        text: expression,
+        at: 0,
+    };
+    let value = parser.sum()?;
+    match parser.peek() {
+        Some(c) => bail!("unexpected '{}'", c),
+        None => Ok(value),
+    }
+}
+
+// Recursive descent, one function per precedence level.
+struct Parser<'a> {
+    text: &'a str,
+    at: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&mut self) -> Option<char> {
+        let rest = &self.text[self.at..];
+        self.at += rest.len() - rest.trim_start().len();
+        self.text[self.at..].chars().next()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        let found = self.peek() == Some(c);
+        if found {
+            self.at += c.len_utf8();
+        }
+        found
+    }
+
+    fn sum(&mut self) -> anyhow::Result<f64> {
+        let mut value = self.product()?;
+        loop {
+            if self.eat('+') {
+                value += self.product()?;
+            } else if self.eat('-') {
+                value -= self.product()?;
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+
+    fn product(&mut self) -> anyhow::Result<f64> {
+        let mut value = self.unary()?;
+        loop {
+            let operator = match self.peek() {
+                Some(c @ ('*' | '/' | '%')) => c,
+                _ => return Ok(value),
+            };
+            self.at += 1;
+            let operand = self.unary()?;
+            value = match operator {
+                '*' => value * operand,
+                _ if operand == 0.0 => bail!("division by zero"),
+                '/' => value / operand,
+                _ => value % operand,
+            };
+        }
+    }
+
+    // Binds looser than powers, so -2^2 is -4.
+    fn unary(&mut self) -> anyhow::Result<f64> {
+        if self.eat('-') {
+            Ok(-self.unary()?)
+        } else if self.eat('+') {
+            self.unary()
+        } else {
+            self.power()
+        }
+    }
+
+    // Right associative, 2^3^2 is 2^9.
+    fn power(&mut self) -> anyhow::Result<f64> {
+        let base = self.primary()?;
+        if self.eat('^') {
+            Ok(base.powf(self.unary()?))
+        } else {
+            Ok(base)
+        }
+    }
+
+    fn primary(&mut self) -> anyhow::Result<f64> {
+        if self.eat('(') {
+            let value = self.sum()?;
+            if !self.eat(')') {
+                bail!("missing ')'");
+            }
+            return Ok(value);
+        }
+        let start = self.at;
+        match self.peek() {
+            Some(c) if c.is_ascii_digit() || c == '.' => {
+                let rest = &self.text[start..];
+                let length = rest
+                    .find(|c: char| !c.is_ascii_digit() && c != '.')
+                    .unwrap_or(rest.len());
+                self.at += length;
+                rest[..length]
+                    .parse()
+                    .with_context(|| format!("'{}' isn't a number", &rest[..length]))
+            }
+            Some(c) if c.is_alphabetic() => {
+                let rest = &self.text[start..];
+                let length = rest
+                    .find(|c: char| !c.is_alphanumeric())
+                    .unwrap_or(rest.len());
+                self.at += length;
+                match &rest[..length] {
+                    "pi" => Ok(std::f64::consts::PI),
+                    "tau" => Ok(std::f64::consts::TAU),
+                    "e" => Ok(std::f64::consts::E),
+                    name => bail!("unknown name '{}'", name),
+                }
+            }
+            Some(c) => bail!("unexpected '{}'", c),
+            None => bail!("expected a number"),
+        }
+    }
+}
@@ -0,0 +1,449 @@
+use std::collections::HashMap;
+
+use cgmath::Rotation3;
+use serde::{Deserialize, Serialize};
+use winit::event::{ElementState, MouseButton, WindowEvent};
+
+use crate::menu_bar::chevron;
+use crate::ui_scene::{Player, Shape, UIScene, Viewport};
+
+pub const LAYOUT_FILE: &str = "wgpie-layout.json";
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SplitDirection {
+    // Panes side by side, first on the left.
+    Horizontal,
+    // Panes stacked, first on top.
+    Vertical,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Pane {
+    First,
+    Second,
+}
+
+impl Pane {
+    fn index(self) -> usize {
+        match self {
+            Pane::First => 0,
+            Pane::Second => 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SplitterStyle {
+    pub divider: f32,
+    // Pane sizes along the split, in pixels. Minimums win when both can't
+    // be met.
+    pub min: [f32; 2],
+    pub max: [f32; 2],
+    pub button_size: f32,
+    pub divider_color: [f32; 3],
+    pub button_color: [f32; 3],
+    // Multiplies the divider color while it's hovered or dragged.
+    pub hover_tint: [f32; 4],
+}
+
+impl Default for SplitterStyle {
+    fn default() -> Self {
+        Self {
+            divider: 6.0,
+            min: [48.0, 48.0],
+            max: [f32::INFINITY, f32::INFINITY],
+            button_size: 14.0,
+            divider_color: [0.18, 0.18, 0.2],
+            button_color: [0.6, 0.6, 0.65],
+            hover_tint: [1.6, 1.6, 1.7, 1.0],
+        }
+    }
+}
+
+// What's worth keeping of a splitter between runs.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SplitState {
+    pub ratio: f32,
+    pub collapsed: Option<Pane>,
+}
+
+// Split states by splitter name, saved next to the window settings.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SplitLayout {
+    pub splits: HashMap<String, SplitState>,
+}
+
+impl SplitLayout {
+    // Missing or unreadable layouts just mean default splits.
+    pub fn load(path: &str) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &str) -> anyhow::Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+struct Content {
+    element: usize,
+    // From the pane's top left, in screen pixels.
+    offset: cgmath::Vector2<f32>,
+}
+
+struct Drag {
+    // Cursor distance from the divider's leading edge when grabbed.
+    grab: f32,
+}
+
+// Two panes sharing a screen rect, with a draggable divider between them.
+// Elements added to a pane are kept at their offset from its top left and
+// clipped to it. A pane can host another splitter by feeding `pane_rect`
+// into the inner splitter's `set_bounds` every frame.
+//
+// Two buttons on the divider collapse the pane they point to, or restore
+// the split if the other pane is collapsed.
+pub struct Splitter {
+    pub style: SplitterStyle,
+    pub name: String,
+    direction: SplitDirection,
+    // Screen rect relative to the viewport.
+    bounds: Viewport,
+    // Share of the space left by the divider that goes to the first pane.
+    ratio: f32,
+    collapsed: Option<Pane>,
+    contents: [Vec<Content>; 2],
+    divider: usize,
+    // Collapsing the first and the second pane.
+    buttons: [usize; 2],
+    cursor: cgmath::Vector2<f32>,
+    drag: Option<Drag>,
+    changed: bool,
+}
+
+impl Splitter {
+    pub fn new(
+        scene: &mut UIScene,
+        device: &wgpu::Device,
+        name: &str,
+        bounds: Viewport,
+        direction: SplitDirection,
+        style: SplitterStyle,
+    ) -> Self {
+        let color = style.divider_color;
+        let mut divider = Player::with_gradient(
+            device,
+            cgmath::Vector2::new(0.0, 0.0),
+            cgmath::Vector2::new(style.divider, style.divider),
+            color,
+            color,
+        );
+        divider.draggable = false;
+        scene.elements.push(divider);
+        let divider = scene.elements.len() - 1;
+        let buttons = [0, 1].map(|_| {
+            scene.elements.push(chevron(device, style.button_color));
+            scene.elements.len() - 1
+        });
+
+        Self {
+            style,
+            name: name.to_string(),
+            direction,
+            bounds,
+            ratio: 0.5,
+            collapsed: None,
+            contents: [Vec::new(), Vec::new()],
+            divider,
+            buttons,
+            cursor: cgmath::Vector2::new(0.0, 0.0),
+            drag: None,
+            changed: false,
+        }
+    }
+
+    pub fn set_bounds(&mut self, bounds: Viewport) {
+        self.bounds = bounds;
+    }
+
+    // Keeps `element` at `offset` from the pane's top left, in pixels.
+    pub fn add(&mut self, pane: Pane, element: usize, offset: cgmath::Vector2<f32>) {
+        self.contents[pane.index()].push(Content { element, offset });
+    }
+
+    pub fn remove(&mut self, scene: &mut UIScene, element: usize) {
+        for contents in &mut self.contents {
+            contents.retain(|content| content.element != element);
+        }
+        scene.elements[element].clip = None;
+    }
+
+    pub fn ratio(&self) -> f32 {
+        self.ratio
+    }
+
+    // Clamped so both panes stay within their size limits.
+    pub fn set_ratio(&mut self, ratio: f32) {
+        let available = self.available();
+        self.ratio = if available > 0.0 {
+            self.clamp_first(ratio * available) / available
+        } else {
+            ratio.clamp(0.0, 1.0)
+        };
+        self.changed = true;
+    }
+
+    pub fn collapsed(&self) -> Option<Pane> {
+        self.collapsed
+    }
+
+    // The split ratio is kept, so expanding restores it.
+    pub fn collapse(&mut self, pane: Option<Pane>) {
+        self.collapsed = pane;
+        self.changed = true;
+    }
+
+    pub fn state(&self) -> SplitState {
+        SplitState {
+            ratio: self.ratio,
+            collapsed: self.collapsed,
+        }
+    }
+
+    pub fn save_state(&self, layout: &mut SplitLayout) {
+        layout.splits.insert(self.name.clone(), self.state());
+    }
+
+    // Leaves the split alone if the layout doesn't know this splitter.
+    pub fn restore(&mut self, layout: &SplitLayout) {
+        if let Some(state) = layout.splits.get(&self.name) {
+            self.ratio = state.ratio.clamp(0.0, 1.0);
+            self.collapsed = state.collapsed;
+        }
+    }
+
+    // Screen rect of a pane relative to the viewport, empty while collapsed.
+    pub fn pane_rect(&self, pane: Pane) -> Viewport {
+        let first = self.first_size();
+        let divider = self.style.divider;
+        let b = self.bounds;
+        let (start, length) = match pane {
+            Pane::First => (0.0, first),
+            Pane::Second => (first + divider, self.length() - first - divider),
+        };
+        let length = length.max(0.0);
+        match self.direction {
+            SplitDirection::Horizontal => Viewport {
+                x: b.x + start,
+                width: length,
+                ..b
+            },
+            SplitDirection::Vertical => Viewport {
+                y: b.y + start,
+                height: length,
+                ..b
+            },
+        }
+    }
+
+    // Call before `UIScene::input`, and skip it if this returns true.
+    pub fn input(&mut self, scene: &UIScene, event: &WindowEvent) -> bool {
+        match event {
+            WindowEvent::CursorMoved { position, .. } => {
+                self.cursor = cgmath::Vector2::new(
+                    position.x as f32 - scene.viewport.x,
+                    position.y as f32 - scene.viewport.y,
+                );
+                let Some(drag) = &self.drag else {
+                    return false;
+                };
+                let first = self.along(self.cursor) - self.along_start() - drag.grab;
+                let available = self.available();
+                if available > 0.0 {
+                    self.collapsed = None;
+                    self.ratio = self.clamp_first(first) / available;
+                    self.changed = true;
+                }
+                true
+            }
+            WindowEvent::MouseInput {
+                state: ElementState::Pressed,
+                button: MouseButton::Left,
+                ..
+            } => {
+                if let Some(pane) = self.button_at(scene, self.cursor) {
+                    let other = match pane {
+                        Pane::First => Pane::Second,
+                        Pane::Second => Pane::First,
+                    };
+                    let collapse = (self.collapsed != Some(other)).then_some(pane);
+                    self.collapse(collapse);
+                    return true;
+                }
+                let divider = self.divider_rect();
+                if !divider.contains(self.cursor) {
+                    return false;
+                }
+                let leading = match self.direction {
+                    SplitDirection::Horizontal => divider.x,
+                    SplitDirection::Vertical => divider.y,
+                };
+                self.drag = Some(Drag {
+                    grab: self.along(self.cursor) - leading,
+                });
+                true
+            }
+            WindowEvent::MouseInput {
+                state: ElementState::Released,
+                button: MouseButton::Left,
+                ..
+            } => self.drag.take().is_some(),
+            _ => false,
+        }
+    }
+
+    // Places the divider and pane contents. Returns whether the split
+    // changed since the last update, e.g. to save the layout.
+    pub fn update(&mut self, scene: &mut UIScene, queue: &wgpu::Queue) -> bool {
+        let scale = 1.0 / scene.camera.scale;
+        let camera = &scene.camera;
+        let place = |element: &mut Player, x: f32, y: f32| {
+            let world = camera.screen_to_world(x, y);
+            element.instance.position.x = world.x;
+            element.instance.position.y = world.y;
+            element.instance.scale = scale;
+        };
+
+        let divider = self.divider_rect();
+        let center = (
+            divider.x + divider.width / 2.0,
+            divider.y + divider.height / 2.0,
+        );
+        let hovered = self.drag.is_some() || divider.contains(self.cursor);
+        let element = &mut scene.elements[self.divider];
+        place(element, center.0, center.1);
+        element.clip = Some(self.bounds);
+        element.instance.tint = if hovered {
+            self.style.hover_tint
+        } else {
+            [1.0; 4]
+        };
+        let size = cgmath::Vector2::new(divider.width, divider.height);
+        if element.size != size {
+            let shape = Shape::Rect {
+                width: size.x,
+                height: size.y,
+            };
+            let color = self.style.divider_color;
+            element.reshape(queue, shape, color, color);
+        }
+
+        for (i, &button) in self.buttons.iter().enumerate() {
+            let (x, y) = self.button_center(i);
+            let element = &mut scene.elements[button];
+            place(element, x, y);
+            element.clip = Some(self.bounds);
+            // The chevron points right, towards +x.
+            let angle = match (self.direction, i) {
+                (SplitDirection::Horizontal, 0) => 180.0,
+                (SplitDirection::Horizontal, _) => 0.0,
+                (SplitDirection::Vertical, 0) => 90.0,
+                (SplitDirection::Vertical, _) => -90.0,
+            };
+            element.instance.rotation = cgmath::Quaternion::from_angle_z(cgmath::Deg(angle));
+        }
+
+        for pane in [Pane::First, Pane::Second] {
+            let rect = self.pane_rect(pane);
+            for content in &self.contents[pane.index()] {
+                let element = &mut scene.elements[content.element];
+                place(
+                    element,
+                    rect.x + content.offset.x,
+                    rect.y + content.offset.y,
+                );
+                element.clip = Some(rect);
+            }
+        }
+        std::mem::take(&mut self.changed)
+    }
+
+    fn length(&self) -> f32 {
+        match self.direction {
+            SplitDirection::Horizontal => self.bounds.width,
+            SplitDirection::Vertical => self.bounds.height,
+        }
+    }
+
+    fn available(&self) -> f32 {
+        (self.length() - self.style.divider).max(0.0)
+    }
+
+    fn along(&self, point: cgmath::Vector2<f32>) -> f32 {
+        match self.direction {
+            SplitDirection::Horizontal => point.x,
+            SplitDirection::Vertical => point.y,
+        }
+    }
+
+    fn along_start(&self) -> f32 {
+        self.along(cgmath::Vector2::new(self.bounds.x, self.bounds.y))
+    }
+
+    fn clamp_first(&self, first: f32) -> f32 {
+        let available = self.available();
+        let [min_first, min_second] = self.style.min;
+        let [max_first, max_second] = self.style.max;
+        let low = min_first.max(available - max_second);
+        let high = max_first.min(available - min_second);
+        first.min(high).max(low).clamp(0.0, available)
+    }
+
+    fn first_size(&self) -> f32 {
+        match self.collapsed {
+            Some(Pane::First) => 0.0,
+            Some(Pane::Second) => self.available(),
+            None => self.clamp_first(self.ratio * self.available()),
+        }
+    }
+
+    fn divider_rect(&self) -> Viewport {
+        let start = self.first_size();
+        let b = self.bounds;
+        match self.direction {
+            SplitDirection::Horizontal => Viewport {
+                x: b.x + start,
+                width: self.style.divider,
+                ..b
+            },
+            SplitDirection::Vertical => Viewport {
+                y: b.y + start,
+                height: self.style.divider,
+                ..b
+            },
+        }
+    }
+
+    // Buttons sit near the start of the divider, one after the other.
+    fn button_center(&self, i: usize) -> (f32, f32) {
+        let divider = self.divider_rect();
+        let step = self.style.button_size * (i as f32 + 0.5) + self.style.divider;
+        match self.direction {
+            SplitDirection::Horizontal => (divider.x + divider.width / 2.0, divider.y + step),
+            SplitDirection::Vertical => (divider.x + step, divider.y + divider.height / 2.0),
+        }
+    }
+
+    fn button_at(&self, scene: &UIScene, point: cgmath::Vector2<f32>) -> Option<Pane> {
+        let half = self.style.button_size / 2.0;
+        [Pane::First, Pane::Second].into_iter().find(|pane| {
+            let (x, y) = self.button_center(pane.index());
+            scene.elements[self.buttons[pane.index()]].visible
+                && (point.x - x).abs() <= half
+                && (point.y - y).abs() <= half
+        })
+    }
+}
@@ -0,0 +1,187 @@
+use std::rc::Rc;
+
+use image::{Rgba, RgbaImage};
+
+use crate::texture::Texture;
+
+// A rect of texels, from the top left.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PixelRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl PixelRect {
+    fn union(&self, other: &PixelRect) -> PixelRect {
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        PixelRect {
+            x,
+            y,
+            width: (self.x + self.width).max(other.x + other.width) - x,
+            height: (self.y + self.height).max(other.y + other.height) - y,
+        }
+    }
+}
+
+// A texture drawn into from the CPU, e.g. a heatmap or a video feed. Edits
+// go to a copy in memory, and `flush` uploads the part they touched:
+//
+//     let mut heatmap = StreamingTexture::new(device, 256, 256, sampler);
+//     scene.set_texture_fill(device, element, &heatmap.texture);
+//     heatmap.set_pixel(x, y, color); // any number of times
+//     if heatmap.flush(queue) { // once per frame
+//         scene.invalidate(element);
+//     }
+//
+// Edits are merged into a single rect, so scattered ones upload everything
+// between them.
+pub struct StreamingTexture {
+    pub texture: Texture,
+    pixels: RgbaImage,
+    dirty: Option<PixelRect>,
+}
+
+impl StreamingTexture {
+    // Transparent black to start with, sRGB like images.
+    pub fn new(device: &wgpu::Device, width: u32, height: u32, sampler: Rc<wgpu::Sampler>) -> Self {
+        let (width, height) = (width.max(1), height.max(1));
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Streaming texture"),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        Self {
+            texture: Texture {
+                texture,
+                view,
+                sampler,
+            },
+            pixels: RgbaImage::new(width, height),
+            dirty: None,
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.pixels.width()
+    }
+
+    pub fn height(&self) -> u32 {
+        self.pixels.height()
+    }
+
+    pub fn pixels(&self) -> &RgbaImage {
+        &self.pixels
+    }
+
+    // For edits `set_pixel` and `write` don't cover. Say where with
+    // `mark_dirty`, or they aren't uploaded.
+    pub fn pixels_mut(&mut self) -> &mut RgbaImage {
+        &mut self.pixels
+    }
+
+    // Ignores texels outside the texture.
+    pub fn set_pixel(&mut self, x: u32, y: u32, color: [u8; 4]) {
+        if x < self.width() && y < self.height() {
+            self.pixels.put_pixel(x, y, Rgba(color));
+            self.mark_dirty(PixelRect {
+                x,
+                y,
+                width: 1,
+                height: 1,
+            });
+        }
+    }
+
+    // Copies `image` in with its top left at `x`, `y`, cut off at the
+    // texture's edges.
+    pub fn write(&mut self, x: u32, y: u32, image: &RgbaImage) {
+        image::imageops::replace(&mut self.pixels, image, x as i64, y as i64);
+        self.mark_dirty(PixelRect {
+            x,
+            y,
+            width: image.width(),
+            height: image.height(),
+        });
+    }
+
+    // Fills the whole texture with one color.
+    pub fn clear(&mut self, color: [u8; 4]) {
+        for pixel in self.pixels.pixels_mut() {
+            *pixel = Rgba(color);
+        }
+        self.mark_dirty(PixelRect {
+            x: 0,
+            y: 0,
+            width: self.width(),
+            height: self.height(),
+        });
+    }
+
+    // Clipped to the texture.
+    pub fn mark_dirty(&mut self, rect: PixelRect) {
+        let x = rect.x.min(self.width());
+        let y = rect.y.min(self.height());
+        let rect = PixelRect {
+            x,
+            y,
+            width: (rect.x.saturating_add(rect.width)).min(self.width()) - x,
+            height: (rect.y.saturating_add(rect.height)).min(self.height()) - y,
+        };
+        if rect.width == 0 || rect.height == 0 {
+            return;
+        }
+        self.dirty = Some(self.dirty.map_or(rect, |dirty| dirty.union(&rect)));
+    }
+
+    // What the next `flush` uploads.
+    pub fn dirty(&self) -> Option<PixelRect> {
+        self.dirty
+    }
+
+    // Uploads the edits since the last flush. Returns false if there were
+    // none.
+    pub fn flush(&mut self, queue: &wgpu::Queue) -> bool {
+        let Some(rect) = self.dirty.take() else {
+            return false;
+        };
+        let width = self.width();
+        queue.write_texture(
+            wgpu::ImageCopyTexture {
+                aspect: wgpu::TextureAspect::All,
+                texture: &self.texture.texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d {
+                    x: rect.x,
+                    y: rect.y,
+                    z: 0,
+                },
+            },
+            &self.pixels,
+            // Rows are read straight out of the full image.
+            wgpu::ImageDataLayout {
+                offset: ((rect.y * width + rect.x) * 4) as u64,
+                bytes_per_row: Some(width * 4),
+                rows_per_image: Some(rect.height),
+            },
+            wgpu::Extent3d {
+                width: rect.width,
+                height: rect.height,
+                depth_or_array_layers: 1,
+            },
+        );
+        true
+    }
+}
@@ -0,0 +1,254 @@
+use std::collections::HashMap;
+
+use crate::font_chain::FontChain;
+use crate::tween::{Easing, Tween};
+use crate::ui_scene::{UIScene, PARAM_BORDER};
+use crate::units::Length;
+
+const TRANSITION_DURATION: f32 = 0.12;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum StyleState {
+    Normal,
+    Hovered,
+    Pressed,
+    Disabled,
+    Selected,
+}
+
+impl StyleState {
+    // Disabled wins over everything, then the most direct interaction.
+    pub fn of(scene: &UIScene, element: usize) -> Self {
+        if !scene.is_enabled(element) {
+            StyleState::Disabled
+        } else if scene.pressed() == Some(element) {
+            StyleState::Pressed
+        } else if scene.hovered() == Some(element) {
+            StyleState::Hovered
+        } else if scene.focused == Some(element) {
+            StyleState::Selected
+        } else {
+            StyleState::Normal
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Style {
+    pub tint: [f32; 4],
+    pub scale: f32,
+    // Border width in pixels, 0 for none. Sprites, patterns, gradients and
+    // SDF text don't draw one.
+    pub border: f32,
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Self {
+            tint: [1.0; 4],
+            scale: 1.0,
+            border: 0.0,
+        }
+    }
+}
+
+// A copy of the text drawn under it, moved by `offset` in world units.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct TextShadow {
+    pub offset: cgmath::Vector2<f32>,
+    pub color: [f32; 4],
+}
+
+impl TextShadow {
+    // Down and to the right, half transparent black.
+    pub fn drop(distance: f32) -> Self {
+        Self {
+            offset: cgmath::Vector2::new(distance, -distance),
+            color: [0.0, 0.0, 0.0, 0.5],
+        }
+    }
+}
+
+// A stroke `width` wide around the outside of the glyphs.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct TextOutline {
+    pub width: f32,
+    pub color: [f32; 4],
+}
+
+// How text is drawn. Each cluster comes from the first font of the chain
+// that has it, e.g. a Latin font, then CJK, then emoji. A shadow or outline
+// keeps HUD text readable over whatever is behind it.
+#[derive(Clone)]
+pub struct TextStyle {
+    pub fonts: FontChain,
+    pub size: Length,
+    pub color: [f32; 4],
+    pub shadow: Option<TextShadow>,
+    pub outline: Option<TextOutline>,
+}
+
+impl TextStyle {
+    pub fn new(fonts: FontChain) -> Self {
+        Self {
+            fonts,
+            size: Length::Points(14.0),
+            color: [1.0; 4],
+            shadow: None,
+            outline: None,
+        }
+    }
+}
+
+// Styles per state. States without a style of their own use `normal`.
+#[derive(Debug, Clone, Default)]
+pub struct StateStyles {
+    pub normal: Style,
+    pub hovered: Option<Style>,
+    pub pressed: Option<Style>,
+    pub disabled: Option<Style>,
+    pub selected: Option<Style>,
+}
+
+impl StateStyles {
+    // Slightly brighter on hover, shrunk when pressed and bordered when
+    // selected. Disabled elements get the scene's `disabled_tint`.
+    pub fn interactive() -> Self {
+        let normal = Style::default();
+        Self {
+            normal,
+            hovered: Some(Style {
+                tint: [1.15, 1.15, 1.15, 1.0],
+                ..normal
+            }),
+            pressed: Some(Style {
+                tint: [0.85, 0.85, 0.85, 1.0],
+                scale: 0.95,
+                ..normal
+            }),
+            disabled: None,
+            selected: Some(Style {
+                border: 2.0,
+                ..normal
+            }),
+        }
+    }
+
+    // Without a disabled style, disabled elements are the normal style
+    // times `scene.disabled_tint`, as unstyled ones are drawn.
+    fn target(&self, scene: &UIScene, state: StyleState) -> Style {
+        match (state, self.disabled) {
+            (StyleState::Disabled, None) => Style {
+                tint: [0, 1, 2, 3].map(|i| self.normal.tint[i] * scene.disabled_tint[i]),
+                ..self.normal
+            },
+            _ => self.get(state),
+        }
+    }
+
+    pub fn get(&self, state: StyleState) -> Style {
+        let style = match state {
+            StyleState::Normal => None,
+            StyleState::Hovered => self.hovered,
+            StyleState::Pressed => self.pressed,
+            StyleState::Disabled => self.disabled,
+            StyleState::Selected => self.selected,
+        };
+        style.unwrap_or(self.normal)
+    }
+}
+
+struct Styled {
+    styles: StateStyles,
+    state: StyleState,
+    tint: [Tween; 4],
+    scale: Tween,
+    border: Tween,
+}
+
+impl Styled {
+    fn new(styles: StateStyles, duration: f32) -> Self {
+        let style = styles.normal;
+        let tween = |value| Tween::new(value, duration, Easing::EaseOut);
+        Self {
+            styles,
+            state: StyleState::Normal,
+            tint: style.tint.map(tween),
+            scale: tween(style.scale),
+            border: tween(style.border),
+        }
+    }
+
+    fn retarget(&mut self, style: Style) {
+        for (tween, value) in self.tint.iter_mut().zip(style.tint) {
+            tween.retarget(value);
+        }
+        self.scale.retarget(style.scale);
+        self.border.retarget(style.border);
+    }
+
+    fn update(&mut self, dt: f32) {
+        for tween in self.tint.iter_mut() {
+            tween.update(dt);
+        }
+        self.scale.update(dt);
+        self.border.update(dt);
+    }
+}
+
+// Animates elements between their state styles. Styled elements get their
+// tint, scale and border from here, so set those through the styles rather
+// than on the instance.
+pub struct StyleTransitions {
+    pub duration: f32,
+    styled: HashMap<usize, Styled>,
+}
+
+impl Default for StyleTransitions {
+    fn default() -> Self {
+        Self {
+            duration: TRANSITION_DURATION,
+            styled: HashMap::new(),
+        }
+    }
+}
+
+impl StyleTransitions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_styles(&mut self, element: usize, styles: StateStyles) {
+        self.styled
+            .insert(element, Styled::new(styles, self.duration));
+    }
+
+    pub fn remove(&mut self, scene: &mut UIScene, element: usize) {
+        if self.styled.remove(&element).is_some() {
+            if let Some(element) = scene.elements.get_mut(element) {
+                element.styled = false;
+            }
+        }
+    }
+
+    // Call once per frame before `UIScene::prepare`.
+    pub fn update(&mut self, scene: &mut UIScene, dt: f32) {
+        for (&element, styled) in self.styled.iter_mut() {
+            let state = StyleState::of(scene, element);
+            if state != styled.state {
+                styled.state = state;
+                styled.retarget(styled.styles.target(scene, state));
+            }
+            styled.update(dt);
+
+            let player = &mut scene.elements[element];
+            player.styled = true;
+            player.instance.tint = [0, 1, 2, 3].map(|i| styled.tint[i].value());
+            player.instance.scale = styled.scale.value();
+            // Other materials keep data there, e.g. a sprite's atlas rect.
+            if player.material.shows_selection() {
+                player.instance.params[PARAM_BORDER] = styled.border.value();
+            }
+        }
+    }
+}
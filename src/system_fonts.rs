@@ -0,0 +1,184 @@
+use std::rc::Rc;
+
+use anyhow::Context;
+use fontdb::{Database, Family, Query, Style, Weight};
+
+use crate::font_chain::{Font, FontChain};
+use crate::text::TextFont;
+
+// Fonts installed on the system, found by family name the way CSS finds
+// them, so text styles needn't bundle their own:
+//
+//     let system = SystemFonts::load();
+//     let fonts = system.chain(&["Inter", "Noto Sans CJK JP", "Noto Color Emoji"])?;
+//     let style = TextStyle::new(fonts);
+//
+// Scanning the font directories takes a while on systems with many fonts,
+// so load once and keep it around.
+pub struct SystemFonts {
+    db: Database,
+}
+
+impl SystemFonts {
+    pub fn load() -> Self {
+        let mut db = Database::new();
+        db.load_system_fonts();
+        log::info!("found {} system font faces", db.len());
+        let mut fonts = Self { db };
+        fonts.guess_generic_families();
+        fonts
+    }
+
+    // The generic families default to Windows' Arial, Times New Roman and
+    // Courier New, and fontconfig doesn't always say otherwise. Where those
+    // aren't installed, take a family whose name fits.
+    fn guess_generic_families(&mut self) {
+        let families = self.families();
+        let installed = |name: &str| families.iter().any(|family| family == name);
+        let guess =
+            |fits: &dyn Fn(&str) -> bool| families.iter().find(|family| fits(family)).cloned();
+        let (sans_serif, serif, monospace) = (
+            self.db.family_name(&Family::SansSerif).to_string(),
+            self.db.family_name(&Family::Serif).to_string(),
+            self.db.family_name(&Family::Monospace).to_string(),
+        );
+        if !installed(&sans_serif) {
+            if let Some(family) = guess(&|name| name.contains("Sans") && !name.contains("Mono")) {
+                self.db.set_sans_serif_family(family);
+            }
+        }
+        if !installed(&serif) {
+            if let Some(family) = guess(&|name| name.contains("Serif") && !name.contains("Sans")) {
+                self.db.set_serif_family(family);
+            }
+        }
+        if !installed(&monospace) {
+            if let Some(family) = guess(&|name| name.contains("Mono") || name.contains("Code")) {
+                self.db.set_monospace_family(family);
+            }
+        }
+    }
+
+    // Family names, sorted and without duplicates.
+    pub fn families(&self) -> Vec<String> {
+        let mut families = self
+            .db
+            .faces()
+            .flat_map(|face| face.families.iter().map(|(name, _)| name.clone()))
+            .collect::<Vec<_>>();
+        families.sort();
+        families.dedup();
+        families
+    }
+
+    // The closest face of `family` to the weight (400 regular, 700 bold)
+    // and style asked for. `family` can also be one of the CSS generic
+    // families, like "sans-serif" or "monospace".
+    pub fn find(&self, family: &str, weight: u16, italic: bool) -> anyhow::Result<SystemFont> {
+        let families = [match family {
+            "serif" => Family::Serif,
+            "sans-serif" => Family::SansSerif,
+            "monospace" => Family::Monospace,
+            "cursive" => Family::Cursive,
+            "fantasy" => Family::Fantasy,
+            name => Family::Name(name),
+        }];
+        let id = self
+            .db
+            .query(&Query {
+                families: &families,
+                weight: Weight(weight),
+                style: if italic { Style::Italic } else { Style::Normal },
+                ..Query::default()
+            })
+            .with_context(|| format!("no font family {} is installed", family))?;
+        let name = self
+            .db
+            .face(id)
+            .map_or(family.to_string(), |face| face.post_script_name.clone());
+        self.db
+            .with_face_data(id, |data, index| SystemFont::parse(&name, data, index))
+            .with_context(|| format!("reading the font file of {}", name))?
+    }
+
+    // Regular faces of the families that are installed, in order. Fails if
+    // none of them is.
+    pub fn chain(&self, families: &[&str]) -> anyhow::Result<FontChain> {
+        let mut fonts = families
+            .iter()
+            .filter_map(|family| match self.find(family, 400, false) {
+                Ok(font) => Some(Rc::new(font) as Rc<dyn Font>),
+                Err(error) => {
+                    log::warn!("{:#}", error);
+                    None
+                }
+            });
+        let primary = fonts
+            .next()
+            .with_context(|| format!("none of {:?} is installed", families))?;
+        Ok(fonts.fold(FontChain::new(primary), FontChain::fallback))
+    }
+}
+
+// A face read from the system, with the file's bytes for rasterizers.
+pub struct SystemFont {
+    pub name: String,
+    pub data: Rc<Vec<u8>>,
+    // Of the face within a collection file.
+    pub index: u32,
+    // Characters with a glyph, as sorted ranges of code points.
+    coverage: Vec<(u32, u32)>,
+}
+
+impl SystemFont {
+    fn parse(name: &str, data: &[u8], index: u32) -> anyhow::Result<Self> {
+        let face = ttf_parser::Face::parse(data, index)
+            .with_context(|| format!("parsing the font {}", name))?;
+        let mut codepoints = Vec::new();
+        if let Some(cmap) = face.tables().cmap {
+            for subtable in cmap.subtables {
+                if subtable.is_unicode() {
+                    subtable.codepoints(|c| {
+                        if subtable.glyph_index(c).is_some_and(|glyph| glyph.0 != 0) {
+                            codepoints.push(c);
+                        }
+                    });
+                }
+            }
+        }
+        codepoints.sort_unstable();
+        codepoints.dedup();
+        let mut coverage: Vec<(u32, u32)> = Vec::new();
+        for c in codepoints {
+            match coverage.last_mut() {
+                Some((_, end)) if *end + 1 == c => *end = c,
+                _ => coverage.push((c, c)),
+            }
+        }
+        Ok(Self {
+            name: name.to_string(),
+            data: Rc::new(data.to_vec()),
+            index,
+            coverage,
+        })
+    }
+
+    // For `Text` to draw with, e.g. as one of its fallbacks:
+    //
+    //     text.fallbacks.push(Rc::new(system.find("Noto Sans CJK JP", 400, false)?.text_font()?));
+    pub fn text_font(&self) -> anyhow::Result<TextFont> {
+        TextFont::from_bytes(&self.name, self.data.to_vec(), self.index)
+    }
+}
+
+impl Font for SystemFont {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn has_glyph(&self, c: char) -> bool {
+        let c = c as u32;
+        let i = self.coverage.partition_point(|&(_, end)| end < c);
+        self.coverage.get(i).is_some_and(|&(start, _)| start <= c)
+    }
+}
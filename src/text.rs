@@ -0,0 +1,882 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use ab_glyph::{Font as _, FontArc, FontVec, GlyphId, GlyphImageFormat, PxScale};
+use anyhow::Context;
+use image::imageops::FilterType;
+use image::{Rgba, RgbaImage};
+
+use crate::atlas::AtlasRegion;
+use crate::dynamic_atlas::{allocate, upload, Shelf};
+use crate::element_pool::ElementPool;
+use crate::font_chain::{Font, FontChain};
+use crate::rich_text::RichText;
+use crate::text_layout::{advance, advances, TextLayout, TextMetrics};
+use crate::texture::Texture;
+use crate::ui_scene::{Player, Shape, UIMaterial, UIScene};
+
+const INITIAL_ATLAS_SIZE: u32 = 256;
+// Pixel size glyphs of an SDF atlas are rasterized at, whatever size they're
+// drawn at.
+pub const SDF_SIZE: f32 = 48.0;
+// How far the distance field reaches past the glyph's edge, in pixels of
+// `SDF_SIZE`. Halos can't be wider.
+pub const SDF_SPREAD: u32 = 8;
+// `UIMaterial::Sdf` packs texel coordinates two to a float, which stays
+// exact up to this size.
+const SDF_MAX_ATLAS_SIZE: u32 = 4096;
+
+// A TrueType or OpenType font glyphs are rasterized from.
+pub struct TextFont {
+    name: String,
+    // Tells the fonts' glyphs apart in a `GlyphAtlas`.
+    id: u64,
+    pub(crate) font: FontArc,
+}
+
+impl TextFont {
+    // `index` picks the face of a collection file, 0 otherwise. Read the
+    // file with `resources::load_binary`, or take a `SystemFont`'s.
+    pub fn from_bytes(name: &str, data: Vec<u8>, index: u32) -> anyhow::Result<Self> {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+        let font = FontVec::try_from_vec_and_index(data, index)
+            .with_context(|| format!("parsing the font {}", name))?;
+        Ok(Self {
+            name: name.to_string(),
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            font: FontArc::new(font),
+        })
+    }
+
+    // Width of `text` on one line at `size`, with kerning. Unlike
+    // `TextMetrics::width` it counts trailing spaces, for placing carets.
+    pub fn advance(&self, size: f32, text: &str) -> f32 {
+        advance(&self.font.as_scaled(PxScale::from(size)), text)
+    }
+
+    // `advance` up to every char boundary of `text`, as (offset, width).
+    pub fn advances(&self, size: f32, text: &str) -> Vec<(usize, f32)> {
+        advances(&self.font.as_scaled(PxScale::from(size)), text)
+    }
+}
+
+impl Font for TextFont {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn has_glyph(&self, c: char) -> bool {
+        self.font.glyph_id(c).0 != 0
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+struct GlyphKey {
+    font: u64,
+    glyph: u16,
+    size: u32,
+}
+
+struct Glyph {
+    // White, with the coverage in alpha, or the glyph's own colors.
+    image: RgbaImage,
+    region: AtlasRegion,
+    // Of the image around the glyph's origin, in pixels, y down.
+    min: [f32; 2],
+    max: [f32; 2],
+    // Drawn as it is, like emoji, rather than in the text's color.
+    color: bool,
+}
+
+// Glyphs rasterized on first use and packed into one texture, which doubles
+// in size when they stop fitting. Nothing is ever evicted, so the atlas only
+// grows with the fonts, sizes and characters shown; `Text` keeps its
+// elements in step with it.
+pub struct GlyphAtlas {
+    pub texture: Texture,
+    pub width: u32,
+    pub height: u32,
+    max_size: u32,
+    // Holds distance fields rather than coverage, see `sdf`.
+    sdf: bool,
+    // None for glyphs that draw nothing, like spaces.
+    glyphs: HashMap<GlyphKey, Option<Glyph>>,
+    shelves: Vec<Shelf>,
+    generation: u64,
+    fill: Option<(u64, Rc<wgpu::BindGroup>)>,
+}
+
+impl GlyphAtlas {
+    pub fn new(device: &wgpu::Device, sampler: Rc<wgpu::Sampler>) -> Self {
+        let size = INITIAL_ATLAS_SIZE;
+        Self {
+            texture: create_texture(device, size, size, sampler),
+            width: size,
+            height: size,
+            max_size: device.limits().max_texture_dimension_2d,
+            sdf: false,
+            glyphs: HashMap::new(),
+            shelves: Vec::new(),
+            generation: 0,
+            fill: None,
+        }
+    }
+
+    // Keeps each glyph once, as a distance field at `SDF_SIZE`, which
+    // `UIMaterial::Sdf` turns back into sharp edges at any size and zoom,
+    // with an optional outline or glow, see `Text::set_halo`. Small text
+    // looks softer than from a bitmap atlas, and thin details round off.
+    // Needs a linear sampler.
+    pub fn sdf(device: &wgpu::Device, sampler: Rc<wgpu::Sampler>) -> Self {
+        let mut atlas = Self::new(device, sampler);
+        atlas.sdf = true;
+        atlas.max_size = atlas.max_size.min(SDF_MAX_ATLAS_SIZE);
+        atlas
+    }
+
+    pub fn is_sdf(&self) -> bool {
+        self.sdf
+    }
+
+    // Changes whenever the atlas grows, which moves every glyph.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    // Glyphs rasterized so far.
+    pub fn len(&self) -> usize {
+        self.glyphs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.glyphs.is_empty()
+    }
+
+    // The fill `scene` draws glyphs with, made again after the atlas grows.
+    fn fill(&mut self, scene: &UIScene, device: &wgpu::Device) -> Rc<wgpu::BindGroup> {
+        match &self.fill {
+            Some((generation, fill)) if *generation == self.generation => fill.clone(),
+            _ => {
+                let fill = scene.create_texture_fill(device, &self.texture);
+                self.fill = Some((self.generation, fill.clone()));
+                fill
+            }
+        }
+    }
+
+    // Rasterizes and uploads the glyph if it's new.
+    fn glyph(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        font: &TextFont,
+        id: GlyphId,
+        size: f32,
+    ) -> Option<&Glyph> {
+        let size = if self.sdf { SDF_SIZE } else { size };
+        let key = GlyphKey {
+            font: font.id,
+            glyph: id.0,
+            size: size.to_bits(),
+        };
+        if !self.glyphs.contains_key(&key) {
+            let glyph = self.rasterize(device, queue, font, id, size);
+            self.glyphs.insert(key, glyph);
+        }
+        self.glyphs[&key].as_ref()
+    }
+
+    fn rasterize(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        font: &TextFont,
+        id: GlyphId,
+        size: f32,
+    ) -> Option<Glyph> {
+        let (image, min, max, color) = match color_glyph(font, id, size) {
+            Some((image, min, max)) => (image, min, max, true),
+            None => {
+                let outline = font
+                    .font
+                    .outline_glyph(id.with_scale_and_position(PxScale::from(size), (0.0, 0.0)))?;
+                let mut bounds = outline.px_bounds();
+                let (width, height) = (bounds.width() as u32, bounds.height() as u32);
+                if width == 0 || height == 0 {
+                    return None;
+                }
+                let image = if self.sdf {
+                    let spread = SDF_SPREAD as f32;
+                    bounds.min.x -= spread;
+                    bounds.min.y -= spread;
+                    bounds.max.x += spread;
+                    bounds.max.y += spread;
+                    let (padded_width, padded_height) =
+                        (width + SDF_SPREAD * 2, height + SDF_SPREAD * 2);
+                    let mut coverage = vec![0.0; (padded_width * padded_height) as usize];
+                    outline.draw(|x, y, c| {
+                        coverage[((y + SDF_SPREAD) * padded_width + x + SDF_SPREAD) as usize] = c;
+                    });
+                    distance_field(&coverage, padded_width, padded_height)
+                } else {
+                    let mut image = RgbaImage::new(width, height);
+                    outline.draw(|x, y, coverage| {
+                        let alpha = (coverage.clamp(0.0, 1.0) * 255.0).round() as u8;
+                        image.put_pixel(x, y, Rgba([255, 255, 255, alpha]));
+                    });
+                    image
+                };
+                let (min, max) = ([bounds.min.x, bounds.min.y], [bounds.max.x, bounds.max.y]);
+                (image, min, max, false)
+            }
+        };
+        let (width, height) = image.dimensions();
+        let region = loop {
+            if let Some(region) =
+                allocate(&mut self.shelves, self.width, self.height, width, height)
+            {
+                break region;
+            }
+            if !self.grow(device, queue) {
+                log::warn!(
+                    "no room in the glyph atlas for a {}x{} glyph",
+                    width,
+                    height
+                );
+                return None;
+            }
+        };
+        upload(queue, &self.texture, &image, region);
+        Some(Glyph {
+            image,
+            region,
+            min,
+            max,
+            color,
+        })
+    }
+
+    // Doubles the texture, up to what the device allows, and packs every
+    // glyph into it again, tallest first.
+    fn grow(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) -> bool {
+        if self.width >= self.max_size && self.height >= self.max_size {
+            return false;
+        }
+        self.width = (self.width * 2).min(self.max_size);
+        self.height = (self.height * 2).min(self.max_size);
+        let sampler = self.texture.sampler.clone();
+        self.texture = create_texture(device, self.width, self.height, sampler);
+        self.shelves.clear();
+        self.generation += 1;
+        let mut glyphs = self.glyphs.values_mut().flatten().collect::<Vec<_>>();
+        glyphs.sort_by_key(|glyph| std::cmp::Reverse(glyph.image.height()));
+        for glyph in glyphs {
+            let (width, height) = glyph.image.dimensions();
+            // Packing in another order can waste more, in which case try
+            // the next size up.
+            let Some(region) = allocate(&mut self.shelves, self.width, self.height, width, height)
+            else {
+                return self.grow(device, queue);
+            };
+            glyph.region = region;
+            upload(queue, &self.texture, &glyph.image, region);
+        }
+        log::info!("glyph atlas grew to {}x{}", self.width, self.height);
+        true
+    }
+
+    // What `UIMaterial::Sdf` reads from `params.xy`.
+    fn packed_rect(region: &AtlasRegion) -> [f32; 2] {
+        let size = SDF_MAX_ATLAS_SIZE as f32;
+        [
+            region.x as f32 + (region.x + region.width) as f32 * size,
+            region.y as f32 + (region.y + region.height) as f32 * size,
+        ]
+    }
+
+    fn uv_rect(&self, region: &AtlasRegion) -> [f32; 4] {
+        let (w, h) = (self.width as f32, self.height as f32);
+        [
+            region.x as f32 / w,
+            region.y as f32 / h,
+            (region.x + region.width) as f32 / w,
+            (region.y + region.height) as f32 / h,
+        ]
+    }
+}
+
+// The glyph's color image at `size` pixels per em, with where it goes like
+// `Glyph::min` and `max`, for fonts with PNG strikes in CBDT or sbix tables
+// like Noto Color Emoji or Apple Color Emoji. Strikes come in a few sizes,
+// so the closest one is scaled. Even in an SDF atlas these stay bitmaps,
+// which blur when zoomed into, as colors don't fit a distance field.
+fn color_glyph(font: &TextFont, id: GlyphId, size: f32) -> Option<(RgbaImage, [f32; 2], [f32; 2])> {
+    let raster = font.font.glyph_raster_image(id, size.ceil() as u16)?;
+    if !matches!(raster.format, GlyphImageFormat::Png) || raster.scale <= 0.0 {
+        return None;
+    }
+    let image = match image::load_from_memory(raster.data) {
+        Ok(image) => image.to_rgba8(),
+        Err(err) => {
+            log::warn!("decoding a color glyph of {}: {}", font.name, err);
+            return None;
+        }
+    };
+    let scale = size / raster.scale;
+    let (width, height) = image.dimensions();
+    let (scaled_width, scaled_height) = (
+        (width as f32 * scale).round() as u32,
+        (height as f32 * scale).round() as u32,
+    );
+    if scaled_width == 0 || scaled_height == 0 {
+        return None;
+    }
+    let image = image::imageops::resize(&image, scaled_width, scaled_height, FilterType::Triangle);
+    // The origin is the image's bottom left, y up.
+    let min = [
+        raster.origin.x * scale,
+        -(raster.origin.y * scale + scaled_height as f32),
+    ];
+    let max = [min[0] + scaled_width as f32, min[1] + scaled_height as f32];
+    Some((image, min, max))
+}
+
+// White texels whose alpha is the signed distance to the edge of the glyph
+// in `coverage`, 0.5 on the edge and 0 and 1 `SDF_SPREAD` texels out and in.
+// Searches every texel's neighbourhood, which is slow but only runs once
+// per glyph.
+fn distance_field(coverage: &[f32], width: u32, height: u32) -> RgbaImage {
+    let spread = SDF_SPREAD as i32;
+    let inside = |x: i32, y: i32| {
+        x >= 0
+            && y >= 0
+            && x < width as i32
+            && y < height as i32
+            && coverage[(y as u32 * width + x as u32) as usize] >= 0.5
+    };
+    RgbaImage::from_fn(width, height, |x, y| {
+        let c = coverage[(y * width + x) as usize];
+        let (x, y) = (x as i32, y as i32);
+        let distance = if c > 0.0 && c < 1.0 {
+            // On the edge, where coverage is as good as distance.
+            c - 0.5
+        } else {
+            let this = inside(x, y);
+            let mut nearest = (spread * spread) as f32;
+            for dy in -spread..=spread {
+                for dx in -spread..=spread {
+                    let d = (dx * dx + dy * dy) as f32;
+                    if d < nearest && inside(x + dx, y + dy) != this {
+                        nearest = d;
+                    }
+                }
+            }
+            let distance = nearest.sqrt() - 0.5;
+            if this {
+                distance
+            } else {
+                -distance
+            }
+        };
+        let alpha = (0.5 + distance / (2.0 * SDF_SPREAD as f32)).clamp(0.0, 1.0);
+        Rgba([255, 255, 255, (alpha * 255.0).round() as u8])
+    })
+}
+
+fn create_texture(
+    device: &wgpu::Device,
+    width: u32,
+    height: u32,
+    sampler: Rc<wgpu::Sampler>,
+) -> Texture {
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Glyph atlas"),
+        size: wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: wgpu::TextureFormat::Rgba8UnormSrgb,
+        usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+        view_formats: &[],
+    });
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+    Texture {
+        texture,
+        view,
+        sampler,
+    }
+}
+
+// Drawn around the glyphs of an SDF atlas by the shader, see
+// `Text::set_halo`. `width` and `softness` together reach at most
+// `SDF_SPREAD / SDF_SIZE` of the text's size, a sixth of it.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Halo {
+    // Past the glyph's edge, in world units.
+    pub width: f32,
+    // How far past `width` it fades out, 0 for a crisp edge.
+    pub softness: f32,
+    pub color: [f32; 3],
+}
+
+impl Halo {
+    pub fn outline(width: f32, color: [f32; 3]) -> Self {
+        Self {
+            width,
+            softness: 0.0,
+            color,
+        }
+    }
+
+    pub fn glow(radius: f32, color: [f32; 3]) -> Self {
+        Self {
+            width: 0.0,
+            softness: radius,
+            color,
+        }
+    }
+}
+
+// A glyph of the laid out text, and where its center sits relative to the
+// text's position.
+struct Placed {
+    key: GlyphKey,
+    offset: cgmath::Vector2<f32>,
+    // Of its span, in world units.
+    size: f32,
+    // Its span's, which the text's color doesn't change.
+    color: Option<[f32; 4]>,
+    // Has colors of its own, see `Glyph::color`, and only takes the alpha.
+    color_glyph: bool,
+}
+
+// Lines of text drawn with one font, one sprite element per glyph:
+//
+//     let font = Rc::new(TextFont::from_bytes("Inter", data, 0)?);
+//     let mut glyphs = GlyphAtlas::new(device, sampler);
+//     let mut title = Text::new(font, 24.0);
+//     title.set_position(&mut scene, cgmath::Vector2::new(-200.0, 150.0));
+//     title.set_text(&mut scene, device, queue, &mut glyphs, "Hello, world");
+//     title.update(&mut scene, device, &mut glyphs); // every frame
+//
+// `position` is the top left of the layout box, see `TextLayout`, and `size`
+// the font's height in world units. Glyphs from a bitmap atlas are rasterized at
+// that size, so zooming in past 1:1 blurs them; those from a
+// `GlyphAtlas::sdf` stay sharp.
+//
+// Spans of `RichText` may have colors, sizes and faces of their own, see
+// `set_rich_text`. Bold and italic spans take the faces below, or `font`
+// where there's none. Clusters none of those has glyphs for come from the
+// first of `fallbacks` that has, like a CJK font and then an emoji font, so
+// whatever users type doesn't turn into tofu. Set the faces before the
+// text. Color glyphs, like those of an emoji font, keep their own colors
+// whatever the text's or the span's.
+pub struct Text {
+    pub font: Rc<TextFont>,
+    pub bold: Option<Rc<TextFont>>,
+    pub italic: Option<Rc<TextFont>>,
+    pub bold_italic: Option<Rc<TextFont>>,
+    pub fallbacks: Vec<Rc<TextFont>>,
+    size: f32,
+    position: cgmath::Vector2<f32>,
+    // Of the laid out text, see `set_scale`.
+    scale: f32,
+    color: [f32; 4],
+    halo: Option<Halo>,
+    visible: bool,
+    text: RichText,
+    layout: TextLayout,
+    metrics: TextMetrics,
+    placed: Vec<Placed>,
+    // Pooled like `SegmentLabel`'s, the ones past `placed` are hidden.
+    elements: Vec<usize>,
+    // Of the atlas the elements were filled from.
+    generation: Option<u64>,
+}
+
+impl Text {
+    pub fn new(font: Rc<TextFont>, size: f32) -> Self {
+        Self {
+            font,
+            bold: None,
+            italic: None,
+            bold_italic: None,
+            fallbacks: Vec::new(),
+            size,
+            position: cgmath::Vector2::new(0.0, 0.0),
+            scale: 1.0,
+            color: [1.0; 4],
+            halo: None,
+            visible: true,
+            text: RichText::new(),
+            layout: TextLayout::default(),
+            metrics: TextMetrics::default(),
+            placed: Vec::new(),
+            elements: Vec::new(),
+            generation: None,
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        self.text.text()
+    }
+
+    pub fn rich_text(&self) -> &RichText {
+        &self.text
+    }
+
+    // Of the widest line, in world units.
+    pub fn width(&self) -> f32 {
+        self.metrics.width
+    }
+
+    pub fn height(&self) -> f32 {
+        self.metrics.height
+    }
+
+    // Of the text as laid out, with where each line went.
+    pub fn metrics(&self) -> &TextMetrics {
+        &self.metrics
+    }
+
+    pub fn layout(&self) -> &TextLayout {
+        &self.layout
+    }
+
+    // One per glyph, some hidden. Widgets telling clicks on their text apart
+    // from others need them.
+    pub fn elements(&self) -> &[usize] {
+        &self.elements
+    }
+
+    pub fn size(&self) -> f32 {
+        self.size
+    }
+
+    // Lays the text out, with kerning, see `set_layout`. New glyphs are
+    // rasterized into `atlas`, and elements added to `scene` as needed.
+    pub fn set_text(
+        &mut self,
+        scene: &mut UIScene,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        atlas: &mut GlyphAtlas,
+        text: &str,
+    ) {
+        self.set_rich_text(scene, device, queue, atlas, RichText::from(text));
+    }
+
+    // Like `set_text`, with styled spans.
+    pub fn set_rich_text(
+        &mut self,
+        scene: &mut UIScene,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        atlas: &mut GlyphAtlas,
+        text: RichText,
+    ) {
+        self.text = text;
+        self.relayout(scene, device, queue, atlas);
+    }
+
+    // Wraps, aligns and cuts the text to `layout`'s box.
+    pub fn set_layout(
+        &mut self,
+        scene: &mut UIScene,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        atlas: &mut GlyphAtlas,
+        layout: TextLayout,
+    ) {
+        self.layout = layout;
+        self.relayout(scene, device, queue, atlas);
+    }
+
+    // Lays the text out again at another size. Spans with a size of their
+    // own keep it.
+    pub fn set_size(
+        &mut self,
+        scene: &mut UIScene,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        atlas: &mut GlyphAtlas,
+        size: f32,
+    ) {
+        self.size = size;
+        self.relayout(scene, device, queue, atlas);
+    }
+
+    pub fn set_position(&mut self, scene: &mut UIScene, position: cgmath::Vector2<f32>) {
+        self.position = position;
+        for (placed, &element) in self.placed.iter().zip(&self.elements) {
+            let at = position + placed.offset * self.scale;
+            let instance = &mut scene.elements[element].instance;
+            instance.position.x = at.x;
+            instance.position.y = at.y;
+            instance.scale = self.scale;
+        }
+    }
+
+    // Draws the text as laid out, scaled around its position, e.g. by
+    // 1 / zoom for labels that keep their size on screen. Unlike `set_size`
+    // it doesn't lay the text out again.
+    pub fn set_scale(&mut self, scene: &mut UIScene, scale: f32) {
+        self.scale = scale;
+        self.set_position(scene, self.position);
+    }
+
+    // Linear, with straight alpha.
+    pub fn set_color(&mut self, scene: &mut UIScene, color: [f32; 4]) {
+        self.color = color;
+        for (placed, &element) in self.placed.iter().zip(&self.elements) {
+            let tint = placed.color.unwrap_or(color);
+            scene.elements[element].instance.tint = if placed.color_glyph {
+                [1.0, 1.0, 1.0, tint[3]]
+            } else {
+                tint
+            };
+        }
+    }
+
+    // Outlines or glows the glyphs. Only drawn from an SDF atlas.
+    pub fn set_halo(&mut self, scene: &mut UIScene, queue: &wgpu::Queue, halo: Option<Halo>) {
+        self.halo = halo;
+        let color = halo.map_or([1.0; 3], |halo| halo.color);
+        for (placed, &element) in self.placed.iter().zip(&self.elements) {
+            let [width, softness] = self.halo_params(placed.size);
+            let element = &mut scene.elements[element];
+            // Bitmap glyphs would take the vertex color as a tint.
+            if element.material == UIMaterial::Sdf {
+                element.reshape(queue, element.shape, color, color);
+                element.instance.params[2] = width;
+                element.instance.params[3] = softness;
+            }
+        }
+    }
+
+    // The halo around glyphs of `size` in distance field units, see
+    // `fs_sdf`.
+    fn halo_params(&self, size: f32) -> [f32; 2] {
+        let Some(halo) = self.halo else {
+            return [0.0; 2];
+        };
+        let scale = SDF_SIZE / size / (2.0 * SDF_SPREAD as f32);
+        [halo.width * scale, halo.softness * scale]
+    }
+
+    pub fn set_visible(&mut self, scene: &mut UIScene, visible: bool) {
+        self.visible = visible;
+        for (i, &element) in self.elements.iter().enumerate() {
+            scene.elements[element].visible = visible && i < self.placed.len();
+        }
+    }
+
+    // Elements like the ones texts make for their glyphs, for `reserve`.
+    pub fn glyph_pool() -> ElementPool {
+        ElementPool::new(|device| {
+            let white = [1.0, 1.0, 1.0];
+            let size = cgmath::Vector2::new(1.0, 1.0);
+            let mut element =
+                Player::with_gradient(device, cgmath::Vector2::new(0.0, 0.0), size, white, white);
+            element.draggable = false;
+            element
+        })
+    }
+
+    // Takes elements for `count` more glyphs from `pool` rather than adding
+    // them to the scene, e.g. for labels that come and go all the time. Give
+    // them back with `release`.
+    pub fn reserve(
+        &mut self,
+        scene: &mut UIScene,
+        device: &wgpu::Device,
+        pool: &mut ElementPool,
+        count: usize,
+    ) {
+        for _ in 0..count {
+            let element = pool.acquire(scene, device, cgmath::Vector2::new(0.0, 0.0));
+            scene.elements[element].visible = false;
+            self.elements.push(element);
+        }
+        self.generation = None;
+    }
+
+    // Gives every glyph element to `pool` and empties the text.
+    pub fn release(&mut self, scene: &mut UIScene, pool: &mut ElementPool) {
+        for element in self.elements.drain(..) {
+            pool.release(scene, element);
+        }
+        self.text = RichText::new();
+        self.metrics = TextMetrics::default();
+        self.placed.clear();
+        self.generation = None;
+    }
+
+    // Fills the elements again if the atlas grew since they were filled,
+    // which other texts sharing it can make happen at any time. Cheap when
+    // it didn't, so call it every frame.
+    pub fn update(&mut self, scene: &mut UIScene, device: &wgpu::Device, atlas: &mut GlyphAtlas) {
+        if self.generation == Some(atlas.generation()) {
+            return;
+        }
+        let fill = atlas.fill(scene, device);
+        for (placed, &element) in self.placed.iter().zip(&self.elements) {
+            let Some(Some(glyph)) = atlas.glyphs.get(&placed.key) else {
+                continue;
+            };
+            if atlas.sdf && !glyph.color {
+                let [width, softness] = self.halo_params(placed.size);
+                let [x, y] = GlyphAtlas::packed_rect(&glyph.region);
+                scene.set_shared_fill(element, &fill, [x, y, width, softness]);
+                scene.elements[element].material = UIMaterial::Sdf;
+            } else {
+                scene.set_shared_fill(element, &fill, atlas.uv_rect(&glyph.region));
+            }
+        }
+        self.generation = Some(atlas.generation());
+    }
+
+    // The face for bold or italic spans, falling back to the closest one
+    // there is.
+    fn face(&self, bold: bool, italic: bool) -> Rc<TextFont> {
+        let faces = match (bold, italic) {
+            (true, true) => [&self.bold_italic, &self.bold, &self.italic],
+            (true, false) => [&self.bold, &None, &None],
+            (false, true) => [&self.italic, &None, &None],
+            (false, false) => [&None, &None, &None],
+        };
+        faces
+            .into_iter()
+            .find_map(|face| face.clone())
+            .unwrap_or_else(|| self.font.clone())
+    }
+
+    fn relayout(
+        &mut self,
+        scene: &mut UIScene,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        atlas: &mut GlyphAtlas,
+    ) {
+        // A face per font and size the spans use, the text's own first.
+        // Spans split into runs where clusters fall back to other fonts.
+        let mut faces = vec![(self.font.clone(), self.size)];
+        let mut runs = Vec::new();
+        let mut run_spans = Vec::new();
+        for (i, span) in self.text.spans().iter().enumerate() {
+            let primary = self.face(span.style.bold, span.style.italic);
+            let size = span.style.size.unwrap_or(self.size);
+            let chain = self
+                .fallbacks
+                .iter()
+                .fold(FontChain::new(primary.clone()), |chain, font| {
+                    chain.fallback(font.clone())
+                });
+            for run in chain.resolve(&self.text.text()[span.range.clone()]) {
+                let font = match run.font {
+                    0 => &primary,
+                    n => &self.fallbacks[n - 1],
+                };
+                let index = match faces
+                    .iter()
+                    .position(|(face, face_size)| Rc::ptr_eq(face, font) && *face_size == size)
+                {
+                    Some(index) => index,
+                    None => {
+                        faces.push((font.clone(), size));
+                        faces.len() - 1
+                    }
+                };
+                let start = span.range.start;
+                runs.push((start + run.range.start..start + run.range.end, index));
+                run_spans.push(i);
+            }
+        }
+        let metrics = self.layout.measure_runs(
+            self.text.text(),
+            &faces
+                .iter()
+                .map(|(font, size)| (font.as_ref(), *size))
+                .collect::<Vec<_>>(),
+            &runs,
+        );
+
+        let mut placed = Vec::new();
+        let mut sizes = Vec::new();
+        for laid in &metrics.glyphs {
+            let id = laid.id;
+            let (font, size) = &faces[runs.get(laid.run).map_or(0, |run| run.1)];
+            let color = run_spans
+                .get(laid.run)
+                .and_then(|&span| self.text.spans()[span].style.color);
+            let raster_size = if atlas.sdf { SDF_SIZE } else { *size };
+            // Of the rasterized glyphs to the text.
+            let scale = size / raster_size;
+            // Whole units keep bitmap glyphs as sharp as they were
+            // rasterized. Layouts run y down, the scene y up.
+            let origin = cgmath::Vector2::new(laid.x.round(), -laid.baseline.round());
+            if let Some(glyph) = atlas.glyph(device, queue, font, id, *size) {
+                let center = [
+                    (glyph.min[0] + glyph.max[0]) / 2.0 * scale,
+                    (glyph.min[1] + glyph.max[1]) / 2.0 * scale,
+                ];
+                placed.push(Placed {
+                    key: GlyphKey {
+                        font: font.id,
+                        glyph: id.0,
+                        size: raster_size.to_bits(),
+                    },
+                    offset: origin + cgmath::Vector2::new(center[0], -center[1]),
+                    size: *size,
+                    color,
+                    color_glyph: glyph.color,
+                });
+                sizes.push((
+                    glyph.region.width as f32 * scale,
+                    glyph.region.height as f32 * scale,
+                ));
+            }
+        }
+        self.metrics = metrics;
+        self.placed = placed;
+
+        // The vertex color is the halo's, which color glyphs don't take.
+        let color = match self.halo {
+            Some(halo) if atlas.sdf => halo.color,
+            _ => [1.0; 3],
+        };
+        while self.elements.len() < self.placed.len() {
+            let mut element = Player::with_gradient(
+                device,
+                cgmath::Vector2::new(0.0, 0.0),
+                cgmath::Vector2::new(1.0, 1.0),
+                color,
+                color,
+            );
+            element.draggable = false;
+            scene.elements.push(element);
+            self.elements.push(scene.elements.len() - 1);
+        }
+        for ((&element, &(width, height)), placed) in
+            self.elements.iter().zip(&sizes).zip(&self.placed)
+        {
+            let shape = Shape::Rect { width, height };
+            // With a halo, pooled elements may have had the other color.
+            if scene.elements[element].shape != shape || color != [1.0; 3] {
+                let color = if placed.color_glyph { [1.0; 3] } else { color };
+                scene.elements[element].reshape(queue, shape, color, color);
+            }
+        }
+        // Glyphs may have moved while rasterizing new ones.
+        self.generation = None;
+        self.update(scene, device, atlas);
+        self.set_position(scene, self.position);
+        self.set_color(scene, self.color);
+        self.set_visible(scene, self.visible);
+    }
+}
@@ -0,0 +1,161 @@
+use std::ops::Range;
+
+use crate::font_chain::clusters;
+use crate::text::TextFont;
+use crate::text_edit::TextEdit;
+use crate::ui_scene::{Player, Shape, UIScene};
+
+const CARET_WIDTH: f32 = 1.5;
+
+// The caret and selection highlight of a one line text field, drawn over
+// text the field shows itself. The text is measured once whenever it
+// changes, so placing the caret and finding where a click landed don't
+// measure it again:
+//
+//     let mut caret = TextCaret::new(&mut scene, device, field, font_size, color, selection);
+//     let text = edit.display_text();
+//     caret.measure(&font, font_size, &text, 0..text.len());
+//     caret.place(&mut scene, queue, &edit, left, editing); // `left` of the text
+//     edit.select(anchor, caret.offset_at(cursor.x - left));
+pub struct TextCaret {
+    field: usize,
+    selection: usize,
+    caret: usize,
+    color: [f32; 3],
+    height: f32,
+    // Cluster boundaries of the measured text and their x from its start.
+    stops: Vec<(usize, f32)>,
+}
+
+impl TextCaret {
+    // Over `field`, hidden until placed while editing.
+    pub fn new(
+        scene: &mut UIScene,
+        device: &wgpu::Device,
+        field: usize,
+        font_size: f32,
+        text: [f32; 4],
+        selection: [f32; 3],
+    ) -> Self {
+        let position = scene.elements[field].instance.position;
+        let position = cgmath::Vector2::new(position.x, position.y);
+        let height = font_size * 1.2;
+        let mut push = |width: f32, color: [f32; 3]| {
+            let size = cgmath::Vector2::new(width, height);
+            let mut element = Player::with_gradient(device, position, size, color, color);
+            element.draggable = false;
+            element.visible = false;
+            scene.elements.push(element);
+            scene.elements.len() - 1
+        };
+        let selection_element = push(1.0, selection);
+        let caret = push(CARET_WIDTH, [text[0], text[1], text[2]]);
+        Self {
+            field,
+            selection: selection_element,
+            caret,
+            color: selection,
+            height,
+            stops: vec![(0, 0.0)],
+        }
+    }
+
+    // The highlight, then the caret.
+    pub fn elements(&self) -> [usize; 2] {
+        [self.selection, self.caret]
+    }
+
+    pub fn contains(&self, element: usize) -> bool {
+        element == self.selection || element == self.caret
+    }
+
+    // Measures the part of `text` in `range`, e.g. a laid out line, as
+    // `font` draws it at `size`. Offsets outside it are clamped to it.
+    pub fn measure(&mut self, font: &TextFont, size: f32, text: &str, range: Range<usize>) {
+        let ends = clusters(&text[range.clone()])
+            .into_iter()
+            .map(|cluster| cluster.end)
+            .collect::<Vec<_>>();
+        self.stops = font
+            .advances(size, &text[range.clone()])
+            .into_iter()
+            .filter(|&(offset, _)| offset == 0 || ends.binary_search(&offset).is_ok())
+            .map(|(offset, x)| (range.start + offset, x))
+            .collect();
+    }
+
+    // From the start of the measured text.
+    pub fn x_of(&self, offset: usize) -> f32 {
+        match self.stops.binary_search_by_key(&offset, |&(stop, _)| stop) {
+            Ok(i) => self.stops[i].1,
+            Err(0) => 0.0,
+            Err(i) => self.stops[i - 1].1,
+        }
+    }
+
+    // The cluster boundary closest to `x`, from the start of the measured
+    // text.
+    pub fn offset_at(&self, x: f32) -> usize {
+        let i = self.stops.partition_point(|&(_, stop)| stop < x);
+        let before = i.checked_sub(1).map(|i| self.stops[i]);
+        match (before, self.stops.get(i)) {
+            (Some(before), Some(&after)) if x - before.1 > after.1 - x => after.0,
+            (Some(before), _) => before.0,
+            (None, Some(after)) => after.0,
+            (None, None) => 0,
+        }
+    }
+
+    // Puts the highlight under `edit`'s selection and the caret at its
+    // caret, in `edit.display_text()` as measured and starting at `left`,
+    // and tells the scene where the field's caret is for input methods.
+    // Both only show while `editing`.
+    pub fn place(
+        &self,
+        scene: &mut UIScene,
+        queue: &wgpu::Queue,
+        edit: &TextEdit,
+        left: f32,
+        editing: bool,
+    ) {
+        let field = scene.elements[self.field].instance.position;
+        let selection = edit.display_selection();
+        let (start, end) = (
+            left + self.x_of(selection.start),
+            left + self.x_of(selection.end),
+        );
+        let caret_x = left + self.x_of(edit.display_caret());
+
+        let element = &mut scene.elements[self.selection];
+        let shape = Shape::Rect {
+            width: (end - start).max(1.0),
+            height: self.height,
+        };
+        if element.shape != shape {
+            element.reshape(queue, shape, self.color, self.color);
+        }
+        element.instance.position.x = (start + end) / 2.0;
+        element.instance.position.y = field.y;
+        element.visible = editing && !selection.is_empty();
+
+        let element = &mut scene.elements[self.caret];
+        element.instance.position.x = caret_x;
+        element.instance.position.y = field.y;
+        element.visible = editing;
+
+        if editing {
+            scene.set_caret(
+                self.field,
+                cgmath::Vector2::new(caret_x - field.x, -self.height / 2.0),
+                cgmath::Vector2::new(caret_x - field.x + CARET_WIDTH, self.height / 2.0),
+            );
+        } else {
+            scene.clear_caret(self.field);
+        }
+    }
+
+    // Shows or hides the caret without moving it, e.g. to blink it.
+    pub fn set_caret_visible(&self, scene: &mut UIScene, visible: bool) {
+        scene.elements[self.caret].visible = visible;
+    }
+}
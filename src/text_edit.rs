@@ -0,0 +1,423 @@
+use std::borrow::Cow;
+use std::ops::Range;
+
+use winit::event::{ElementState, Ime, KeyboardInput, ModifiersState, VirtualKeyCode, WindowEvent};
+
+use crate::font_chain::clusters;
+use crate::ui_scene::UIScene;
+
+// How far a key moves the caret.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Motion {
+    Cluster,
+    Word,
+    Line,
+    Text,
+}
+
+// A key held down, repeated by `TextEdit::update`.
+struct Held {
+    key: VirtualKeyCode,
+    // Seconds since it went down.
+    elapsed: f32,
+    repeats: u32,
+}
+
+// The editing side of a text input: the text, the caret and the selection,
+// driven by window events. Drawing is up to the widget owning it:
+//
+//     let mut edit = TextEdit::new("name");
+//     if edit.input(&scene, &event) {
+//         text.set_text(&mut scene, device, queue, &mut atlas, edit.text());
+//     }
+//     if edit.update(&scene, dt) { // once per frame, for held keys
+//         text.set_text(&mut scene, device, queue, &mut atlas, edit.text());
+//     }
+//
+// Held keys repeat on `InputConfig`'s timing rather than the system's, whose
+// repeats come at different rates per platform or not at all. Typed
+// characters still repeat the system's way, through `ReceivedCharacter`.
+// Word and line moves take the platform's modifiers: Ctrl and Home/End, or
+// Option and Cmd on macOS. Shift extends the selection with any of them.
+// Text an input method is composing shows in place of the selection until
+// it's committed, so widgets draw `display_text` rather than `text`.
+pub struct TextEdit {
+    text: String,
+    // Byte offsets, on cluster boundaries. The selection runs between them.
+    caret: usize,
+    anchor: usize,
+    // Enter inserts a line break rather than being left to the app.
+    pub multiline: bool,
+    held: Option<Held>,
+    // What the input method is composing, and its cursor in it.
+    preedit: String,
+    preedit_cursor: Option<usize>,
+}
+
+impl TextEdit {
+    // With the caret at the end.
+    pub fn new(text: &str) -> Self {
+        Self {
+            text: text.to_string(),
+            caret: text.len(),
+            anchor: text.len(),
+            multiline: false,
+            held: None,
+            preedit: String::new(),
+            preedit_cursor: None,
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    // Replaces the text, moving the caret to the end. What's being composed
+    // is dropped.
+    pub fn set_text(&mut self, text: &str) {
+        self.preedit.clear();
+        self.text = text.to_string();
+        self.caret = text.len();
+        self.anchor = text.len();
+    }
+
+    pub fn caret(&self) -> usize {
+        self.caret
+    }
+
+    // Empty, at the caret, when nothing is selected.
+    pub fn selection(&self) -> Range<usize> {
+        self.caret.min(self.anchor)..self.caret.max(self.anchor)
+    }
+
+    pub fn selected_text(&self) -> &str {
+        &self.text[self.selection()]
+    }
+
+    pub fn is_composing(&self) -> bool {
+        !self.preedit.is_empty()
+    }
+
+    // The text with what's being composed in place of the selection.
+    pub fn display_text(&self) -> Cow<'_, str> {
+        if !self.is_composing() {
+            return Cow::Borrowed(&self.text);
+        }
+        let mut text = self.text.clone();
+        text.replace_range(self.selection(), &self.preedit);
+        Cow::Owned(text)
+    }
+
+    // `selection` in `display_text`, which covers what's being composed.
+    pub fn display_selection(&self) -> Range<usize> {
+        let selection = self.selection();
+        if self.is_composing() {
+            selection.start..selection.start + self.preedit.len()
+        } else {
+            selection
+        }
+    }
+
+    // `caret` in `display_text`, where the input method has it while
+    // composing.
+    pub fn display_caret(&self) -> usize {
+        if self.is_composing() {
+            let start = self.selection().start;
+            start + self.preedit_cursor.unwrap_or(self.preedit.len())
+        } else {
+            self.caret
+        }
+    }
+
+    // Selects from `anchor` to `caret`, snapped to clusters.
+    pub fn select(&mut self, anchor: usize, caret: usize) {
+        self.anchor = self.snap(anchor);
+        self.caret = self.snap(caret);
+    }
+
+    pub fn select_all(&mut self) {
+        self.select(0, self.text.len());
+    }
+
+    // Replaces the selection with `text`, leaving the caret after it.
+    pub fn insert(&mut self, text: &str) {
+        let selection = self.selection();
+        self.text.replace_range(selection.clone(), text);
+        self.caret = selection.start + text.len();
+        self.anchor = self.caret;
+    }
+
+    // Takes keys while focused. Returns true if the event was used.
+    pub fn input(&mut self, scene: &UIScene, event: &WindowEvent) -> bool {
+        let modifiers = scene.shortcuts.modifiers();
+        match event {
+            WindowEvent::Ime(Ime::Preedit(text, cursor)) => {
+                self.preedit = text.clone();
+                self.preedit_cursor = cursor.map(|(_, end)| end.min(text.len()));
+                true
+            }
+            WindowEvent::Ime(Ime::Commit(text)) => {
+                self.preedit.clear();
+                self.insert(text);
+                true
+            }
+            WindowEvent::Ime(Ime::Disabled) => {
+                self.preedit.clear();
+                false
+            }
+            // Keys go to the input method while it's composing.
+            WindowEvent::KeyboardInput { .. } if self.is_composing() => true,
+            WindowEvent::ReceivedCharacter(c) => {
+                // Shortcuts and keys with their own handling also send
+                // characters, like '\u{8}' for backspace. AltGr comes as
+                // Ctrl+Alt on Windows, and types characters like '@' on
+                // many layouts.
+                let ctrl = modifiers.ctrl() && !modifiers.alt();
+                if ctrl || (cfg!(target_os = "macos") && modifiers.logo()) {
+                    return false;
+                }
+                match c {
+                    '\r' | '\n' if self.multiline => self.insert("\n"),
+                    c if c.is_control() => return false,
+                    c => self.insert(c.encode_utf8(&mut [0; 4])),
+                }
+                true
+            }
+            WindowEvent::KeyboardInput {
+                input:
+                    KeyboardInput {
+                        state,
+                        virtual_keycode: Some(key),
+                        ..
+                    },
+                ..
+            } => match state {
+                // The system's own repeats of a held key.
+                ElementState::Pressed if self.held.as_ref().is_some_and(|h| h.key == *key) => true,
+                ElementState::Pressed => {
+                    let used = self.key(*key, modifiers);
+                    if used && repeats(*key) {
+                        self.held = Some(Held {
+                            key: *key,
+                            elapsed: 0.0,
+                            repeats: 0,
+                        });
+                    }
+                    used
+                }
+                ElementState::Released => {
+                    if self.held.as_ref().is_some_and(|h| h.key == *key) {
+                        self.held = None;
+                    }
+                    false
+                }
+            },
+            // Releases go elsewhere once the window loses focus.
+            WindowEvent::Focused(false) => {
+                self.held = None;
+                false
+            }
+            _ => false,
+        }
+    }
+
+    // Repeats the held key, with the modifiers held now. Returns true if it
+    // did.
+    pub fn update(&mut self, scene: &UIScene, dt: f32) -> bool {
+        let Some(held) = &mut self.held else {
+            return false;
+        };
+        let config = &scene.input_config;
+        held.elapsed += dt;
+        let mut due = 0;
+        while held.elapsed
+            >= config.key_repeat_delay + held.repeats as f32 * config.key_repeat_interval.max(0.001)
+        {
+            held.repeats += 1;
+            due += 1;
+        }
+        let key = held.key;
+        let modifiers = scene.shortcuts.modifiers();
+        for _ in 0..due {
+            self.key(key, modifiers);
+        }
+        due > 0
+    }
+
+    fn key(&mut self, key: VirtualKeyCode, modifiers: ModifiersState) -> bool {
+        let extend = modifiers.shift();
+        let word = if cfg!(target_os = "macos") {
+            modifiers.alt()
+        } else {
+            modifiers.ctrl()
+        };
+        let line = cfg!(target_os = "macos") && modifiers.logo();
+        match key {
+            VirtualKeyCode::Left | VirtualKeyCode::Right => {
+                let forward = key == VirtualKeyCode::Right;
+                let motion = if line {
+                    Motion::Line
+                } else if word {
+                    Motion::Word
+                } else {
+                    Motion::Cluster
+                };
+                // Without Shift, a plain arrow drops the selection on the
+                // side it points to.
+                let selection = self.selection();
+                if motion == Motion::Cluster && !extend && !selection.is_empty() {
+                    self.caret = if forward {
+                        selection.end
+                    } else {
+                        selection.start
+                    };
+                    self.anchor = self.caret;
+                } else {
+                    let caret = self.target(motion, forward);
+                    self.move_to(caret, extend);
+                }
+            }
+            VirtualKeyCode::Up | VirtualKeyCode::Down => {
+                let forward = key == VirtualKeyCode::Down;
+                let caret = if line {
+                    self.target(Motion::Text, forward)
+                } else if self.multiline {
+                    self.vertical(forward)
+                } else {
+                    return false;
+                };
+                self.move_to(caret, extend);
+            }
+            VirtualKeyCode::Home | VirtualKeyCode::End => {
+                let motion = if modifiers.ctrl() || line {
+                    Motion::Text
+                } else {
+                    Motion::Line
+                };
+                let caret = self.target(motion, key == VirtualKeyCode::End);
+                self.move_to(caret, extend);
+            }
+            VirtualKeyCode::Back | VirtualKeyCode::Delete => {
+                let forward = key == VirtualKeyCode::Delete;
+                if self.selection().is_empty() {
+                    let motion = if line {
+                        Motion::Line
+                    } else if word {
+                        Motion::Word
+                    } else {
+                        Motion::Cluster
+                    };
+                    self.anchor = self.target(motion, forward);
+                }
+                self.insert("");
+            }
+            VirtualKeyCode::A if primary(modifiers) => self.select_all(),
+            _ => return false,
+        }
+        true
+    }
+
+    fn move_to(&mut self, caret: usize, extend: bool) {
+        self.caret = caret;
+        if !extend {
+            self.anchor = caret;
+        }
+    }
+
+    // Where `motion` takes the caret, forward or back.
+    fn target(&self, motion: Motion, forward: bool) -> usize {
+        let caret = self.caret;
+        match (motion, forward) {
+            (Motion::Cluster, true) => clusters(&self.text[caret..])
+                .first()
+                .map_or(caret, |cluster| caret + cluster.end),
+            (Motion::Cluster, false) => clusters(&self.text[..caret])
+                .last()
+                .map_or(caret, |cluster| cluster.start),
+            // To the end of this or the next word.
+            (Motion::Word, true) => {
+                let rest = &self.text[caret..];
+                let start = rest.find(is_word).unwrap_or(rest.len());
+                let end = rest[start..]
+                    .find(|c| !is_word(c))
+                    .map_or(rest.len(), |end| start + end);
+                self.snap(caret + end)
+            }
+            // To the start of this or the previous word.
+            (Motion::Word, false) => {
+                let mut chars = self.text[..caret].char_indices().rev().peekable();
+                while chars.next_if(|&(_, c)| !is_word(c)).is_some() {}
+                let mut start = 0;
+                while let Some((i, _)) = chars.next_if(|&(_, c)| is_word(c)) {
+                    start = i;
+                }
+                self.snap(start)
+            }
+            (Motion::Line, true) => self.text[caret..]
+                .find('\n')
+                .map_or(self.text.len(), |end| caret + end),
+            (Motion::Line, false) => self.text[..caret].rfind('\n').map_or(0, |i| i + 1),
+            (Motion::Text, true) => self.text.len(),
+            (Motion::Text, false) => 0,
+        }
+    }
+
+    // The same column, in clusters, on the next or previous line, or the
+    // text's end past the last line.
+    fn vertical(&self, down: bool) -> usize {
+        let line_start = self.text[..self.caret].rfind('\n').map_or(0, |i| i + 1);
+        let column = clusters(&self.text[line_start..self.caret]).len();
+        let target_start = if down {
+            match self.text[self.caret..].find('\n') {
+                Some(end) => self.caret + end + 1,
+                None => return self.text.len(),
+            }
+        } else {
+            match line_start.checked_sub(1) {
+                Some(end) => self.text[..end].rfind('\n').map_or(0, |i| i + 1),
+                None => return 0,
+            }
+        };
+        let line_end = self.text[target_start..]
+            .find('\n')
+            .map_or(self.text.len(), |end| target_start + end);
+        clusters(&self.text[target_start..line_end])
+            .get(column)
+            .map_or(line_end, |cluster| target_start + cluster.start)
+    }
+
+    // The start of the cluster `offset` falls in.
+    fn snap(&self, offset: usize) -> usize {
+        let offset = offset.min(self.text.len());
+        clusters(&self.text)
+            .into_iter()
+            .find(|cluster| cluster.end > offset)
+            .map_or(self.text.len(), |cluster| cluster.start)
+    }
+}
+
+fn is_word(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+// Ctrl, or Cmd on macOS, which shortcuts like select all go with.
+pub(crate) fn primary(modifiers: ModifiersState) -> bool {
+    if cfg!(target_os = "macos") {
+        modifiers.logo()
+    } else {
+        modifiers.ctrl()
+    }
+}
+
+// Keys that keep going while held. Everything else acts once per press.
+fn repeats(key: VirtualKeyCode) -> bool {
+    matches!(
+        key,
+        VirtualKeyCode::Left
+            | VirtualKeyCode::Right
+            | VirtualKeyCode::Up
+            | VirtualKeyCode::Down
+            | VirtualKeyCode::Back
+            | VirtualKeyCode::Delete
+    )
+}
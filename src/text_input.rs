@@ -0,0 +1,369 @@
+use std::rc::Rc;
+
+use winit::event::{ElementState, KeyboardInput, VirtualKeyCode, WindowEvent};
+
+use crate::text::{GlyphAtlas, Text, TextFont};
+use crate::text_caret::TextCaret;
+use crate::text_edit::{primary, TextEdit};
+use crate::text_layout::{TextLayout, VerticalAlign};
+use crate::ui_scene::{Player, UIScene};
+
+type ChangeHandler = Box<dyn FnMut(&str)>;
+
+#[derive(Debug, Clone)]
+pub struct TextInputStyle {
+    pub width: f32,
+    pub height: f32,
+    pub padding: f32,
+    pub font_size: f32,
+    // Seconds the caret shows, then hides, for. 0 keeps it on.
+    pub blink: f32,
+    pub field: [f32; 3],
+    pub text: [f32; 4],
+    pub selection: [f32; 3],
+}
+
+impl Default for TextInputStyle {
+    fn default() -> Self {
+        Self {
+            width: 200.0,
+            height: 22.0,
+            padding: 6.0,
+            font_size: 13.0,
+            blink: 0.53,
+            field: [0.1, 0.1, 0.12],
+            text: [0.85, 0.85, 0.88, 1.0],
+            selection: [0.22, 0.32, 0.5],
+        }
+    }
+}
+
+// A one line text field. Clicking it places the caret and dragging selects,
+// the keys of `TextEdit` edit, and the platform's shortcuts or the Cut, Copy
+// and Paste keys go through `UIScene::clipboard`. Text wider than the field
+// scrolls to keep the caret in view:
+//
+//     let mut name = TextInput::new(&mut scene, device, font, position, "untitled",
+//         TextInputStyle::default());
+//     name.on_change(move |text| rename(text));
+//     if !name.input(&mut scene, &event) { // before the scene's
+//         scene.input(&event);
+//     }
+//     name.update(&mut scene, device, queue, &mut glyphs, dt); // every frame
+//
+// Enter and Escape are left to the app, e.g. to apply the name or to give
+// focus back.
+pub struct TextInput {
+    pub style: TextInputStyle,
+    field: usize,
+    caret: TextCaret,
+    text: Text,
+    // Whether the text was given its box, which takes the atlas.
+    laid_out: bool,
+    edit: TextEdit,
+    editing: bool,
+    // How far the text is scrolled left, in world units.
+    scroll: f32,
+    // Seconds since the caret last moved, so it shows while typing.
+    blink: f32,
+    // Where selecting with the pointer started, while it's down.
+    press: Option<usize>,
+    on_change: Option<ChangeHandler>,
+    changed: bool,
+    dirty: bool,
+}
+
+impl TextInput {
+    // `position` is the center of the field. The text shows from the first
+    // `update` on.
+    pub fn new(
+        scene: &mut UIScene,
+        device: &wgpu::Device,
+        font: Rc<TextFont>,
+        position: cgmath::Vector2<f32>,
+        text: &str,
+        style: TextInputStyle,
+    ) -> Self {
+        let size = cgmath::Vector2::new(style.width, style.height);
+        let mut element = Player::with_gradient(device, position, size, style.field, style.field);
+        element.draggable = false;
+        scene.elements.push(element);
+        let field = scene.elements.len() - 1;
+        // Placed by `redraw`.
+        let caret = TextCaret::new(
+            scene,
+            device,
+            field,
+            style.font_size,
+            style.text,
+            style.selection,
+        );
+        let mut shown = Text::new(font, style.font_size);
+        shown.set_color(scene, style.text);
+
+        Self {
+            field,
+            caret,
+            text: shown,
+            laid_out: false,
+            edit: TextEdit::new(text),
+            editing: false,
+            scroll: 0.0,
+            blink: 0.0,
+            press: None,
+            on_change: None,
+            changed: false,
+            dirty: true,
+            style,
+        }
+    }
+
+    // The field, which takes focus while editing.
+    pub fn element(&self) -> usize {
+        self.field
+    }
+
+    pub fn text(&self) -> &str {
+        self.edit.text()
+    }
+
+    // Doesn't count as a change, so the handler isn't called.
+    pub fn set_text(&mut self, text: &str) {
+        self.edit.set_text(text);
+        self.dirty = true;
+    }
+
+    pub fn selected_text(&self) -> &str {
+        self.edit.selected_text()
+    }
+
+    pub fn is_editing(&self) -> bool {
+        self.editing
+    }
+
+    // Called with the text from `update` after edits changed it, once per
+    // frame however many there were.
+    pub fn on_change(&mut self, handler: impl FnMut(&str) + 'static) {
+        self.on_change = Some(Box::new(handler));
+    }
+
+    // Takes keys while focused. Returns true if the event was used.
+    pub fn input(&mut self, scene: &mut UIScene, event: &WindowEvent) -> bool {
+        if !self.editing {
+            return false;
+        }
+        // Keys go to the input method while it's composing.
+        let composing = self.edit.is_composing();
+        if let (
+            false,
+            WindowEvent::KeyboardInput {
+                input:
+                    KeyboardInput {
+                        state: ElementState::Pressed,
+                        virtual_keycode: Some(key),
+                        ..
+                    },
+                ..
+            },
+        ) = (composing, event)
+        {
+            let shortcut = primary(scene.shortcuts.modifiers());
+            let before = self.edit.text().to_string();
+            match key {
+                VirtualKeyCode::C if shortcut => self.copy(scene),
+                VirtualKeyCode::Copy => self.copy(scene),
+                VirtualKeyCode::X if shortcut => self.cut(scene),
+                VirtualKeyCode::Cut => self.cut(scene),
+                VirtualKeyCode::V if shortcut => self.paste(scene),
+                VirtualKeyCode::Paste => self.paste(scene),
+                _ => return self.edit_input(scene, event),
+            }
+            self.edited(&before);
+            return true;
+        }
+        self.edit_input(scene, event)
+    }
+
+    fn edit_input(&mut self, scene: &UIScene, event: &WindowEvent) -> bool {
+        let before = self.edit.text().to_string();
+        let used = self.edit.input(scene, event);
+        if used {
+            self.edited(&before);
+        }
+        used
+    }
+
+    fn copy(&mut self, scene: &mut UIScene) {
+        if !self.edit.selection().is_empty() {
+            scene.clipboard.set(self.edit.selected_text());
+        }
+    }
+
+    fn cut(&mut self, scene: &mut UIScene) {
+        self.copy(scene);
+        self.edit.insert("");
+    }
+
+    // Line breaks become spaces, and other control characters go.
+    fn paste(&mut self, scene: &mut UIScene) {
+        let Some(text) = scene.clipboard.get() else {
+            return;
+        };
+        let text = text
+            .lines()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .replace(char::is_control, "");
+        self.edit.insert(&text);
+    }
+
+    // Restarts the blink, and marks a change if the text rather than only
+    // the caret changed.
+    fn edited(&mut self, before: &str) {
+        self.dirty = true;
+        self.blink = 0.0;
+        if self.edit.text() != before {
+            self.changed = true;
+        }
+    }
+
+    // Follows focus and the pointer, repeats held keys, blinks the caret,
+    // calls the change handler and redraws what changed.
+    pub fn update(
+        &mut self,
+        scene: &mut UIScene,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        atlas: &mut GlyphAtlas,
+        dt: f32,
+    ) {
+        // Clicks on the text and caret land on their own elements.
+        if scene
+            .focused
+            .is_some_and(|element| self.is_field(element) && element != self.field)
+        {
+            scene.set_focus(Some(self.field));
+        }
+        let focused = scene.focused == Some(self.field);
+        if focused != self.editing {
+            self.editing = focused;
+            self.blink = 0.0;
+            self.dirty = true;
+        }
+        self.update_press(scene);
+        if self.editing {
+            let before = self.edit.text().to_string();
+            if self.edit.update(scene, dt) {
+                self.edited(&before);
+            }
+        }
+        if std::mem::take(&mut self.changed) {
+            if let Some(handler) = &mut self.on_change {
+                handler(self.edit.text());
+            }
+        }
+
+        if self.dirty {
+            self.redraw(scene, device, queue, atlas);
+        }
+        self.text.update(scene, device, atlas);
+
+        self.blink += dt;
+        let shown = self.style.blink <= 0.0 || (self.blink / self.style.blink) % 2.0 < 1.0;
+        self.caret.set_caret_visible(scene, self.editing && shown);
+
+        // Scrolled text stays inside the field.
+        let field = &scene.elements[self.field];
+        let center = cgmath::Vector2::new(field.instance.position.x, field.instance.position.y);
+        let half = cgmath::Vector2::new(self.style.width, self.style.height) / 2.0;
+        let clip = scene
+            .camera
+            .world_rect_to_screen(center - half, center + half);
+        for &element in self.text.elements().iter().chain(&self.caret.elements()) {
+            scene.elements[element].clip = Some(clip);
+        }
+    }
+
+    fn is_field(&self, element: usize) -> bool {
+        element == self.field
+            || self.caret.contains(element)
+            || self.text.elements().contains(&element)
+    }
+
+    // Places the caret where the field was pressed, and selects from there
+    // while the pointer is dragged.
+    fn update_press(&mut self, scene: &UIScene) {
+        // Offsets into the composed text don't fit the text itself.
+        if self.edit.is_composing()
+            || !scene
+                .pressed()
+                .is_some_and(|element| self.is_field(element))
+        {
+            self.press = None;
+            return;
+        }
+        let offset = self
+            .caret
+            .offset_at(scene.cursor().x - self.text_left(scene));
+        let anchor = *self.press.get_or_insert(offset);
+        if self.edit.selection() != (anchor.min(offset)..anchor.max(offset))
+            || self.edit.caret() != offset
+        {
+            self.edit.select(anchor, offset);
+            self.blink = 0.0;
+            self.dirty = true;
+        }
+    }
+
+    // The left edge of the text, scrolled, in world units.
+    fn text_left(&self, scene: &UIScene) -> f32 {
+        scene.elements[self.field].instance.position.x - self.style.width / 2.0 + self.style.padding
+            - self.scroll
+    }
+
+    fn redraw(
+        &mut self,
+        scene: &mut UIScene,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        atlas: &mut GlyphAtlas,
+    ) {
+        self.dirty = false;
+        let style = &self.style;
+        if !self.laid_out {
+            // Unbounded sideways, it scrolls rather than ending in '…'.
+            let layout = TextLayout {
+                height: Some(style.height),
+                wrap: false,
+                vertical_align: VerticalAlign::Middle,
+                ..TextLayout::default()
+            };
+            self.text.set_layout(scene, device, queue, atlas, layout);
+            self.laid_out = true;
+        }
+        let text = self.edit.display_text();
+        self.text.set_text(scene, device, queue, atlas, &text);
+        self.caret
+            .measure(&self.text.font, style.font_size, &text, 0..text.len());
+
+        // Keeps the caret in view, and no more scrolled than the text needs.
+        let visible = (style.width - 2.0 * style.padding).max(0.0);
+        let caret_x = self.caret.x_of(self.edit.display_caret());
+        let end = self.caret.x_of(text.len());
+        if caret_x - self.scroll > visible {
+            self.scroll = caret_x - visible;
+        } else if caret_x < self.scroll {
+            self.scroll = caret_x;
+        }
+        self.scroll = self.scroll.min((end - visible).max(0.0)).max(0.0);
+
+        let field = scene.elements[self.field].instance.position;
+        let left = self.text_left(scene);
+        self.text.set_position(
+            scene,
+            cgmath::Vector2::new(left, field.y + style.height / 2.0),
+        );
+        self.caret
+            .place(scene, queue, &self.edit, left, self.editing);
+    }
+}
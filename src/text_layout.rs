@@ -0,0 +1,440 @@
+use std::ops::Range;
+
+use ab_glyph::{Font as _, FontArc, GlyphId, PxScale, PxScaleFont, ScaleFont as _};
+
+use crate::font_chain::clusters;
+use crate::text::TextFont;
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum TextAlign {
+    #[default]
+    Left,
+    Center,
+    Right,
+    // Stretches the spaces of wrapped lines to fill the width. The last
+    // line of a paragraph stays left aligned.
+    Justify,
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum VerticalAlign {
+    #[default]
+    Top,
+    Middle,
+    Bottom,
+}
+
+// How text fills its box, whose top left is the text's position:
+//
+//     let layout = TextLayout {
+//         max_width: Some(240.0),
+//         max_lines: Some(2),
+//         align: TextAlign::Center,
+//         ..TextLayout::default()
+//     };
+//     let metrics = layout.measure(&font, 16.0, "A longer caption than fits");
+//     panel.set_height(metrics.height + padding * 2.0);
+//
+// Text that doesn't fit is cut at the last line that does, which ends in
+// '…'. Without a `max_width` or `height` the box is as large as the text.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextLayout {
+    pub max_width: Option<f32>,
+    pub height: Option<f32>,
+    // Breaks lines between words to stay within `max_width`, or within a
+    // word that's wider on its own. Otherwise lines break at '\n' only and
+    // the ones too wide are cut.
+    pub wrap: bool,
+    pub max_lines: Option<usize>,
+    pub align: TextAlign,
+    pub vertical_align: VerticalAlign,
+    // Of the font's line height.
+    pub line_spacing: f32,
+}
+
+impl Default for TextLayout {
+    fn default() -> Self {
+        Self {
+            max_width: None,
+            height: None,
+            wrap: true,
+            max_lines: None,
+            align: TextAlign::Left,
+            vertical_align: VerticalAlign::Top,
+            line_spacing: 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineMetrics {
+    // Bytes of the text shown on the line, without the line break or the
+    // spaces it wrapped at.
+    pub range: Range<usize>,
+    // Whether the line was cut, and ends in '…'.
+    pub ellipsis: bool,
+    // Of the line's left end and its baseline, from the box's top left,
+    // y down.
+    pub x: f32,
+    pub baseline: f32,
+    pub width: f32,
+}
+
+// Sizes of laid out text, in the units of its font size.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TextMetrics {
+    // Of the widest line.
+    pub width: f32,
+    // From the first line's ascent to the last one's descent.
+    pub height: f32,
+    // Between baselines, with the spacing. These three are of the first
+    // face, lines with larger ones are further apart.
+    pub line_height: f32,
+    pub ascent: f32,
+    // Below the baseline, so negative.
+    pub descent: f32,
+    pub lines: Vec<LineMetrics>,
+    // Whether any of the text was cut.
+    pub truncated: bool,
+    pub(crate) glyphs: Vec<LaidGlyph>,
+}
+
+// A glyph's origin, placed like `LineMetrics::x` and `baseline`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) struct LaidGlyph {
+    pub id: GlyphId,
+    // Of the text, which says the face and style.
+    pub run: usize,
+    pub x: f32,
+    pub baseline: f32,
+}
+
+struct Line {
+    range: Range<usize>,
+    ellipsis: bool,
+    // Ends its paragraph, so isn't justified.
+    last: bool,
+}
+
+// The fonts at their sizes the text is set in, and which runs of it use
+// which. Kerning only applies within a face.
+struct Faces<'a> {
+    fonts: Vec<PxScaleFont<&'a FontArc>>,
+    // Byte ranges in order, covering the text, and their faces.
+    runs: &'a [(Range<usize>, usize)],
+}
+
+impl Faces<'_> {
+    // The run `offset` falls in, or the last one past the end.
+    fn run_at(&self, offset: usize) -> usize {
+        self.runs
+            .partition_point(|(range, _)| range.end <= offset)
+            .min(self.runs.len() - 1)
+    }
+
+    fn font_at(&self, offset: usize) -> &PxScaleFont<&FontArc> {
+        &self.fonts[self.runs[self.run_at(offset)].1]
+    }
+
+    // Width of `range` of `text` on one line.
+    fn advance(&self, text: &str, range: Range<usize>) -> f32 {
+        let mut width = 0.0;
+        let mut previous: Option<(usize, GlyphId)> = None;
+        for (i, c) in text[range.clone()].char_indices() {
+            let face = self.runs[self.run_at(range.start + i)].1;
+            let font = &self.fonts[face];
+            let id = font.glyph_id(c);
+            if let Some((previous_face, previous)) = previous {
+                if previous_face == face {
+                    width += font.kern(previous, id);
+                }
+            }
+            width += font.h_advance(id);
+            previous = Some((face, id));
+        }
+        width
+    }
+
+    // Of the tallest face on the line: ascent, descent and line gap.
+    fn extent(&self, range: Range<usize>) -> (f32, f32, f32) {
+        let first = self.run_at(range.start);
+        let last = self.run_at(range.end.saturating_sub(1).max(range.start));
+        self.runs[first..=last]
+            .iter()
+            .map(|&(_, face)| &self.fonts[face])
+            .fold((0.0f32, 0.0f32, 0.0f32), |(ascent, descent, gap), font| {
+                (
+                    ascent.max(font.ascent()),
+                    descent.min(font.descent()),
+                    gap.max(font.line_gap()),
+                )
+            })
+    }
+}
+
+impl TextLayout {
+    // Lays `text` out at `size` without drawing it, for widgets sizing
+    // themselves to their text. `Text` shows the same layout.
+    pub fn measure(&self, font: &TextFont, size: f32, text: &str) -> TextMetrics {
+        self.measure_runs(text, &[(font, size)], &[(0..text.len(), 0)])
+    }
+
+    // With runs of the text set in different faces, each a font at a size.
+    // The runs cover the text in order, and lines are as tall as the
+    // tallest face on them.
+    pub(crate) fn measure_runs(
+        &self,
+        text: &str,
+        faces: &[(&TextFont, f32)],
+        runs: &[(Range<usize>, usize)],
+    ) -> TextMetrics {
+        let empty = [(0..text.len(), 0)];
+        let faces = Faces {
+            fonts: faces
+                .iter()
+                .map(|(font, size)| font.font.as_scaled(PxScale::from(*size)))
+                .collect(),
+            runs: if runs.is_empty() { &empty } else { runs },
+        };
+        let base = &faces.fonts[0];
+        let (ascent, descent) = (base.ascent(), base.descent());
+
+        let mut lines = Vec::new();
+        let mut start = 0;
+        for paragraph in text.split('\n') {
+            self.wrap(&faces, text, start..start + paragraph.len(), &mut lines);
+            start += paragraph.len() + 1;
+        }
+
+        // Baselines from the top of the block, and the block's height with
+        // each line.
+        let extents = lines
+            .iter()
+            .map(|line| faces.extent(line.range.clone()))
+            .collect::<Vec<_>>();
+        let mut baselines = Vec::with_capacity(lines.len());
+        let mut heights = Vec::with_capacity(lines.len());
+        for (i, &(line_ascent, line_descent, gap)) in extents.iter().enumerate() {
+            let baseline = match i.checked_sub(1) {
+                None => line_ascent,
+                Some(previous) => {
+                    let (_, previous_descent, _) = extents[previous];
+                    baselines[previous] + (line_ascent - previous_descent + gap) * self.line_spacing
+                }
+            };
+            baselines.push(baseline);
+            heights.push(baseline - line_descent);
+        }
+
+        let mut truncated = false;
+        let mut max_lines = self.max_lines.unwrap_or(usize::MAX).max(1);
+        if let Some(height) = self.height {
+            let fit = heights.iter().filter(|&&bottom| bottom <= height).count();
+            max_lines = max_lines.min(fit.max(1));
+        }
+        if lines.len() > max_lines {
+            lines.truncate(max_lines);
+            if let Some(line) = lines.last_mut() {
+                line.ellipsis = true;
+            }
+            truncated = true;
+        }
+
+        // Set in the face of the text it follows.
+        let ellipsis_of = |line: &Line| {
+            let font = faces.font_at(line.range.end.saturating_sub(1).max(line.range.start));
+            let ellipsis = if font.glyph_id('…').0 != 0 {
+                "…"
+            } else {
+                "..."
+            };
+            let width = ellipsis
+                .chars()
+                .map(|c| font.h_advance(font.glyph_id(c)))
+                .sum();
+            (ellipsis, width)
+        };
+        for line in &mut lines {
+            let shown = text[line.range.clone()].trim_end();
+            line.range.end = line.range.start + shown.len();
+            let Some(max_width) = self.max_width else {
+                continue;
+            };
+            if !line.ellipsis && faces.advance(text, line.range.clone()) > max_width {
+                line.ellipsis = true;
+                truncated = true;
+            }
+            if line.ellipsis {
+                // Whole clusters go until the ellipsis fits.
+                let start = line.range.start;
+                while line.range.end > start
+                    && faces.advance(text, line.range.clone()) + ellipsis_of(line).1 > max_width
+                {
+                    let shown = &text[line.range.clone()];
+                    let last = clusters(shown).pop().map_or(0, |cluster| cluster.start);
+                    line.range.end = start + shown[..last].trim_end().len();
+                }
+            }
+        }
+
+        let widths = lines
+            .iter()
+            .map(|line| {
+                let ellipsis = if line.ellipsis {
+                    ellipsis_of(line).1
+                } else {
+                    0.0
+                };
+                faces.advance(text, line.range.clone()) + ellipsis
+            })
+            .collect::<Vec<_>>();
+        let widest = widths.iter().copied().fold(0.0, f32::max);
+        let box_width = self.max_width.unwrap_or(widest);
+        let height = heights[lines.len() - 1];
+        let top = match (self.height, self.vertical_align) {
+            (Some(box_height), VerticalAlign::Middle) => (box_height - height) / 2.0,
+            (Some(box_height), VerticalAlign::Bottom) => box_height - height,
+            _ => 0.0,
+        };
+
+        let mut metrics = TextMetrics {
+            width: 0.0,
+            height,
+            line_height: (base.height() + base.line_gap()) * self.line_spacing,
+            ascent,
+            descent,
+            lines: Vec::new(),
+            truncated,
+            glyphs: Vec::new(),
+        };
+        for (i, (line, mut width)) in lines.into_iter().zip(widths).enumerate() {
+            let shown = &text[line.range.clone()];
+            let spaces = shown.chars().filter(|&c| c == ' ').count();
+            let justify = self.align == TextAlign::Justify && !line.last && !line.ellipsis;
+            let stretch = if justify && spaces > 0 {
+                let stretch = (box_width - width).max(0.0) / spaces as f32;
+                width += stretch * spaces as f32;
+                stretch
+            } else {
+                0.0
+            };
+            let x = match self.align {
+                TextAlign::Left | TextAlign::Justify => 0.0,
+                TextAlign::Center => (box_width - width) / 2.0,
+                TextAlign::Right => box_width - width,
+            };
+            let baseline = top + baselines[i];
+            let mut pen = x;
+            let mut previous: Option<(usize, GlyphId)> = None;
+            let mut place = |run: usize, c: char| {
+                let face = faces.runs[run].1;
+                let font = &faces.fonts[face];
+                let id = font.glyph_id(c);
+                if let Some((previous_face, previous)) = previous {
+                    if previous_face == face {
+                        pen += font.kern(previous, id);
+                    }
+                }
+                metrics.glyphs.push(LaidGlyph {
+                    id,
+                    run,
+                    x: pen,
+                    baseline,
+                });
+                pen += font.h_advance(id);
+                if c == ' ' {
+                    pen += stretch;
+                }
+                previous = Some((face, id));
+            };
+            for (offset, c) in shown.char_indices() {
+                place(faces.run_at(line.range.start + offset), c);
+            }
+            if line.ellipsis {
+                let run = faces.run_at(line.range.end.saturating_sub(1).max(line.range.start));
+                for c in ellipsis_of(&line).0.chars() {
+                    place(run, c);
+                }
+            }
+            metrics.width = metrics.width.max(width);
+            metrics.lines.push(LineMetrics {
+                range: line.range,
+                ellipsis: line.ellipsis,
+                x,
+                baseline,
+                width,
+            });
+        }
+        metrics
+    }
+
+    // Breaks one paragraph into lines.
+    fn wrap(&self, faces: &Faces, text: &str, range: Range<usize>, lines: &mut Vec<Line>) {
+        let max_width = self.max_width.filter(|_| self.wrap);
+        let mut start = range.start;
+        loop {
+            let end = max_width.map_or(range.end, |max_width| {
+                break_at(faces, text, start..range.end, max_width)
+            });
+            lines.push(Line {
+                range: start..end,
+                ellipsis: false,
+                last: end == range.end,
+            });
+            if end == range.end {
+                break;
+            }
+            start = end;
+        }
+    }
+}
+
+// Where the line starting `range` breaks to stay `max_width` wide: after
+// the last space before the text gets too wide, or before the last cluster
+// if there's no space. At least one cluster goes on the line, however wide.
+fn break_at(faces: &Faces, text: &str, range: Range<usize>, max_width: f32) -> usize {
+    let mut after_space = None;
+    for cluster in clusters(&text[range.clone()]) {
+        let (start, end) = (range.start + cluster.start, range.start + cluster.end);
+        let whitespace = text[start..end].chars().all(char::is_whitespace);
+        if whitespace {
+            after_space = Some(end);
+        } else if start > range.start && faces.advance(text, range.start..end) > max_width {
+            return after_space.unwrap_or(start);
+        }
+    }
+    range.end
+}
+
+// Width of `text` on one line, with kerning.
+pub(crate) fn advance(font: &PxScaleFont<&FontArc>, text: &str) -> f32 {
+    let mut width = 0.0;
+    let mut previous: Option<GlyphId> = None;
+    for c in text.chars() {
+        let id = font.glyph_id(c);
+        if let Some(previous) = previous {
+            width += font.kern(previous, id);
+        }
+        width += font.h_advance(id);
+        previous = Some(id);
+    }
+    width
+}
+
+// `advance` up to every char boundary of `text`, from (0, 0.0) to its end,
+// in one pass.
+pub(crate) fn advances(font: &PxScaleFont<&FontArc>, text: &str) -> Vec<(usize, f32)> {
+    let mut stops = vec![(0, 0.0)];
+    let mut width = 0.0;
+    let mut previous: Option<GlyphId> = None;
+    for (i, c) in text.char_indices() {
+        let id = font.glyph_id(c);
+        if let Some(previous) = previous {
+            width += font.kern(previous, id);
+        }
+        width += font.h_advance(id);
+        previous = Some(id);
+        stops.push((i + c.len_utf8(), width));
+    }
+    stops
+}
@@ -1,10 +1,95 @@
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::rc::Rc;
+
 use anyhow::*;
 use image::GenericImageView;
 
+// Sampler settings for a texture or material. wgpu has no LOD bias, so the
+// LOD clamps are the knob for pushing sampling towards sharper/blurrier mips.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SamplerConfig {
+    pub address_mode: wgpu::AddressMode,
+    pub mag_filter: wgpu::FilterMode,
+    pub min_filter: wgpu::FilterMode,
+    pub mipmap_filter: wgpu::FilterMode,
+    pub lod_min_clamp: f32,
+    pub lod_max_clamp: f32,
+}
+
+impl Default for SamplerConfig {
+    fn default() -> Self {
+        Self {
+            address_mode: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Nearest,
+            min_filter: wgpu::FilterMode::Nearest,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            lod_min_clamp: 0.0,
+            lod_max_clamp: 32.0,
+        }
+    }
+}
+
+impl SamplerConfig {
+    // Crisp texels, for pixel art. Same as the default.
+    pub fn nearest() -> Self {
+        Self::default()
+    }
+
+    // Smooth magnification and mip blending, for photos and painted textures.
+    pub fn linear() -> Self {
+        Self {
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Linear,
+            ..Self::default()
+        }
+    }
+}
+
+impl Eq for SamplerConfig {}
+
+impl Hash for SamplerConfig {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.address_mode.hash(state);
+        self.mag_filter.hash(state);
+        self.min_filter.hash(state);
+        self.mipmap_filter.hash(state);
+        self.lod_min_clamp.to_bits().hash(state);
+        self.lod_max_clamp.to_bits().hash(state);
+    }
+}
+
+#[derive(Default)]
+pub struct SamplerCache {
+    samplers: HashMap<SamplerConfig, Rc<wgpu::Sampler>>,
+}
+
+impl SamplerCache {
+    pub fn get(&mut self, device: &wgpu::Device, config: SamplerConfig) -> Rc<wgpu::Sampler> {
+        self.samplers
+            .entry(config)
+            .or_insert_with(|| {
+                Rc::new(device.create_sampler(&wgpu::SamplerDescriptor {
+                    address_mode_u: config.address_mode,
+                    address_mode_v: config.address_mode,
+                    address_mode_w: config.address_mode,
+                    mag_filter: config.mag_filter,
+                    min_filter: config.min_filter,
+                    mipmap_filter: config.mipmap_filter,
+                    lod_min_clamp: config.lod_min_clamp,
+                    lod_max_clamp: config.lod_max_clamp,
+                    ..Default::default()
+                }))
+            })
+            .clone()
+    }
+}
+
 pub struct Texture {
     pub texture: wgpu::Texture,
     pub view: wgpu::TextureView,
-    pub sampler: wgpu::Sampler,
+    pub sampler: Rc<wgpu::Sampler>,
 }
 
 impl Texture {
@@ -13,9 +98,10 @@ impl Texture {
         queue: &wgpu::Queue,
         bytes: &[u8],
         label: &str,
+        sampler: Rc<wgpu::Sampler>,
     ) -> Result<Self> {
         let img = image::load_from_memory(bytes)?;
-        Self::from_image(device, queue, &img, Some(label))
+        Self::from_image(device, queue, &img, Some(label), sampler)
     }
 
     pub fn from_image(
@@ -23,6 +109,7 @@ impl Texture {
         queue: &wgpu::Queue,
         img: &image::DynamicImage,
         label: Option<&str>,
+        sampler: Rc<wgpu::Sampler>,
     ) -> Result<Self> {
 
         let rgba = img.to_rgba8();
@@ -62,15 +149,6 @@ impl Texture {
         );
 
         let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
-        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
-            address_mode_u: wgpu::AddressMode::ClampToEdge,
-            address_mode_v: wgpu::AddressMode::ClampToEdge,
-            address_mode_w: wgpu::AddressMode::ClampToEdge,
-            mag_filter: wgpu::FilterMode::Nearest,
-            mipmap_filter: wgpu::FilterMode::Nearest,
-            ..Default::default()
-        });
-
 
         Ok(Self {
             texture,
@@ -104,7 +182,7 @@ impl Texture {
 
         let texture = device.create_texture(&desc);
         let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
-        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+        let sampler = Rc::new(device.create_sampler(&wgpu::SamplerDescriptor {
             address_mode_u: wgpu::AddressMode::ClampToEdge,
             address_mode_v: wgpu::AddressMode::ClampToEdge,
             address_mode_w: wgpu::AddressMode::ClampToEdge,
@@ -115,8 +193,55 @@ impl Texture {
             lod_max_clamp: 100.0,
             compare: Some(wgpu::CompareFunction::LessEqual),
             ..Default::default()
+        }));
+
+        Self {
+            texture,
+            view,
+            sampler,
+        }
+    }
+
+    // A color target scenes can render into and later passes can sample,
+    // with `filter` when sampled at a different size.
+    pub fn create_render_target(
+        device: &wgpu::Device,
+        config: &wgpu::SurfaceConfiguration,
+        format: wgpu::TextureFormat,
+        filter: wgpu::FilterMode,
+        label: &str,
+    ) -> Self {
+        let size = wgpu::Extent3d {
+            width: config.width,
+            height: config.height,
+            depth_or_array_layers: 1,
+        };
+
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(label),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
         });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let sampler = Rc::new(device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: filter,
+            min_filter: filter,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        }));
 
-        Self { texture, view, sampler }
+        Self {
+            texture,
+            view,
+            sampler,
+        }
     }
 }
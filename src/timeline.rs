@@ -0,0 +1,459 @@
+use cgmath::Rotation3;
+use winit::event::{ElementState, MouseButton, MouseScrollDelta, WindowEvent};
+
+use crate::clip::Clip;
+use crate::element_pool::ElementPool;
+use crate::ui_scene::{Player, Shape, UIScene};
+
+const MARKER_SIZE: f32 = 9.0;
+const TICK_WIDTH: f32 = 1.0;
+const PLAYHEAD_WIDTH: f32 = 2.0;
+// Ruler steps in seconds. The smallest step keeping ticks at least
+// `MIN_TICK_SPACING` pixels apart is used.
+const TICK_STEPS: [f32; 12] = [
+    0.01, 0.02, 0.05, 0.1, 0.2, 0.5, 1.0, 2.0, 5.0, 10.0, 30.0, 60.0,
+];
+const MIN_TICK_SPACING: f32 = 40.0;
+const ZOOM_STEP: f32 = 1.15;
+const MIN_ZOOM: f32 = 5.0;
+const MAX_ZOOM: f32 = 5000.0;
+// Pixels panned per wheel line while shift is held.
+const PAN_STEP: f32 = 40.0;
+
+// A keyframe as (track, key) indices into the clip.
+pub type KeyRef = (usize, usize);
+
+#[derive(Debug, Clone)]
+pub struct TimelineStyle {
+    pub size: cgmath::Vector2<f32>,
+    pub ruler_height: f32,
+    pub track_height: f32,
+    pub background: [f32; 3],
+    pub ruler: [f32; 3],
+    pub tick: [f32; 3],
+    // Alternating track row colors.
+    pub rows: ([f32; 3], [f32; 3]),
+    pub key: [f32; 3],
+    pub selected_key: [f32; 3],
+    pub playhead: [f32; 3],
+    pub selection: [f32; 4],
+}
+
+impl Default for TimelineStyle {
+    fn default() -> Self {
+        Self {
+            size: cgmath::Vector2::new(600.0, 200.0),
+            ruler_height: 24.0,
+            track_height: 28.0,
+            background: [0.08, 0.08, 0.09],
+            ruler: [0.16, 0.16, 0.18],
+            tick: [0.6, 0.6, 0.6],
+            rows: ([0.12, 0.12, 0.13], [0.14, 0.14, 0.15]),
+            key: [0.85, 0.85, 0.85],
+            selected_key: [1.0, 0.7, 0.2],
+            playhead: [0.9, 0.25, 0.2],
+            selection: [0.4, 0.6, 1.0, 0.25],
+        }
+    }
+}
+
+enum Gesture {
+    Scrub,
+    MoveKeys {
+        grab: f32,
+        origins: Vec<(KeyRef, f32)>,
+    },
+    Select {
+        from: cgmath::Vector2<f32>,
+        // Keys selected before the box, kept when extending with shift.
+        base: Vec<KeyRef>,
+    },
+}
+
+// Keyframe editor for a `Clip`: a ruler to scrub the playhead on, one row per
+// track with a marker per key, box selection and dragging selected keys in
+// time. The wheel zooms around the cursor, shift+wheel pans. Tracks past the
+// rows that fit in `style.size` aren't shown.
+//
+// Feed window events to `input` before `UIScene::input` and skip the scene
+// for events it handled, then call `update` before `UIScene::prepare`.
+pub struct Timeline {
+    pub style: TimelineStyle,
+    pub playhead: f32,
+    // Time at the left edge, in seconds.
+    pub start: f32,
+    pub pixels_per_second: f32,
+    position: cgmath::Vector2<f32>,
+    rows: usize,
+    cursor: cgmath::Vector2<f32>,
+    selection: Vec<KeyRef>,
+    gesture: Option<Gesture>,
+    playhead_element: usize,
+    selection_box: usize,
+    ticks: ElementPool,
+    live_ticks: Vec<usize>,
+    markers: ElementPool,
+    live_markers: Vec<(KeyRef, usize)>,
+}
+
+impl Timeline {
+    pub fn new(
+        scene: &mut UIScene,
+        device: &wgpu::Device,
+        position: cgmath::Vector2<f32>,
+        style: TimelineStyle,
+    ) -> Self {
+        let size = style.size;
+        let top = position.y + size.y / 2.0;
+        let rows = ((size.y - style.ruler_height) / style.track_height).max(0.0) as usize;
+
+        let mut push = |position: cgmath::Vector2<f32>, size, color: [f32; 3]| {
+            let mut element = Player::with_gradient(device, position, size, color, color);
+            element.draggable = false;
+            scene.elements.push(element);
+            scene.elements.len() - 1
+        };
+
+        push(position, size, style.background);
+        push(
+            cgmath::Vector2::new(position.x, top - style.ruler_height / 2.0),
+            cgmath::Vector2::new(size.x, style.ruler_height),
+            style.ruler,
+        );
+        for row in 0..rows {
+            let y = top - style.ruler_height - (row as f32 + 0.5) * style.track_height;
+            let color = if row % 2 == 0 {
+                style.rows.0
+            } else {
+                style.rows.1
+            };
+            push(
+                cgmath::Vector2::new(position.x, y),
+                cgmath::Vector2::new(size.x, style.track_height - 1.0),
+                color,
+            );
+        }
+
+        // Reserved before the playhead so it draws over ticks and markers.
+        let tick_size = cgmath::Vector2::new(TICK_WIDTH, style.ruler_height / 2.0);
+        let tick_color = style.tick;
+        let mut ticks = ElementPool::new(move |device| {
+            let mut element = Player::with_gradient(
+                device,
+                cgmath::Vector2::new(0.0, 0.0),
+                tick_size,
+                tick_color,
+                tick_color,
+            );
+            element.draggable = false;
+            element
+        });
+        ticks.reserve(scene, device, (size.x / MIN_TICK_SPACING) as usize + 2);
+        let mut markers = ElementPool::new(|device| {
+            let white = [1.0, 1.0, 1.0];
+            let mut element = Player::with_gradient(
+                device,
+                cgmath::Vector2::new(0.0, 0.0),
+                cgmath::Vector2::new(MARKER_SIZE, MARKER_SIZE),
+                white,
+                white,
+            );
+            element.draggable = false;
+            element
+        });
+        markers.reserve(scene, device, rows * 8);
+
+        let mut push = |position: cgmath::Vector2<f32>, size, color: [f32; 3]| {
+            let mut element = Player::with_gradient(device, position, size, color, color);
+            element.draggable = false;
+            element.visible = false;
+            scene.elements.push(element);
+            scene.elements.len() - 1
+        };
+        let playhead_element = push(
+            position,
+            cgmath::Vector2::new(PLAYHEAD_WIDTH, size.y),
+            style.playhead,
+        );
+        let selection_box = push(position, cgmath::Vector2::new(1.0, 1.0), [1.0; 3]);
+        scene.elements[selection_box].instance.tint = style.selection;
+
+        Self {
+            style,
+            playhead: 0.0,
+            start: 0.0,
+            pixels_per_second: 100.0,
+            position,
+            rows,
+            cursor: position,
+            selection: Vec::new(),
+            gesture: None,
+            playhead_element,
+            selection_box,
+            ticks,
+            live_ticks: Vec::new(),
+            markers,
+            live_markers: Vec::new(),
+        }
+    }
+
+    pub fn selection(&self) -> &[KeyRef] {
+        &self.selection
+    }
+
+    pub fn clear_selection(&mut self) {
+        self.selection.clear();
+    }
+
+    pub fn delete_selected(&mut self, clip: &mut Clip) {
+        // Highest indices first so the remaining ones stay valid.
+        self.selection.sort_unstable();
+        for &(track, key) in self.selection.iter().rev() {
+            clip.tracks[track].keys.remove(key);
+        }
+        self.selection.clear();
+    }
+
+    // Returns whether the timeline used the event.
+    pub fn input(&mut self, scene: &UIScene, clip: &mut Clip, event: &WindowEvent) -> bool {
+        match event {
+            WindowEvent::CursorMoved { position, .. } => {
+                self.cursor = scene.camera.screen_to_world(
+                    position.x as f32 - scene.viewport.x,
+                    position.y as f32 - scene.viewport.y,
+                );
+                let time = self.time_at(self.cursor.x);
+                match &self.gesture {
+                    None => return false,
+                    Some(Gesture::Scrub) => self.playhead = time.clamp(0.0, clip.duration),
+                    Some(Gesture::MoveKeys { grab, origins }) => {
+                        let delta = time - grab;
+                        for &((track, key), origin) in origins {
+                            clip.tracks[track].keys[key].time =
+                                (origin + delta).clamp(0.0, clip.duration);
+                        }
+                    }
+                    Some(Gesture::Select { from, base }) => {
+                        let mut selection = base.clone();
+                        for key in self.keys_in(clip, *from, self.cursor) {
+                            if !selection.contains(&key) {
+                                selection.push(key);
+                            }
+                        }
+                        self.selection = selection;
+                    }
+                }
+                true
+            }
+            WindowEvent::MouseInput {
+                state: ElementState::Pressed,
+                button: MouseButton::Left,
+                ..
+            } => {
+                if !self.contains(self.cursor) {
+                    return false;
+                }
+                let shift = scene.shortcuts.modifiers().shift();
+                let time = self.time_at(self.cursor.x);
+                if self.cursor.y >= self.top() - self.style.ruler_height {
+                    self.playhead = time.clamp(0.0, clip.duration);
+                    self.gesture = Some(Gesture::Scrub);
+                } else if let Some(key) = self.key_at(scene, self.cursor) {
+                    if !self.selection.contains(&key) {
+                        if !shift {
+                            self.selection.clear();
+                        }
+                        self.selection.push(key);
+                    }
+                    let origins = self
+                        .selection
+                        .iter()
+                        .map(|&(track, key)| ((track, key), clip.tracks[track].keys[key].time))
+                        .collect();
+                    self.gesture = Some(Gesture::MoveKeys {
+                        grab: time,
+                        origins,
+                    });
+                } else {
+                    if !shift {
+                        self.selection.clear();
+                    }
+                    self.gesture = Some(Gesture::Select {
+                        from: self.cursor,
+                        base: self.selection.clone(),
+                    });
+                }
+                true
+            }
+            WindowEvent::MouseInput {
+                state: ElementState::Released,
+                button: MouseButton::Left,
+                ..
+            } => self.gesture.take().is_some(),
+            WindowEvent::MouseWheel { delta, .. } => {
+                if !self.contains(self.cursor) {
+                    return false;
+                }
+                let lines = match delta {
+                    MouseScrollDelta::LineDelta(_, y) => *y,
+                    MouseScrollDelta::PixelDelta(pixels) => pixels.y as f32 / PAN_STEP,
+                };
+                if scene.shortcuts.modifiers().shift() {
+                    self.start -= lines * PAN_STEP / self.pixels_per_second;
+                } else {
+                    // Keep the time under the cursor in place.
+                    let anchor = self.time_at(self.cursor.x);
+                    self.pixels_per_second =
+                        (self.pixels_per_second * ZOOM_STEP.powf(lines)).clamp(MIN_ZOOM, MAX_ZOOM);
+                    self.start = anchor - (self.cursor.x - self.left()) / self.pixels_per_second;
+                }
+                self.start = self.start.max(0.0);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn update(
+        &mut self,
+        scene: &mut UIScene,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        clip: &Clip,
+    ) {
+        // Keys may have been removed since the last frame.
+        self.selection.retain(|&(track, key)| {
+            clip.tracks
+                .get(track)
+                .is_some_and(|track| key < track.keys.len())
+        });
+
+        for element in self.live_ticks.drain(..) {
+            self.ticks.release(scene, element);
+        }
+        for (_, element) in self.live_markers.drain(..) {
+            self.markers.release(scene, element);
+        }
+
+        let (left, right) = (self.left(), self.left() + self.style.size.x);
+        let step = TICK_STEPS
+            .iter()
+            .copied()
+            .find(|step| step * self.pixels_per_second >= MIN_TICK_SPACING)
+            .unwrap_or(TICK_STEPS[TICK_STEPS.len() - 1]);
+        let y = self.top() - self.style.ruler_height * 0.75;
+        let mut i = (self.start / step).ceil() as i64;
+        loop {
+            let x = self.x_of(i as f32 * step);
+            if x > right {
+                break;
+            }
+            let element = self
+                .ticks
+                .acquire(scene, device, cgmath::Vector2::new(x, y));
+            self.live_ticks.push(element);
+            i += 1;
+        }
+
+        for (t, track) in clip.tracks.iter().enumerate().take(self.rows) {
+            for (k, key) in track.keys.iter().enumerate() {
+                let x = self.x_of(key.time);
+                if x < left || x > right {
+                    continue;
+                }
+                let position = cgmath::Vector2::new(x, self.row_y(t));
+                let element = self.markers.acquire(scene, device, position);
+                let color = if self.selection.contains(&(t, k)) {
+                    self.style.selected_key
+                } else {
+                    self.style.key
+                };
+                let instance = &mut scene.elements[element].instance;
+                instance.rotation = cgmath::Quaternion::from_angle_z(cgmath::Deg(45.0));
+                instance.tint = [color[0], color[1], color[2], 1.0];
+                self.live_markers.push(((t, k), element));
+            }
+        }
+
+        let x = self.x_of(self.playhead);
+        let playhead = &mut scene.elements[self.playhead_element];
+        playhead.visible = x >= left && x <= right;
+        playhead.instance.position.x = x;
+
+        let selection_box = &mut scene.elements[self.selection_box];
+        match &self.gesture {
+            Some(Gesture::Select { from, .. }) => {
+                let min =
+                    cgmath::Vector2::new(from.x.min(self.cursor.x), from.y.min(self.cursor.y));
+                let max =
+                    cgmath::Vector2::new(from.x.max(self.cursor.x), from.y.max(self.cursor.y));
+                let shape = Shape::Rect {
+                    width: (max.x - min.x).max(1.0),
+                    height: (max.y - min.y).max(1.0),
+                };
+                selection_box.reshape(queue, shape, [1.0; 3], [1.0; 3]);
+                selection_box.instance.position.x = (min.x + max.x) / 2.0;
+                selection_box.instance.position.y = (min.y + max.y) / 2.0;
+                selection_box.visible = true;
+            }
+            _ => selection_box.visible = false,
+        }
+    }
+
+    fn left(&self) -> f32 {
+        self.position.x - self.style.size.x / 2.0
+    }
+
+    fn top(&self) -> f32 {
+        self.position.y + self.style.size.y / 2.0
+    }
+
+    fn x_of(&self, time: f32) -> f32 {
+        self.left() + (time - self.start) * self.pixels_per_second
+    }
+
+    fn time_at(&self, x: f32) -> f32 {
+        self.start + (x - self.left()) / self.pixels_per_second
+    }
+
+    fn row_y(&self, row: usize) -> f32 {
+        self.top() - self.style.ruler_height - (row as f32 + 0.5) * self.style.track_height
+    }
+
+    fn contains(&self, point: cgmath::Vector2<f32>) -> bool {
+        let half = self.style.size / 2.0;
+        (point.x - self.position.x).abs() <= half.x && (point.y - self.position.y).abs() <= half.y
+    }
+
+    fn key_at(&self, scene: &UIScene, point: cgmath::Vector2<f32>) -> Option<KeyRef> {
+        self.live_markers
+            .iter()
+            .rev()
+            .find(|(_, element)| scene.elements[*element].contains(point))
+            .map(|&(key, _)| key)
+    }
+
+    fn keys_in(
+        &self,
+        clip: &Clip,
+        a: cgmath::Vector2<f32>,
+        b: cgmath::Vector2<f32>,
+    ) -> Vec<KeyRef> {
+        let (min_x, max_x) = (a.x.min(b.x), a.x.max(b.x));
+        let (min_y, max_y) = (a.y.min(b.y), a.y.max(b.y));
+        let mut keys = Vec::new();
+        for (t, track) in clip.tracks.iter().enumerate().take(self.rows) {
+            let y = self.row_y(t);
+            if y < min_y || y > max_y {
+                continue;
+            }
+            for (k, key) in track.keys.iter().enumerate() {
+                let x = self.x_of(key.time);
+                if x >= min_x && x <= max_x {
+                    keys.push((t, k));
+                }
+            }
+        }
+        keys
+    }
+}
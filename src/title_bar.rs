@@ -0,0 +1,272 @@
+use std::time::{Duration, Instant};
+
+use winit::dpi::PhysicalSize;
+use winit::event::{ElementState, MouseButton, WindowEvent};
+use winit::window::{CursorIcon, ResizeDirection, Window};
+
+use crate::deletion_queue::DeletionQueue;
+use crate::ui_scene::{Player, UIScene};
+
+const DOUBLE_CLICK: Duration = Duration::from_millis(400);
+
+// What a point of the window stands for when the app draws its own chrome.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum HitRegion {
+    Client,
+    // Dragging moves the window, double clicking toggles maximize.
+    Caption,
+    Minimize,
+    Maximize,
+    Close,
+    Resize(ResizeDirection),
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TitleBarResponse {
+    Ignored,
+    Handled,
+    // The close button was clicked, the host decides whether to exit.
+    CloseRequested,
+}
+
+#[derive(Debug, Clone)]
+pub struct TitleBarStyle {
+    pub height: f32,
+    pub button_width: f32,
+    // Width of the invisible resize borders around the window.
+    pub border: f32,
+    pub background: [f32; 3],
+    pub button: [f32; 3],
+    pub close: [f32; 3],
+    // Multiplies button colors under the cursor.
+    pub hover_tint: [f32; 4],
+}
+
+impl Default for TitleBarStyle {
+    fn default() -> Self {
+        Self {
+            height: 32.0,
+            button_width: 46.0,
+            border: 6.0,
+            background: [0.12, 0.12, 0.14],
+            button: [0.2, 0.2, 0.23],
+            close: [0.75, 0.15, 0.15],
+            hover_tint: [1.4, 1.4, 1.4, 1.0],
+        }
+    }
+}
+
+// Layout of the chrome in physical window pixels, usable on its own by hosts
+// that draw the title bar themselves.
+pub fn hit_test(style: &TitleBarStyle, size: PhysicalSize<u32>, x: f32, y: f32) -> HitRegion {
+    let (width, height) = (size.width as f32, size.height as f32);
+    let left = x < style.border;
+    let right = x >= width - style.border;
+    let top = y < style.border;
+    let bottom = y >= height - style.border;
+    let direction = match (top, bottom, left, right) {
+        (true, _, true, _) => Some(ResizeDirection::NorthWest),
+        (true, _, _, true) => Some(ResizeDirection::NorthEast),
+        (_, true, true, _) => Some(ResizeDirection::SouthWest),
+        (_, true, _, true) => Some(ResizeDirection::SouthEast),
+        (true, ..) => Some(ResizeDirection::North),
+        (_, true, ..) => Some(ResizeDirection::South),
+        (_, _, true, _) => Some(ResizeDirection::West),
+        (_, _, _, true) => Some(ResizeDirection::East),
+        _ => None,
+    };
+    if let Some(direction) = direction {
+        return HitRegion::Resize(direction);
+    }
+
+    if y >= style.height {
+        return HitRegion::Client;
+    }
+    match ((width - x) / style.button_width) as u32 {
+        0 => HitRegion::Close,
+        1 => HitRegion::Maximize,
+        2 => HitRegion::Minimize,
+        _ => HitRegion::Caption,
+    }
+}
+
+// A title bar drawn with scene elements for windows created without
+// decorations. The elements are kept at fixed screen positions whatever the
+// camera does.
+pub struct TitleBar {
+    pub style: TitleBarStyle,
+    size: PhysicalSize<u32>,
+    cursor: (f32, f32),
+    hovered: HitRegion,
+    last_caption_press: Option<Instant>,
+    background: usize,
+    // Minimize, maximize, close.
+    buttons: [usize; 3],
+}
+
+impl TitleBar {
+    pub fn new(
+        scene: &mut UIScene,
+        device: &wgpu::Device,
+        size: PhysicalSize<u32>,
+        style: TitleBarStyle,
+    ) -> Self {
+        let background = Self::push(scene, Self::create_background(device, &style, size));
+        let button_size = cgmath::Vector2::new(style.button_width, style.height);
+        let buttons = [style.button, style.button, style.close].map(|color| {
+            Self::push(
+                scene,
+                Player::with_gradient(
+                    device,
+                    cgmath::Vector2::new(0.0, 0.0),
+                    button_size,
+                    color,
+                    color,
+                ),
+            )
+        });
+
+        Self {
+            style,
+            size,
+            cursor: (0.0, 0.0),
+            hovered: HitRegion::Client,
+            last_caption_press: None,
+            background,
+            buttons,
+        }
+    }
+
+    pub fn resize(
+        &mut self,
+        scene: &mut UIScene,
+        device: &wgpu::Device,
+        deletion_queue: &mut DeletionQueue,
+        size: PhysicalSize<u32>,
+    ) {
+        self.size = size;
+        let mut background = Self::create_background(device, &self.style, size);
+        background.draggable = false;
+        let old = std::mem::replace(&mut scene.elements[self.background], background);
+        deletion_queue.defer(old.vertex_buffer);
+        deletion_queue.defer(old.index_buffer);
+        for buffer in old.instance_buffers {
+            deletion_queue.defer(buffer);
+        }
+    }
+
+    pub fn input(&mut self, window: &Window, event: &WindowEvent) -> TitleBarResponse {
+        match event {
+            WindowEvent::CursorMoved { position, .. } => {
+                self.cursor = (position.x as f32, position.y as f32);
+                let hovered = hit_test(&self.style, self.size, self.cursor.0, self.cursor.1);
+                if hovered != self.hovered {
+                    window.set_cursor_icon(match hovered {
+                        HitRegion::Resize(direction) => direction.into(),
+                        _ => CursorIcon::Default,
+                    });
+                    self.hovered = hovered;
+                }
+                TitleBarResponse::Ignored
+            }
+            WindowEvent::CursorLeft { .. } => {
+                self.hovered = HitRegion::Client;
+                TitleBarResponse::Ignored
+            }
+            WindowEvent::MouseInput {
+                state: ElementState::Pressed,
+                button: MouseButton::Left,
+                ..
+            } => self.press(window),
+            _ => TitleBarResponse::Ignored,
+        }
+    }
+
+    fn press(&mut self, window: &Window) -> TitleBarResponse {
+        let result = match self.hovered {
+            HitRegion::Client => return TitleBarResponse::Ignored,
+            HitRegion::Close => return TitleBarResponse::CloseRequested,
+            HitRegion::Minimize => {
+                window.set_minimized(true);
+                Ok(())
+            }
+            HitRegion::Maximize => {
+                window.set_maximized(!window.is_maximized());
+                Ok(())
+            }
+            HitRegion::Caption => {
+                let now = Instant::now();
+                let double = self
+                    .last_caption_press
+                    .is_some_and(|last| now - last < DOUBLE_CLICK);
+                if double {
+                    self.last_caption_press = None;
+                    window.set_maximized(!window.is_maximized());
+                    Ok(())
+                } else {
+                    self.last_caption_press = Some(now);
+                    window.drag_window()
+                }
+            }
+            HitRegion::Resize(direction) => window.drag_resize_window(direction),
+        };
+        if let Err(e) = result {
+            log::warn!("Couldn't move or resize the window: {}", e);
+        }
+        TitleBarResponse::Handled
+    }
+
+    // Call before `UIScene::prepare` so the chrome follows the camera.
+    pub fn update(&self, scene: &mut UIScene) {
+        let (width, height) = (self.style.button_width, self.style.height);
+        let screen_width = self.size.width as f32;
+        let mut place = |index: usize, x: f32, y: f32| {
+            let world = scene.camera.screen_to_world(x, y);
+            let scale = 1.0 / scene.camera.scale;
+            let instance = &mut scene.elements[index].instance;
+            instance.position.x = world.x;
+            instance.position.y = world.y;
+            instance.scale = scale;
+        };
+
+        place(self.background, screen_width / 2.0, height / 2.0);
+        for (i, &button) in self.buttons.iter().enumerate() {
+            let x = screen_width - width * (3 - i) as f32 + width / 2.0;
+            place(button, x, height / 2.0);
+        }
+
+        let hovered = match self.hovered {
+            HitRegion::Minimize => Some(0),
+            HitRegion::Maximize => Some(1),
+            HitRegion::Close => Some(2),
+            _ => None,
+        };
+        for (i, &button) in self.buttons.iter().enumerate() {
+            scene.elements[button].instance.tint = if hovered == Some(i) {
+                self.style.hover_tint
+            } else {
+                [1.0; 4]
+            };
+        }
+    }
+
+    fn create_background(
+        device: &wgpu::Device,
+        style: &TitleBarStyle,
+        size: PhysicalSize<u32>,
+    ) -> Player {
+        Player::with_gradient(
+            device,
+            cgmath::Vector2::new(0.0, 0.0),
+            cgmath::Vector2::new(size.width as f32, style.height),
+            style.background,
+            style.background,
+        )
+    }
+
+    fn push(scene: &mut UIScene, mut element: Player) -> usize {
+        element.draggable = false;
+        scene.elements.push(element);
+        scene.elements.len() - 1
+    }
+}
@@ -0,0 +1,313 @@
+use wgpu::util::DeviceExt;
+
+use crate::{gpu_errors, texture};
+
+pub const HDR_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+// Turns HDR on with the named operator, e.g. `WGPIE_HDR=aces`.
+pub const HDR_ENV: &str = "WGPIE_HDR";
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Tonemap {
+    // Clamp to [0, 1], handy to compare against the operators below.
+    Clamp,
+    Reinhard,
+    Aces,
+}
+
+impl Tonemap {
+    pub fn from_env() -> Option<Self> {
+        let value = std::env::var(HDR_ENV).ok()?;
+        let tonemap = Self::parse(&value);
+        if tonemap.is_none() && !value.trim().eq_ignore_ascii_case("off") {
+            log::warn!("Unknown {} operator `{}`", HDR_ENV, value);
+        }
+        tonemap
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "clamp" => Some(Tonemap::Clamp),
+            "reinhard" => Some(Tonemap::Reinhard),
+            "aces" => Some(Tonemap::Aces),
+            _ => None,
+        }
+    }
+
+    fn id(&self) -> u32 {
+        match self {
+            Tonemap::Clamp => 0,
+            Tonemap::Reinhard => 1,
+            Tonemap::Aces => 2,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct TonemapUniform {
+    mode: u32,
+    exposure: f32,
+    // Non-zero when the surface isn't sRGB, so the pass has to encode the
+    // linear scene colors itself.
+    encode_srgb: u32,
+    // Uniform buffers need 16 byte alignment.
+    _padding: f32,
+}
+
+// Scenes render into `target` (a float texture, so values above 1.0
+// survive) and `render` maps it down to the surface format.
+//
+// With a `pixel_scale` above 1 the target is that many times smaller than
+// the surface and gets blown up with nearest filtering, centered, so every
+// scene pixel covers the same whole number of screen pixels.
+pub struct TonemapPass {
+    pub target: texture::Texture,
+    pub tonemap: Tonemap,
+    pub exposure: f32,
+    encode_srgb: bool,
+    pixel_scale: u32,
+    // Where the scaled target lands on the surface, letterboxed in black.
+    viewport: [f32; 4],
+    render_pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    bind_group: wgpu::BindGroup,
+    uniform_buffer: wgpu::Buffer,
+}
+
+impl TonemapPass {
+    // Float render targets aren't available everywhere (e.g. some WebGL2
+    // implementations), callers should fall back to rendering directly.
+    pub fn is_supported(adapter: &wgpu::Adapter) -> bool {
+        adapter
+            .get_texture_format_features(HDR_FORMAT)
+            .allowed_usages
+            .contains(wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING)
+    }
+
+    pub fn new(
+        device: &wgpu::Device,
+        config: &wgpu::SurfaceConfiguration,
+        tonemap: Tonemap,
+        pixel_scale: u32,
+    ) -> Self {
+        let _operation = gpu_errors::operation(format!("creating {:?} tonemap pass", tonemap));
+        let pixel_scale = pixel_scale.max(1);
+        let target = Self::create_target(device, config, pixel_scale);
+        let viewport = Self::viewport(config, &target, pixel_scale);
+        let encode_srgb = !config.format.is_srgb();
+
+        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Tonemap uniform buffer"),
+            contents: bytemuck::cast_slice(&[TonemapUniform {
+                mode: tonemap.id(),
+                exposure: 1.0,
+                encode_srgb: encode_srgb as u32,
+                _padding: 0.0,
+            }]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("tonemap_bind_group_layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let bind_group =
+            Self::create_bind_group(device, &bind_group_layout, &target, &uniform_buffer);
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("tonemap shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("tonemap.wgsl").into()),
+        });
+
+        let render_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Tonemap pipeline layout"),
+                bind_group_layouts: &[&bind_group_layout],
+                push_constant_ranges: &[],
+            });
+
+        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Tonemap Pipeline"),
+            layout: Some(&render_pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: config.format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        Self {
+            target,
+            tonemap,
+            exposure: 1.0,
+            encode_srgb,
+            pixel_scale,
+            viewport,
+            render_pipeline,
+            bind_group_layout,
+            bind_group,
+            uniform_buffer,
+        }
+    }
+
+    fn create_target(
+        device: &wgpu::Device,
+        config: &wgpu::SurfaceConfiguration,
+        pixel_scale: u32,
+    ) -> texture::Texture {
+        let mut scaled = config.clone();
+        scaled.width = (config.width / pixel_scale).max(1);
+        scaled.height = (config.height / pixel_scale).max(1);
+        let filter = if pixel_scale > 1 {
+            wgpu::FilterMode::Nearest
+        } else {
+            wgpu::FilterMode::Linear
+        };
+        texture::Texture::create_render_target(device, &scaled, HDR_FORMAT, filter, "hdr_target")
+    }
+
+    fn viewport(
+        config: &wgpu::SurfaceConfiguration,
+        target: &texture::Texture,
+        pixel_scale: u32,
+    ) -> [f32; 4] {
+        let size = target.texture.size();
+        let width = (size.width * pixel_scale).min(config.width);
+        let height = (size.height * pixel_scale).min(config.height);
+        [
+            (config.width.saturating_sub(width) / 2) as f32,
+            (config.height.saturating_sub(height) / 2) as f32,
+            width as f32,
+            height as f32,
+        ]
+    }
+
+    pub fn pixel_scale(&self) -> u32 {
+        self.pixel_scale
+    }
+
+    // Size scenes should render at.
+    pub fn target_size(&self) -> (u32, u32) {
+        let size = self.target.texture.size();
+        (size.width, size.height)
+    }
+
+    // A surface position in target pixels, for input handling.
+    pub fn to_target(&self, x: f32, y: f32) -> (f32, f32) {
+        let [left, top, ..] = self.viewport;
+        let scale = self.pixel_scale as f32;
+        ((x - left) / scale, (y - top) / scale)
+    }
+
+    fn create_bind_group(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        target: &texture::Texture,
+        uniform_buffer: &wgpu::Buffer,
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("tonemap_bind_group"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&target.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&target.sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: uniform_buffer.as_entire_binding(),
+                },
+            ],
+        })
+    }
+
+    pub fn resize(&mut self, device: &wgpu::Device, config: &wgpu::SurfaceConfiguration) {
+        self.target = Self::create_target(device, config, self.pixel_scale);
+        self.viewport = Self::viewport(config, &self.target, self.pixel_scale);
+        self.bind_group = Self::create_bind_group(
+            device,
+            &self.bind_group_layout,
+            &self.target,
+            &self.uniform_buffer,
+        );
+    }
+
+    pub fn update(&self, queue: &wgpu::Queue) {
+        queue.write_buffer(
+            &self.uniform_buffer,
+            0,
+            bytemuck::cast_slice(&[TonemapUniform {
+                mode: self.tonemap.id(),
+                exposure: self.exposure,
+                encode_srgb: self.encode_srgb as u32,
+                _padding: 0.0,
+            }]),
+        );
+    }
+
+    pub fn render(&self, encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView) {
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Tonemap pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: true,
+                },
+            })],
+            depth_stencil_attachment: None,
+        });
+
+        let [x, y, width, height] = self.viewport;
+        render_pass.set_viewport(x, y, width, height, 0.0, 1.0);
+        render_pass.set_pipeline(&self.render_pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
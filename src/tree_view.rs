@@ -0,0 +1,696 @@
+use std::rc::Rc;
+
+use cgmath::Rotation3;
+use winit::event::{ElementState, KeyboardInput, MouseButton, VirtualKeyCode, WindowEvent};
+
+use crate::layout_direction::LayoutDirection;
+use crate::menu_bar::chevron;
+use crate::rich_text::{RichText, SpanStyle};
+use crate::search_box::find_match;
+use crate::text::{GlyphAtlas, Text, TextFont};
+use crate::text_layout::{TextAlign, TextLayout, VerticalAlign};
+use crate::ui_scene::{Player, UIScene, Viewport};
+
+#[derive(Debug, Clone)]
+pub struct TreeStyle {
+    pub row_height: f32,
+    pub indent: f32,
+    pub font_size: f32,
+    pub row: [f32; 3],
+    pub selected: [f32; 3],
+    pub label: [f32; 4],
+    pub disclosure: [f32; 3],
+    // Of the part of a label matching the filter.
+    pub highlight: [f32; 4],
+    // Multiplies the row a drag would drop onto.
+    pub drop_tint: [f32; 4],
+}
+
+impl Default for TreeStyle {
+    fn default() -> Self {
+        Self {
+            row_height: 20.0,
+            indent: 16.0,
+            font_size: 12.0,
+            row: [0.14, 0.14, 0.16],
+            selected: [0.22, 0.32, 0.5],
+            label: [0.85, 0.85, 0.88, 1.0],
+            disclosure: [0.7, 0.7, 0.75],
+            highlight: [0.95, 0.75, 0.3, 1.0],
+            drop_tint: [1.6, 1.6, 1.8, 1.0],
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TreeEvent {
+    SelectionChanged,
+    Toggled(usize),
+    // `parent` is `None` for items moved to the top level.
+    Reparented { item: usize, parent: Option<usize> },
+}
+
+struct Item {
+    label: String,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    expanded: bool,
+}
+
+// Scene elements showing one row, reused for whichever item scrolls into it.
+struct RowElements {
+    background: usize,
+    disclosure: usize,
+    // Only laid out again when the item, the filter or the indent changed.
+    label: Text,
+}
+
+struct Press {
+    screen: cgmath::Vector2<f32>,
+    item: usize,
+}
+
+// A hierarchy of labeled items, e.g. a scene inspector, shown in a screen
+// rect relative to the viewport. Only the rows in view have elements, so
+// large trees cost no more to draw than small ones.
+//
+// Clicking selects, Ctrl toggles and Shift extends the selection. Dragging
+// selected items onto another row makes them its children, dropping below
+// the last row moves them to the top level. After a click inside the tree,
+// the arrows move through rows, Left and Right collapse and expand, and
+// Shift extends the selection.
+//
+// Right to left, rows indent from the right, disclosure arrows point left
+// and Left expands.
+//
+// A filter, usually from a `SearchBox`, shows only the items whose labels
+// contain it, with the match highlighted, and the items they're in,
+// expanded or not:
+//
+//     let mut tree = TreeView::new(&mut scene, device, font, bounds, TreeStyle::default());
+//     let root = tree.add(None, "Scene");
+//     tree.add(Some(root), "Camera");
+//     tree.set_filter(&query);
+//     tree.update(&mut scene, device, queue, &mut glyphs); // every frame
+pub struct TreeView {
+    pub style: TreeStyle,
+    // None follows `UIScene::direction`.
+    pub direction: Option<LayoutDirection>,
+    bounds: Viewport,
+    items: Vec<Item>,
+    roots: Vec<usize>,
+    // Items shown in display order, with their depth.
+    visible: Vec<(usize, usize)>,
+    selected: Vec<usize>,
+    // Where shift-selection extends from and where the arrows move from.
+    anchor: Option<usize>,
+    lead: Option<usize>,
+    scroll: f32,
+    // Empty shows every item.
+    filter: String,
+    font: Rc<TextFont>,
+    rows: Vec<RowElements>,
+    focused: bool,
+    cursor: cgmath::Vector2<f32>,
+    press: Option<Press>,
+    dragging: bool,
+    events: Vec<TreeEvent>,
+    dirty: bool,
+}
+
+impl TreeView {
+    pub fn new(
+        scene: &mut UIScene,
+        device: &wgpu::Device,
+        font: Rc<TextFont>,
+        bounds: Viewport,
+        style: TreeStyle,
+    ) -> Self {
+        let mut tree = Self {
+            style,
+            direction: None,
+            bounds,
+            items: Vec::new(),
+            roots: Vec::new(),
+            visible: Vec::new(),
+            selected: Vec::new(),
+            anchor: None,
+            lead: None,
+            scroll: 0.0,
+            filter: String::new(),
+            font,
+            rows: Vec::new(),
+            focused: false,
+            cursor: cgmath::Vector2::new(0.0, 0.0),
+            press: None,
+            dragging: false,
+            events: Vec::new(),
+            dirty: true,
+        };
+        tree.reserve_rows(scene, device);
+        tree
+    }
+
+    // Adds more row elements if the tree got taller.
+    pub fn set_bounds(&mut self, scene: &mut UIScene, device: &wgpu::Device, bounds: Viewport) {
+        self.bounds = bounds;
+        self.reserve_rows(scene, device);
+        self.scroll_by(0.0);
+    }
+
+    pub fn add(&mut self, parent: Option<usize>, label: &str) -> usize {
+        let id = self.items.len();
+        self.items.push(Item {
+            label: label.to_string(),
+            parent,
+            children: Vec::new(),
+            expanded: false,
+        });
+        self.siblings_mut(parent).push(id);
+        self.dirty = true;
+        id
+    }
+
+    pub fn label(&self, item: usize) -> &str {
+        &self.items[item].label
+    }
+
+    pub fn parent(&self, item: usize) -> Option<usize> {
+        self.items[item].parent
+    }
+
+    pub fn children(&self, item: Option<usize>) -> &[usize] {
+        match item {
+            Some(item) => &self.items[item].children,
+            None => &self.roots,
+        }
+    }
+
+    pub fn selection(&self) -> &[usize] {
+        &self.selected
+    }
+
+    // Selects `items`, e.g. to follow a selection made elsewhere, and
+    // scrolls the first one into view.
+    pub fn set_selection(&mut self, items: &[usize]) {
+        self.refresh();
+        self.selected = items.to_vec();
+        self.anchor = items.first().copied();
+        self.lead = self.anchor;
+        if let Some(first) = self.anchor {
+            self.scroll_to(first);
+        }
+        self.events.push(TreeEvent::SelectionChanged);
+    }
+
+    // Ignores case and surrounding spaces, see `find_match`. Scrolls back to
+    // the top.
+    pub fn set_filter(&mut self, query: &str) {
+        let query = query.trim();
+        if self.filter != query {
+            self.filter = query.to_string();
+            self.scroll = 0.0;
+            self.dirty = true;
+        }
+    }
+
+    pub fn filter(&self) -> &str {
+        &self.filter
+    }
+
+    pub fn set_expanded(&mut self, item: usize, expanded: bool) {
+        if self.items[item].expanded != expanded {
+            self.items[item].expanded = expanded;
+            self.events.push(TreeEvent::Toggled(item));
+            self.dirty = true;
+        }
+    }
+
+    // Moves `item` under `parent`, at the end. Fails if that would put an
+    // item inside itself.
+    pub fn reparent(&mut self, item: usize, parent: Option<usize>) -> bool {
+        let mut ancestor = parent;
+        while let Some(a) = ancestor {
+            if a == item {
+                return false;
+            }
+            ancestor = self.items[a].parent;
+        }
+
+        let old = self.items[item].parent;
+        self.siblings_mut(old).retain(|&i| i != item);
+        self.siblings_mut(parent).push(item);
+        self.items[item].parent = parent;
+        if let Some(parent) = parent {
+            self.items[parent].expanded = true;
+        }
+        self.events.push(TreeEvent::Reparented { item, parent });
+        self.dirty = true;
+        true
+    }
+
+    pub fn drain_events(&mut self) -> Vec<TreeEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    // Call before `UIScene::input`, and skip it if this returns true.
+    pub fn input(&mut self, scene: &UIScene, event: &WindowEvent) -> bool {
+        self.refresh();
+        match event {
+            WindowEvent::CursorMoved { position, .. } => {
+                self.cursor = cgmath::Vector2::new(
+                    position.x as f32 - scene.viewport.x,
+                    position.y as f32 - scene.viewport.y,
+                );
+                if let Some(press) = &self.press {
+                    if !self.dragging
+                        && scene
+                            .input_config
+                            .exceeds_deadzone(press.screen, self.cursor)
+                    {
+                        self.dragging = true;
+                    }
+                }
+                self.dragging
+            }
+            WindowEvent::MouseWheel { delta, .. } if self.bounds.contains(self.cursor) => {
+                let delta = scene.input_config.scroll_delta(delta);
+                self.scroll_by(-delta.y);
+                true
+            }
+            WindowEvent::MouseInput {
+                state: ElementState::Pressed,
+                button: MouseButton::Left,
+                ..
+            } => {
+                self.focused = self.bounds.contains(self.cursor);
+                if !self.focused {
+                    return false;
+                }
+                let Some(row) = self.row_at(self.cursor) else {
+                    return true;
+                };
+                let (item, depth) = self.visible[row];
+                let indent = depth as f32 * self.style.indent;
+                let direction = self.direction.unwrap_or(scene.direction);
+                let (a, b) = (
+                    direction.place(self.bounds.x, self.bounds.width, indent),
+                    direction.place(self.bounds.x, self.bounds.width, indent + self.style.indent),
+                );
+                let has_children = !self.items[item].children.is_empty();
+                if has_children && (a.min(b)..a.max(b)).contains(&self.cursor.x) {
+                    self.set_expanded(item, !self.items[item].expanded);
+                    return true;
+                }
+
+                let modifiers = scene.shortcuts.modifiers();
+                if modifiers.shift() {
+                    self.select_range(item);
+                } else if modifiers.ctrl() {
+                    self.toggle_selected(item);
+                } else if !self.selected.contains(&item) {
+                    self.select_only(item);
+                }
+                // Clicking a selected item keeps the selection until release,
+                // so it can still be dragged as a whole.
+                self.press = Some(Press {
+                    screen: self.cursor,
+                    item,
+                });
+                true
+            }
+            WindowEvent::MouseInput {
+                state: ElementState::Released,
+                button: MouseButton::Left,
+                ..
+            } => {
+                let Some(press) = self.press.take() else {
+                    return false;
+                };
+                if std::mem::take(&mut self.dragging) {
+                    if let Some(parent) = self.drop_target() {
+                        let mut moved = self.dragged_roots();
+                        moved.sort_by_key(|&item| self.display_index(item));
+                        for item in moved {
+                            self.reparent(item, parent);
+                        }
+                    }
+                } else {
+                    let modifiers = scene.shortcuts.modifiers();
+                    if !modifiers.shift() && !modifiers.ctrl() && self.selected.len() > 1 {
+                        self.select_only(press.item);
+                    }
+                }
+                true
+            }
+            WindowEvent::KeyboardInput {
+                input:
+                    KeyboardInput {
+                        state: ElementState::Pressed,
+                        virtual_keycode: Some(key),
+                        ..
+                    },
+                ..
+            } if self.focused => self.key(scene, *key),
+            _ => false,
+        }
+    }
+
+    fn key(&mut self, scene: &UIScene, key: VirtualKeyCode) -> bool {
+        let Some(lead) = self.lead.filter(|&lead| self.display_index(lead).is_some()) else {
+            if let Some(&(first, _)) = self.visible.first() {
+                self.select_only(first);
+            }
+            return !self.visible.is_empty();
+        };
+        let row = self.display_index(lead).unwrap_or(0);
+        let item = &self.items[lead];
+        let direction = self.direction.unwrap_or(scene.direction);
+        let target = match key {
+            VirtualKeyCode::Up => row.checked_sub(1).map(|row| self.visible[row].0),
+            VirtualKeyCode::Down => self.visible.get(row + 1).map(|&(item, _)| item),
+            key if key == direction.backward_key()
+                && item.expanded
+                && !item.children.is_empty() =>
+            {
+                self.set_expanded(lead, false);
+                return true;
+            }
+            key if key == direction.backward_key() => item.parent,
+            key if key == direction.forward_key()
+                && !item.expanded
+                && !item.children.is_empty() =>
+            {
+                self.set_expanded(lead, true);
+                return true;
+            }
+            key if key == direction.forward_key() => item.children.first().copied(),
+            VirtualKeyCode::Space => {
+                self.toggle_selected(lead);
+                return true;
+            }
+            _ => return false,
+        };
+
+        if let Some(target) = target {
+            if scene.shortcuts.modifiers().shift() {
+                self.select_range(target);
+            } else {
+                self.select_only(target);
+            }
+            self.scroll_to(target);
+        }
+        true
+    }
+
+    // Places the visible rows. Call before `UIScene::prepare`.
+    pub fn update(
+        &mut self,
+        scene: &mut UIScene,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        atlas: &mut GlyphAtlas,
+    ) {
+        self.refresh();
+        let style = &self.style;
+        let scale = 1.0 / scene.camera.scale;
+        let direction = self.direction.unwrap_or(scene.direction);
+        let (left, span) = (self.bounds.x, self.bounds.width);
+        let first = (self.scroll / style.row_height).floor().max(0.0) as usize;
+        let drop = if self.dragging {
+            self.drop_target()
+        } else {
+            None
+        };
+
+        for (slot, row) in self.rows.iter_mut().enumerate() {
+            let shown = self.visible.get(first + slot).copied();
+            for element in [row.background, row.disclosure] {
+                scene.elements[element].visible = shown.is_some();
+                scene.elements[element].clip = Some(self.bounds);
+            }
+            row.label.set_visible(scene, shown.is_some());
+            let Some((item, depth)) = shown else {
+                row.label.update(scene, device, atlas);
+                continue;
+            };
+
+            let top = self.bounds.y + (first + slot) as f32 * style.row_height - self.scroll;
+            let center_y = top + style.row_height / 2.0;
+            let indent = depth as f32 * style.indent;
+            let place = |element: &mut Player, x: f32| {
+                let world = scene.camera.screen_to_world(x, center_y);
+                element.instance.position.x = world.x;
+                element.instance.position.y = world.y;
+                element.instance.scale = scale;
+            };
+
+            let background = &mut scene.elements[row.background];
+            place(background, self.bounds.x + self.bounds.width / 2.0);
+            let color = if self.selected.contains(&item) {
+                style.selected
+            } else {
+                style.row
+            };
+            let tint = if drop == Some(Some(item)) {
+                style.drop_tint
+            } else {
+                [1.0; 4]
+            };
+            background.instance.tint = [
+                color[0] * tint[0],
+                color[1] * tint[1],
+                color[2] * tint[2],
+                tint[3],
+            ];
+
+            let entry = &self.items[item];
+            let disclosure = &mut scene.elements[row.disclosure];
+            place(
+                disclosure,
+                direction.place(left, span, indent + style.indent / 2.0),
+            );
+            disclosure.visible = !entry.children.is_empty();
+            let angle = if entry.expanded || !self.filter.is_empty() {
+                cgmath::Deg(-90.0)
+            } else {
+                direction.flow_angle()
+            };
+            disclosure.instance.rotation = cgmath::Quaternion::from_angle_z(angle);
+
+            // The label fills the rest of the row, towards its end.
+            let (start, end) = (
+                direction.place(left, span, indent + style.indent),
+                direction.place(left, span, span),
+            );
+            let layout = TextLayout {
+                max_width: Some((end - start).abs()),
+                height: Some(style.row_height),
+                wrap: false,
+                align: if direction.is_rtl() {
+                    TextAlign::Right
+                } else {
+                    TextAlign::Left
+                },
+                vertical_align: VerticalAlign::Middle,
+                ..TextLayout::default()
+            };
+            if *row.label.layout() != layout {
+                row.label.set_layout(scene, device, queue, atlas, layout);
+            }
+            let text = match find_match(&entry.label, &self.filter) {
+                Some(found) => RichText::new()
+                    .push(&entry.label[..found.start], SpanStyle::default())
+                    .push(
+                        &entry.label[found.clone()],
+                        SpanStyle::default().color(style.highlight),
+                    )
+                    .push(&entry.label[found.end..], SpanStyle::default()),
+                None => RichText::from(entry.label.as_str()),
+            };
+            if *row.label.rich_text() != text {
+                row.label.set_rich_text(scene, device, queue, atlas, text);
+            }
+            let top_left = scene.camera.screen_to_world(start.min(end), top);
+            row.label.set_scale(scene, scale);
+            row.label.set_position(scene, top_left);
+            for &glyph in row.label.elements() {
+                scene.elements[glyph].clip = Some(self.bounds);
+            }
+            row.label.update(scene, device, atlas);
+        }
+    }
+
+    fn refresh(&mut self) {
+        if !std::mem::take(&mut self.dirty) {
+            return;
+        }
+        self.visible.clear();
+        // While filtering, the matches and the items they're in.
+        let mut kept = vec![self.filter.is_empty(); self.items.len()];
+        if !self.filter.is_empty() {
+            for item in 0..self.items.len() {
+                if find_match(&self.items[item].label, &self.filter).is_none() {
+                    continue;
+                }
+                let mut ancestor = Some(item);
+                while let Some(a) = ancestor.filter(|&a| !kept[a]) {
+                    kept[a] = true;
+                    ancestor = self.items[a].parent;
+                }
+            }
+        }
+        let mut stack: Vec<(usize, usize)> = self.roots.iter().rev().map(|&i| (i, 0)).collect();
+        while let Some((item, depth)) = stack.pop() {
+            if !kept[item] {
+                continue;
+            }
+            self.visible.push((item, depth));
+            if self.items[item].expanded || !self.filter.is_empty() {
+                stack.extend(
+                    self.items[item]
+                        .children
+                        .iter()
+                        .rev()
+                        .map(|&i| (i, depth + 1)),
+                );
+            }
+        }
+        self.scroll_by(0.0);
+    }
+
+    fn reserve_rows(&mut self, scene: &mut UIScene, device: &wgpu::Device) {
+        let needed = (self.bounds.height / self.style.row_height).ceil() as usize + 1;
+        let white = [1.0, 1.0, 1.0];
+        let push = |scene: &mut UIScene, mut element: Player| {
+            element.draggable = false;
+            element.visible = false;
+            scene.elements.push(element);
+            scene.elements.len() - 1
+        };
+        while self.rows.len() < needed {
+            let origin = cgmath::Vector2::new(0.0, 0.0);
+            let size = cgmath::Vector2::new(self.bounds.width, self.style.row_height);
+            let background = push(
+                scene,
+                Player::with_gradient(device, origin, size, white, white),
+            );
+            let disclosure = push(scene, chevron(device, self.style.disclosure));
+            let mut label = Text::new(self.font.clone(), self.style.font_size);
+            label.set_color(scene, self.style.label);
+            self.rows.push(RowElements {
+                background,
+                disclosure,
+                label,
+            });
+        }
+    }
+
+    fn siblings_mut(&mut self, parent: Option<usize>) -> &mut Vec<usize> {
+        match parent {
+            Some(parent) => &mut self.items[parent].children,
+            None => &mut self.roots,
+        }
+    }
+
+    fn display_index(&self, item: usize) -> Option<usize> {
+        self.visible.iter().position(|&(i, _)| i == item)
+    }
+
+    fn row_at(&self, point: cgmath::Vector2<f32>) -> Option<usize> {
+        if !self.bounds.contains(point) {
+            return None;
+        }
+        let row = ((point.y - self.bounds.y + self.scroll) / self.style.row_height) as usize;
+        (row < self.visible.len()).then_some(row)
+    }
+
+    fn scroll_by(&mut self, delta: f32) {
+        let content = self.visible.len() as f32 * self.style.row_height;
+        let max = (content - self.bounds.height).max(0.0);
+        self.scroll = (self.scroll + delta).clamp(0.0, max);
+    }
+
+    fn scroll_to(&mut self, item: usize) {
+        let Some(row) = self.display_index(item) else {
+            return;
+        };
+        let top = row as f32 * self.style.row_height;
+        let bottom = top + self.style.row_height;
+        if top < self.scroll {
+            self.scroll_by(top - self.scroll);
+        } else if bottom > self.scroll + self.bounds.height {
+            self.scroll_by(bottom - self.scroll - self.bounds.height);
+        }
+    }
+
+    fn select_only(&mut self, item: usize) {
+        self.selected = vec![item];
+        self.anchor = Some(item);
+        self.lead = Some(item);
+        self.events.push(TreeEvent::SelectionChanged);
+    }
+
+    fn toggle_selected(&mut self, item: usize) {
+        if let Some(i) = self.selected.iter().position(|&s| s == item) {
+            self.selected.remove(i);
+        } else {
+            self.selected.push(item);
+        }
+        self.anchor = Some(item);
+        self.lead = Some(item);
+        self.events.push(TreeEvent::SelectionChanged);
+    }
+
+    // Selects the visible rows between the anchor and `item`.
+    fn select_range(&mut self, item: usize) {
+        let anchor = self.anchor.unwrap_or(item);
+        let (Some(from), Some(to)) = (self.display_index(anchor), self.display_index(item)) else {
+            return self.select_only(item);
+        };
+        let range = from.min(to)..=from.max(to);
+        self.selected = self.visible[range].iter().map(|&(i, _)| i).collect();
+        self.anchor = Some(anchor);
+        self.lead = Some(item);
+        self.events.push(TreeEvent::SelectionChanged);
+    }
+
+    // Selected items whose ancestors aren't selected too, which move along
+    // with their parent anyway.
+    fn dragged_roots(&self) -> Vec<usize> {
+        self.selected
+            .iter()
+            .copied()
+            .filter(|&item| {
+                let mut ancestor = self.items[item].parent;
+                while let Some(a) = ancestor {
+                    if self.selected.contains(&a) {
+                        return false;
+                    }
+                    ancestor = self.items[a].parent;
+                }
+                true
+            })
+            .collect()
+    }
+
+    // The parent dragged items would get, `Some(None)` being the top level.
+    // Dropping into a dragged item or one of its children isn't possible.
+    fn drop_target(&self) -> Option<Option<usize>> {
+        if !self.bounds.contains(self.cursor) {
+            return None;
+        }
+        let Some(row) = self.row_at(self.cursor) else {
+            return Some(None);
+        };
+        let target = self.visible[row].0;
+        let mut ancestor = Some(target);
+        while let Some(a) = ancestor {
+            if self.selected.contains(&a) {
+                return None;
+            }
+            ancestor = self.items[a].parent;
+        }
+        Some(Some(target))
+    }
+}
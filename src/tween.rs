@@ -0,0 +1,86 @@
+use serde::{Deserialize, Serialize};
+
+pub const EASING_SAMPLES: usize = 32;
+
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Easing {
+    Linear,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+    // Evenly spaced samples over 0..1, interpolated linearly. Used for
+    // curves made in the curve editor, see `Curve::to_easing`.
+    Sampled([f32; EASING_SAMPLES]),
+}
+
+impl Easing {
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t,
+            Easing::EaseOut => 1.0 - (1.0 - t) * (1.0 - t),
+            Easing::EaseInOut => {
+                if t < 0.5 {
+                    2.0 * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(2) / 2.0
+                }
+            }
+            Easing::Sampled(samples) => {
+                let x = t * (EASING_SAMPLES - 1) as f32;
+                let i = (x as usize).min(EASING_SAMPLES - 2);
+                samples[i] + (samples[i + 1] - samples[i]) * (x - i as f32)
+            }
+        }
+    }
+}
+
+// A value that animates from `from` to `to` over `duration` seconds.
+// Retargeting mid-flight starts the new animation from the current value,
+// so interrupted transitions don't jump.
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+pub struct Tween {
+    pub from: f32,
+    pub to: f32,
+    pub duration: f32,
+    pub elapsed: f32,
+    pub easing: Easing,
+}
+
+impl Tween {
+    pub fn new(value: f32, duration: f32, easing: Easing) -> Self {
+        Self {
+            from: value,
+            to: value,
+            duration,
+            elapsed: duration,
+            easing,
+        }
+    }
+
+    pub fn value(&self) -> f32 {
+        if self.duration <= 0.0 {
+            return self.to;
+        }
+        let t = self.easing.apply(self.elapsed / self.duration);
+        self.from + (self.to - self.from) * t
+    }
+
+    pub fn retarget(&mut self, to: f32) {
+        if to == self.to {
+            return;
+        }
+        self.from = self.value();
+        self.to = to;
+        self.elapsed = 0.0;
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        self.elapsed = (self.elapsed + dt).min(self.duration);
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+}
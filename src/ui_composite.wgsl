@@ -0,0 +1,17 @@
+// Blends the resolved multisampled UI over the target
+
+@group(0) @binding(0)
+var t_ui: texture_2d<f32>;
+
+// A single triangle covering the whole screen, no vertex buffer needed.
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> @builtin(position) vec4<f32> {
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    return vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+}
+
+// The UI texture is the size of the target, so pixels map one to one.
+@fragment
+fn fs_main(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    return textureLoad(t_ui, vec2<i32>(position.xy), 0);
+}
@@ -1,17 +1,52 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::time::Instant;
+
 use cgmath::{Rotation3, SquareMatrix};
+use lyon::math::{point, vector, Angle, Box2D, Point};
+use lyon::path::builder::BorderRadii;
+use lyon::path::{Path, Polygon, Winding};
+use lyon::tessellation::{
+    BuffersBuilder, FillGeometryBuilder, FillOptions, FillTessellator, FillVertex, LineCap,
+    LineJoin, StrokeOptions, StrokeTessellator, StrokeVertex, VertexBuffers,
+};
+use serde::{Deserialize, Serialize};
 use wgpu::util::DeviceExt;
-use winit::event::{ElementState, KeyboardInput, VirtualKeyCode, WindowEvent};
+use winit::event::{
+    ElementState, KeyboardInput, MouseButton, MouseScrollDelta, VirtualKeyCode, WindowEvent,
+};
+
+use crate::atlas::Atlas;
+use crate::clipboard::{Clipboard, LocalClipboard};
+use crate::color::linear_to_srgb;
+use crate::debug_flags::DebugFlags;
+use crate::deletion_queue::DeletionQueue;
+use crate::document::Document;
+use crate::frame_context::{FrameContext, PerFrame};
+use crate::gamma_audit::blends_in_linear_space;
+use crate::gpu_context::GpuContext;
+use crate::gpu_errors;
+use crate::guides::Guides;
+use crate::input::{InputConfig, ScrollPriority};
+use crate::kinetic::{Bounds, Kinetic};
+use crate::layout_direction::LayoutDirection;
+use crate::shortcuts::{Chord, ShortcutRegistry, ShortcutScope};
+use crate::texture::Texture;
+use crate::tween::{Easing, Tween};
 
 #[repr(C)]
 #[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct Vertex {
     pub position: [f32; 3],
-    pub color: [f32; 3],
+    // Straight alpha, the shader premultiplies it.
+    pub color: [f32; 4],
+    // 0..1 across the shape's bounding box, +v pointing up.
+    pub uv: [f32; 2],
 }
 
 impl Vertex {
-    const ATTRIBS: [wgpu::VertexAttribute; 2] =
-        wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x3];
+    const ATTRIBS: [wgpu::VertexAttribute; 3] =
+        wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x4, 2 => Float32x2];
     fn desc() -> wgpu::VertexBufferLayout<'static> {
         use std::mem;
         wgpu::VertexBufferLayout {
@@ -22,17 +57,248 @@ impl Vertex {
     }
 }
 
+// A vertex color from an RGB one.
+pub fn opaque([r, g, b]: [f32; 3]) -> [f32; 4] {
+    [r, g, b, 1.0]
+}
+
+// The scissor rect covering `rect`, in target pixels. Edges round outward
+// so fractional rects lose nothing, and stay within `viewport`, which
+// wgpu keeps within the target, so the rect never reaches past it.
+fn scissor_rect(viewport: &Viewport, rect: &Viewport) -> (u32, u32, u32, u32) {
+    let (min_x, max_x) = (viewport.x.floor(), (viewport.x + viewport.width).ceil());
+    let (min_y, max_y) = (viewport.y.floor(), (viewport.y + viewport.height).ceil());
+    let x0 = rect.x.floor().clamp(min_x, max_x).max(0.0);
+    let y0 = rect.y.floor().clamp(min_y, max_y).max(0.0);
+    let x1 = (rect.x + rect.width).ceil().clamp(x0, max_x.max(x0));
+    let y1 = (rect.y + rect.height).ceil().clamp(y0, max_y.max(y0));
+    (x0 as u32, y0 as u32, (x1 - x0) as u32, (y1 - y0) as u32)
+}
+
+// Whether `b` can be drawn in the same instanced call as `a`, being the
+// same geometry through the same pipeline and bind groups.
+fn shares_draw(a: &Player, b: &Player) -> bool {
+    Rc::ptr_eq(&a.vertex_buffer, &b.vertex_buffer)
+        && Rc::ptr_eq(&a.index_buffer, &b.index_buffer)
+        && a.num_indices == b.num_indices
+        && (a.material, a.blend) == (b.material, b.blend)
+        && a.texture_fill.as_ref().map(Rc::as_ptr) == b.texture_fill.as_ref().map(Rc::as_ptr)
+}
+
+// `PALETTE_SIZE` sRGB texels through the stops, holding the first and last
+// colors past the ends. Stops needn't be sorted.
+fn bake_gradient(stops: &[(f32, [f32; 4])]) -> Vec<[u8; 4]> {
+    let mut stops = stops
+        .iter()
+        .map(|&(offset, [r, g, b, a])| (offset.clamp(0.0, 1.0), [r * a, g * a, b * a, a]))
+        .collect::<Vec<_>>();
+    stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+    (0..PALETTE_SIZE)
+        .map(|i| {
+            let t = i as f32 / (PALETTE_SIZE - 1) as f32;
+            let next = stops.partition_point(|&(offset, _)| offset < t);
+            let [r, g, b, a] = match (next.checked_sub(1), stops.get(next)) {
+                (Some(before), Some(&(end, after))) => {
+                    let (start, before) = stops[before];
+                    let f = if end > start {
+                        (t - start) / (end - start)
+                    } else {
+                        1.0
+                    };
+                    [0, 1, 2, 3].map(|c| before[c] + (after[c] - before[c]) * f)
+                }
+                (Some(last), None) => stops[last].1,
+                (None, Some(&(_, first))) => first,
+                (None, None) => [0.0; 4],
+            };
+            let straight = |c: f32| if a > 0.0 { c / a } else { 0.0 };
+            let encode = |c: f32| (linear_to_srgb(c.clamp(0.0, 1.0)) * 255.0).round() as u8;
+            [
+                encode(straight(r)),
+                encode(straight(g)),
+                encode(straight(b)),
+                (a.clamp(0.0, 1.0) * 255.0).round() as u8,
+            ]
+        })
+        .collect()
+}
+
+// Slots in `Instance::params` understood by the built-in UI material.
+// Custom materials are free to interpret the four floats however they like.
+pub const PARAM_PHASE: usize = 0;
+pub const PARAM_PROGRESS: usize = 1;
+pub const PARAM_SELECTION: usize = 2;
+pub const PARAM_BORDER: usize = 3;
+// Materials without a fill read their palette row from the progress slot.
+pub const PARAM_PALETTE: usize = PARAM_PROGRESS;
+// Entries per palette, see `UIScene::set_palettes`.
+pub const PALETTE_SIZE: usize = 256;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum UIMaterial {
+    Solid,
+    // Clock wipe starting at 12 o'clock, going clockwise up to
+    // `params[PARAM_PROGRESS]`. Unfilled parts aren't drawn, so put a
+    // background element underneath for cooldown indicators.
+    RadialFill,
+    // Left to right fill up to `params[PARAM_PROGRESS]`.
+    LinearFill,
+    // Looks the vertex color up in palette `params[PARAM_PALETTE]`, for team
+    // colors and damage flashes from one greyscale asset. The tint still
+    // applies on top.
+    PaletteSwap,
+    // Samples the scene's atlas within the sub-rect held in `params` as
+    // u0, v0, u1, v1, see `atlas::Atlas::sprite`. Times the vertex color
+    // and tint.
+    Sprite,
+    // Repeats the scene's pattern texture across the shape, see
+    // `set_pattern` and `Player::fill_pattern`. Times the vertex color and
+    // tint.
+    Pattern,
+    // One of the scene's gradients across the element's bounds, see
+    // `add_gradient` and `set_fill`. Times the vertex color and tint.
+    Gradient,
+    // A glyph's distance field in the texture fill, sharp at any zoom, see
+    // `text::GlyphAtlas::sdf`. Fills with the tint, and draws an outline or
+    // glow in the vertex color.
+    Sdf,
+}
+
+impl UIMaterial {
+    const ALL: [UIMaterial; 8] = [
+        UIMaterial::Solid,
+        UIMaterial::RadialFill,
+        UIMaterial::LinearFill,
+        UIMaterial::PaletteSwap,
+        UIMaterial::Sprite,
+        UIMaterial::Pattern,
+        UIMaterial::Gradient,
+        UIMaterial::Sdf,
+    ];
+
+    fn entry_point(&self) -> &'static str {
+        match self {
+            UIMaterial::Solid => "fs_main",
+            UIMaterial::RadialFill => "fs_radial_fill",
+            UIMaterial::LinearFill => "fs_linear_fill",
+            UIMaterial::PaletteSwap => "fs_palette_swap",
+            UIMaterial::Sprite => "fs_sprite",
+            UIMaterial::Pattern => "fs_pattern",
+            UIMaterial::Gradient => "fs_gradient",
+            UIMaterial::Sdf => "fs_sdf",
+        }
+    }
+
+    // Materials that read `params` as data don't pulse when selected, nor
+    // get a border, and those slots are left to them.
+    pub(crate) fn shows_selection(&self) -> bool {
+        !matches!(
+            self,
+            UIMaterial::Sprite | UIMaterial::Pattern | UIMaterial::Gradient | UIMaterial::Sdf
+        )
+    }
+}
+
+// How a gradient runs across an element's bounds. Angles are in radians.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum GradientKind {
+    // Left to right at 0, turning counterclockwise, from corner to corner.
+    Linear { angle: f32 },
+    // From the center out to the middle of the edges.
+    Radial,
+    // Around the center, clockwise from 12 o'clock turned by `angle`.
+    Conic { angle: f32 },
+}
+
+// What fills an element's shape, see `UIScene::set_fill`.
+pub enum Fill<'a> {
+    // The vertex colors, as the element was made.
+    Solid,
+    // Stretched over the bounds, see `UIScene::set_texture_fill`.
+    Texture(&'a Texture),
+    // A gradient added with `UIScene::add_gradient`.
+    Gradient { gradient: usize, kind: GradientKind },
+}
+
+// What `UIMaterial::Pattern` tiles are laid out in.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PatternSpace {
+    // The element's own units, so the pattern moves, turns and scales with
+    // it.
+    Local,
+    // Scene units, so neighbouring elements line up like windows onto one
+    // pattern, e.g. a striped background split into panels.
+    World,
+}
+
+// How an element's colors combine with what's already drawn under it.
+// Non-normal modes only see the scene itself when multisampling, which draws
+// the UI onto an empty target before blending it over the frame.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum BlendMode {
+    #[default]
+    Normal,
+    // Brightens, for glows and light effects.
+    Additive,
+    // Darkens, for shadows and tinted glass.
+    Multiply,
+    // Brightens without blowing out, for highlights.
+    Screen,
+}
+
+impl BlendMode {
+    const VARIANTS: [BlendMode; 3] = [BlendMode::Additive, BlendMode::Multiply, BlendMode::Screen];
+
+    // Colors coming out of the shader are premultiplied.
+    fn blend_state(&self) -> wgpu::BlendState {
+        let over = wgpu::BlendComponent {
+            src_factor: wgpu::BlendFactor::One,
+            dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
+            operation: wgpu::BlendOperation::Add,
+        };
+        let color = |src_factor, dst_factor| wgpu::BlendComponent {
+            src_factor,
+            dst_factor,
+            operation: wgpu::BlendOperation::Add,
+        };
+        match self {
+            BlendMode::Normal => wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING,
+            // Adds light without covering anything, so coverage stays as is.
+            BlendMode::Additive => wgpu::BlendState {
+                color: color(wgpu::BlendFactor::One, wgpu::BlendFactor::One),
+                alpha: color(wgpu::BlendFactor::Zero, wgpu::BlendFactor::One),
+            },
+            // src * dst where covered, dst elsewhere.
+            BlendMode::Multiply => wgpu::BlendState {
+                color: color(wgpu::BlendFactor::Dst, wgpu::BlendFactor::OneMinusSrcAlpha),
+                alpha: over,
+            },
+            // 1 - (1 - src) * (1 - dst).
+            BlendMode::Screen => wgpu::BlendState {
+                color: color(wgpu::BlendFactor::One, wgpu::BlendFactor::OneMinusSrc),
+                alpha: over,
+            },
+        }
+    }
+}
+
 pub struct Instance {
     pub position: cgmath::Vector3<f32>,
     pub rotation: cgmath::Quaternion<f32>,
+    pub scale: f32,
+    pub tint: [f32; 4],
+    pub params: [f32; 4],
 }
 
 impl Instance {
     fn to_raw(&self) -> InstanceRaw {
         InstanceRaw {
             model: (cgmath::Matrix4::from_translation(self.position)
-                * cgmath::Matrix4::from(self.rotation))
+                * cgmath::Matrix4::from(self.rotation)
+                * cgmath::Matrix4::from_scale(self.scale))
             .into(),
+            tint: self.tint,
+            params: self.params,
         }
     }
 }
@@ -41,11 +307,15 @@ impl Instance {
 #[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
 struct InstanceRaw {
     pub model: [[f32; 4]; 4],
+    pub tint: [f32; 4],
+    pub params: [f32; 4],
 }
 
 impl InstanceRaw {
-    const ATTRIBS: [wgpu::VertexAttribute; 4] =
-        wgpu::vertex_attr_array![5 => Float32x4, 6 => Float32x4, 7 => Float32x4, 8 => Float32x4];
+    const ATTRIBS: [wgpu::VertexAttribute; 6] = wgpu::vertex_attr_array![
+        5 => Float32x4, 6 => Float32x4, 7 => Float32x4, 8 => Float32x4, 9 => Float32x4,
+        10 => Float32x4
+    ];
 
     fn desc() -> wgpu::VertexBufferLayout<'static> {
         use std::mem;
@@ -57,127 +327,3326 @@ impl InstanceRaw {
     }
 }
 
-const VERTICES: &[Vertex] = &[
-    Vertex {
-        position: [-0.5, -0.5, 0.0],
-        color: [0.5, 0.0, 0.0],
-    }, // A
-    Vertex {
-        position: [0.5, -0.5, 0.0],
-        color: [0.5, 0.0, 0.0],
-    }, // B
-    Vertex {
-        position: [0.5, 0.5, 0.0],
-        color: [0.5, 0.0, 0.0],
-    } // C
+pub const OPENGL_TO_WGPU_MATRIX: cgmath::Matrix4<f32> = cgmath::Matrix4::new(
+    1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.5, 0.5, 0.0, 0.0, 0.0, 1.0,
+);
 
-];
-const INDICES: &[u16] = &[0, 1, 2];
+// World units map 1:1 to pixels at scale 1.0, with the origin at the center
+// of the window and +y pointing up.
+pub struct OrthographicCamera {
+    pub position: cgmath::Vector2<f32>,
+    pub scale: f32,
+    pub width: f32,
+    pub height: f32,
+}
 
-pub struct UIScene {
-    pub render_pipeline: wgpu::RenderPipeline,
-    pub vertex_buffer: wgpu::Buffer,
-    pub index_buffer: wgpu::Buffer,
+impl OrthographicCamera {
+    fn new(width: f32, height: f32) -> Self {
+        Self {
+            position: cgmath::Vector2::new(0.0, 0.0),
+            scale: 1.0,
+            width,
+            height,
+        }
+    }
+
+    pub fn add_scale(&mut self, amount: f32) {
+        self.scale = (self.scale + amount).max(0.1);
+    }
+
+    fn build_view_projection_matrix(&self) -> cgmath::Matrix4<f32> {
+        let half_w = self.width / 2.0 / self.scale;
+        let half_h = self.height / 2.0 / self.scale;
+        let proj = cgmath::ortho(
+            self.position.x - half_w,
+            self.position.x + half_w,
+            self.position.y - half_h,
+            self.position.y + half_h,
+            -1.0,
+            1.0,
+        );
+        OPENGL_TO_WGPU_MATRIX * proj
+    }
+
+    pub fn screen_to_world(&self, x: f32, y: f32) -> cgmath::Vector2<f32> {
+        cgmath::Vector2::new(
+            (x - self.width / 2.0) / self.scale + self.position.x,
+            (self.height / 2.0 - y) / self.scale + self.position.y,
+        )
+    }
+
+    pub fn world_to_screen(&self, point: cgmath::Vector2<f32>) -> cgmath::Vector2<f32> {
+        cgmath::Vector2::new(
+            (point.x - self.position.x) * self.scale + self.width / 2.0,
+            self.height / 2.0 - (point.y - self.position.y) * self.scale,
+        )
+    }
+
+    // The screen rect covering the world rect between the two corners, in
+    // any order. Screen space goes down while the world goes up.
+    pub fn world_rect_to_screen(
+        &self,
+        a: cgmath::Vector2<f32>,
+        b: cgmath::Vector2<f32>,
+    ) -> Viewport {
+        let (a, b) = (self.world_to_screen(a), self.world_to_screen(b));
+        Viewport {
+            x: a.x.min(b.x),
+            y: a.y.min(b.y),
+            width: (a.x - b.x).abs(),
+            height: (a.y - b.y).abs(),
+        }
+    }
 }
 
-impl UIScene {
-    pub async fn new(device: &wgpu::Device, config: &wgpu::SurfaceConfiguration) -> Self {
-        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-            label: Some("shader"),
-            source: wgpu::ShaderSource::Wgsl(include_str!("ui_shader.wgsl").into()),
-        });
+// What the scene draws behind its elements. Colors are linear with straight
+// alpha, see `color::Color` for sRGB ones.
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub enum Background {
+    // Draw over whatever is in the target, e.g. the 3D scene.
+    #[default]
+    None,
+    // Clears the whole target, not just the scene's viewport.
+    Solid([f32; 4]),
+    // Top to bottom across the viewport.
+    VerticalGradient {
+        top: [f32; 4],
+        bottom: [f32; 4],
+    },
+    // Squares of `size` pixels, the usual backdrop for transparent content.
+    Checkerboard {
+        light: [f32; 4],
+        dark: [f32; 4],
+        size: f32,
+    },
+}
 
-        let render_pipeline_layout =
-            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-                label: Some("UI Render pipeline layout"),
-                bind_group_layouts: &[],
-                push_constant_ranges: &[],
-            });
+impl Background {
+    fn mode(&self) -> u32 {
+        match self {
+            Background::None | Background::Solid(_) => 0,
+            Background::VerticalGradient { .. } => 1,
+            Background::Checkerboard { .. } => 2,
+        }
+    }
+}
 
-        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("UI Render Pipeline"),
-            layout: Some(&render_pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &shader,
-                entry_point: "vs_main",     // 1.
-                buffers: &[Vertex::desc()], // 2.
-            },
-            fragment: Some(wgpu::FragmentState {
-                // 3.
-                module: &shader,
-                entry_point: "fs_main",
-                targets: &[Some(wgpu::ColorTargetState {
-                    // 4.
-                    format: config.format,
-                    blend: Some(wgpu::BlendState::REPLACE),
-                    write_mask: wgpu::ColorWrites::ALL,
-                })],
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct CameraUniform {
+    view_proj: [[f32; 4]; 4],
+    // Seconds since the scene started, for shader-side animations. Lives
+    // here so animated materials don't need a bind group of their own.
+    time: f32,
+    // Vertex colors are linear. Targets that neither encode to sRGB on write
+    // nor store floats get them encoded by the shader instead, so they still
+    // look as intended, blending in gamma space aside.
+    encode_srgb: u32,
+    // Drawn by the background pass, see `Background`.
+    background_mode: u32,
+    checker_size: f32,
+    background: [[f32; 4]; 2],
+}
+
+impl CameraUniform {
+    fn new() -> Self {
+        Self {
+            view_proj: cgmath::Matrix4::identity().into(),
+            time: 0.0,
+            encode_srgb: 0,
+            background_mode: 0,
+            checker_size: 0.0,
+            background: [[0.0; 4]; 2],
+        }
+    }
+
+    pub(crate) fn update_view_proj(&mut self, camera: &OrthographicCamera) {
+        self.view_proj = camera.build_view_projection_matrix().into();
+    }
+
+    fn update_background(&mut self, background: &Background) {
+        self.background_mode = background.mode();
+        (self.background, self.checker_size) = match *background {
+            Background::VerticalGradient { top, bottom } => ([top, bottom], 0.0),
+            Background::Checkerboard { light, dark, size } => ([light, dark], size.max(1.0)),
+            Background::None | Background::Solid(_) => ([[0.0; 4]; 2], 0.0),
+        };
+    }
+}
+
+// Number of stacked shadow copies drawn under a lifted element. Each layer is
+// slightly larger and fainter than the previous one, which reads as a soft
+// shadow without needing a blur pass.
+pub(crate) const SHADOW_LAYERS: usize = 4;
+const LIFT_SCALE: f32 = 1.05;
+const LIFT_DURATION: f32 = 0.15;
+// Bounds for the degrees per segment when flattening arcs, so huge radii
+// don't end up with thousands of segments and tiny ones stay round.
+const MIN_ARC_STEP: f32 = 0.5;
+const MAX_ARC_STEP: f32 = 30.0;
+// Instances the batch buffer starts out with room for, see `UIScene::prepare`.
+const BATCH_CAPACITY: usize = 256;
+
+// How finely curved shapes, paths and outlines are flattened into triangles.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Tessellation {
+    // Largest distance from the true curve, in element units.
+    Fixed(f32),
+    // Largest distance from the true curve in screen pixels, whatever the
+    // zoom. The element is retessellated whenever zooming changes the
+    // needed tolerance by a factor of two, see `UIScene::retessellate`.
+    Screen(f32),
+}
+
+impl Default for Tessellation {
+    fn default() -> Self {
+        Tessellation::Fixed(FillOptions::DEFAULT_TOLERANCE)
+    }
+}
+
+impl Tessellation {
+    // Tolerance in element units for an element drawn `scale` times larger
+    // than its units, camera zoom included. Screen tolerances are rounded
+    // down to a power of two so small zoom changes keep the tessellation.
+    pub fn tolerance(&self, scale: f32) -> f32 {
+        match *self {
+            Tessellation::Fixed(tolerance) => tolerance,
+            Tessellation::Screen(pixels) => {
+                let tolerance = pixels / scale.max(f32::EPSILON);
+                2f32.powf(tolerance.log2().floor())
+            }
+        }
+    }
+}
+
+// Outline of an element, centered on its position.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Shape {
+    Rect {
+        width: f32,
+        height: f32,
+    },
+    // Top is the +y side, as on screen.
+    RoundedRect {
+        width: f32,
+        height: f32,
+        radii: BorderRadii,
+    },
+    Circle {
+        radius: f32,
+    },
+    Ellipse {
+        rx: f32,
+        ry: f32,
+    },
+    // Corners on a circle of `radius`, the first one pointing up.
+    Polygon {
+        sides: u32,
+        radius: f32,
+    },
+    // Points alternate between the outer and inner radius, the first one
+    // pointing up.
+    Star {
+        points: u32,
+        outer: f32,
+        inner: f32,
+    },
+    // A ring segment between two angles in degrees, counterclockwise from
+    // +x. An inner radius of 0 makes a pie slice, a sweep of 360 or more a
+    // full ring.
+    Arc {
+        radius: f32,
+        inner: f32,
+        start: f32,
+        end: f32,
+    },
+}
+
+impl Shape {
+    pub fn size(&self) -> cgmath::Vector2<f32> {
+        match *self {
+            Shape::Rect { width, height } | Shape::RoundedRect { width, height, .. } => {
+                cgmath::Vector2::new(width, height)
+            }
+            Shape::Circle { radius } => cgmath::Vector2::new(radius * 2.0, radius * 2.0),
+            Shape::Ellipse { rx, ry } => cgmath::Vector2::new(rx * 2.0, ry * 2.0),
+            Shape::Polygon { radius, .. } => cgmath::Vector2::new(radius * 2.0, radius * 2.0),
+            Shape::Star { outer, inner, .. } => {
+                let radius = outer.max(inner);
+                cgmath::Vector2::new(radius * 2.0, radius * 2.0)
+            }
+            Shape::Arc { radius, .. } => cgmath::Vector2::new(radius * 2.0, radius * 2.0),
+        }
+    }
+
+    // Corners of polygons and stars, counterclockwise. Empty for other
+    // shapes.
+    fn corners(&self) -> Vec<Point> {
+        let corner = |angle: f32, radius: f32| {
+            let angle = angle + std::f32::consts::FRAC_PI_2;
+            point(angle.cos() * radius, angle.sin() * radius)
+        };
+        match *self {
+            Shape::Polygon { sides, radius } => {
+                let sides = sides.max(3);
+                let step = std::f32::consts::TAU / sides as f32;
+                (0..sides)
+                    .map(|i| corner(i as f32 * step, radius))
+                    .collect()
+            }
+            Shape::Star {
+                points,
+                outer,
+                inner,
+            } => {
+                let corners = points.max(2) * 2;
+                let step = std::f32::consts::TAU / corners as f32;
+                (0..corners)
+                    .map(|i| corner(i as f32 * step, if i % 2 == 0 { outer } else { inner }))
+                    .collect()
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn tessellate(&self, tolerance: f32, output: &mut dyn FillGeometryBuilder) {
+        let mut tessellator = FillTessellator::new();
+        let options = FillOptions::tolerance(tolerance);
+        let center = point(0.0, 0.0);
+        match *self {
+            Shape::Rect { .. } => {
+                tessellator.tessellate_rectangle(&self.bounds(), &options, output)
+            }
+            Shape::RoundedRect { .. }
+            | Shape::Polygon { .. }
+            | Shape::Star { .. }
+            | Shape::Arc { .. } => {
+                tessellator.tessellate_path(&self.flattened(tolerance), &options, output)
+            }
+            Shape::Circle { radius } => {
+                tessellator.tessellate_circle(center, radius, &options, output)
+            }
+            Shape::Ellipse { rx, ry } => tessellator.tessellate_ellipse(
+                center,
+                vector(rx, ry),
+                Angle::radians(0.0),
+                Winding::Positive,
+                &options,
+                output,
+            ),
+        }
+        .unwrap();
+    }
+
+    fn bounds(&self) -> Box2D {
+        let half = self.size() / 2.0;
+        Box2D::new(point(-half.x, -half.y), point(half.x, half.y))
+    }
+
+    // The outline as a closed path, for stroking.
+    pub(crate) fn path(&self) -> Path {
+        self.flattened(FillOptions::DEFAULT_TOLERANCE)
+    }
+
+    // Like `path`, with arcs split into segments that stay within
+    // `tolerance` of the circle. Other curves are flattened by lyon.
+    pub(crate) fn flattened(&self, tolerance: f32) -> Path {
+        let mut builder = Path::builder();
+        let center = point(0.0, 0.0);
+        match *self {
+            Shape::Rect { .. } => builder.add_rectangle(&self.bounds(), Winding::Positive),
+            Shape::RoundedRect { radii, .. } => {
+                // lyon's top is the -y side.
+                let flipped = BorderRadii {
+                    top_left: radii.bottom_left,
+                    top_right: radii.bottom_right,
+                    bottom_left: radii.top_left,
+                    bottom_right: radii.top_right,
+                };
+                builder.add_rounded_rectangle(&self.bounds(), &flipped, Winding::Positive);
+            }
+            Shape::Circle { radius } => builder.add_circle(center, radius, Winding::Positive),
+            Shape::Ellipse { rx, ry } => builder.add_ellipse(
+                center,
+                vector(rx, ry),
+                Angle::radians(0.0),
+                Winding::Positive,
+            ),
+            Shape::Polygon { .. } | Shape::Star { .. } => builder.add_polygon(Polygon {
+                points: &self.corners(),
+                closed: true,
             }),
-            primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleList, // 1.
-                strip_index_format: None,
-                front_face: wgpu::FrontFace::Ccw, // 2.
-                cull_mode: Some(wgpu::Face::Back),
-                // Setting this to anything other than Fill requires Features::NON_FILL_POLYGON_MODE
-                polygon_mode: wgpu::PolygonMode::Fill,
-                // Requires Features::DEPTH_CLIP_CONTROL
-                unclipped_depth: false,
-                // Requires Features::CONSERVATIVE_RASTERIZATION
-                conservative: false,
-            },
-            depth_stencil: None, // 1.
-            multisample: wgpu::MultisampleState {
-                count: 1,                         // 2.
-                mask: !0,                         // 3.
-                alpha_to_coverage_enabled: false, // 4.
-            },
-            multiview: None, // 5.
-        });
+            Shape::Arc {
+                radius,
+                inner,
+                start,
+                end,
+            } => {
+                let (start, end) = (start.min(end), start.max(end));
+                // Segments spanning `step` degrees bulge `tolerance` off the
+                // outer circle.
+                let step = (2.0 * (1.0 - tolerance / radius.max(tolerance)).acos())
+                    .to_degrees()
+                    .clamp(MIN_ARC_STEP, MAX_ARC_STEP);
+                let arc = move |radius: f32, from: f32, to: f32| {
+                    let steps = ((to - from).abs() / step).ceil().max(1.0) as u32;
+                    (0..=steps).map(move |i| {
+                        let angle = (from + (to - from) * i as f32 / steps as f32).to_radians();
+                        point(angle.cos() * radius, angle.sin() * radius)
+                    })
+                };
+                if end - start >= 360.0 {
+                    // Two circles, the inner one cut out by the even-odd fill.
+                    builder.add_circle(center, radius, Winding::Positive);
+                    if inner > 0.0 {
+                        builder.add_circle(center, inner, Winding::Negative);
+                    }
+                } else {
+                    let mut points: Vec<Point> = arc(radius, start, end).collect();
+                    if inner > 0.0 {
+                        points.extend(arc(inner, end, start));
+                    } else {
+                        points.push(center);
+                    }
+                    builder.add_polygon(Polygon {
+                        points: &points,
+                        closed: true,
+                    });
+                }
+            }
+        }
+        builder.build()
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Stroke {
+    pub width: f32,
+    pub join: LineJoin,
+    // Ends of open paths, closed shapes don't have any.
+    pub cap: LineCap,
+}
+
+impl Stroke {
+    pub fn new(width: f32) -> Self {
+        Self {
+            width,
+            join: LineJoin::Miter,
+            cap: LineCap::Butt,
+        }
+    }
+
+    pub fn options(&self) -> StrokeOptions {
+        StrokeOptions::default()
+            .with_line_width(self.width)
+            .with_line_join(self.join)
+            .with_line_cap(self.cap)
+    }
+}
+
+// A border drawn centered on a shape's edge.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Outline {
+    pub stroke: Stroke,
+    pub color: [f32; 3],
+}
+
+// What an element draws, as a path in element units, for exporting it
+// without rasterizing. The fill goes from `left` to `right` across the
+// element's size box.
+#[derive(Clone)]
+pub(crate) struct Drawing {
+    pub(crate) path: Path,
+    pub(crate) fill: Option<([f32; 4], [f32; 4])>,
+    pub(crate) outline: Option<Outline>,
+}
+
+pub struct Player {
+    // Shared with other elements of the same shape when created through a
+    // `GeometryCache`, see `upload_geometry` and `reshape`.
+    pub vertex_buffer: Rc<wgpu::Buffer>,
+    pub index_buffer: Rc<wgpu::Buffer>,
+    pub num_indices: u32,
+    pub instance: Instance,
+    // The element and its shadow layers while lifted, otherwise it draws
+    // from the scene's batch buffer. One per frame in flight, so `prepare`
+    // never writes what the GPU may still be reading.
+    pub instance_buffers: PerFrame<wgpu::Buffer>,
+    pub size: cgmath::Vector2<f32>,
+    // Used for hit testing, `size` is its bounding box.
+    pub shape: Shape,
+    pub draggable: bool,
+    pub elevation: Tween,
+    pub material: UIMaterial,
+    pub blend: BlendMode,
+    // Elements with a higher z index draw over lower ones, and equal ones in
+    // insertion order. Hit testing follows the same order.
+    pub z_index: i32,
+    // Hidden elements are neither drawn nor hit-tested, which lets pooled
+    // elements be parked without removing them from the scene.
+    pub visible: bool,
+    // Disabled elements are drawn grayed out, can't be focused and don't
+    // get input. Disabling an element disables its children as well.
+    pub enabled: bool,
+    // Set by `StyleTransitions` for the elements it styles, whose disabled
+    // look then comes from their `StateStyles` instead of `disabled_tint`.
+    pub styled: bool,
+    pub parent: Option<usize>,
+    // Direction of this element's subtree, None to follow the parent's,
+    // see `UIScene::direction_of`.
+    pub direction: Option<LayoutDirection>,
+    // Screen rect, relative to the viewport, outside of which the element
+    // is neither drawn nor hit-tested.
+    pub clip: Option<Viewport>,
+    // Clips descendants to this element's screen bounds, on top of their own
+    // `clip`, e.g. for panels whose contents scroll.
+    pub clips_children: bool,
+    // What ancestors with `clips_children` leave visible, as of the last
+    // `prepare`.
+    inherited_clip: Option<Viewport>,
+    // Color replacing the tint, and how much of it is left, see `flash`.
+    flash: Option<([f32; 4], Tween)>,
+    // Applies to elements made from a shape or a path, see
+    // `set_tessellation`.
+    pub tessellation: Tessellation,
+    // What the current geometry was tessellated with.
+    tolerance: f32,
+    // Colors of elements made from a shape, to tessellate them again.
+    gradient: Option<([f32; 4], [f32; 4])>,
+    // Outlined shapes and paths, which can't be drawn again from `shape`
+    // and `gradient`.
+    drawing: Option<Drawing>,
+    // Made by `from_path`, whose `drawing` isn't centered like a shape's.
+    from_path: bool,
+    // Bumped whenever the geometry is written, so damage tracking notices
+    // changes made in place.
+    revision: u32,
+    // Material bind group sampling a texture of its own instead of the
+    // atlas, see `UIScene::set_texture_fill`.
+    pub(crate) texture_fill: Option<Rc<wgpu::BindGroup>>,
+}
+
+impl Player {
+    pub fn new(device: &wgpu::Device, position: cgmath::Vector2<f32>, color: [f32; 3]) -> Self {
+        Self::with_gradient(
+            device,
+            position,
+            cgmath::Vector2::new(50.0, 50.0),
+            color,
+            color,
+        )
+    }
+
+    // A rectangle whose color goes from `left` to `right` horizontally.
+    pub fn with_gradient(
+        device: &wgpu::Device,
+        position: cgmath::Vector2<f32>,
+        size: cgmath::Vector2<f32>,
+        left: [f32; 3],
+        right: [f32; 3],
+    ) -> Self {
+        let shape = Shape::Rect {
+            width: size.x,
+            height: size.y,
+        };
+        Self::with_shape(device, position, shape, left, right)
+    }
+
+    pub fn with_shape(
+        device: &wgpu::Device,
+        position: cgmath::Vector2<f32>,
+        shape: Shape,
+        left: [f32; 3],
+        right: [f32; 3],
+    ) -> Self {
+        Self::with_shape_rgba(device, position, shape, opaque(left), opaque(right))
+    }
+
+    // Like `with_shape`, with colors that can be translucent, e.g. a fill
+    // fading out towards one side.
+    pub fn with_shape_rgba(
+        device: &wgpu::Device,
+        position: cgmath::Vector2<f32>,
+        shape: Shape,
+        left: [f32; 4],
+        right: [f32; 4],
+    ) -> Self {
+        let geometry = Self::shape_geometry(shape, FillOptions::DEFAULT_TOLERANCE, left, right);
+        let mut element = Self::from_geometry(device, position, shape.size(), &geometry);
+        element.shape = shape;
+        element.gradient = Some((left, right));
+        element
+    }
+
+    // An outlined shape, filled with a `left` to `right` gradient unless
+    // `fill` is None. Fill and outline share one element, so they move and
+    // get hit as one.
+    pub fn with_outline(
+        device: &wgpu::Device,
+        position: cgmath::Vector2<f32>,
+        shape: Shape,
+        fill: Option<([f32; 3], [f32; 3])>,
+        outline: Outline,
+    ) -> Self {
+        let drawing = Drawing {
+            path: shape.path(),
+            fill: fill.map(|(left, right)| (opaque(left), opaque(right))),
+            outline: Some(outline),
+        };
+        let geometry = Self::outlined_geometry(shape, &drawing, FillOptions::DEFAULT_TOLERANCE);
+        let mut element = Self::from_geometry(device, position, shape.size(), &geometry);
+        element.shape = shape;
+        element.drawing = Some(drawing);
+        element
+    }
+
+    // Any path, in coordinates relative to `position`, filled with `fill`
+    // and/or stroked with `outline`. Paths aren't centered like shapes, so
+    // the hit box is made large enough to cover the path around `position`.
+    pub fn from_path(
+        device: &wgpu::Device,
+        position: cgmath::Vector2<f32>,
+        path: &Path,
+        fill: Option<[f32; 3]>,
+        outline: Option<Outline>,
+    ) -> Self {
+        assert!(
+            fill.is_some() || outline.is_some(),
+            "a path needs a fill or an outline to be drawn"
+        );
+        let drawing = Drawing {
+            path: path.clone(),
+            fill: fill.map(|color| (opaque(color), opaque(color))),
+            outline,
+        };
+        let (geometry, min, max) = Self::path_geometry(&drawing, FillOptions::DEFAULT_TOLERANCE);
+        let size = cgmath::Vector2::new(
+            2.0 * min.x.abs().max(max.x.abs()),
+            2.0 * min.y.abs().max(max.y.abs()),
+        );
+        let mut element = Self::from_geometry(device, position, size, &geometry);
+        element.drawing = Some(drawing);
+        element.from_path = true;
+        element
+    }
+
+    // The fill of the shape, if any, under its outline.
+    fn outlined_geometry(
+        shape: Shape,
+        drawing: &Drawing,
+        tolerance: f32,
+    ) -> VertexBuffers<Vertex, u16> {
+        let mut geometry = match drawing.fill {
+            Some((left, right)) => Self::shape_geometry(shape, tolerance, left, right),
+            None => VertexBuffers::new(),
+        };
+        if let Some(outline) = &drawing.outline {
+            Self::stroke_into(
+                &mut geometry,
+                &drawing.path,
+                outline,
+                shape.size(),
+                tolerance,
+            );
+        }
+        geometry
+    }
+
+    // The path's fill under its outline, with uv spanning the bounding box
+    // like it does for shapes, and that box.
+    fn path_geometry(
+        drawing: &Drawing,
+        tolerance: f32,
+    ) -> (
+        VertexBuffers<Vertex, u16>,
+        cgmath::Vector2<f32>,
+        cgmath::Vector2<f32>,
+    ) {
+        let mut geometry: VertexBuffers<Vertex, u16> = VertexBuffers::new();
+        if let Some((color, _)) = drawing.fill {
+            FillTessellator::new()
+                .tessellate_path(
+                    &drawing.path,
+                    &FillOptions::tolerance(tolerance),
+                    &mut BuffersBuilder::new(&mut geometry, |vertex: FillVertex| {
+                        let p = vertex.position();
+                        Vertex {
+                            position: [p.x, p.y, 0.0],
+                            color,
+                            uv: [0.0, 0.0],
+                        }
+                    }),
+                )
+                .unwrap();
+        }
+        if let Some(outline) = &drawing.outline {
+            let unit = cgmath::Vector2::new(1.0, 1.0);
+            Self::stroke_into(&mut geometry, &drawing.path, outline, unit, tolerance);
+        }
+
+        let mut min = cgmath::Vector2::new(f32::INFINITY, f32::INFINITY);
+        let mut max = -min;
+        for vertex in &geometry.vertices {
+            min.x = min.x.min(vertex.position[0]);
+            min.y = min.y.min(vertex.position[1]);
+            max.x = max.x.max(vertex.position[0]);
+            max.y = max.y.max(vertex.position[1]);
+        }
+        // An empty path draws nothing and shouldn't be hit either.
+        if geometry.vertices.is_empty() {
+            min = cgmath::Vector2::new(0.0, 0.0);
+            max = min;
+        }
+        let extent = max - min;
+        for vertex in &mut geometry.vertices {
+            vertex.uv = [
+                (vertex.position[0] - min.x) / extent.x.max(f32::EPSILON),
+                (vertex.position[1] - min.y) / extent.y.max(f32::EPSILON),
+            ];
+        }
+        (geometry, min, max)
+    }
+
+    // Appends the stroked path, flattened to `tolerance`, with uv relative
+    // to a `size` box centered on the origin.
+    pub(crate) fn stroke_into(
+        geometry: &mut VertexBuffers<Vertex, u16>,
+        path: &Path,
+        outline: &Outline,
+        size: cgmath::Vector2<f32>,
+        tolerance: f32,
+    ) {
+        StrokeTessellator::new()
+            .tessellate_path(
+                path,
+                &outline.stroke.options().with_tolerance(tolerance),
+                &mut BuffersBuilder::new(geometry, |vertex: StrokeVertex| {
+                    let p = vertex.position();
+                    Vertex {
+                        position: [p.x, p.y, 0.0],
+                        color: opaque(outline.color),
+                        uv: [p.x / size.x + 0.5, p.y / size.y + 0.5],
+                    }
+                }),
+            )
+            .unwrap();
+    }
+
+    // Swaps in new geometry of any shape, keeping the instance state. The old
+    // buffers are freed once the GPU is done with them.
+    pub fn set_geometry(
+        &mut self,
+        device: &wgpu::Device,
+        deletion_queue: &mut DeletionQueue,
+        geometry: &VertexBuffers<Vertex, u16>,
+    ) {
+        let _operation = gpu_errors::operation("replacing element buffers");
+        let (vertex_buffer, index_buffer) = Self::create_geometry_buffers(device, geometry);
+        deletion_queue.defer(std::mem::replace(&mut self.vertex_buffer, vertex_buffer));
+        deletion_queue.defer(std::mem::replace(&mut self.index_buffer, index_buffer));
+        self.num_indices = geometry.indices.len() as u32;
+    }
+
+    // Writes into the current buffers when the geometry fits, so shapes that
+    // change every frame, like a sweeping progress arc, don't allocate.
+    // Buffers that are too small are replaced with room to spare.
+    pub fn upload_geometry(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        deletion_queue: &mut DeletionQueue,
+        geometry: &VertexBuffers<Vertex, u16>,
+    ) {
+        let mut indices = geometry.indices.clone();
+        // Buffer writes have to be a multiple of four bytes.
+        if indices.len() % 2 == 1 {
+            indices.push(0);
+        }
+        let vertices: &[u8] = bytemuck::cast_slice(&geometry.vertices);
+        let indices: &[u8] = bytemuck::cast_slice(&indices);
 
+        // Shared buffers are replaced as well, so the other elements keep
+        // their geometry.
+        let mut grow = |buffer: &mut Rc<wgpu::Buffer>, bytes: usize, label, usage| {
+            if bytes as wgpu::BufferAddress <= buffer.size() && Rc::strong_count(buffer) == 1 {
+                return;
+            }
+            let _operation = gpu_errors::operation("growing element buffers");
+            let grown = Rc::new(device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(label),
+                size: (bytes * 2) as wgpu::BufferAddress,
+                usage: usage | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }));
+            deletion_queue.defer(std::mem::replace(buffer, grown));
+        };
+        grow(
+            &mut self.vertex_buffer,
+            vertices.len(),
+            "UI Vertex Buffer",
+            wgpu::BufferUsages::VERTEX,
+        );
+        grow(
+            &mut self.index_buffer,
+            indices.len(),
+            "UI Index buffer",
+            wgpu::BufferUsages::INDEX,
+        );
+
+        queue.write_buffer(&self.vertex_buffer, 0, vertices);
+        queue.write_buffer(&self.index_buffer, 0, indices);
+        self.num_indices = geometry.indices.len() as u32;
+        self.revision = self.revision.wrapping_add(1);
+    }
+
+    // Retessellates into the current buffers, for shapes that don't keep
+    // their tessellation when they change, e.g. an arc whose sweep animates.
+    pub fn set_shape(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        deletion_queue: &mut DeletionQueue,
+        shape: Shape,
+        left: [f32; 3],
+        right: [f32; 3],
+    ) {
+        let (left, right) = (opaque(left), opaque(right));
+        let geometry = Self::shape_geometry(shape, self.tolerance, left, right);
+        self.upload_geometry(device, queue, deletion_queue, &geometry);
+        self.size = shape.size();
+        self.shape = shape;
+        self.gradient = Some((left, right));
+        self.drawing = None;
+    }
+
+    // Switches to another tessellation, retessellating right away for
+    // an element drawn at `camera_scale`. Elements made from raw geometry
+    // keep it.
+    pub fn set_tessellation(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        deletion_queue: &mut DeletionQueue,
+        tessellation: Tessellation,
+        camera_scale: f32,
+    ) {
+        self.tessellation = tessellation;
+        self.retessellate(device, queue, deletion_queue, camera_scale);
+    }
+
+    // Tessellates the shape or path again if the tolerance it needs at
+    // `camera_scale` changed. Returns whether it did.
+    pub fn retessellate(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        deletion_queue: &mut DeletionQueue,
+        camera_scale: f32,
+    ) -> bool {
+        if self.gradient.is_none() && self.drawing.is_none() {
+            return false;
+        }
+        let tolerance = self
+            .tessellation
+            .tolerance(camera_scale * self.instance.scale);
+        if tolerance == self.tolerance {
+            return false;
+        }
+        self.tolerance = tolerance;
+        let geometry = match (&self.drawing, self.gradient) {
+            (Some(drawing), _) if self.from_path => Self::path_geometry(drawing, tolerance).0,
+            (Some(drawing), _) => Self::outlined_geometry(self.shape, drawing, tolerance),
+            (None, Some((left, right))) => Self::shape_geometry(self.shape, tolerance, left, right),
+            (None, None) => return false,
+        };
+        self.upload_geometry(device, queue, deletion_queue, &geometry);
+        true
+    }
+
+    // Rewrites the vertices in place, e.g. to resize a selection rectangle
+    // every frame without allocating. The new shape has to tessellate the
+    // same way as the current one, which holds for rects but not for curved
+    // shapes of a different size. Elements sharing their geometry have to use
+    // `set_shape` instead.
+    pub fn reshape(&mut self, queue: &wgpu::Queue, shape: Shape, left: [f32; 3], right: [f32; 3]) {
+        let (left, right) = (opaque(left), opaque(right));
+        let geometry = Self::shape_geometry(shape, self.tolerance, left, right);
+        debug_assert_eq!(geometry.indices.len() as u32, self.num_indices);
+        debug_assert_eq!(
+            Rc::strong_count(&self.vertex_buffer),
+            1,
+            "reshaping an element with shared geometry"
+        );
+        queue.write_buffer(
+            &self.vertex_buffer,
+            0,
+            bytemuck::cast_slice(&geometry.vertices),
+        );
+        self.size = shape.size();
+        self.shape = shape;
+        self.gradient = Some((left, right));
+        self.drawing = None;
+        self.revision = self.revision.wrapping_add(1);
+    }
+
+    fn shape_geometry(
+        shape: Shape,
+        tolerance: f32,
+        left: [f32; 4],
+        right: [f32; 4],
+    ) -> VertexBuffers<Vertex, u16> {
+        let size = shape.size();
+        let mut geometry: VertexBuffers<Vertex, u16> = VertexBuffers::new();
+        shape.tessellate(
+            tolerance,
+            &mut BuffersBuilder::new(&mut geometry, |vertex: FillVertex| {
+                let p = vertex.position();
+                let uv = [p.x / size.x + 0.5, p.y / size.y + 0.5];
+                Vertex {
+                    position: [p.x, p.y, 0.0],
+                    color: [0, 1, 2, 3].map(|i| left[i] + (right[i] - left[i]) * uv[0]),
+                    uv,
+                }
+            }),
+        );
+        geometry
+    }
+
+    fn create_geometry_buffers(
+        device: &wgpu::Device,
+        geometry: &VertexBuffers<Vertex, u16>,
+    ) -> (Rc<wgpu::Buffer>, Rc<wgpu::Buffer>) {
         let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some("UI Vertex Buffer"),
-            contents: bytemuck::cast_slice(VERTICES),
+            contents: bytemuck::cast_slice(&geometry.vertices),
             usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
         });
 
         let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some("UI Index buffer"),
-            contents: bytemuck::cast_slice(INDICES),
-            usage: wgpu::BufferUsages::INDEX,
+            contents: bytemuck::cast_slice(&geometry.indices),
+            usage: wgpu::BufferUsages::INDEX | wgpu::BufferUsages::COPY_DST,
+        });
+        (Rc::new(vertex_buffer), Rc::new(index_buffer))
+    }
+
+    // Builds an element from already tessellated geometry, in coordinates
+    // relative to `position`. `size` is the box used for hit testing,
+    // centered on `position`.
+    pub fn from_geometry(
+        device: &wgpu::Device,
+        position: cgmath::Vector2<f32>,
+        size: cgmath::Vector2<f32>,
+        geometry: &VertexBuffers<Vertex, u16>,
+    ) -> Self {
+        let _operation = gpu_errors::operation("creating element buffers");
+        let (vertex_buffer, index_buffer) = Self::create_geometry_buffers(device, geometry);
+        let num_indices = geometry.indices.len() as u32;
+        Self::from_buffers(
+            device,
+            position,
+            size,
+            vertex_buffer,
+            index_buffer,
+            num_indices,
+        )
+    }
+
+    // A shape element drawing buffers it shares with other elements, each
+    // with its own instance.
+    pub(crate) fn with_shared_geometry(
+        device: &wgpu::Device,
+        position: cgmath::Vector2<f32>,
+        shape: Shape,
+        (left, right): ([f32; 3], [f32; 3]),
+        (vertex_buffer, index_buffer): (Rc<wgpu::Buffer>, Rc<wgpu::Buffer>),
+        num_indices: u32,
+    ) -> Self {
+        let mut element = Self::from_buffers(
+            device,
+            position,
+            shape.size(),
+            vertex_buffer,
+            index_buffer,
+            num_indices,
+        );
+        element.shape = shape;
+        element.gradient = Some((opaque(left), opaque(right)));
+        element
+    }
+
+    fn from_buffers(
+        device: &wgpu::Device,
+        position: cgmath::Vector2<f32>,
+        size: cgmath::Vector2<f32>,
+        vertex_buffer: Rc<wgpu::Buffer>,
+        index_buffer: Rc<wgpu::Buffer>,
+        num_indices: u32,
+    ) -> Self {
+        let instance = Instance {
+            position: cgmath::Vector3::new(position.x, position.y, 0.0),
+            rotation: cgmath::Quaternion::from_axis_angle(
+                cgmath::Vector3::unit_z(),
+                cgmath::Deg(0.0),
+            ),
+            scale: 1.0,
+            tint: [1.0, 1.0, 1.0, 1.0],
+            params: [0.0; 4],
+        };
+
+        // The element itself is the last instance, preceded by its shadow layers.
+        let instance_buffers = PerFrame::new(|_| {
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("UI Instance buffer"),
+                size: ((SHADOW_LAYERS + 1) * std::mem::size_of::<InstanceRaw>())
+                    as wgpu::BufferAddress,
+                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            })
         });
 
         Self {
-            render_pipeline,
             vertex_buffer,
             index_buffer,
+            num_indices,
+            instance,
+            instance_buffers,
+            size,
+            shape: Shape::Rect {
+                width: size.x,
+                height: size.y,
+            },
+            draggable: true,
+            elevation: Tween::new(0.0, LIFT_DURATION, Easing::EaseOut),
+            material: UIMaterial::Solid,
+            blend: BlendMode::Normal,
+            z_index: 0,
+            visible: true,
+            enabled: true,
+            styled: false,
+            parent: None,
+            clip: None,
+            clips_children: false,
+            inherited_clip: None,
+            flash: None,
+            tessellation: Tessellation::default(),
+            tolerance: FillOptions::DEFAULT_TOLERANCE,
+            gradient: None,
+            drawing: None,
+            from_path: false,
+            revision: 0,
+            texture_fill: None,
+            direction: None,
         }
     }
 
-    pub fn resize(&mut self, device: &wgpu::Device, config: &wgpu::SurfaceConfiguration) {}
+    pub fn contains(&self, point: cgmath::Vector2<f32>) -> bool {
+        let half = self.size * self.instance.scale / 2.0;
+        let dx = point.x - self.instance.position.x;
+        let dy = point.y - self.instance.position.y;
+        match self.shape {
+            Shape::Rect { .. } => dx.abs() <= half.x && dy.abs() <= half.y,
+            Shape::RoundedRect { radii, .. } => {
+                if dx.abs() > half.x || dy.abs() > half.y {
+                    return false;
+                }
+                let radius = match (dx < 0.0, dy < 0.0) {
+                    (true, false) => radii.top_left,
+                    (false, false) => radii.top_right,
+                    (true, true) => radii.bottom_left,
+                    (false, true) => radii.bottom_right,
+                } * self.instance.scale;
+                // Only the square in the very corner is cut by the rounding.
+                let corner = cgmath::Vector2::new(
+                    (dx.abs() - (half.x - radius)).max(0.0),
+                    (dy.abs() - (half.y - radius)).max(0.0),
+                );
+                corner.x * corner.x + corner.y * corner.y <= radius * radius
+            }
+            Shape::Circle { .. } | Shape::Ellipse { .. } => {
+                (dx / half.x).powi(2) + (dy / half.y).powi(2) <= 1.0
+            }
+            Shape::Polygon { .. } | Shape::Star { .. } => {
+                // Even-odd crossing test in the shape's own units.
+                let (x, y) = (dx / self.instance.scale, dy / self.instance.scale);
+                let corners = self.shape.corners();
+                let mut inside = false;
+                let mut previous = corners[corners.len() - 1];
+                for &corner in &corners {
+                    if (corner.y > y) != (previous.y > y)
+                        && x < previous.x
+                            + (y - previous.y) * (corner.x - previous.x) / (corner.y - previous.y)
+                    {
+                        inside = !inside;
+                    }
+                    previous = corner;
+                }
+                inside
+            }
+            Shape::Arc {
+                radius,
+                inner,
+                start,
+                end,
+            } => {
+                let distance = (dx * dx + dy * dy).sqrt() / self.instance.scale;
+                if distance > radius || distance < inner {
+                    return false;
+                }
+                let (start, end) = (start.min(end), start.max(end));
+                let angle = dy.atan2(dx).to_degrees();
+                end - start >= 360.0 || (angle - start).rem_euclid(360.0) <= end - start
+            }
+        }
+    }
 
-    pub fn input(&mut self, event: &WindowEvent) -> bool {
-        false
+    // What the element draws, with arcs of shapes flattened to `tolerance`.
+    // None for elements made from raw geometry.
+    pub(crate) fn drawing(&self, tolerance: f32) -> Option<Drawing> {
+        if let Some(drawing) = &self.drawing {
+            return Some(drawing.clone());
+        }
+        let gradient = self.gradient?;
+        Some(Drawing {
+            path: self.shape.flattened(tolerance),
+            fill: Some(gradient),
+            outline: None,
+        })
     }
 
-    pub fn update(&mut self, queue: &wgpu::Queue) {}
+    // Whether it covers everything under it, as far as its colors go.
+    // Elements made from raw geometry might have translucent vertices.
+    fn is_opaque(&self) -> bool {
+        let colors = match (&self.drawing, self.gradient) {
+            (Some(_), _) => true,
+            (None, Some((left, right))) => left[3] >= 1.0 && right[3] >= 1.0,
+            (None, None) => false,
+        };
+        colors
+            && self.blend == BlendMode::Normal
+            && self.instance.tint[3] >= 1.0
+            && !self.is_elevated()
+            && self.flash.is_none()
+    }
 
-    pub fn render(&self, encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView) {
-        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-            label: Some("UI Render pass"),
-            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                view: &view,
-                resolve_target: None,
-                ops: wgpu::Operations {
-                    load: wgpu::LoadOp::Load,
-                    store: true,
+    // Both `clip` and what ancestors clip away.
+    fn effective_clip(&self) -> Option<Viewport> {
+        match (self.clip, self.inherited_clip) {
+            (Some(clip), Some(inherited)) => Some(clip.intersect(&inherited)),
+            (clip, inherited) => clip.or(inherited),
+        }
+    }
+
+    pub fn is_elevated(&self) -> bool {
+        self.elevation.value() > 0.0
+    }
+
+    // Fills the shape with the scene's pattern, one copy every `tile` units
+    // of `space`, turned by `angle` radians, e.g. 45 degrees for warning
+    // stripes along a bar.
+    pub fn fill_pattern(&mut self, tile: cgmath::Vector2<f32>, space: PatternSpace, angle: f32) {
+        self.material = UIMaterial::Pattern;
+        let world = match space {
+            PatternSpace::Local => 0.0,
+            PatternSpace::World => 1.0,
+        };
+        self.instance.params = [tile.x, tile.y, world, angle];
+    }
+
+    // Switches the tint to `color` and fades back to the element's own tint
+    // over `duration` seconds, e.g. red when something takes a hit. Tints
+    // multiply the element's colors, so white elements flash best. Flashing
+    // again restarts the fade.
+    pub fn flash(&mut self, color: [f32; 4], duration: f32) {
+        let mut fade = Tween::new(1.0, duration, Easing::EaseOut);
+        fade.retarget(0.0);
+        self.flash = Some((color, fade));
+    }
+
+    pub fn is_flashing(&self) -> bool {
+        self.flash.is_some()
+    }
+
+    // The tint to draw with, `instance.tint` itself staying untouched.
+    fn current_tint(&self) -> [f32; 4] {
+        match &self.flash {
+            Some((color, fade)) => {
+                let t = fade.value();
+                [0, 1, 2, 3].map(|i| self.instance.tint[i] + (color[i] - self.instance.tint[i]) * t)
+            }
+            None => self.instance.tint,
+        }
+    }
+
+    // `tint` multiplies the element's own tint, shadows are left alone.
+    // Drawn at depth `z`, shadows included.
+    fn instances(&self, tint: [f32; 4], z: f32) -> Vec<InstanceRaw> {
+        let lift = self.elevation.value();
+        let position = cgmath::Vector3::new(self.instance.position.x, self.instance.position.y, z);
+        let mut raw = (0..SHADOW_LAYERS)
+            .map(|i| {
+                let spread = (i + 1) as f32 / SHADOW_LAYERS as f32;
+                Instance {
+                    position: position + cgmath::Vector3::new(4.0, -6.0, 0.0) * lift,
+                    rotation: self.instance.rotation,
+                    scale: self.instance.scale * (1.0 + 0.12 * spread * lift),
+                    tint: [0.0, 0.0, 0.0, 0.25 * (1.0 - spread * 0.7) * lift],
+                    // Keep the progress so shadows of fill materials match
+                    // the visible part of the element.
+                    params: [0.0, self.instance.params[PARAM_PROGRESS], 0.0, 0.0],
+                }
+                .to_raw()
+            })
+            .collect::<Vec<_>>();
+
+        raw.push(
+            Instance {
+                position,
+                rotation: self.instance.rotation,
+                scale: self.instance.scale * (1.0 + (LIFT_SCALE - 1.0) * lift),
+                tint: {
+                    let own = self.current_tint();
+                    [0, 1, 2, 3].map(|i| own[i] * tint[i])
                 },
-            })],
-            depth_stencil_attachment: None,
+                params: self.instance.params,
+            }
+            .to_raw(),
+        );
+        raw
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Viewport {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Viewport {
+    pub fn contains(&self, point: cgmath::Vector2<f32>) -> bool {
+        (self.x..self.x + self.width).contains(&point.x)
+            && (self.y..self.y + self.height).contains(&point.y)
+    }
+
+    pub fn intersect(&self, other: &Viewport) -> Viewport {
+        let x = self.x.max(other.x);
+        let y = self.y.max(other.y);
+        Viewport {
+            x,
+            y,
+            width: ((self.x + self.width).min(other.x + other.width) - x).max(0.0),
+            height: ((self.y + self.height).min(other.y + other.height) - y).max(0.0),
+        }
+    }
+
+    pub fn union(&self, other: &Viewport) -> Viewport {
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        Viewport {
+            x,
+            y,
+            width: (self.x + self.width).max(other.x + other.width) - x,
+            height: (self.y + self.height).max(other.y + other.height) - y,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.width <= 0.0 || self.height <= 0.0
+    }
+}
+
+type DrawnState = (
+    UIMaterial,
+    BlendMode,
+    *const wgpu::Buffer,
+    u32,
+    u32,
+    Option<*const wgpu::BindGroup>,
+);
+
+// What an element looked like when last prepared, to tell what changed.
+struct Drawn {
+    instances: Vec<InstanceRaw>,
+    // Screen rect it covered, shadows included. None when it wasn't drawn.
+    rect: Option<Viewport>,
+    state: DrawnState,
+}
+
+#[derive(Debug, Clone)]
+pub enum UIEvent {
+    Scrolled {
+        element: usize,
+        delta: cgmath::Vector2<f32>,
+    },
+    Shortcut(String),
+    // Pressed and released without dragging.
+    Clicked(usize),
+    // A file is dragged over the window, one event per file. Most platforms
+    // send no cursor moves while this goes on.
+    FileHovered(std::path::PathBuf),
+    // The drag left the window or was aborted.
+    FileHoverCancelled,
+    // One event per dropped file. `element` is the one last hovered, which
+    // may be stale where the platform didn't report the cursor during the
+    // drag.
+    FileDropped {
+        path: std::path::PathBuf,
+        element: Option<usize>,
+    },
+    // A dialog from `FileDialogs::show` closed, without paths if it was
+    // cancelled.
+    FilesChosen {
+        dialog: u64,
+        paths: Vec<std::path::PathBuf>,
+    },
+    // The query of a `SearchBox`, whose field `element` is, once typing
+    // paused. Empty when it was cleared.
+    SearchChanged {
+        element: usize,
+        query: String,
+    },
+}
+
+struct Press {
+    screen: cgmath::Vector2<f32>,
+    element: Option<usize>,
+}
+
+struct Drag {
+    element: usize,
+    offset: cgmath::Vector2<f32>,
+}
+
+// Where a multisampled scene draws before its samples are resolved and
+// blended over the target.
+struct MsaaTargets {
+    color: wgpu::TextureView,
+    resolved: wgpu::TextureView,
+    bind_group: wgpu::BindGroup,
+}
+
+impl MsaaTargets {
+    fn new(
+        device: &wgpu::Device,
+        resources: &UIResources,
+        config: &wgpu::SurfaceConfiguration,
+    ) -> Option<Self> {
+        let layout = resources.composite_bind_group_layout.as_ref()?;
+        let texture = |label, samples, usage| {
+            device
+                .create_texture(&wgpu::TextureDescriptor {
+                    label: Some(label),
+                    size: wgpu::Extent3d {
+                        width: config.width.max(1),
+                        height: config.height.max(1),
+                        depth_or_array_layers: 1,
+                    },
+                    mip_level_count: 1,
+                    sample_count: samples,
+                    dimension: wgpu::TextureDimension::D2,
+                    format: config.format,
+                    usage,
+                    view_formats: &[],
+                })
+                .create_view(&wgpu::TextureViewDescriptor::default())
+        };
+        let color = texture(
+            "UI Multisampled target",
+            resources.samples,
+            wgpu::TextureUsages::RENDER_ATTACHMENT,
+        );
+        let resolved = texture(
+            "UI Resolved target",
+            1,
+            wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+        );
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("ui_composite_bind_group"),
+            layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(&resolved),
+            }],
         });
+        Some(Self {
+            color,
+            resolved,
+            bind_group,
+        })
+    }
+}
+
+pub struct UIScene {
+    pub resources: Rc<UIResources>,
+    pub camera: OrthographicCamera,
+    pub camera_uniform: CameraUniform,
+    pub camera_buffers: PerFrame<wgpu::Buffer>,
+    pub camera_bind_groups: PerFrame<wgpu::BindGroup>,
+    // Palettes, atlas, pattern and gradients, see `set_palettes`,
+    // `set_atlas`, `set_pattern` and `add_gradient`.
+    pub material_bind_group: wgpu::BindGroup,
+    palette: wgpu::TextureView,
+    atlas: (wgpu::TextureView, Rc<wgpu::Sampler>),
+    pattern: (wgpu::TextureView, Rc<wgpu::Sampler>),
+    // One baked gradient per row.
+    gradients: (wgpu::TextureView, Vec<Vec<[u8; 4]>>),
+    pub elements: Vec<Player>,
+    pub viewport: Viewport,
+    // Whether the host gave the scene a viewport of its own, which resizing
+    // the target then leaves alone.
+    custom_viewport: bool,
+    pub pan: Kinetic,
+    pub input_config: InputConfig,
+    pub shortcuts: ShortcutRegistry,
+    pub focused: Option<usize>,
+    // Shared by the text fields, the app's own unless replaced.
+    pub clipboard: Box<dyn Clipboard>,
+    // Caret rects of text elements, relative to their position, see
+    // `set_caret`.
+    carets: HashMap<usize, (cgmath::Vector2<f32>, cgmath::Vector2<f32>)>,
+    pub debug: DebugFlags,
+    // Applied on top of the tint of disabled elements.
+    pub disabled_tint: [f32; 4],
+    pub background: Background,
+    // Alignment lines, see `editor::Editor`.
+    pub guides: Guides,
+    // None treats everything visible as the document.
+    pub document: Option<Document>,
+    // Target pixels per device independent pixel, see `units::Length`.
+    pub scale_factor: f32,
+    // For elements and widgets that don't set their own, e.g. from
+    // `LayoutDirection::for_locale`.
+    pub direction: LayoutDirection,
+    // Screen rect that changed in the last `prepare`, see `damage`.
+    damage: Option<Viewport>,
+    drawn: Vec<Drawn>,
+    // Camera, viewport, debug views and background as of the last
+    // `prepare`, any change to which redraws everything.
+    drawn_scene: Option<(Vec<u8>, Viewport, DebugFlags, Background)>,
+    redraw_all: bool,
+    // The frame of the last `prepare`, whose instance buffers are current.
+    pub(crate) prepared: Option<FrameContext>,
+    // Every element's own instance in draw order, so runs of elements
+    // sharing geometry and pipeline draw in one call, see `draw_elements`.
+    pub(crate) batch_buffers: PerFrame<wgpu::Buffer>,
+    // Draw order as of the last `prepare`, the batch buffer's order.
+    batch_order: Vec<usize>,
+    // Where each element's instance is in the batch buffer.
+    pub(crate) batch_slots: Vec<u32>,
+    hovered: Option<usize>,
+    events: Vec<UIEvent>,
+    cursor: cgmath::Vector2<f32>,
+    cursor_screen: cgmath::Vector2<f32>,
+    press: Option<Press>,
+    drag: Option<Drag>,
+    started: Instant,
+    // Pushed clips, each with the number of elements when it was pushed.
+    clip_stack: Vec<(usize, Viewport)>,
+    msaa: Option<MsaaTargets>,
+    depth: Option<wgpu::TextureView>,
+}
+
+// Pipeline state that only depends on the target format, shared by every
+// scene rendering into that format through the `GpuContext`.
+pub struct UIResources {
+    pub render_pipeline: wgpu::RenderPipeline,
+    pub radial_fill_pipeline: wgpu::RenderPipeline,
+    pub linear_fill_pipeline: wgpu::RenderPipeline,
+    pub palette_swap_pipeline: wgpu::RenderPipeline,
+    pub sprite_pipeline: wgpu::RenderPipeline,
+    pub pattern_pipeline: wgpu::RenderPipeline,
+    pub gradient_pipeline: wgpu::RenderPipeline,
+    pub sdf_pipeline: wgpu::RenderPipeline,
+    // Debug views, see `DebugFlags`.
+    pub overdraw_pipeline: wgpu::RenderPipeline,
+    pub bounds_pipeline: wgpu::RenderPipeline,
+    // Gradients and checkerboards, see `Background`.
+    pub background_pipeline: wgpu::RenderPipeline,
+    // None where the device can't draw lines, see `DebugFlags::wireframe`.
+    pub wireframe_pipeline: Option<wgpu::RenderPipeline>,
+    // Every material in every other blend mode, the pipelines above being
+    // the normal ones.
+    blend_pipelines: HashMap<(UIMaterial, BlendMode), wgpu::RenderPipeline>,
+    pub camera_bind_group_layout: wgpu::BindGroupLayout,
+    pub material_bind_group_layout: wgpu::BindGroupLayout,
+    pub format: wgpu::TextureFormat,
+    // Samples per pixel the pipelines render with.
+    pub samples: u32,
+    // Whether the pipelines test and write a `Texture::DEPTH_FORMAT` depth
+    // attachment, see `UISceneOptions::depth`.
+    pub depth: bool,
+    // Blends the resolved UI over the target when multisampling, see
+    // `UIScene::with_msaa`.
+    pub composite_pipeline: Option<wgpu::RenderPipeline>,
+    pub composite_bind_group_layout: Option<wgpu::BindGroupLayout>,
+}
+
+impl UIResources {
+    pub fn new(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        samples: u32,
+        depth: bool,
+    ) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("ui_shader.wgsl").into()),
+        });
+
+        let camera_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("ui_camera_bind_group_layout"),
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                }],
+            });
+
+        let material_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("ui_material_bind_group_layout"),
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            multisampled: false,
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            multisampled: false,
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 2,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 3,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            multisampled: false,
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 4,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 5,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            multisampled: false,
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        },
+                        count: None,
+                    },
+                ],
+            });
+
+        let render_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("UI Render pipeline layout"),
+                bind_group_layouts: &[&camera_bind_group_layout, &material_bind_group_layout],
+                push_constant_ranges: &[],
+            });
+
+        // Debug views draw over everything, whatever its depth.
+        let depth_stencil = |test: bool| {
+            depth.then(|| wgpu::DepthStencilState {
+                format: Texture::DEPTH_FORMAT,
+                depth_write_enabled: test,
+                // Equal lets an element draw over its own shadow.
+                depth_compare: if test {
+                    wgpu::CompareFunction::LessEqual
+                } else {
+                    wgpu::CompareFunction::Always
+                },
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            })
+        };
+        let pipeline = |entry_point: &str, blend: wgpu::BlendState, test: bool| {
+            Self::create_pipeline(
+                device,
+                &render_pipeline_layout,
+                &shader,
+                wgpu::ColorTargetState {
+                    format,
+                    blend: Some(blend),
+                    write_mask: wgpu::ColorWrites::ALL,
+                },
+                samples,
+                entry_point,
+                depth_stencil(test),
+            )
+        };
+        let material_pipeline = |material: UIMaterial, blend: BlendMode| {
+            pipeline(material.entry_point(), blend.blend_state(), true)
+        };
+        let blend_pipelines = BlendMode::VARIANTS
+            .into_iter()
+            .flat_map(|blend| UIMaterial::ALL.map(|material| (material, blend)))
+            .map(|key| (key, material_pipeline(key.0, key.1)))
+            .collect();
+        let (composite_pipeline, composite_bind_group_layout) = (samples > 1)
+            .then(|| Self::create_composite(device, format))
+            .unzip();
+        let additive = wgpu::BlendComponent {
+            src_factor: wgpu::BlendFactor::One,
+            dst_factor: wgpu::BlendFactor::One,
+            operation: wgpu::BlendOperation::Add,
+        };
+
+        Self {
+            render_pipeline: material_pipeline(UIMaterial::Solid, BlendMode::Normal),
+            radial_fill_pipeline: material_pipeline(UIMaterial::RadialFill, BlendMode::Normal),
+            linear_fill_pipeline: material_pipeline(UIMaterial::LinearFill, BlendMode::Normal),
+            palette_swap_pipeline: material_pipeline(UIMaterial::PaletteSwap, BlendMode::Normal),
+            sprite_pipeline: material_pipeline(UIMaterial::Sprite, BlendMode::Normal),
+            pattern_pipeline: material_pipeline(UIMaterial::Pattern, BlendMode::Normal),
+            gradient_pipeline: material_pipeline(UIMaterial::Gradient, BlendMode::Normal),
+            sdf_pipeline: material_pipeline(UIMaterial::Sdf, BlendMode::Normal),
+            overdraw_pipeline: pipeline(
+                "fs_overdraw",
+                wgpu::BlendState {
+                    color: additive,
+                    alpha: additive,
+                },
+                false,
+            ),
+            bounds_pipeline: pipeline(
+                "fs_bounds",
+                wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING,
+                false,
+            ),
+            background_pipeline: Self::create_background(
+                device,
+                &render_pipeline_layout,
+                &shader,
+                format,
+                samples,
+                depth_stencil(false),
+            ),
+            wireframe_pipeline: device
+                .features()
+                .contains(wgpu::Features::POLYGON_MODE_LINE)
+                .then(|| pipeline("fs_wireframe", wgpu::BlendState::REPLACE, false)),
+            blend_pipelines,
+            camera_bind_group_layout,
+            material_bind_group_layout,
+            format,
+            samples,
+            depth,
+            composite_pipeline,
+            composite_bind_group_layout,
+        }
+    }
+
+    fn create_background(
+        device: &wgpu::Device,
+        layout: &wgpu::PipelineLayout,
+        shader: &wgpu::ShaderModule,
+        format: wgpu::TextureFormat,
+        samples: u32,
+        depth_stencil: Option<wgpu::DepthStencilState>,
+    ) -> wgpu::RenderPipeline {
+        gpu_errors::scoped(device, "creating UI background pipeline", || {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("UI Background Pipeline"),
+                layout: Some(layout),
+                vertex: wgpu::VertexState {
+                    module: shader,
+                    entry_point: "vs_background",
+                    buffers: &[],
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: shader,
+                    entry_point: "fs_background",
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format,
+                        blend: Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                }),
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil,
+                multisample: wgpu::MultisampleState {
+                    count: samples,
+                    ..Default::default()
+                },
+                multiview: None,
+            })
+        })
+    }
+
+    fn create_composite(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+    ) -> (wgpu::RenderPipeline, wgpu::BindGroupLayout) {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("UI composite shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("ui_composite.wgsl").into()),
+        });
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("ui_composite_bind_group_layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    multisampled: false,
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                },
+                count: None,
+            }],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("UI composite pipeline layout"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = gpu_errors::scoped(device, "creating UI composite pipeline", || {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("UI Composite Pipeline"),
+                layout: Some(&pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: "vs_main",
+                    buffers: &[],
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    entry_point: "fs_main",
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format,
+                        blend: Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                }),
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState::default(),
+                multiview: None,
+            })
+        });
+        (pipeline, layout)
+    }
+
+    // Composite and debug views included.
+    pub fn pipeline_count(&self) -> usize {
+        11 + self.blend_pipelines.len()
+            + self.wireframe_pipeline.iter().count()
+            + self.composite_pipeline.iter().count()
+    }
+
+    pub fn pipeline(&self, material: UIMaterial, blend: BlendMode) -> &wgpu::RenderPipeline {
+        if blend != BlendMode::Normal {
+            return &self.blend_pipelines[&(material, blend)];
+        }
+        match material {
+            UIMaterial::Solid => &self.render_pipeline,
+            UIMaterial::RadialFill => &self.radial_fill_pipeline,
+            UIMaterial::LinearFill => &self.linear_fill_pipeline,
+            UIMaterial::PaletteSwap => &self.palette_swap_pipeline,
+            UIMaterial::Sprite => &self.sprite_pipeline,
+            UIMaterial::Pattern => &self.pattern_pipeline,
+            UIMaterial::Gradient => &self.gradient_pipeline,
+            UIMaterial::Sdf => &self.sdf_pipeline,
+        }
+    }
+
+    // Materials share the vertex stage and only differ in their fragment
+    // entry point.
+    fn create_pipeline(
+        device: &wgpu::Device,
+        layout: &wgpu::PipelineLayout,
+        shader: &wgpu::ShaderModule,
+        target: wgpu::ColorTargetState,
+        samples: u32,
+        entry_point: &str,
+        depth_stencil: Option<wgpu::DepthStencilState>,
+    ) -> wgpu::RenderPipeline {
+        let label = format!("creating UI pipeline for {}", entry_point);
+        gpu_errors::scoped(device, label, || {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("UI Render Pipeline"),
+                layout: Some(layout),
+                vertex: wgpu::VertexState {
+                    module: shader,
+                    entry_point: "vs_main",                          // 1.
+                    buffers: &[Vertex::desc(), InstanceRaw::desc()], // 2.
+                },
+                fragment: Some(wgpu::FragmentState {
+                    // 3.
+                    module: shader,
+                    entry_point,
+                    targets: &[Some(target)], // 4.
+                }),
+                primitive: wgpu::PrimitiveState {
+                    topology: wgpu::PrimitiveTopology::TriangleList, // 1.
+                    strip_index_format: None,
+                    front_face: wgpu::FrontFace::Ccw, // 2.
+                    // Tessellated 2D shapes don't guarantee a consistent winding.
+                    cull_mode: None,
+                    // Setting this to anything other than Fill requires Features::POLYGON_MODE_LINE,
+                    // which only the wireframe view asks for.
+                    polygon_mode: if entry_point == "fs_wireframe" {
+                        wgpu::PolygonMode::Line
+                    } else {
+                        wgpu::PolygonMode::Fill
+                    },
+                    // Requires Features::DEPTH_CLIP_CONTROL
+                    unclipped_depth: false,
+                    // Requires Features::CONSERVATIVE_RASTERIZATION
+                    conservative: false,
+                },
+                depth_stencil, // 1.
+                multisample: wgpu::MultisampleState {
+                    count: samples,                   // 2.
+                    mask: !0,                         // 3.
+                    alpha_to_coverage_enabled: false, // 4.
+                },
+                multiview: None, // 5.
+            })
+        })
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct UISceneOptions {
+    // Antialiases shape edges with `samples` samples per pixel. 4 works on
+    // every adapter, 2 and 8 depend on the adapter and target format.
+    pub samples: u32,
+    // Resolves overlaps with a depth buffer, so opaque elements no longer
+    // have to be drawn back to front and get batched by pipeline instead.
+    // Translucent ones still draw in order, on top of them.
+    pub depth: bool,
+}
+
+impl Default for UISceneOptions {
+    fn default() -> Self {
+        Self {
+            samples: 1,
+            depth: false,
+        }
+    }
+}
+
+impl UIScene {
+    pub async fn new(gpu: &mut GpuContext, config: &wgpu::SurfaceConfiguration) -> Self {
+        Self::with_options(gpu, config, UISceneOptions::default()).await
+    }
+
+    // See `UISceneOptions::samples`.
+    pub async fn with_msaa(
+        gpu: &mut GpuContext,
+        config: &wgpu::SurfaceConfiguration,
+        samples: u32,
+    ) -> Self {
+        let options = UISceneOptions {
+            samples,
+            ..Default::default()
+        };
+        Self::with_options(gpu, config, options).await
+    }
+
+    pub async fn with_options(
+        gpu: &mut GpuContext,
+        config: &wgpu::SurfaceConfiguration,
+        options: UISceneOptions,
+    ) -> Self {
+        let resources = gpu.ui_resources(config.format, options.samples, options.depth);
+        let device = &gpu.device;
+        let msaa = MsaaTargets::new(device, &resources, config);
+        let depth = Self::depth_target(device, &resources, config);
+
+        let camera = OrthographicCamera::new(config.width as f32, config.height as f32);
+        let mut camera_uniform = CameraUniform::new();
+        camera_uniform.update_view_proj(&camera);
+        camera_uniform.encode_srgb = !blends_in_linear_space(config.format) as u32;
+
+        let camera_buffers = PerFrame::new(|_| {
+            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("UI Camera buffer"),
+                contents: bytemuck::cast_slice(&[camera_uniform]),
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            })
+        });
+
+        let batch_buffers = PerFrame::new(|_| Self::batch_buffer(device, BATCH_CAPACITY));
+
+        let camera_bind_groups = camera_buffers.map(|buffer| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("ui_camera_bind_group"),
+                layout: &resources.camera_bind_group_layout,
+                entries: &[wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: buffer.as_entire_binding(),
+                }],
+            })
+        });
+
+        // A greyscale ramp until the app brings its own palettes.
+        let ramp = (0..PALETTE_SIZE)
+            .map(|i| {
+                let v = (i * 255 / (PALETTE_SIZE - 1)) as u8;
+                [v, v, v, 255]
+            })
+            .collect::<Vec<_>>();
+        let palette = Self::rows_view(device, &gpu.queue, "UI Palette texture", &[ramp]);
+        let gradients = (
+            Self::rows_view(device, &gpu.queue, "UI Gradient texture", &[]),
+            Vec::new(),
+        );
+        // One white texel until the app brings its own atlas and pattern,
+        // so sprites and patterns without one show up as their vertex
+        // color.
+        let white = device.create_texture_with_data(
+            &gpu.queue,
+            &wgpu::TextureDescriptor {
+                label: Some("UI Atlas placeholder"),
+                size: wgpu::Extent3d {
+                    width: 1,
+                    height: 1,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::Rgba8UnormSrgb,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            },
+            &[255; 4],
+        );
+        let atlas = (
+            white.create_view(&wgpu::TextureViewDescriptor::default()),
+            gpu.samplers.get(device, gpu.default_sampler),
+        );
+        let pattern = (
+            white.create_view(&wgpu::TextureViewDescriptor::default()),
+            gpu.samplers.get(device, gpu.default_sampler),
+        );
+        let material_bind_group =
+            Self::material_bind_group(device, &resources, &palette, &atlas, &pattern, &gradients.0);
+
+        let elements = vec![
+            Player::new(device, cgmath::Vector2::new(-100.0, 0.0), [0.8, 0.3, 0.3]),
+            Player::new(device, cgmath::Vector2::new(0.0, 0.0), [0.3, 0.8, 0.3]),
+            Player::new(device, cgmath::Vector2::new(100.0, 0.0), [0.3, 0.3, 0.8]),
+        ];
+
+        let mut shortcuts = ShortcutRegistry::new();
+        shortcuts
+            .register(
+                Chord::new(VirtualKeyCode::F9),
+                ShortcutScope::Global,
+                Self::WIREFRAME_ACTION,
+                "Toggle the UI wireframe view",
+            )
+            .expect("the registry starts out empty");
+
+        let mut pan = Kinetic::new();
+        pan.bounds = Some(Bounds {
+            min: cgmath::Vector2::new(-1000.0, -1000.0),
+            max: cgmath::Vector2::new(1000.0, 1000.0),
+        });
+
+        Self {
+            resources,
+            camera,
+            camera_uniform,
+            camera_buffers,
+            camera_bind_groups,
+            material_bind_group,
+            palette,
+            atlas,
+            pattern,
+            gradients,
+            elements,
+            viewport: Viewport {
+                x: 0.0,
+                y: 0.0,
+                width: config.width as f32,
+                height: config.height as f32,
+            },
+            custom_viewport: false,
+            pan,
+            input_config: InputConfig::default(),
+            shortcuts,
+            focused: None,
+            clipboard: Box::<LocalClipboard>::default(),
+            debug: DebugFlags::default(),
+            disabled_tint: [0.5, 0.5, 0.5, 0.6],
+            background: Background::None,
+            guides: Guides::default(),
+            document: None,
+            scale_factor: 1.0,
+            direction: LayoutDirection::default(),
+            carets: HashMap::new(),
+            damage: None,
+            drawn: Vec::new(),
+            drawn_scene: None,
+            redraw_all: true,
+            prepared: None,
+            batch_buffers,
+            batch_order: Vec::new(),
+            batch_slots: Vec::new(),
+            hovered: None,
+            events: Vec::new(),
+            cursor: cgmath::Vector2::new(0.0, 0.0),
+            cursor_screen: cgmath::Vector2::new(0.0, 0.0),
+            press: None,
+            drag: None,
+            started: Instant::now(),
+            clip_stack: Vec::new(),
+            msaa,
+            depth,
+        }
+    }
+
+    // Replaces the rows `UIMaterial::PaletteSwap` looks colors up in, one
+    // palette per row, in sRGB. Palettes shorter than `PALETTE_SIZE` are
+    // padded with transparent entries, longer ones cut off.
+    pub fn set_palettes(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        palettes: &[Vec<[u8; 4]>],
+    ) {
+        self.palette = Self::rows_view(device, queue, "UI Palette texture", palettes);
+        self.update_material_bind_group(device);
+    }
+
+    // Replaces the texture `UIMaterial::Sprite` samples. Sprites keep their
+    // sub-rects, so only swap in atlases laid out the same way.
+    pub fn set_atlas(&mut self, device: &wgpu::Device, atlas: &Atlas) {
+        self.set_atlas_texture(device, &atlas.texture);
+    }
+
+    // Same as `set_atlas`, for atlases managed some other way, e.g. a
+    // `dynamic_atlas::DynamicAtlas`.
+    pub fn set_atlas_texture(&mut self, device: &wgpu::Device, texture: &Texture) {
+        self.atlas = (
+            texture
+                .texture
+                .create_view(&wgpu::TextureViewDescriptor::default()),
+            texture.sampler.clone(),
+        );
+        self.update_material_bind_group(device);
+    }
+
+    // Replaces the texture `UIMaterial::Pattern` repeats. Its sampler should
+    // repeat too, or tiles past the first smear its edge:
+    //
+    //     let repeat = SamplerConfig { address_mode: wgpu::AddressMode::Repeat, ..SamplerConfig::linear() };
+    //     let stripes = Texture::from_image(device, queue, &image, Some("Stripes"), gpu.samplers.get(device, repeat))?;
+    //     scene.set_pattern(device, &stripes);
+    pub fn set_pattern(&mut self, device: &wgpu::Device, texture: &Texture) {
+        self.pattern = (
+            texture
+                .texture
+                .create_view(&wgpu::TextureViewDescriptor::default()),
+            texture.sampler.clone(),
+        );
+        self.update_material_bind_group(device);
+    }
+
+    // Fills the element with `texture`, stretched over its bounds, e.g. the
+    // target of an `OffscreenScene` for a minimap. The element becomes a
+    // `UIMaterial::Sprite` over the whole texture, and is tinted like one.
+    pub fn set_texture_fill(&mut self, device: &wgpu::Device, element: usize, texture: &Texture) {
+        let fill = self.create_texture_fill(device, texture);
+        self.set_shared_fill(element, &fill, [0.0, 0.0, 1.0, 1.0]);
+    }
+
+    // A fill for elements showing parts of one texture, e.g. the glyphs of
+    // a `GlyphAtlas`, see `set_shared_fill`. Only good for this scene.
+    pub fn create_texture_fill(
+        &self,
+        device: &wgpu::Device,
+        texture: &Texture,
+    ) -> Rc<wgpu::BindGroup> {
+        let fill = (
+            texture
+                .texture
+                .create_view(&wgpu::TextureViewDescriptor::default()),
+            texture.sampler.clone(),
+        );
+        Rc::new(Self::material_bind_group(
+            device,
+            &self.resources,
+            &self.palette,
+            &fill,
+            &self.pattern,
+            &self.gradients.0,
+        ))
+    }
+
+    // Fills the element with the `uv` sub-rect of a fill made by
+    // `create_texture_fill`, as u0, v0, u1, v1 from the top left.
+    pub fn set_shared_fill(&mut self, element: usize, fill: &Rc<wgpu::BindGroup>, uv: [f32; 4]) {
+        let element = &mut self.elements[element];
+        element.texture_fill = Some(fill.clone());
+        element.material = UIMaterial::Sprite;
+        element.instance.params = uv;
+    }
+
+    // Bakes a gradient through `stops`, pairs of offset from 0 to 1 and
+    // linear color with straight alpha, and returns its index for
+    // `Fill::Gradient`. Colors mix premultiplied, so fading to transparent
+    // doesn't darken.
+    pub fn add_gradient(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        stops: &[(f32, [f32; 4])],
+    ) -> usize {
+        self.gradients.1.push(bake_gradient(stops));
+        self.update_gradients(device, queue);
+        self.gradients.1.len() - 1
+    }
+
+    // Changes the stops of a gradient, for every element filled with it.
+    pub fn set_gradient(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        gradient: usize,
+        stops: &[(f32, [f32; 4])],
+    ) {
+        self.gradients.1[gradient] = bake_gradient(stops);
+        self.update_gradients(device, queue);
+    }
+
+    fn update_gradients(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        self.gradients.0 = Self::rows_view(device, queue, "UI Gradient texture", &self.gradients.1);
+        self.update_material_bind_group(device);
+    }
+
+    pub fn set_fill(&mut self, device: &wgpu::Device, element: usize, fill: Fill) {
+        match fill {
+            Fill::Solid => {
+                let element = &mut self.elements[element];
+                element.texture_fill = None;
+                element.material = UIMaterial::Solid;
+                element.instance.params = [0.0; 4];
+            }
+            Fill::Texture(texture) => self.set_texture_fill(device, element, texture),
+            Fill::Gradient { gradient, kind } => {
+                let (kind, angle) = match kind {
+                    GradientKind::Linear { angle } => (0.0, angle),
+                    GradientKind::Radial => (1.0, 0.0),
+                    GradientKind::Conic { angle } => (2.0, angle),
+                };
+                let element = &mut self.elements[element];
+                element.texture_fill = None;
+                element.material = UIMaterial::Gradient;
+                element.instance.params = [kind, gradient as f32, angle, 0.0];
+            }
+        }
+    }
+
+    // Back to sampling the atlas.
+    pub fn clear_texture_fill(&mut self, element: usize) {
+        self.elements[element].texture_fill = None;
+    }
+
+    pub fn has_texture_fill(&self, element: usize) -> bool {
+        self.elements[element].texture_fill.is_some()
+    }
+
+    // Counts the element as changed in the next `prepare`'s damage, for
+    // changes the scene can't see, like new contents of its texture fill.
+    pub fn invalidate(&mut self, element: usize) {
+        if let Some(drawn) = self.drawn.get_mut(element) {
+            drawn.instances.clear();
+        }
+    }
+
+    fn update_material_bind_group(&mut self, device: &wgpu::Device) {
+        self.material_bind_group = Self::material_bind_group(
+            device,
+            &self.resources,
+            &self.palette,
+            &self.atlas,
+            &self.pattern,
+            &self.gradients.0,
+        );
+        self.redraw_all = true;
+    }
+
+    fn material_bind_group(
+        device: &wgpu::Device,
+        resources: &UIResources,
+        palette: &wgpu::TextureView,
+        (atlas, atlas_sampler): &(wgpu::TextureView, Rc<wgpu::Sampler>),
+        (pattern, pattern_sampler): &(wgpu::TextureView, Rc<wgpu::Sampler>),
+        gradients: &wgpu::TextureView,
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("ui_material_bind_group"),
+            layout: &resources.material_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(palette),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(atlas),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::Sampler(atlas_sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: wgpu::BindingResource::TextureView(pattern),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: wgpu::BindingResource::Sampler(pattern_sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 5,
+                    resource: wgpu::BindingResource::TextureView(gradients),
+                },
+            ],
+        })
+    }
+
+    // `PALETTE_SIZE` texels per row, padded with transparent ones.
+    fn rows_view(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        label: &str,
+        rows: &[Vec<[u8; 4]>],
+    ) -> wgpu::TextureView {
+        let mut texels = vec![[0u8; 4]; PALETTE_SIZE * rows.len().max(1)];
+        for (texels, row) in texels.chunks_mut(PALETTE_SIZE).zip(rows) {
+            let len = row.len().min(PALETTE_SIZE);
+            texels[..len].copy_from_slice(&row[..len]);
+        }
+        let texture = device.create_texture_with_data(
+            queue,
+            &wgpu::TextureDescriptor {
+                label: Some(label),
+                size: wgpu::Extent3d {
+                    width: PALETTE_SIZE as u32,
+                    height: rows.len().max(1) as u32,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::Rgba8UnormSrgb,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            },
+            bytemuck::cast_slice(&texels),
+        );
+        texture.create_view(&wgpu::TextureViewDescriptor::default())
+    }
+
+    fn depth_target(
+        device: &wgpu::Device,
+        resources: &UIResources,
+        config: &wgpu::SurfaceConfiguration,
+    ) -> Option<wgpu::TextureView> {
+        let texture = resources.depth.then(|| {
+            device.create_texture(&wgpu::TextureDescriptor {
+                label: Some("UI Depth target"),
+                size: wgpu::Extent3d {
+                    width: config.width.max(1),
+                    height: config.height.max(1),
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: resources.samples,
+                dimension: wgpu::TextureDimension::D2,
+                format: Texture::DEPTH_FORMAT,
+                usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+                view_formats: &[],
+            })
+        })?;
+        Some(texture.create_view(&wgpu::TextureViewDescriptor::default()))
+    }
+
+    pub fn resize(&mut self, device: &wgpu::Device, config: &wgpu::SurfaceConfiguration) {
+        if self.msaa.is_some() {
+            self.msaa = MsaaTargets::new(device, &self.resources, config);
+        }
+        self.depth = Self::depth_target(device, &self.resources, config);
+        self.redraw_all = true;
+        if !self.custom_viewport {
+            self.fit_viewport(Viewport {
+                x: 0.0,
+                y: 0.0,
+                width: config.width as f32,
+                height: config.height as f32,
+            });
+        }
+    }
+
+    // Shortcut action bound to F9, unregister it from `shortcuts` to use
+    // the key for something else.
+    pub const WIREFRAME_ACTION: &'static str = "debug.wireframe";
+
+    pub fn toggle_wireframe(&mut self) {
+        if self.resources.wireframe_pipeline.is_none() {
+            log::warn!("The device can't draw lines, no wireframe view");
+            return;
+        }
+        self.debug.wireframe = !self.debug.wireframe;
+    }
+
+    // Restricts the scene to a region of the target, e.g. when a host
+    // application only gives the UI part of its window.
+    pub fn set_viewport(&mut self, viewport: Viewport) {
+        self.custom_viewport = true;
+        self.fit_viewport(viewport);
+    }
+
+    fn fit_viewport(&mut self, viewport: Viewport) {
+        self.viewport = viewport;
+        self.camera.width = viewport.width;
+        self.camera.height = viewport.height;
+    }
+
+    pub fn input(&mut self, event: &WindowEvent) -> bool {
+        if let Some(action) = self.shortcuts.input(event, self.focused) {
+            if action == Self::WIREFRAME_ACTION {
+                self.toggle_wireframe();
+            } else {
+                self.events.push(UIEvent::Shortcut(action.to_string()));
+            }
+            return true;
+        }
+
+        match event {
+            WindowEvent::HoveredFile(path) => {
+                self.events.push(UIEvent::FileHovered(path.clone()));
+                true
+            }
+            WindowEvent::HoveredFileCancelled => {
+                self.events.push(UIEvent::FileHoverCancelled);
+                true
+            }
+            WindowEvent::DroppedFile(path) => {
+                self.events.push(UIEvent::FileDropped {
+                    path: path.clone(),
+                    element: self.hovered,
+                });
+                true
+            }
+            WindowEvent::CursorMoved { position, .. } => {
+                let screen = cgmath::Vector2::new(
+                    position.x as f32 - self.viewport.x,
+                    position.y as f32 - self.viewport.y,
+                );
+                let delta = screen - self.cursor_screen;
+                self.cursor_screen = screen;
+                self.cursor = self.camera.screen_to_world(screen.x, screen.y);
+                self.hovered = self.element_at(self.cursor);
+
+                if let Some(press) = &self.press {
+                    if self.drag.is_none()
+                        && !self.pan.is_dragging()
+                        && self.input_config.exceeds_deadzone(press.screen, screen)
+                    {
+                        self.begin_drag(press.element);
+                    }
+                }
+
+                if self.pan.is_dragging() {
+                    // Dragging the canvas moves the camera the opposite way.
+                    self.pan
+                        .drag_by(cgmath::Vector2::new(-delta.x, delta.y) / self.camera.scale);
+                    return true;
+                }
+                if let Some(drag) = &self.drag {
+                    let position = self.cursor + drag.offset;
+                    let element = &mut self.elements[drag.element];
+                    element.instance.position.x = position.x;
+                    element.instance.position.y = position.y;
+                    return true;
+                }
+                false
+            }
+            WindowEvent::MouseInput {
+                state: ElementState::Pressed,
+                button: MouseButton::Left,
+                ..
+            } => {
+                let element = self.element_at(self.cursor);
+                self.set_focus(element);
+                // Kept even over empty space, where dragging pans the canvas,
+                // but the click itself is left to the scene behind the UI.
+                self.press = Some(Press {
+                    screen: self.cursor_screen,
+                    element,
+                });
+                element.is_some()
+            }
+            WindowEvent::MouseInput {
+                state: ElementState::Released,
+                button: MouseButton::Left,
+                ..
+            } => {
+                let Some(press) = self.press.take() else {
+                    return false;
+                };
+                let panned = self.pan.is_dragging();
+                if panned {
+                    self.pan.end_drag();
+                }
+                if let Some(drag) = self.drag.take() {
+                    self.elements[drag.element].elevation.retarget(0.0);
+                    return true;
+                }
+                if let (Some(element), false) = (press.element, panned) {
+                    self.events.push(UIEvent::Clicked(element));
+                }
+                press.element.is_some() || panned
+            }
+            WindowEvent::KeyboardInput {
+                input:
+                    KeyboardInput {
+                        state: ElementState::Pressed,
+                        virtual_keycode: Some(VirtualKeyCode::Tab),
+                        ..
+                    },
+                ..
+            } => {
+                let before = self.focused;
+                self.focus_next(self.shortcuts.modifiers().shift());
+                self.focused != before
+            }
+            WindowEvent::MouseWheel { delta, .. } => {
+                let pixels = self.input_config.scroll_delta(delta);
+                if self.input_config.scroll_priority == ScrollPriority::Widget {
+                    if let Some(element) = self.element_at(self.cursor) {
+                        self.events.push(UIEvent::Scrolled {
+                            element,
+                            delta: pixels,
+                        });
+                        return true;
+                    }
+                }
+
+                let world = pixels / self.camera.scale;
+                // Scrolling on at the end of the canvas goes to the scene
+                // behind the UI instead.
+                if let Some(bounds) = self.pan.bounds {
+                    let offset = self.pan.offset;
+                    if bounds.clamp(offset) == offset && bounds.clamp(offset + world) == offset {
+                        return false;
+                    }
+                }
+                match delta {
+                    // Wheel notches are coarse, so they're turned into a
+                    // fling that glides the same distance. Touchpads already
+                    // report smooth (and often OS-smoothed) deltas.
+                    MouseScrollDelta::LineDelta(..) => self.pan.fling_distance(world),
+                    MouseScrollDelta::PixelDelta(..) => self.pan.drag_by(world),
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    // Whether `disabled_tint` applies, rather than a disabled style.
+    fn is_grayed(&self, element: usize) -> bool {
+        !self.elements[element].styled && !self.is_enabled(element)
+    }
+
+    pub fn is_enabled(&self, element: usize) -> bool {
+        let mut current = Some(element);
+        // Bounded in case of a parent cycle.
+        for _ in 0..=self.elements.len() {
+            match current {
+                Some(i) if !self.elements[i].enabled => return false,
+                Some(i) => current = self.elements[i].parent,
+                None => return true,
+            }
+        }
+        true
+    }
+
+    pub fn set_parent(&mut self, child: usize, parent: Option<usize>) {
+        self.elements[child].parent = parent;
+    }
+
+    // Clips every element added until the matching `pop_clip` to `rect`, a
+    // screen rect relative to the viewport, within clips pushed before it:
+    //
+    //     scene.push_clip(panel);
+    //     scene.elements.push(row);
+    //     scene.pop_clip();
+    //
+    // For clips that follow a moving element, see `Player::clips_children`.
+    pub fn push_clip(&mut self, rect: Viewport) {
+        self.clip_stack.push((self.elements.len(), rect));
+    }
+
+    pub fn pop_clip(&mut self) {
+        let (start, rect) = self
+            .clip_stack
+            .pop()
+            .expect("`pop_clip` without a matching `push_clip`");
+        for element in self.elements.iter_mut().skip(start) {
+            element.clip = Some(element.clip.map_or(rect, |clip| clip.intersect(&rect)));
+        }
+    }
+
+    // Where `element` covers the viewport, ignoring its rotation.
+    fn screen_rect(&self, element: &Player) -> Viewport {
+        let half = element.size * element.instance.scale / 2.0;
+        let center = cgmath::Vector2::new(element.instance.position.x, element.instance.position.y);
+        self.camera
+            .world_rect_to_screen(center - half, center + half)
+    }
+
+    // Scissors `element` to a world rect, e.g. the filled part of a progress
+    // bar. The clip is in screen space, so set it again if the camera moves.
+    pub fn clip_to_world(
+        &mut self,
+        element: usize,
+        min: cgmath::Vector2<f32>,
+        max: cgmath::Vector2<f32>,
+    ) {
+        self.elements[element].clip = Some(self.camera.world_rect_to_screen(min, max));
+    }
+
+    // The direction of the closest ancestor, or the element itself, that has
+    // one, else the scene's.
+    pub fn direction_of(&self, element: usize) -> LayoutDirection {
+        let mut current = Some(element);
+        // Bounded in case of a parent cycle.
+        for _ in 0..self.elements.len() {
+            let Some(element) = current.and_then(|i| self.elements.get(i)) else {
+                break;
+            };
+            if let Some(direction) = element.direction {
+                return direction;
+            }
+            current = element.parent;
+        }
+        self.direction
+    }
+
+    fn update_inherited_clips(&mut self) {
+        let clips = (0..self.elements.len())
+            .map(|i| {
+                let mut clip: Option<Viewport> = None;
+                let mut current = self.elements[i].parent;
+                // Bounded in case of a parent cycle.
+                for _ in 0..self.elements.len() {
+                    let Some(ancestor) = current else {
+                        break;
+                    };
+                    let ancestor = &self.elements[ancestor];
+                    if ancestor.clips_children {
+                        let rect = self.screen_rect(ancestor);
+                        clip = Some(clip.map_or(rect, |clip| clip.intersect(&rect)));
+                    }
+                    current = ancestor.parent;
+                }
+                clip
+            })
+            .collect::<Vec<_>>();
+        for (element, clip) in self.elements.iter_mut().zip(clips) {
+            element.inherited_clip = clip;
+        }
+    }
+
+    // Moves focus to the next (or previous) visible, enabled element in
+    // insertion order, wrapping around.
+    pub fn focus_next(&mut self, reverse: bool) {
+        let count = self.elements.len();
+        if count == 0 {
+            return;
+        }
+        let start = self.focused.unwrap_or(if reverse { 0 } else { count - 1 });
+        let next = (1..=count)
+            .map(|step| {
+                if reverse {
+                    (start + count - step) % count
+                } else {
+                    (start + step) % count
+                }
+            })
+            .find(|&i| self.elements[i].visible && self.is_enabled(i));
+        self.set_focus(next);
+    }
+
+    pub fn set_focus(&mut self, element: Option<usize>) {
+        let element = element.filter(|&i| self.is_enabled(i));
+        // Others keep data there, e.g. a sprite's atlas rect.
+        let shows_selection = |player: &&mut Player| player.material.shows_selection();
+        if let Some(previous) = self
+            .focused
+            .and_then(|i| self.elements.get_mut(i))
+            .filter(shows_selection)
+        {
+            previous.instance.params[PARAM_SELECTION] = 0.0;
+        }
+        if let Some(next) = element
+            .and_then(|i| self.elements.get_mut(i))
+            .filter(shows_selection)
+        {
+            next.instance.params[PARAM_SELECTION] = 1.0;
+        }
+        self.focused = element;
+    }
+
+    // Where text typed into `element` goes, from `min` to `max` in element
+    // units relative to its position. Text widgets set it whenever the caret
+    // moves, so input methods can put their candidate window next to it.
+    pub fn set_caret(
+        &mut self,
+        element: usize,
+        min: cgmath::Vector2<f32>,
+        max: cgmath::Vector2<f32>,
+    ) {
+        self.carets.insert(element, (min, max));
+    }
+
+    // For elements that stop taking text.
+    pub fn clear_caret(&mut self, element: usize) {
+        self.carets.remove(&element);
+    }
+
+    // The focused element's caret in window pixels, or None if the focused
+    // element doesn't take text.
+    pub fn caret_rect(&self) -> Option<Viewport> {
+        let focused = self.focused?;
+        let (min, max) = self.carets.get(&focused)?;
+        let element = self.elements.get(focused).filter(|e| e.visible)?;
+        let position =
+            cgmath::Vector2::new(element.instance.position.x, element.instance.position.y);
+        let scale = element.instance.scale;
+        let rect = self
+            .camera
+            .world_rect_to_screen(position + min * scale, position + max * scale);
+        Some(Viewport {
+            x: self.viewport.x + rect.x,
+            y: self.viewport.y + rect.y,
+            ..rect
+        })
+    }
+
+    // Removes an element for good. Its slot keeps a hidden, disabled
+    // placeholder, so the indices widgets hold of the other elements stay
+    // valid, and its children move to the top level. Its buffers may still
+    // be in use by a frame in flight, so they go through the deletion queue
+    // instead of being dropped here. Use an `ElementPool` for elements that
+    // come and go often.
+    pub fn remove_element(
+        &mut self,
+        device: &wgpu::Device,
+        index: usize,
+        deletion_queue: &mut DeletionQueue,
+    ) {
+        let black = [0.0, 0.0, 0.0];
+        let mut placeholder = Player::with_gradient(
+            device,
+            cgmath::Vector2::new(0.0, 0.0),
+            cgmath::Vector2::new(1.0, 1.0),
+            black,
+            black,
+        );
+        placeholder.visible = false;
+        placeholder.enabled = false;
+        placeholder.draggable = false;
+        let element = std::mem::replace(&mut self.elements[index], placeholder);
+        deletion_queue.defer(element.vertex_buffer);
+        deletion_queue.defer(element.index_buffer);
+        for buffer in element.instance_buffers {
+            deletion_queue.defer(buffer);
+        }
+
+        for element in &mut self.elements {
+            if element.parent == Some(index) {
+                element.parent = None;
+            }
+        }
+        if self.focused == Some(index) {
+            self.focused = None;
+        }
+        if self.hovered == Some(index) {
+            self.hovered = None;
+        }
+        self.carets.remove(&index);
+        if let Some(press) = self
+            .press
+            .as_mut()
+            .filter(|press| press.element == Some(index))
+        {
+            press.element = None;
+        }
+        if self.drag.as_ref().is_some_and(|drag| drag.element == index) {
+            self.drag = None;
+        }
+    }
+
+    fn element_at(&self, point: cgmath::Vector2<f32>) -> Option<usize> {
+        let screen = self.camera.world_to_screen(point);
+        // Topmost element wins, which is the last one drawn.
+        self.draw_order().into_iter().rev().find(|&i| {
+            let element = &self.elements[i];
+            element.visible
+                && self.is_enabled(i)
+                && element
+                    .effective_clip()
+                    .is_none_or(|clip| clip.contains(screen))
+                && element.contains(point)
+        })
+    }
+
+    fn begin_drag(&mut self, element: Option<usize>) {
+        match element {
+            Some(index) if self.elements[index].draggable => {
+                let element = &mut self.elements[index];
+                element.elevation.retarget(1.0);
+                let position = element.instance.position;
+                self.drag = Some(Drag {
+                    element: index,
+                    offset: cgmath::Vector2::new(position.x, position.y) - self.cursor,
+                });
+            }
+            Some(_) => {}
+            None => self.pan.begin_drag(),
+        }
+    }
+
+    // In world coordinates.
+    pub fn cursor(&self) -> cgmath::Vector2<f32> {
+        self.cursor
+    }
+
+    pub fn hovered(&self) -> Option<usize> {
+        self.hovered
+    }
+
+    // The element under the left button, if it was pressed on one.
+    pub fn pressed(&self) -> Option<usize> {
+        self.press.as_ref().and_then(|press| press.element)
+    }
+
+    pub fn drain_events(&mut self) -> Vec<UIEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    // For events that don't come from window input, e.g. file dialogs.
+    pub fn push_event(&mut self, event: UIEvent) {
+        self.events.push(event);
+    }
+
+    // Puts events back in front of any queued since they were drained, for
+    // consumers that only handle some kinds of events.
+    pub fn requeue_events(&mut self, events: Vec<UIEvent>) {
+        self.events.splice(0..0, events);
+    }
+
+    // Retessellates elements whose `tessellation` was changed, or whose
+    // `Tessellation::Screen` tolerance zooming made too coarse or needlessly
+    // fine. Call before `prepare`.
+    pub fn retessellate(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        deletion_queue: &mut DeletionQueue,
+    ) {
+        let scale = self.camera.scale;
+        for element in &mut self.elements {
+            element.retessellate(device, queue, deletion_queue, scale);
+        }
+    }
+
+    // Uploads everything the next `render_into` needs. Kept separate from
+    // rendering so hosts with their own frame loop decide when writes happen.
+    pub fn prepare(
+        &mut self,
+        frame: &FrameContext,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        deletion_queue: &mut DeletionQueue,
+    ) {
+        let now = Instant::now();
+        let dt = frame.dt;
+
+        self.pan.update(dt);
+        self.camera.position = self.pan.offset;
+        self.camera_uniform.time = (now - self.started).as_secs_f32();
+        self.camera_uniform.update_background(&self.background);
+        self.write_camera(frame, queue);
+
+        self.update_inherited_clips();
+
+        if self.focused.is_some_and(|i| !self.is_enabled(i)) {
+            self.set_focus(None);
+        }
+
+        let grayed = (0..self.elements.len())
+            .map(|i| self.is_grayed(i))
+            .collect::<Vec<_>>();
+        // With a depth buffer every element gets its own depth, from its
+        // place in the draw order, between the camera's near and far planes.
+        let mut depths = self
+            .elements
+            .iter()
+            .map(|element| element.instance.position.z)
+            .collect::<Vec<_>>();
+        if self.depth.is_some() {
+            let count = self.elements.len() as f32 + 1.0;
+            for (rank, i) in self.draw_order().into_iter().enumerate() {
+                depths[i] = (rank as f32 + 1.0) / count * 2.0 - 1.0;
+            }
+        }
+        let mut instances = Vec::with_capacity(self.elements.len());
+        for ((element, grayed), z) in self.elements.iter_mut().zip(grayed).zip(depths) {
+            element.elevation.update(dt);
+            if let Some((_, fade)) = &mut element.flash {
+                fade.update(dt);
+                if fade.is_finished() {
+                    element.flash = None;
+                }
+            }
+            let tint = if grayed { self.disabled_tint } else { [1.0; 4] };
+            let raw = element.instances(tint, z);
+            // Everything else draws from the batch buffer, see `write_batch`.
+            if element.is_elevated() {
+                queue.write_buffer(
+                    element.instance_buffers.get(frame),
+                    0,
+                    bytemuck::cast_slice(&raw),
+                );
+            }
+            instances.push(raw);
+        }
+        self.write_batch(frame, device, queue, deletion_queue, &instances);
+        self.update_damage(instances);
+        self.prepared = Some(*frame);
+    }
+
+    // Puts each element's own instance, without its shadows, into the
+    // frame's batch buffer in draw order.
+    fn write_batch(
+        &mut self,
+        frame: &FrameContext,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        deletion_queue: &mut DeletionQueue,
+        instances: &[Vec<InstanceRaw>],
+    ) {
+        self.batch_order = self.pass_order();
+        self.batch_slots = vec![0; self.elements.len()];
+        for (slot, &i) in self.batch_order.iter().enumerate() {
+            self.batch_slots[i] = slot as u32;
+        }
+        let batch = self
+            .batch_order
+            .iter()
+            .map(|&i| instances[i][SHADOW_LAYERS])
+            .collect::<Vec<_>>();
+        if batch.is_empty() {
+            return;
+        }
+        let size = std::mem::size_of_val(batch.as_slice()) as wgpu::BufferAddress;
+        if self.batch_buffers.get(frame).size() < size {
+            let buffer = Self::batch_buffer(device, batch.len().next_power_of_two());
+            deletion_queue.defer(std::mem::replace(self.batch_buffers.get_mut(frame), buffer));
+        }
+        queue.write_buffer(
+            self.batch_buffers.get(frame),
+            0,
+            bytemuck::cast_slice(&batch),
+        );
+    }
+
+    fn batch_buffer(device: &wgpu::Device, capacity: usize) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("UI Batch instance buffer"),
+            size: (capacity * std::mem::size_of::<InstanceRaw>()) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    // `draw_order`, with opaque elements first when there's a depth buffer.
+    fn pass_order(&self) -> Vec<usize> {
+        let mut order = self.draw_order();
+        if self.depth.is_some() {
+            // Opaque elements can't wrongly cover what's drawn after them,
+            // so they go first, grouped by pipeline. The others still blend
+            // back to front.
+            order.sort_by_key(|&i| {
+                let element = &self.elements[i];
+                let opaque =
+                    element.is_opaque() && (self.disabled_tint[3] >= 1.0 || !self.is_grayed(i));
+                if opaque {
+                    (false, element.material as usize, element.blend as usize)
+                } else {
+                    (true, 0, 0)
+                }
+            });
+        }
+        order
+    }
+
+    // Where the frame about to be rendered differs from the last one, in
+    // screen pixels relative to the viewport, or None if it's the same. Hosts
+    // that only draw the UI can skip frames without damage, see
+    // `RendererOptions::skip_idle_frames`, and ones rendering into a target
+    // they keep can scissor to it.
+    pub fn damage(&self) -> Option<Viewport> {
+        self.damage
+    }
+
+    fn update_damage(&mut self, instances: Vec<Vec<InstanceRaw>>) {
+        let full = Viewport {
+            x: 0.0,
+            y: 0.0,
+            ..self.viewport
+        };
+        // Time only animates elements with a selection pulse, checked below.
+        let mut camera = self.camera_uniform;
+        camera.time = 0.0;
+        let scene = (
+            bytemuck::bytes_of(&camera).to_vec(),
+            self.viewport,
+            self.debug,
+            self.background,
+        );
+        let everything = std::mem::take(&mut self.redraw_all)
+            || self.drawn.len() > self.elements.len()
+            || self.drawn_scene.as_ref() != Some(&scene);
+        self.drawn_scene = Some(scene);
+
+        let mut damage: Option<Viewport> = None;
+        let mut add = |rect: Option<Viewport>| {
+            if let Some(rect) = rect {
+                damage = Some(damage.map_or(rect, |damage| damage.union(&rect)));
+            }
+        };
+        let mut drawn = Vec::with_capacity(instances.len());
+        for (i, instances) in instances.into_iter().enumerate() {
+            let element = &self.elements[i];
+            let rect = element
+                .visible
+                .then(|| self.covered_rect(element, &instances))
+                .filter(|rect| !rect.is_empty());
+            let state = (
+                element.material,
+                element.blend,
+                Rc::as_ptr(&element.vertex_buffer),
+                element.revision,
+                element.num_indices,
+                element.texture_fill.as_ref().map(Rc::as_ptr),
+            );
+            let pulsing = element.instance.params[PARAM_SELECTION] > 0.0
+                && element.material.shows_selection();
+            match self.drawn.get(i) {
+                Some(before)
+                    if !pulsing
+                        && before.state == state
+                        && before.rect == rect
+                        && bytemuck::cast_slice::<_, u8>(&before.instances)
+                            == bytemuck::cast_slice::<_, u8>(&instances) => {}
+                Some(before) => {
+                    add(before.rect);
+                    add(rect);
+                }
+                None => add(rect),
+            }
+            drawn.push(Drawn {
+                instances,
+                rect,
+                state,
+            });
+        }
+        self.drawn = drawn;
+        self.damage = if everything {
+            Some(full)
+        } else {
+            damage
+                .map(|damage| damage.intersect(&full))
+                .filter(|damage| !damage.is_empty())
+        };
+    }
+
+    // The element's box under each instance drawn, on screen and clipped,
+    // with a pixel to spare for antialiasing.
+    fn covered_rect(&self, element: &Player, instances: &[InstanceRaw]) -> Viewport {
+        let drawn = if element.is_elevated() {
+            instances
+        } else {
+            &instances[SHADOW_LAYERS..]
+        };
+        // Strokes reach half their width past the shape.
+        let reach = element
+            .drawing
+            .as_ref()
+            .and_then(|drawing| drawing.outline)
+            .map_or(0.0, |outline| outline.stroke.width / 2.0);
+        let half = element.size / 2.0 + cgmath::Vector2::new(reach, reach);
+        let mut rect: Option<Viewport> = None;
+        for raw in drawn {
+            let model = cgmath::Matrix4::from(raw.model);
+            for (x, y) in [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)] {
+                let p = model * cgmath::Vector4::new(half.x * x, half.y * y, 0.0, 1.0);
+                let p = self.camera.world_to_screen(cgmath::Vector2::new(p.x, p.y));
+                let point = Viewport {
+                    x: p.x - 1.0,
+                    y: p.y - 1.0,
+                    width: 2.0,
+                    height: 2.0,
+                };
+                rect = Some(rect.map_or(point, |rect| rect.union(&point)));
+            }
+        }
+        let rect = rect.unwrap_or(Viewport {
+            x: 0.0,
+            y: 0.0,
+            width: 0.0,
+            height: 0.0,
+        });
+        match element.effective_clip() {
+            Some(clip) => rect.intersect(&clip),
+            None => rect,
+        }
+    }
+
+    // Back to front by z index, except lifted elements which are moved to
+    // the top layer until they've settled back down.
+    pub(crate) fn draw_order(&self) -> Vec<usize> {
+        let mut order = (0..self.elements.len()).collect::<Vec<_>>();
+        order.sort_by_key(|&i| {
+            let element = &self.elements[i];
+            (element.is_elevated(), element.z_index)
+        });
+        order
+    }
+
+    pub fn render(
+        &mut self,
+        frame: &FrameContext,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        view: &wgpu::TextureView,
+    ) {
+        let viewport = self.viewport;
+        self.render_into(frame, queue, encoder, view, &viewport);
+    }
+
+    // Records the UI pass only; call `prepare` first for this frame. A
+    // viewport other than the scene's becomes the scene's, with the camera
+    // resized to it.
+    pub fn render_into(
+        &mut self,
+        frame: &FrameContext,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        view: &wgpu::TextureView,
+        viewport: &Viewport,
+    ) {
+        if *viewport != self.viewport {
+            self.set_viewport(*viewport);
+            self.write_camera(frame, queue);
+        }
+        let (target, resolve_target, load) = match &self.msaa {
+            // The multisampled target starts out empty and is blended over
+            // `view` once resolved, see `composite`.
+            Some(msaa) => (
+                &msaa.color,
+                Some(&msaa.resolved),
+                wgpu::LoadOp::Clear(self.clear_color().unwrap_or(wgpu::Color::TRANSPARENT)),
+            ),
+            None => (
+                view,
+                None,
+                self.clear_color()
+                    .map_or(wgpu::LoadOp::Load, wgpu::LoadOp::Clear),
+            ),
+        };
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("UI Render pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: target,
+                resolve_target,
+                ops: wgpu::Operations {
+                    load,
+                    // Only the resolved samples are needed afterwards.
+                    store: self.msaa.is_none(),
+                },
+            })],
+            depth_stencil_attachment: self.depth.as_ref().map(|view| {
+                wgpu::RenderPassDepthStencilAttachment {
+                    view,
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(1.0),
+                        store: false,
+                    }),
+                    stencil_ops: None,
+                }
+            }),
+        });
+        self.draw_elements(&mut render_pass, frame, viewport);
+        drop(render_pass);
+
+        if let Some(msaa) = &self.msaa {
+            self.composite(encoder, view, viewport, msaa);
+        }
+    }
+
+    fn write_camera(&mut self, frame: &FrameContext, queue: &wgpu::Queue) {
+        self.camera_uniform.update_view_proj(&self.camera);
+        queue.write_buffer(
+            self.camera_buffers.get(frame),
+            0,
+            bytemuck::cast_slice(&[self.camera_uniform]),
+        );
+    }
+
+    // Premultiplied and encoded like the shader's output.
+    fn clear_color(&self) -> Option<wgpu::Color> {
+        let Background::Solid([r, g, b, a]) = self.background else {
+            return None;
+        };
+        let encode = |c: f32| {
+            if self.camera_uniform.encode_srgb != 0 {
+                linear_to_srgb(c)
+            } else {
+                c
+            }
+        };
+        Some(wgpu::Color {
+            r: (encode(r) * a) as f64,
+            g: (encode(g) * a) as f64,
+            b: (encode(b) * a) as f64,
+            a: a as f64,
+        })
+    }
+
+    fn draw_elements<'a>(
+        &'a self,
+        render_pass: &mut wgpu::RenderPass<'a>,
+        frame: &FrameContext,
+        viewport: &Viewport,
+    ) {
+        render_pass.set_viewport(
+            viewport.x,
+            viewport.y,
+            viewport.width,
+            viewport.height,
+            0.0,
+            1.0,
+        );
+        render_pass.set_bind_group(0, self.camera_bind_groups.get(frame), &[]);
+        render_pass.set_bind_group(1, &self.material_bind_group, &[]);
+
+        if self.background.mode() != 0 {
+            render_pass.set_pipeline(&self.resources.background_pipeline);
+            render_pass.draw(0..3, 0..1);
+        }
+
+        // With their place in the batch buffer.
+        let visible = self
+            .batch_order
+            .iter()
+            .enumerate()
+            .filter_map(|(slot, &i)| {
+                let element = &self.elements[i];
+                element.visible.then_some((slot as u32, element))
+            });
+
+        // Clip rects are relative to the scene viewport, scissor rects to
+        // the target.
+        let scissor = |element: &Player| {
+            let full = Viewport {
+                x: 0.0,
+                y: 0.0,
+                ..*viewport
+            };
+            let clip = element
+                .effective_clip()
+                .map_or(full, |clip| clip.intersect(&full));
+            scissor_rect(
+                viewport,
+                &Viewport {
+                    x: viewport.x + clip.x,
+                    y: viewport.y + clip.y,
+                    ..clip
+                },
+            )
+        };
+        let mut current_scissor = None;
+
+        if self.debug.overdraw {
+            render_pass.set_pipeline(&self.resources.overdraw_pipeline);
+        }
+        let batch = self.batch_buffers.get(frame);
+        let mut material = None;
+        let mut fill = None;
+        // Elements not lifted are drawn from the batch buffer, consecutive
+        // ones with the same geometry, pipeline, fill and scissor rect in
+        // one call. The run is the first element and its batch instances.
+        let mut run: Option<(&Player, std::ops::Range<u32>)> = None;
+        for (slot, element) in visible.clone() {
+            let rect = scissor(element);
+            if rect.2 == 0 || rect.3 == 0 {
+                continue;
+            }
+            if let Some((first, instances)) = &mut run {
+                if !element.is_elevated()
+                    && instances.end == slot
+                    && current_scissor == Some(rect)
+                    && shares_draw(first, element)
+                {
+                    instances.end += 1;
+                    continue;
+                }
+            }
+            if let Some((first, instances)) = run.take() {
+                Self::draw_batch(render_pass, batch, first, instances);
+            }
+            if current_scissor != Some(rect) {
+                current_scissor = Some(rect);
+                render_pass.set_scissor_rect(rect.0, rect.1, rect.2, rect.3);
+            }
+            let group = element.texture_fill.as_deref();
+            if fill != Some(group.map(|g| g as *const _)) {
+                fill = Some(group.map(|g| g as *const _));
+                render_pass.set_bind_group(1, group.unwrap_or(&self.material_bind_group), &[]);
+            }
+            let key = (element.material, element.blend);
+            if !self.debug.overdraw && material != Some(key) {
+                material = Some(key);
+                render_pass.set_pipeline(self.resources.pipeline(key.0, key.1));
+            }
+            if element.is_elevated() {
+                Self::draw_element(render_pass, frame, element, 0..(SHADOW_LAYERS as u32 + 1));
+            } else {
+                run = Some((element, slot..slot + 1));
+            }
+        }
+        if let Some((first, instances)) = run {
+            Self::draw_batch(render_pass, batch, first, instances);
+        }
+
+        if let (true, Some(pipeline)) = (self.debug.wireframe, &self.resources.wireframe_pipeline) {
+            render_pass.set_pipeline(pipeline);
+            for (slot, element) in visible.clone() {
+                let rect = scissor(element);
+                if rect.2 == 0 || rect.3 == 0 {
+                    continue;
+                }
+                if current_scissor != Some(rect) {
+                    current_scissor = Some(rect);
+                    render_pass.set_scissor_rect(rect.0, rect.1, rect.2, rect.3);
+                }
+                Self::draw_batch(render_pass, batch, element, slot..slot + 1);
+            }
+        }
+
+        if self.debug.bounds {
+            render_pass.set_pipeline(&self.resources.bounds_pipeline);
+            for (slot, element) in visible {
+                let rect = scissor(element);
+                if rect.2 == 0 || rect.3 == 0 {
+                    continue;
+                }
+                if current_scissor != Some(rect) {
+                    current_scissor = Some(rect);
+                    render_pass.set_scissor_rect(rect.0, rect.1, rect.2, rect.3);
+                }
+                Self::draw_batch(render_pass, batch, element, slot..slot + 1);
+            }
+        }
+    }
+
+    // Blends the resolved UI over `view`. The UI was drawn onto transparent
+    // black, so its colors are premultiplied by then.
+    fn composite(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        view: &wgpu::TextureView,
+        viewport: &Viewport,
+        msaa: &MsaaTargets,
+    ) {
+        let Some(pipeline) = &self.resources.composite_pipeline else {
+            return;
+        };
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("UI Composite pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: true,
+                },
+            })],
+            depth_stencil_attachment: None,
+        });
+        let (x, y, width, height) = scissor_rect(viewport, viewport);
+        render_pass.set_scissor_rect(x, y, width, height);
+        render_pass.set_pipeline(pipeline);
+        render_pass.set_bind_group(0, &msaa.bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+
+    // `instances` of the batch buffer, with `element`'s geometry.
+    fn draw_batch<'a>(
+        render_pass: &mut wgpu::RenderPass<'a>,
+        batch: &'a wgpu::Buffer,
+        element: &'a Player,
+        instances: std::ops::Range<u32>,
+    ) {
+        render_pass.set_vertex_buffer(0, element.vertex_buffer.slice(..));
+        render_pass.set_vertex_buffer(1, batch.slice(..));
+        render_pass.set_index_buffer(element.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+        render_pass.draw_indexed(0..element.num_indices, 0, instances);
+    }
 
-        render_pass.set_pipeline(&self.render_pipeline);
-        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
-        render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
-        render_pass.draw_indexed(0..INDICES.len() as u32, 0, 0..1);
+    fn draw_element<'a>(
+        render_pass: &mut wgpu::RenderPass<'a>,
+        frame: &FrameContext,
+        element: &'a Player,
+        instances: std::ops::Range<u32>,
+    ) {
+        render_pass.set_vertex_buffer(0, element.vertex_buffer.slice(..));
+        render_pass.set_vertex_buffer(1, element.instance_buffers.get(frame).slice(..));
+        render_pass.set_index_buffer(element.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+        render_pass.draw_indexed(0..element.num_indices, 0, instances);
     }
 }
@@ -1,29 +1,299 @@
 // Vertex shader
 
+struct InstanceInput {
+    @location(5) model_matrix_0: vec4<f32>,
+    @location(6) model_matrix_1: vec4<f32>,
+    @location(7) model_matrix_2: vec4<f32>,
+    @location(8) model_matrix_3: vec4<f32>,
+    @location(9) tint: vec4<f32>,
+    @location(10) params: vec4<f32>,
+}
+
 struct VertexInput {
     @location(0) position: vec3<f32>,
-    @location(1) color: vec3<f32>
+    @location(1) color: vec4<f32>,
+    @location(2) uv: vec2<f32>,
 }
 
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
-    @location(0) color: vec3<f32>,
+    @location(0) color: vec4<f32>,
+    // The same for the whole instance, and not interpolated so materials
+    // can pack integers into it exactly.
+    @location(1) @interpolate(flat) params: vec4<f32>,
+    @location(2) uv: vec2<f32>,
+    // Untinted vertex color and the tint, for materials that read the
+    // color as data.
+    @location(3) vertex_color: vec4<f32>,
+    @location(4) tint: vec4<f32>,
+    // Position in the element's units and the scene's, for patterns.
+    @location(5) local: vec2<f32>,
+    @location(6) world: vec2<f32>,
+};
+
+struct CameraUniform {
+    view_proj: mat4x4<f32>,
+    time: f32,
+    // Non-zero for targets that store what they're given, see
+    // `CameraUniform::encode_srgb`.
+    encode_srgb: u32,
+    // 1 for a vertical gradient, 2 for a checkerboard, see `Background`.
+    background_mode: u32,
+    checker_size: f32,
+    background: array<vec4<f32>, 2>,
 };
 
+@group(0) @binding(0)
+var<uniform> camera: CameraUniform;
+
+// One palette per row, see `UIScene::set_palettes`.
+@group(1) @binding(0)
+var t_palette: texture_2d<f32>;
+
+// Sprites and icons, see `UIScene::set_atlas`.
+@group(1) @binding(1)
+var t_atlas: texture_2d<f32>;
+@group(1) @binding(2)
+var s_atlas: sampler;
+
+// Repeated by patterns, see `UIScene::set_pattern`.
+@group(1) @binding(3)
+var t_pattern: texture_2d<f32>;
+@group(1) @binding(4)
+var s_pattern: sampler;
+
+// One gradient per row, see `UIScene::add_gradient`.
+@group(1) @binding(5)
+var t_gradients: texture_2d<f32>;
 
 @vertex
 fn vs_main(
     model: VertexInput,
+    instance: InstanceInput,
 ) -> VertexOutput {
+    let model_matrix = mat4x4<f32>(
+        instance.model_matrix_0,
+        instance.model_matrix_1,
+        instance.model_matrix_2,
+        instance.model_matrix_3,
+    );
     var out: VertexOutput;
-    out.clip_position = vec4<f32>(model.position, 1.0);
-    out.color = model.color;
+    let world = model_matrix * vec4<f32>(model.position, 1.0);
+    out.clip_position = camera.view_proj * world;
+    out.color = model.color * instance.tint;
+    out.vertex_color = model.color;
+    out.tint = instance.tint;
+    out.params = instance.params;
+    out.uv = model.uv;
+    out.local = model.position.xy;
+    out.world = world.xy;
     return out;
 }
 
 
 
+fn linear_to_srgb(color: vec3<f32>) -> vec3<f32> {
+    let low = color * 12.92;
+    let high = 1.055 * pow(color, vec3<f32>(1.0 / 2.4)) - 0.055;
+    return select(high, low, color <= vec3<f32>(0.0031308));
+}
+
+// params: x = phase, y = progress, z = selection amount, w = border width
+// in pixels
+fn shade(in: VertexOutput) -> vec4<f32> {
+    let pulse = 0.5 + 0.5 * sin(camera.time * 6.0 + in.params.x);
+    let highlight = in.params.z * 0.25 * pulse;
+    var color = min(in.color.rgb + vec3<f32>(highlight), vec3<f32>(1.0));
+
+    // Distance to the closest edge in pixels, using the uv derivatives so
+    // the width doesn't depend on the element size or zoom.
+    let to_edge = min(in.uv, vec2<f32>(1.0) - in.uv) / fwidth(in.uv);
+    let border = 1.0 - smoothstep(in.params.w - 0.5, in.params.w + 0.5, min(to_edge.x, to_edge.y));
+    color = mix(color, color * 0.35, border * step(0.001, in.params.w));
+    if camera.encode_srgb != 0u {
+        color = linear_to_srgb(color);
+    }
+    // Premultiplied, which blends translucent edges without dark fringes.
+    return vec4<f32>(color * in.color.a, in.color.a);
+}
+
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32>{
-    return vec4(0.5, 0.0, 0.0, 1.0);
+    return shade(in);
+}
+
+const TAU: f32 = 6.28318530718;
+
+@fragment
+fn fs_radial_fill(in: VertexOutput) -> @location(0) vec4<f32>{
+    let d = in.uv - vec2<f32>(0.5);
+    // 0 at 12 o'clock, growing clockwise.
+    let angle = fract(atan2(d.x, d.y) / TAU + 1.0);
+    if angle > in.params.y {
+        discard;
+    }
+    return shade(in);
+}
+
+// The vertex color's red channel picks an entry of the palette in row
+// params.y, 0 being the first entry and 1 the last.
+@fragment
+fn fs_palette_swap(in: VertexOutput) -> @location(0) vec4<f32>{
+    let size = textureDimensions(t_palette);
+    let index = u32(round(clamp(in.vertex_color.r, 0.0, 1.0) * f32(size.x - 1u)));
+    let row = min(u32(max(in.params.y, 0.0)), size.y - 1u);
+    let entry = textureLoad(t_palette, vec2<u32>(index, row), 0);
+    var swapped = in;
+    swapped.color = vec4<f32>(entry.rgb, entry.a * in.vertex_color.a) * in.tint;
+    return shade(swapped);
+}
+
+// params holds the sprite's sub-rect of the atlas, top left then bottom
+// right, so it doesn't get the pulse or border of other materials.
+@fragment
+fn fs_sprite(in: VertexOutput) -> @location(0) vec4<f32>{
+    // uv goes up with the element, the atlas down.
+    let t = vec2<f32>(in.uv.x, 1.0 - in.uv.y);
+    let texel = textureSample(t_atlas, s_atlas, mix(in.params.xy, in.params.zw, t));
+    var sprite = in;
+    sprite.color = texel * in.color;
+    sprite.params = vec4<f32>(0.0);
+    return shade(sprite);
+}
+
+// params: xy = tile size, z = 1 to tile in world space rather than the
+// element's, w = angle in radians.
+@fragment
+fn fs_pattern(in: VertexOutput) -> @location(0) vec4<f32>{
+    let p = select(in.local, in.world, in.params.z > 0.5);
+    let c = cos(in.params.w);
+    let s = sin(in.params.w);
+    // Shadows pass no tile size.
+    let tile = max(abs(in.params.xy), vec2<f32>(0.0001));
+    let turned = vec2<f32>(c * p.x + s * p.y, c * p.y - s * p.x);
+    // The texture goes down, the scene up. The sampler does the wrapping.
+    let t = vec2<f32>(turned.x, -turned.y) / tile;
+    let texel = textureSample(t_pattern, s_pattern, t);
+    var pattern = in;
+    pattern.color = texel * in.color;
+    pattern.params = vec4<f32>(0.0);
+    return shade(pattern);
+}
+
+// params: x = 0 linear, 1 radial, 2 conic, y = gradient row, z = angle in
+// radians, see `GradientKind`.
+@fragment
+fn fs_gradient(in: VertexOutput) -> @location(0) vec4<f32>{
+    let d = in.uv - vec2<f32>(0.5);
+    let c = cos(in.params.z);
+    let s = sin(in.params.z);
+    var t: f32;
+    if in.params.x < 0.5 {
+        t = dot(d, vec2<f32>(c, s)) / (abs(c) + abs(s)) + 0.5;
+    } else if in.params.x < 1.5 {
+        t = length(d) * 2.0;
+    } else {
+        t = fract((atan2(d.x, d.y) - in.params.z) / TAU + 1.0);
+    }
+    // Mixed by hand between the two closest texels of the row.
+    let size = textureDimensions(t_gradients);
+    let row = min(u32(max(in.params.y, 0.0)), size.y - 1u);
+    let x = clamp(t, 0.0, 1.0) * f32(size.x - 1u);
+    let i = u32(x);
+    let a = textureLoad(t_gradients, vec2<u32>(i, row), 0);
+    let b = textureLoad(t_gradients, vec2<u32>(min(i + 1u, size.x - 1u), row), 0);
+    var gradient = in;
+    gradient.color = mix(a, b, fract(x)) * in.color;
+    gradient.params = vec4<f32>(0.0);
+    return shade(gradient);
+}
+
+// params: xy = the glyph's texel rect in the texture fill, each axis packed
+// as min + 4096 * max, z = halo width and w = how far it fades out, both in
+// distance field units, see `text::Halo`.
+@fragment
+fn fs_sdf(in: VertexOutput) -> @location(0) vec4<f32>{
+    let high = floor(in.params.xy / 4096.0);
+    let low = in.params.xy - high * 4096.0;
+    let t = vec2<f32>(in.uv.x, 1.0 - in.uv.y);
+    let size = vec2<f32>(textureDimensions(t_atlas));
+    let d = textureSample(t_atlas, s_atlas, mix(low, high, t) / size).a;
+    // Half a screen pixel of antialiasing at any zoom.
+    let aa = max(fwidth(d) * 0.5, 0.0001);
+    let fill = smoothstep(0.5 - aa, 0.5 + aa, d);
+    let edge = 0.5 - in.params.z;
+    let halo = smoothstep(edge - aa - in.params.w, edge + aa, d)
+        * step(0.0001, in.params.z + in.params.w)
+        * (1.0 - fill);
+    let coverage = fill + halo;
+    var glyph = in;
+    glyph.color = vec4<f32>(
+        (in.tint.rgb * fill + in.vertex_color.rgb * halo) / max(coverage, 0.0001),
+        coverage * in.tint.a,
+    );
+    glyph.params = vec4<f32>(0.0);
+    return shade(glyph);
+}
+
+// Background, a single triangle covering the viewport.
+
+struct BackgroundOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    // 0 at the top of the viewport, 1 at the bottom.
+    @location(0) y: f32,
+};
+
+@vertex
+fn vs_background(@builtin(vertex_index) index: u32) -> BackgroundOutput {
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    var out: BackgroundOutput;
+    out.clip_position = vec4<f32>(uv * 2.0 - 1.0, 1.0, 1.0);
+    out.y = 1.0 - uv.y;
+    return out;
+}
+
+@fragment
+fn fs_background(in: BackgroundOutput) -> @location(0) vec4<f32> {
+    var color: vec4<f32>;
+    if camera.background_mode == 2u {
+        let cell = vec2<i32>(floor(in.clip_position.xy / camera.checker_size));
+        color = camera.background[(cell.x + cell.y) & 1];
+    } else {
+        color = mix(camera.background[0], camera.background[1], clamp(in.y, 0.0, 1.0));
+    }
+    var rgb = color.rgb;
+    if camera.encode_srgb != 0u {
+        rgb = linear_to_srgb(rgb);
+    }
+    return vec4<f32>(rgb * color.a, color.a);
+}
+
+// Debug views
+
+@fragment
+fn fs_overdraw(in: VertexOutput) -> @location(0) vec4<f32>{
+    return vec4<f32>(0.1, 0.04, 0.02, 1.0);
+}
+
+@fragment
+fn fs_wireframe(in: VertexOutput) -> @location(0) vec4<f32>{
+    return vec4<f32>(0.2, 1.0, 0.5, 1.0);
+}
+
+@fragment
+fn fs_bounds(in: VertexOutput) -> @location(0) vec4<f32>{
+    // About 1.5 pixels whatever the element's size or the camera zoom.
+    let edge = fwidth(in.uv) * 1.5;
+    if all(in.uv > edge) && all(in.uv < vec2<f32>(1.0) - edge) {
+        discard;
+    }
+    return vec4<f32>(1.0, 0.0, 1.0, 1.0);
+}
+
+@fragment
+fn fs_linear_fill(in: VertexOutput) -> @location(0) vec4<f32>{
+    if in.uv.x > in.params.y {
+        discard;
+    }
+    return shade(in);
 }
@@ -0,0 +1,65 @@
+use serde::{Deserialize, Serialize};
+
+use crate::ui_scene::{Stroke, UIScene};
+
+// A length with its unit spelled out. The scene otherwise mixes two without
+// saying so: element sizes, positions and strokes are world units, which
+// zoom with the camera, while styles, clips and viewports are target pixels,
+// which don't. Neither follows the display's scale factor.
+//
+//     let border = scene.stroke(Length::Points(1.0)); // a hairline at any zoom and DPI
+//     let width = scene.world_length(Length::Percent(50.0), scene.viewport_world().x);
+//
+// Only `TextStyle::size` and `UIScene::stroke` take a `Length` so far.
+// Shape sizes, `Stroke::new` and the widget styles still take plain numbers
+// in the units above, so convert with `world_length` or `pixel_length`
+// before passing them.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Length {
+    // Pixels of the target the scene renders into.
+    Px(f32),
+    // Device independent pixels, multiplied by the scale factor, see
+    // `UIScene::scale_factor`.
+    Points(f32),
+    // Scene units, scaled by the camera's zoom on screen.
+    World(f32),
+    // Of whatever extent the caller resolves it against.
+    Percent(f32),
+}
+
+impl UIScene {
+    // `length` in world units at the current zoom, percentages being of
+    // `extent` world units.
+    pub fn world_length(&self, length: Length, extent: f32) -> f32 {
+        match length {
+            Length::Px(px) => px / self.camera.scale,
+            Length::Points(points) => points * self.scale_factor / self.camera.scale,
+            Length::World(world) => world,
+            Length::Percent(percent) => extent * percent / 100.0,
+        }
+    }
+
+    // `length` in target pixels at the current zoom, percentages being of
+    // `extent` pixels.
+    pub fn pixel_length(&self, length: Length, extent: f32) -> f32 {
+        match length {
+            Length::Px(px) => px,
+            Length::Points(points) => points * self.scale_factor,
+            Length::World(world) => world * self.camera.scale,
+            Length::Percent(percent) => extent * percent / 100.0,
+        }
+    }
+
+    // The viewport's size in world units, what screen-relative percentages
+    // of world lengths are usually of.
+    pub fn viewport_world(&self) -> cgmath::Vector2<f32> {
+        cgmath::Vector2::new(self.viewport.width, self.viewport.height) / self.camera.scale
+    }
+
+    // A stroke `width` wide for elements at scale 1. Strokes are tessellated
+    // into the geometry, so screen-sized ones have to be made again after
+    // zooming to stay that size.
+    pub fn stroke(&self, width: Length) -> Stroke {
+        Stroke::new(self.world_length(width, 0.0))
+    }
+}
@@ -0,0 +1,137 @@
+use serde::{Deserialize, Serialize};
+use winit::dpi::{PhysicalPosition, PhysicalSize};
+use winit::monitor::{MonitorHandle, VideoMode};
+use winit::window::{Fullscreen, Window, WindowBuilder};
+
+pub const SETTINGS_FILE: &str = "wgpie-window.json";
+
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub enum FullscreenMode {
+    Windowed,
+    Borderless,
+    // Switches the monitor to its largest video mode.
+    Exclusive,
+}
+
+#[derive(Debug, Clone)]
+pub struct MonitorInfo {
+    pub index: usize,
+    pub name: Option<String>,
+    pub position: PhysicalPosition<i32>,
+    pub size: PhysicalSize<u32>,
+    pub scale_factor: f64,
+}
+
+pub fn monitors(window: &Window) -> Vec<MonitorInfo> {
+    window
+        .available_monitors()
+        .enumerate()
+        .map(|(index, monitor)| MonitorInfo {
+            index,
+            name: monitor.name(),
+            position: monitor.position(),
+            size: monitor.size(),
+            scale_factor: monitor.scale_factor(),
+        })
+        .collect()
+}
+
+fn best_video_mode(monitor: &MonitorHandle) -> Option<VideoMode> {
+    monitor.video_modes().max_by_key(|mode| {
+        let size = mode.size();
+        (
+            size.width * size.height,
+            mode.bit_depth(),
+            mode.refresh_rate_millihertz(),
+        )
+    })
+}
+
+// Applies a fullscreen mode on the given monitor (or the window's current
+// monitor). Winit follows up with a `Resized` event, which is where the
+// surface gets reconfigured.
+pub fn set_fullscreen(window: &Window, mode: FullscreenMode, monitor: Option<usize>) {
+    let monitor = monitor
+        .and_then(|index| window.available_monitors().nth(index))
+        .or_else(|| window.current_monitor());
+
+    let fullscreen = match mode {
+        FullscreenMode::Windowed => None,
+        FullscreenMode::Borderless => Some(Fullscreen::Borderless(monitor)),
+        FullscreenMode::Exclusive => match monitor.as_ref().and_then(best_video_mode) {
+            Some(video_mode) => Some(Fullscreen::Exclusive(video_mode)),
+            None => {
+                log::warn!("No video modes available, falling back to borderless fullscreen");
+                Some(Fullscreen::Borderless(monitor))
+            }
+        },
+    };
+    window.set_fullscreen(fullscreen);
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WindowSettings {
+    pub position: Option<(i32, i32)>,
+    pub size: Option<(u32, u32)>,
+    pub fullscreen: FullscreenMode,
+    pub monitor: Option<String>,
+}
+
+impl Default for WindowSettings {
+    fn default() -> Self {
+        Self {
+            position: None,
+            size: None,
+            fullscreen: FullscreenMode::Windowed,
+            monitor: None,
+        }
+    }
+}
+
+impl WindowSettings {
+    // Missing or unreadable settings just mean a default window.
+    pub fn load(path: &str) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &str) -> anyhow::Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn capture(window: &Window, fullscreen: FullscreenMode) -> Self {
+        let mut settings = Self::load(SETTINGS_FILE);
+        settings.fullscreen = fullscreen;
+        settings.monitor = window.current_monitor().and_then(|m| m.name());
+        // Keep the last windowed placement so leaving fullscreen restores it.
+        if fullscreen == FullscreenMode::Windowed {
+            let size = window.inner_size();
+            settings.size = Some((size.width, size.height));
+            settings.position = window.outer_position().ok().map(|p| (p.x, p.y));
+        }
+        settings
+    }
+
+    pub fn apply_to_builder(&self, mut builder: WindowBuilder) -> WindowBuilder {
+        if let Some((width, height)) = self.size {
+            builder = builder.with_inner_size(PhysicalSize::new(width, height));
+        }
+        if let Some((x, y)) = self.position {
+            builder = builder.with_position(PhysicalPosition::new(x, y));
+        }
+        builder
+    }
+
+    // Fullscreen needs a live window to find the monitor by name.
+    pub fn apply_to_window(&self, window: &Window) {
+        if self.fullscreen != FullscreenMode::Windowed {
+            let monitor = window
+                .available_monitors()
+                .position(|m| m.name().is_some() && m.name() == self.monitor);
+            set_fullscreen(window, self.fullscreen, monitor);
+        }
+    }
+}